@@ -1,6 +1,10 @@
 use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
 use ibc_client_cw::context::Context;
 use ibc_client_cw::types::{ContractError, InstantiateMsg, QueryMsg, SudoMsg};
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::context::{
+    ClientExecutionContext, ClientValidationContext, ExtClientValidationContext,
+};
 
 use crate::client_type::SovTmClient;
 
@@ -24,8 +28,14 @@ pub fn instantiate(
 pub fn sudo(deps: DepsMut<'_>, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
     let mut ctx = SovTmContext::new_mut(deps, env)?;
 
+    let is_recovery = matches!(msg, SudoMsg::MigrateClientStore(_));
+
     let data = ctx.sudo(msg)?;
 
+    if is_recovery {
+        carry_over_update_meta(&mut ctx)?;
+    }
+
     Ok(Response::default().set_data(data))
 }
 
@@ -35,3 +45,23 @@ pub fn query(deps: Deps<'_>, env: Env, msg: QueryMsg) -> Result<Binary, Contract
 
     ctx.query(msg)
 }
+
+/// Carries the update metadata the substitute client recorded at its latest
+/// height over to the subject client, which took over the consensus state at
+/// that height upon recovery. The client itself only sees the store of the
+/// subject, so it records the time and height of the recovery instead.
+fn carry_over_update_meta(ctx: &mut SovTmContext<'_>) -> Result<(), ContractError> {
+    let client_id = ctx.client_id();
+
+    ctx.set_substitute_prefix();
+
+    let latest_height = ctx.client_state(&client_id)?.latest_height();
+
+    let (processed_time, processed_height) = ctx.client_update_meta(&client_id, &latest_height)?;
+
+    ctx.set_subject_prefix();
+
+    ctx.store_update_meta(client_id, latest_height, processed_time, processed_height)?;
+
+    Ok(())
+}
@@ -1,26 +1,34 @@
 use std::ops::{Add, Sub};
+use std::str::FromStr;
 use std::time::Duration;
 
 use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
 use cosmwasm_std::{coins, from_json, Deps, DepsMut, Empty, MessageInfo, StdError};
 use ibc_client_cw::types::{
     CheckForMisbehaviourMsgRaw, ExportMetadataMsg, GenesisMetadata, InstantiateMsg, QueryMsg,
-    QueryResponse, StatusMsg, VerifyClientMessageRaw,
+    QueryResponse, StatusMsg, UpdateStateMsgRaw, VerifyClientMessageRaw,
 };
 use ibc_client_cw::utils::AnyCodec;
 use ibc_client_tendermint::types::Header;
+use ibc_core::client::context::{ClientValidationContext, ExtClientValidationContext};
 use ibc_core::client::types::{Height, Status};
-use ibc_core::host::types::identifiers::ChainId;
+use ibc_core::host::types::identifiers::{ChainId, ClientId};
+use ibc_core::host::types::path::ClientConsensusStatePath;
 use ibc_core::primitives::Timestamp;
 use sov_celestia_client::types::client_message::test_util::dummy_sov_header;
-use sov_celestia_client::types::client_message::SovTmHeader;
+use sov_celestia_client::types::client_message::{
+    CelestiaValidityCondition, SovTmHeader, SovTmMisbehaviour,
+};
 use sov_celestia_client::types::client_state::test_util::{
     dummy_checksum, dummy_sov_consensus_state, mock_celestia_chain_id, ClientStateConfig,
     TendermintParamsConfig,
 };
 use sov_celestia_client::types::client_state::SovTmClientState;
 use sov_celestia_client::types::consensus_state::SovTmConsensusState;
-use sov_celestia_client::types::sovereign::{Root, SovereignParamsConfig};
+use sov_celestia_client::types::sovereign::{
+    AggregatedProof, AggregatedProofPublicData, MockVerifier, Root, SovereignParamsConfig, ZkVm,
+};
+use tendermint::Hash;
 use tendermint_testgen::{Generator, Validator};
 
 use crate::entrypoint::SovTmContext;
@@ -33,8 +41,10 @@ pub struct Fixture {
     pub genesis_da_height: Height,
     pub trusted_da_height: Height,
     pub target_height: Height,
+    pub trusted_da_block_hash: Hash,
     pub validators: Vec<Validator>,
     pub migration_mode: bool,
+    pub zk_vm: ZkVm,
 }
 
 impl Default for Fixture {
@@ -48,12 +58,14 @@ impl Default for Fixture {
             genesis_da_height: Height::new(0, 3).unwrap(),
             trusted_da_height: Height::new(0, 5).unwrap(),
             target_height: Height::new(0, 10).unwrap(),
+            trusted_da_block_hash: Hash::Sha256([2; 32]),
             validators: vec![
                 Validator::new("1").voting_power(40),
                 Validator::new("2").voting_power(30),
                 Validator::new("3").voting_power(30),
             ],
             migration_mode: false,
+            zk_vm: ZkVm::Mock,
         }
     }
 }
@@ -64,6 +76,11 @@ impl Fixture {
         self
     }
 
+    pub fn with_zk_vm(mut self, zk_vm: ZkVm) -> Self {
+        self.zk_vm = zk_vm;
+        self
+    }
+
     pub fn ctx_ref<'a>(&self, deps: Deps<'a, Empty>) -> SovTmContext<'a> {
         let mut ctx = SovTmContext::new_ref(deps, mock_env()).expect("never fails");
 
@@ -85,13 +102,70 @@ impl Fixture {
     }
 
     pub fn dummy_instantiate_msg(&self) -> InstantiateMsg {
+        self.instantiate_msg_at(self.trusted_da_height, self.trusted_timestamp)
+    }
+
+    /// Constructs an instantiate message whose consensus state is older than
+    /// the trusting period, so the client is expired from the start.
+    pub fn dummy_expired_instantiate_msg(&self) -> InstantiateMsg {
+        let expired_timestamp = self
+            .trusted_timestamp
+            .sub(Duration::from_secs(2))
+            .expect("never fails");
+
+        self.instantiate_msg_at(self.trusted_da_height, expired_timestamp)
+    }
+
+    /// Constructs an instantiate message for a substitute client, which is
+    /// ahead of the subject client by being trusted at the target height.
+    pub fn dummy_substitute_instantiate_msg(&self) -> InstantiateMsg {
+        self.instantiate_msg_at(
+            self.target_height
+                .add(self.genesis_da_height.revision_height()),
+            self.trusted_timestamp,
+        )
+    }
+
+    /// Constructs an instantiate message whose consensus state does not record
+    /// the hash of its DA block, as the ones of upgraded clients may not.
+    pub fn dummy_instantiate_msg_without_da_block_hash(&self) -> InstantiateMsg {
+        self.instantiate_msg_with_block_hash(self.trusted_da_height, self.trusted_timestamp, None)
+    }
+
+    fn instantiate_msg_at(&self, da_height: Height, timestamp: Timestamp) -> InstantiateMsg {
+        self.instantiate_msg_with_block_hash(da_height, timestamp, Some(self.trusted_da_block_hash))
+    }
+
+    fn instantiate_msg_with_block_hash(
+        &self,
+        da_height: Height,
+        timestamp: Timestamp,
+        block_hash: Option<Hash>,
+    ) -> InstantiateMsg {
+        let sov_client_state = self.sov_client_state_at(da_height);
+
+        let mut sov_consensus_state = dummy_sov_consensus_state(timestamp);
+
+        sov_consensus_state.da_params.block_hash = block_hash;
+
+        InstantiateMsg {
+            client_state: SovTmClientState::encode_to_any_vec(sov_client_state),
+            consensus_state: SovTmConsensusState::encode_to_any_vec(sov_consensus_state),
+            checksum: dummy_checksum(),
+        }
+    }
+
+    /// Constructs a client state trusted at the given DA height, whose
+    /// aggregated proofs are generated with the zkVM of the fixture.
+    pub fn sov_client_state_at(&self, da_height: Height) -> SovTmClientState {
         // Setting the `trusting_period` to 1 second allows the quick client
-        // freeze for the `happy_cw_client_recovery` test.
+        // expiry for the client recovery tests.
         let sovereign_params = SovereignParamsConfig::builder()
             .genesis_da_height(self.genesis_da_height)
             .trusting_period(Duration::from_secs(1))
+            .zk_vm(self.zk_vm)
             .latest_height(
-                self.trusted_da_height
+                da_height
                     .sub(self.genesis_da_height.revision_height())
                     .unwrap(),
             )
@@ -99,21 +173,17 @@ impl Fixture {
 
         let tendermint_params = TendermintParamsConfig::builder().build();
 
-        let sov_client_state = ClientStateConfig::builder()
+        ClientStateConfig::builder()
             .sovereign_params(sovereign_params)
             .tendermint_params(tendermint_params)
-            .build();
-
-        let sov_consensus_state = dummy_sov_consensus_state(self.trusted_timestamp);
-
-        InstantiateMsg {
-            client_state: SovTmClientState::encode_to_any_vec(sov_client_state),
-            consensus_state: SovTmConsensusState::encode_to_any_vec(sov_consensus_state),
-            checksum: dummy_checksum(),
-        }
+            .build()
     }
 
     fn dummy_header(&self, header_height: Height) -> Vec<u8> {
+        SovTmHeader::encode_to_any_vec(self.sov_header_at(header_height))
+    }
+
+    fn sov_header_at(&self, header_height: Height) -> SovTmHeader {
         // NOTE: since mock context has a fixed timestamp, we only can add up
         // to allowed clock drift (3s)
         let future_time = self
@@ -141,18 +211,40 @@ impl Fixture {
             trusted_next_validator_set: light_block.next_validators,
         };
 
-        let sov_header = dummy_sov_header(
+        // The aggregated proof starts at the slot following the trusted one
+        let mut sov_header = dummy_sov_header(
             tm_header,
             self.trusted_da_height
                 .revision_height()
-                .sub(self.genesis_da_height.revision_height()),
+                .sub(self.genesis_da_height.revision_height())
+                + 1,
             header_height
                 .revision_height()
                 .sub(self.genesis_da_height.revision_height()),
             Root::from([0; 32]),
+            Root::from([0; 32]),
         );
 
-        SovTmHeader::encode_to_any_vec(sov_header)
+        let public_data = &mut sov_header.aggregated_proof.public_data;
+
+        // The only DA block the validity conditions refer to extends the
+        // trusted one
+        public_data.validity_conditions = vec![CelestiaValidityCondition::new(
+            self.trusted_da_block_hash
+                .as_bytes()
+                .try_into()
+                .expect("never fails"),
+            public_data
+                .final_slot_hash
+                .as_slice()
+                .try_into()
+                .expect("never fails"),
+        )
+        .into()];
+
+        sov_header.aggregated_proof.serialized_proof = MockVerifier::prove(public_data);
+
+        sov_header
     }
 
     pub fn dummy_client_message(&self) -> Vec<u8> {
@@ -162,6 +254,49 @@ impl Fixture {
         )
     }
 
+    /// Constructs a dummy client message whose aggregated proof is rejected by
+    /// the verifier of the client.
+    pub fn dummy_client_message_with_invalid_proof(&self) -> Vec<u8> {
+        let mut sov_header = self.sov_header_at(
+            self.target_height
+                .add(self.genesis_da_height.revision_height()),
+        );
+
+        sov_header.aggregated_proof.serialized_proof = vec![0; 32].into();
+
+        SovTmHeader::encode_to_any_vec(sov_header)
+    }
+
+    /// Constructs a dummy client message with a valid aggregated proof, but
+    /// whose validity conditions refer to a DA block the client cannot verify.
+    pub fn dummy_client_message_with_unverifiable_validity_conditions(&self) -> Vec<u8> {
+        self.dummy_client_message_with_public_data(|public_data| {
+            public_data.validity_conditions =
+                vec![CelestiaValidityCondition::new([0; 32], [1; 32]).into()];
+        })
+    }
+
+    /// Constructs a dummy client message whose aggregated proof is generated
+    /// anew over the public data altered by the given closure, so that only
+    /// the alteration is at fault.
+    pub fn dummy_client_message_with_public_data(
+        &self,
+        alter: impl FnOnce(&mut AggregatedProofPublicData),
+    ) -> Vec<u8> {
+        let mut sov_header = self.sov_header_at(
+            self.target_height
+                .add(self.genesis_da_height.revision_height()),
+        );
+
+        let public_data = &mut sov_header.aggregated_proof.public_data;
+
+        alter(public_data);
+
+        sov_header.aggregated_proof.serialized_proof = MockVerifier::prove(public_data);
+
+        SovTmHeader::encode_to_any_vec(sov_header)
+    }
+
     /// Constructs a dummy misbehaviour message that is one block behind the
     /// trusted height, but with a future timestamp.
     pub fn dummy_misbehaviour_message(&self) -> Vec<u8> {
@@ -170,6 +305,109 @@ impl Fixture {
         self.dummy_header(prev_height)
     }
 
+    /// Constructs a dummy misbehaviour message evidencing a fork of the rollup
+    /// at the target height, through two aggregated proofs of the header at
+    /// the target height that end at the given state roots.
+    pub fn dummy_rollup_fork_message(
+        &self,
+        final_state_root_1: Root,
+        final_state_root_2: Root,
+    ) -> Vec<u8> {
+        self.dummy_rollup_fork_message_with_public_data(
+            |public_data| public_data.final_state_root = final_state_root_1,
+            |public_data| public_data.final_state_root = final_state_root_2,
+        )
+    }
+
+    /// Constructs a dummy misbehaviour message evidencing a fork of the rollup
+    /// at the target height, through two aggregated proofs of the header at
+    /// the target height generated anew over the public data altered by the
+    /// given closures.
+    pub fn dummy_rollup_fork_message_with_public_data(
+        &self,
+        alter_1: impl FnOnce(&mut AggregatedProofPublicData),
+        alter_2: impl FnOnce(&mut AggregatedProofPublicData),
+    ) -> Vec<u8> {
+        fn aggregated_proof(
+            mut aggregated_proof: AggregatedProof,
+            alter: impl FnOnce(&mut AggregatedProofPublicData),
+        ) -> AggregatedProof {
+            alter(&mut aggregated_proof.public_data);
+
+            aggregated_proof.serialized_proof = MockVerifier::prove(&aggregated_proof.public_data);
+
+            aggregated_proof
+        }
+
+        let sov_header = self.sov_header_at(
+            self.target_height
+                .add(self.genesis_da_height.revision_height()),
+        );
+
+        let misbehaviour = SovTmMisbehaviour::new_rollup_fork(
+            ClientId::from_str("08-wasm-0").expect("never fails"),
+            aggregated_proof(sov_header.aggregated_proof.clone(), alter_1),
+            aggregated_proof(sov_header.aggregated_proof, alter_2),
+        );
+
+        SovTmMisbehaviour::encode_to_any_vec(misbehaviour)
+    }
+
+    /// Instantiates the substitute client a block before the recovery, so
+    /// that its update metadata tells apart from the one of the recovery.
+    pub fn instantiate_substitute(&self, deps: DepsMut<'_>) {
+        let mut env = mock_env();
+
+        env.block.height -= 1;
+        env.block.time = env.block.time.minus_seconds(1);
+
+        let mut ctx = SovTmContext::new_mut(deps, env).expect("never fails");
+
+        ctx.set_substitute_prefix();
+
+        ctx.instantiate(self.dummy_substitute_instantiate_msg())
+            .unwrap();
+    }
+
+    /// Returns the consensus state of the subject client, or of the substitute
+    /// one, at the given height along with its update metadata.
+    pub fn consensus_state_with_update_meta(
+        &self,
+        deps: Deps<'_>,
+        substitute: bool,
+        height: Height,
+    ) -> (SovTmConsensusState, (Timestamp, Height)) {
+        let mut ctx = self.ctx_ref(deps);
+
+        if substitute {
+            ctx.set_substitute_prefix();
+        }
+
+        let client_id = ctx.client_id();
+
+        let consensus_state = ctx
+            .consensus_state(&ClientConsensusStatePath::new(
+                client_id.clone(),
+                height.revision_number(),
+                height.revision_height(),
+            ))
+            .unwrap()
+            .into_inner();
+
+        let update_meta = ctx.client_update_meta(&client_id, &height).unwrap();
+
+        (consensus_state, update_meta)
+    }
+
+    /// Updates the client up to the target height.
+    pub fn update_client(&self, deps: DepsMut<'_>) {
+        let client_message = self.dummy_client_message();
+
+        self.ctx_mut(deps)
+            .sudo(UpdateStateMsgRaw { client_message }.into())
+            .unwrap();
+    }
+
     pub fn verify_client_message(&self, deps: Deps<'_>, client_message: Vec<u8>) {
         let resp = self.query(deps, VerifyClientMessageRaw { client_message }.into());
 
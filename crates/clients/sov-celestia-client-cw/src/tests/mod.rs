@@ -1,11 +1,25 @@
 pub mod fixture;
 
+use std::ops::Add;
+
 use cosmwasm_std::from_json;
 use cosmwasm_std::testing::{mock_dependencies, mock_env};
+use cosmwasm_std::Deps;
 use ibc_client_cw::types::{
-    ContractResult, MigrateClientStoreMsg, UpdateStateMsgRaw, UpdateStateOnMisbehaviourMsgRaw,
+    CheckForMisbehaviourMsgRaw, ContractResult, MigrateClientStoreMsg, UpdateStateMsgRaw,
+    UpdateStateOnMisbehaviourMsgRaw, VerifyClientMessageRaw,
+};
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::{Height, Status};
+use ibc_core::commitment_types::commitment::{CommitmentProofBytes, CommitmentRoot};
+use sov_celestia_client::client_state::ClientState;
+use sov_celestia_client::types::client_message::CelestiaValidityCondition;
+use sov_celestia_client::types::client_state::test_util::dummy_sov_consensus_state;
+use sov_celestia_client::types::client_state::SovTmClientState;
+use sov_celestia_client::types::proto::v1::ClientState as RawSovTmClientState;
+use sov_celestia_client::types::sovereign::{
+    AggregatedProofPublicData, Root, SlotNumber, ValidityCondition, ZkVm,
 };
-use ibc_core::client::types::Status;
 
 use crate::entrypoint::{instantiate, sudo};
 use crate::tests::fixture::{dummy_msg_info, Fixture};
@@ -29,6 +43,72 @@ fn happy_cw_create_client() {
     fxt.check_client_status(deps.as_ref(), Status::Active);
 }
 
+#[test]
+#[cfg(not(feature = "risc0"))]
+fn cw_create_client_with_unsupported_zk_vm() {
+    let fxt = Fixture::default().with_zk_vm(ZkVm::Risc0);
+
+    let mut deps = mock_dependencies();
+
+    let instantiate_msg = fxt.dummy_instantiate_msg();
+
+    assert!(instantiate(deps.as_mut(), mock_env(), dummy_msg_info(), instantiate_msg).is_err());
+}
+
+#[test]
+fn cw_decode_client_state_without_zk_vm() {
+    let fxt = Fixture::default();
+
+    let mut raw_client_state =
+        RawSovTmClientState::from(fxt.sov_client_state_at(fxt.trusted_da_height));
+
+    // Client states encoded before the zkVM was recorded leave it empty
+    raw_client_state
+        .sovereign_params
+        .as_mut()
+        .unwrap()
+        .zk_vm
+        .clear();
+
+    let client_state = SovTmClientState::try_from(raw_client_state).unwrap();
+
+    assert_eq!(client_state.zk_vm(), ZkVm::default());
+}
+
+#[test]
+#[cfg(not(feature = "risc0"))]
+fn cw_upgrade_client_with_unsupported_zk_vm() {
+    let fxt = Fixture::default();
+
+    let client_state = ClientState::from(fxt.sov_client_state_at(fxt.trusted_da_height));
+
+    let upgrade_height = fxt
+        .target_height
+        .add(fxt.genesis_da_height.revision_height());
+
+    let verify_upgrade_client = |zk_vm: ZkVm| {
+        let upgraded_client_state = fxt
+            .clone()
+            .with_zk_vm(zk_vm)
+            .sov_client_state_at(upgrade_height);
+
+        client_state
+            .verify_upgrade_client(
+                upgraded_client_state.into(),
+                dummy_sov_consensus_state(fxt.trusted_timestamp).into(),
+                CommitmentProofBytes::try_from(vec![0]).unwrap(),
+                CommitmentProofBytes::try_from(vec![0]).unwrap(),
+                &CommitmentRoot::from_bytes(&[0; 32]),
+            )
+            .unwrap_err()
+            .to_string()
+    };
+
+    // The upgrade to a supported zkVM only fails on its dummy proofs
+    assert!(!verify_upgrade_client(ZkVm::Mock).contains("not supported"));
+    assert!(verify_upgrade_client(ZkVm::Risc0).contains("not supported"));
+}
+
 #[test]
 fn happy_cw_update_client() {
     let fxt = Fixture::default();
@@ -66,7 +146,388 @@ fn happy_cw_update_client() {
 }
 
 #[test]
-fn happy_cw_recovery_client() {
+fn cw_update_client_with_invalid_aggregated_proof() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    let client_message = fxt.dummy_client_message_with_invalid_proof();
+
+    let ctx = fxt.ctx_ref(deps.as_ref());
+
+    assert!(ctx
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .is_err());
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_update_client_with_unverifiable_validity_conditions() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    let client_message = fxt.dummy_client_message_with_unverifiable_validity_conditions();
+
+    let ctx = fxt.ctx_ref(deps.as_ref());
+
+    assert!(ctx
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .is_err());
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_update_client_with_validity_conditions_not_extending_trusted_block() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    let client_message = fxt.dummy_client_message_with_public_data(|public_data| {
+        public_data.validity_conditions = vec![untrusted_validity_condition(public_data)];
+    });
+
+    let error = fxt
+        .ctx_ref(deps.as_ref())
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .unwrap_err();
+
+    assert!(
+        error.to_string().contains("trusted DA block hash"),
+        "{error}"
+    );
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_update_client_without_trusted_da_block_hash() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg_without_da_block_hash(),
+    )
+    .unwrap();
+
+    let error = fxt
+        .ctx_ref(deps.as_ref())
+        .query(
+            VerifyClientMessageRaw {
+                client_message: fxt.dummy_client_message(),
+            }
+            .into(),
+        )
+        .unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains("does not record its DA block hash"),
+        "{error}"
+    );
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_update_client_with_discontinuous_aggregated_proof() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    let client_messages = [
+        (
+            fxt.dummy_client_message_with_public_data(|public_data| {
+                public_data.initial_state_root = Root::from([1; 32]);
+            }),
+            "initial state root",
+        ),
+        (
+            fxt.dummy_client_message_with_public_data(|public_data| {
+                public_data.final_slot_hash = vec![1; 32];
+            }),
+            "final slot hash",
+        ),
+        // The proof skips the slot following the trusted one
+        (
+            fxt.dummy_client_message_with_public_data(|public_data| {
+                public_data.initial_slot_number = SlotNumber(public_data.initial_slot_number.0 + 1);
+            }),
+            "initial slot number",
+        ),
+    ];
+
+    let ctx = fxt.ctx_ref(deps.as_ref());
+
+    for (client_message, expected_error) in client_messages {
+        let error = ctx
+            .query(VerifyClientMessageRaw { client_message }.into())
+            .unwrap_err();
+
+        assert!(error.to_string().contains(expected_error), "{error}");
+    }
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn happy_cw_freeze_client_on_rollup_fork() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    fxt.update_client(deps.as_mut());
+
+    let client_message = fxt.dummy_rollup_fork_message(Root::from([0; 32]), Root::from([1; 32]));
+
+    fxt.verify_client_message(deps.as_ref(), client_message.clone());
+
+    fxt.check_for_misbehaviour(deps.as_ref(), client_message.clone());
+
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        UpdateStateOnMisbehaviourMsgRaw { client_message }.into(),
+    )
+    .unwrap();
+
+    fxt.check_client_status(deps.as_ref(), Status::Frozen);
+}
+
+#[test]
+fn cw_no_rollup_fork_on_matching_state_roots() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    fxt.update_client(deps.as_mut());
+
+    let client_message = fxt.dummy_rollup_fork_message(Root::from([0; 32]), Root::from([0; 32]));
+
+    fxt.verify_client_message(deps.as_ref(), client_message.clone());
+
+    let resp = fxt.query(
+        deps.as_ref(),
+        CheckForMisbehaviourMsgRaw { client_message }.into(),
+    );
+
+    assert_eq!(resp.found_misbehaviour, Some(false));
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_rollup_fork_with_mismatched_final_slot_hashes() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    fxt.update_client(deps.as_mut());
+
+    let client_message = fxt.dummy_rollup_fork_message_with_public_data(
+        |public_data| public_data.final_state_root = Root::from([1; 32]),
+        |public_data| public_data.final_slot_hash = vec![1; 32],
+    );
+
+    let error = fxt
+        .ctx_ref(deps.as_ref())
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .unwrap_err();
+
+    assert!(
+        error.to_string().contains("same final slot hash"),
+        "{error}"
+    );
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_rollup_fork_not_anchored_to_verified_da_block() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    // The client has not verified any DA block at the final slot yet
+    let client_message = fxt.dummy_rollup_fork_message(Root::from([0; 32]), Root::from([1; 32]));
+
+    assert!(fxt
+        .ctx_ref(deps.as_ref())
+        .query(
+            VerifyClientMessageRaw {
+                client_message: client_message.clone()
+            }
+            .into()
+        )
+        .is_err());
+
+    fxt.update_client(deps.as_mut());
+
+    // Both proofs end at the same DA block, but not at the verified one
+    let client_message = fxt.dummy_rollup_fork_message_with_public_data(
+        |public_data| public_data.final_slot_hash = vec![1; 32],
+        |public_data| {
+            public_data.final_slot_hash = vec![1; 32];
+            public_data.final_state_root = Root::from([1; 32]);
+        },
+    );
+
+    let error = fxt
+        .ctx_ref(deps.as_ref())
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .unwrap_err();
+
+    assert!(error.to_string().contains("DA block hash"), "{error}");
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_rollup_fork_with_unverifiable_validity_conditions() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    fxt.update_client(deps.as_mut());
+
+    // The second proof relies on a DA block the client cannot verify
+    let client_message = fxt.dummy_rollup_fork_message_with_public_data(
+        |_| {},
+        |public_data| {
+            public_data.final_state_root = Root::from([1; 32]);
+            public_data.validity_conditions =
+                vec![CelestiaValidityCondition::new([0; 32], [1; 32]).into()];
+        },
+    );
+
+    let error = fxt
+        .ctx_ref(deps.as_ref())
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .unwrap_err();
+
+    assert!(error.to_string().contains("validity condition"), "{error}");
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn cw_rollup_fork_with_validity_conditions_not_extending_trusted_block() {
+    let fxt = Fixture::default();
+
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        dummy_msg_info(),
+        fxt.dummy_instantiate_msg(),
+    )
+    .unwrap();
+
+    fxt.update_client(deps.as_mut());
+
+    let client_message = fxt.dummy_rollup_fork_message_with_public_data(
+        |_| {},
+        |public_data| {
+            public_data.final_state_root = Root::from([1; 32]);
+            public_data.validity_conditions = vec![untrusted_validity_condition(public_data)];
+        },
+    );
+
+    let error = fxt
+        .ctx_ref(deps.as_ref())
+        .query(VerifyClientMessageRaw { client_message }.into())
+        .unwrap_err();
+
+    assert!(
+        error.to_string().contains("trusted DA block hash"),
+        "{error}"
+    );
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+}
+
+#[test]
+fn happy_cw_recovery_frozen_client() {
     let fxt = Fixture::default().migration_mode();
 
     let mut deps = mock_dependencies();
@@ -75,9 +536,7 @@ fn happy_cw_recovery_client() {
 
     // ------------------- Create subject client -------------------
 
-    let instantiate_msg = fxt.dummy_instantiate_msg();
-
-    ctx.instantiate(instantiate_msg.clone()).unwrap();
+    ctx.instantiate(fxt.dummy_instantiate_msg()).unwrap();
 
     // ------------------- Freeze subject client -------------------
 
@@ -90,11 +549,41 @@ fn happy_cw_recovery_client() {
     ctx.sudo(UpdateStateOnMisbehaviourMsgRaw { client_message }.into())
         .unwrap();
 
+    fxt.check_client_status(deps.as_ref(), Status::Frozen);
+
     // ------------------- Create substitute client -------------------
 
-    ctx.set_substitute_prefix();
+    fxt.instantiate_substitute(deps.as_mut());
 
-    ctx.instantiate(instantiate_msg).unwrap();
+    // ------------------- Recover subject client -------------------
+
+    let resp = sudo(deps.as_mut(), mock_env(), MigrateClientStoreMsg {}.into()).unwrap();
+
+    assert_eq!(0, resp.messages.len());
+
+    fxt.check_client_status(deps.as_ref(), Status::Active);
+
+    check_recovered_consensus_state(&fxt, deps.as_ref());
+}
+
+#[test]
+fn happy_cw_recovery_expired_client() {
+    let fxt = Fixture::default().migration_mode();
+
+    let mut deps = mock_dependencies();
+
+    let mut ctx = fxt.ctx_mut(deps.as_mut());
+
+    // ------------------- Create expired subject client -------------------
+
+    ctx.instantiate(fxt.dummy_expired_instantiate_msg())
+        .unwrap();
+
+    fxt.check_client_status(deps.as_ref(), Status::Expired);
+
+    // ------------------- Create substitute client -------------------
+
+    fxt.instantiate_substitute(deps.as_mut());
 
     // ------------------- Recover subject client -------------------
 
@@ -103,4 +592,38 @@ fn happy_cw_recovery_client() {
     assert_eq!(0, resp.messages.len());
 
     fxt.check_client_status(deps.as_ref(), Status::Active);
+
+    check_recovered_consensus_state(&fxt, deps.as_ref());
+}
+
+/// Checks that the recovered client took over the consensus state of the
+/// substitute at its latest height, along with the time and height at which
+/// the substitute processed it.
+fn check_recovered_consensus_state(fxt: &Fixture, deps: Deps<'_>) {
+    let (substitute_consensus_state, substitute_update_meta) =
+        fxt.consensus_state_with_update_meta(deps, true, fxt.target_height);
+
+    let (consensus_state, update_meta) =
+        fxt.consensus_state_with_update_meta(deps, false, fxt.target_height);
+
+    assert_eq!(consensus_state, substitute_consensus_state);
+    assert_eq!(update_meta, substitute_update_meta);
+    assert_ne!(
+        update_meta.1,
+        Height::new(0, mock_env().block.height).unwrap()
+    );
+}
+
+/// Returns a validity condition that refers to the final slot of the given
+/// public data, but does not extend the trusted DA block.
+fn untrusted_validity_condition(public_data: &AggregatedProofPublicData) -> ValidityCondition {
+    CelestiaValidityCondition::new(
+        [0; 32],
+        public_data
+            .final_slot_hash
+            .as_slice()
+            .try_into()
+            .expect("never fails"),
+    )
+    .into()
 }
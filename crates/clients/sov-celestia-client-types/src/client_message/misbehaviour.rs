@@ -12,7 +12,7 @@ use tendermint::merkle::MerkleHash;
 
 use super::header::{Header, SovTmHeader};
 use crate::proto::v1::Misbehaviour as RawSovTmMisbehaviour;
-use crate::sovereign::Error;
+use crate::sovereign::{AggregatedProof, Error};
 
 pub const SOV_TENDERMINT_MISBEHAVIOUR_TYPE_URL: &str =
     "/ibc.lightclients.sovereign.tendermint.v1.Misbehaviour";
@@ -22,8 +22,26 @@ pub const SOV_TENDERMINT_MISBEHAVIOUR_TYPE_URL: &str =
 #[derive(Clone, PartialEq, Eq)]
 pub struct Misbehaviour<H> {
     client_id: ClientId,
-    header_1: Box<Header<H>>,
-    header_2: Box<Header<H>>,
+    evidence: MisbehaviourEvidence<H>,
+}
+
+/// Defines the evidence of a misbehaviour, either of the DA layer or of the
+/// rollup itself.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, PartialEq, Eq)]
+pub enum MisbehaviourEvidence<H> {
+    /// Two conflicting headers, whose DA headers either equivocate or break
+    /// the monotonicity of time.
+    Headers {
+        header_1: Box<Header<H>>,
+        header_2: Box<Header<H>>,
+    },
+    /// Two aggregated proofs committing to different state roots for the same
+    /// slot, which proves a fork of the rollup.
+    AggregatedProofs {
+        aggregated_proof_1: Box<AggregatedProof>,
+        aggregated_proof_2: Box<AggregatedProof>,
+    },
 }
 
 impl<H> Misbehaviour<H> {
@@ -31,8 +49,25 @@ impl<H> Misbehaviour<H> {
     pub fn new(client_id: ClientId, header_1: Header<H>, header_2: Header<H>) -> Self {
         Self {
             client_id,
-            header_1: Box::new(header_1),
-            header_2: Box::new(header_2),
+            evidence: MisbehaviourEvidence::Headers {
+                header_1: Box::new(header_1),
+                header_2: Box::new(header_2),
+            },
+        }
+    }
+
+    /// Creates a new misbehaviour evidencing a fork of the rollup
+    pub fn new_rollup_fork(
+        client_id: ClientId,
+        aggregated_proof_1: AggregatedProof,
+        aggregated_proof_2: AggregatedProof,
+    ) -> Self {
+        Self {
+            client_id,
+            evidence: MisbehaviourEvidence::AggregatedProofs {
+                aggregated_proof_1: Box::new(aggregated_proof_1),
+                aggregated_proof_2: Box::new(aggregated_proof_2),
+            },
         }
     }
 
@@ -41,24 +76,26 @@ impl<H> Misbehaviour<H> {
         &self.client_id
     }
 
-    /// Getter for the first header
-    pub fn header_1(&self) -> &Header<H> {
-        &self.header_1
-    }
-
-    /// Getter for the second header
-    pub fn header_2(&self) -> &Header<H> {
-        &self.header_2
+    /// Getter for the misbehaviour evidence
+    pub fn evidence(&self) -> &MisbehaviourEvidence<H> {
+        &self.evidence
     }
 }
 
 impl<H> Debug for Misbehaviour<H> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Misbehaviour {{ client_id: {:?}, header_1: {{...}}, header_2: {{...}} }}",
-            self.client_id,
-        )
+        match self.evidence {
+            MisbehaviourEvidence::Headers { .. } => write!(
+                f,
+                "Misbehaviour {{ client_id: {:?}, header_1: {{...}}, header_2: {{...}} }}",
+                self.client_id,
+            ),
+            MisbehaviourEvidence::AggregatedProofs { .. } => write!(
+                f,
+                "Misbehaviour {{ client_id: {:?}, aggregated_proof_1: {{...}}, aggregated_proof_2: {{...}} }}",
+                self.client_id,
+            ),
+        }
     }
 }
 
@@ -73,46 +110,81 @@ impl SovTmMisbehaviour {
     }
 
     pub fn validate_basic<H: MerkleHash + Sha256 + Default>(&self) -> Result<(), Error> {
-        self.header_1.validate_basic::<H>()?;
-        self.header_2.validate_basic::<H>()?;
+        match &self.evidence {
+            MisbehaviourEvidence::Headers { header_1, header_2 } => {
+                header_1.validate_basic::<H>()?;
+                header_2.validate_basic::<H>()?;
 
-        if self.header_1.da_header.signed_header.header.chain_id
-            != self.header_2.da_header.signed_header.header.chain_id
-        {
-            return Err(Error::invalid("headers must have identical chain_ids"));
-        }
+                if header_1.da_header.signed_header.header.chain_id
+                    != header_2.da_header.signed_header.header.chain_id
+                {
+                    return Err(Error::invalid("headers must have identical chain_ids"));
+                }
+
+                if header_1.height() < header_2.height() {
+                    return Err(Error::invalid(format!(
+                        "header_1 height is less than header_2 height ({} < {})",
+                        header_1.height(),
+                        header_2.height()
+                    )));
+                }
+            }
+            MisbehaviourEvidence::AggregatedProofs {
+                aggregated_proof_1,
+                aggregated_proof_2,
+            } => {
+                aggregated_proof_1.validate_basic()?;
+                aggregated_proof_2.validate_basic()?;
 
-        if self.header_1.height() < self.header_2.height() {
-            return Err(Error::invalid(format!(
-                "header_1 height is less than header_2 height ({} < {})",
-                self.header_1.height(),
-                self.header_2.height()
-            )));
+                if aggregated_proof_1.final_slot_number() != aggregated_proof_2.final_slot_number()
+                {
+                    return Err(Error::invalid(format!(
+                        "aggregated proofs must end at the same slot ({} != {})",
+                        aggregated_proof_1.final_slot_number(),
+                        aggregated_proof_2.final_slot_number()
+                    )));
+                }
+            }
         }
 
         Ok(())
     }
 
-    pub fn into_tendermint_misbehaviour(&self) -> TmMisbehaviour {
-        TmMisbehaviour::new(
-            self.client_id.clone(),
-            self.header_1.da_header.clone(),
-            self.header_2.da_header.clone(),
-        )
+    /// Returns the Tendermint misbehaviour of the DA layer, if the evidence
+    /// consists of headers.
+    pub fn into_tendermint_misbehaviour(&self) -> Option<TmMisbehaviour> {
+        match &self.evidence {
+            MisbehaviourEvidence::Headers { header_1, header_2 } => Some(TmMisbehaviour::new(
+                self.client_id.clone(),
+                header_1.da_header.clone(),
+                header_2.da_header.clone(),
+            )),
+            MisbehaviourEvidence::AggregatedProofs { .. } => None,
+        }
     }
 }
 
 impl core::fmt::Display for SovTmMisbehaviour {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
-        write!(
-            f,
-            "{} h1: {}-{} h2: {}-{}",
-            self.client_id,
-            self.header_1.height(),
-            self.header_1.da_header,
-            self.header_2.height(),
-            self.header_2.da_header,
-        )
+        match &self.evidence {
+            MisbehaviourEvidence::Headers { header_1, header_2 } => write!(
+                f,
+                "{} h1: {}-{} h2: {}-{}",
+                self.client_id,
+                header_1.height(),
+                header_1.da_header,
+                header_2.height(),
+                header_2.da_header,
+            ),
+            MisbehaviourEvidence::AggregatedProofs {
+                aggregated_proof_1,
+                aggregated_proof_2,
+            } => write!(
+                f,
+                "{} p1: {} p2: {}",
+                self.client_id, aggregated_proof_1, aggregated_proof_2,
+            ),
+        }
     }
 }
 
@@ -126,6 +198,32 @@ impl TryFrom<RawSovTmMisbehaviour> for SovTmMisbehaviour {
             description: "".into(),
         })?;
 
+        // A fork of the rollup is evidenced by aggregated proofs alone, in
+        // place of the headers.
+        if raw.header_1.is_none() && raw.header_2.is_none() {
+            let aggregated_proof_1: AggregatedProof = raw
+                .aggregated_proof_1
+                .ok_or(Error::missing("aggregated_proof_1"))?
+                .try_into()?;
+
+            let aggregated_proof_2: AggregatedProof = raw
+                .aggregated_proof_2
+                .ok_or(Error::missing("aggregated_proof_2"))?
+                .try_into()?;
+
+            return Ok(Self::new_rollup_fork(
+                client_id,
+                aggregated_proof_1,
+                aggregated_proof_2,
+            ));
+        }
+
+        if raw.aggregated_proof_1.is_some() || raw.aggregated_proof_2.is_some() {
+            return Err(Error::invalid(
+                "misbehaviour cannot carry both headers and aggregated proofs",
+            ))?;
+        }
+
         let header_1: SovTmHeader = raw
             .header_1
             .ok_or(ClientError::Other {
@@ -146,11 +244,28 @@ impl TryFrom<RawSovTmMisbehaviour> for SovTmMisbehaviour {
 
 impl From<SovTmMisbehaviour> for RawSovTmMisbehaviour {
     fn from(value: SovTmMisbehaviour) -> Self {
-        #[allow(deprecated)]
-        RawSovTmMisbehaviour {
-            client_id: value.client_id.to_string(),
-            header_1: Some((*value.header_1).into()),
-            header_2: Some((*value.header_2).into()),
+        let client_id = value.client_id.to_string();
+
+        match value.evidence {
+            #[allow(deprecated)]
+            MisbehaviourEvidence::Headers { header_1, header_2 } => RawSovTmMisbehaviour {
+                client_id,
+                header_1: Some((*header_1).into()),
+                header_2: Some((*header_2).into()),
+                aggregated_proof_1: None,
+                aggregated_proof_2: None,
+            },
+            #[allow(deprecated)]
+            MisbehaviourEvidence::AggregatedProofs {
+                aggregated_proof_1,
+                aggregated_proof_2,
+            } => RawSovTmMisbehaviour {
+                client_id,
+                header_1: None,
+                header_2: None,
+                aggregated_proof_1: Some((*aggregated_proof_1).into()),
+                aggregated_proof_2: Some((*aggregated_proof_2).into()),
+            },
         }
     }
 }
@@ -1,5 +1,6 @@
 mod header;
 mod misbehaviour;
+mod validity_condition;
 
 use core::fmt::Debug;
 
@@ -9,6 +10,7 @@ use ibc_core::primitives::prelude::*;
 use ibc_core::primitives::proto::{Any, Protobuf};
 pub use misbehaviour::*;
 use prost::Message;
+pub use validity_condition::*;
 
 use crate::sovereign::Error;
 
@@ -71,23 +73,76 @@ impl From<SovTmClientMessage> for Any {
 #[cfg(feature = "test-util")]
 pub mod test_util {
     use ibc_client_tendermint::types::Header as TmHeader;
+    use tendermint::crypto::default::Sha256;
 
     use super::*;
     use crate::client_state::test_util::HeaderConfig;
     use crate::sovereign::{AggregatedProofConfig, PublicDataConfig, Root};
 
+    /// Constructs a dummy header whose aggregated proof chains from the given
+    /// initial state root up to the given DA header, which is the only DA
+    /// block its validity conditions refer to.
     pub fn dummy_sov_header(
         da_header: TmHeader,
         initial_slot_number: u64,
         final_slot_number: u64,
+        initial_state_root: Root,
         final_state_root: Root,
     ) -> SovTmHeader {
+        let block_hash = da_header
+            .signed_header
+            .header()
+            .hash_with::<Sha256>()
+            .as_bytes()
+            .try_into()
+            .expect("never fails");
+
+        let prev_hash = da_header
+            .signed_header
+            .header()
+            .last_block_id
+            .as_ref()
+            .map_or([0; 32], |id| {
+                id.hash.as_bytes().try_into().expect("never fails")
+            });
+
+        dummy_sov_header_with_validity_conditions(
+            da_header,
+            initial_slot_number,
+            final_slot_number,
+            initial_state_root,
+            final_state_root,
+            vec![CelestiaValidityCondition::new(prev_hash, block_hash)],
+        )
+    }
+
+    /// Constructs a dummy header whose aggregated proof chains from the given
+    /// initial state root up to the given DA header, through the DA blocks
+    /// the given validity conditions refer to.
+    pub fn dummy_sov_header_with_validity_conditions(
+        da_header: TmHeader,
+        initial_slot_number: u64,
+        final_slot_number: u64,
+        initial_state_root: Root,
+        final_state_root: Root,
+        validity_conditions: Vec<CelestiaValidityCondition>,
+    ) -> SovTmHeader {
+        let final_slot_hash = da_header
+            .signed_header
+            .header()
+            .hash_with::<Sha256>()
+            .as_bytes()
+            .to_vec();
+
         let aggregated_proof = AggregatedProofConfig::builder()
             .public_data(
                 PublicDataConfig::builder()
                     .initial_slot_number(initial_slot_number.into())
                     .final_slot_number(final_slot_number.into())
+                    .initial_state_root(initial_state_root)
                     .final_state_root(final_state_root)
+                    .final_slot_hash(final_slot_hash)
+                    .validity_conditions(validity_conditions.into_iter().map(Into::into).collect())
                     .build(),
             )
             .build();
@@ -0,0 +1,60 @@
+use ibc_core::primitives::prelude::*;
+
+use crate::sovereign::{Error, ValidityCondition};
+
+/// The validity condition emitted by the Celestia adapter of the Sovereign SDK
+/// for each DA block the rollup relied on, which links the hash of the block
+/// to the hash of its parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CelestiaValidityCondition {
+    pub prev_hash: [u8; 32],
+    pub block_hash: [u8; 32],
+}
+
+impl CelestiaValidityCondition {
+    /// The length of the encoded validity condition, which is the Borsh
+    /// encoding of its two hashes.
+    pub const ENCODED_LEN: usize = 64;
+
+    pub fn new(prev_hash: [u8; 32], block_hash: [u8; 32]) -> Self {
+        Self {
+            prev_hash,
+            block_hash,
+        }
+    }
+
+    /// Returns whether the validity condition refers to the block right after
+    /// the one of the given validity condition.
+    pub fn extends(&self, parent: &Self) -> bool {
+        self.prev_hash == parent.block_hash
+    }
+}
+
+impl TryFrom<&ValidityCondition> for CelestiaValidityCondition {
+    type Error = Error;
+
+    fn try_from(value: &ValidityCondition) -> Result<Self, Self::Error> {
+        let bytes = value.as_slice();
+
+        if bytes.len() != Self::ENCODED_LEN {
+            return Err(Error::invalid("Celestia validity condition").given(value));
+        }
+
+        let (prev_hash, block_hash) = bytes.split_at(32);
+
+        Ok(Self {
+            prev_hash: prev_hash.try_into().map_err(Error::source)?,
+            block_hash: block_hash.try_into().map_err(Error::source)?,
+        })
+    }
+}
+
+impl From<CelestiaValidityCondition> for ValidityCondition {
+    fn from(value: CelestiaValidityCondition) -> Self {
+        let mut bytes = value.prev_hash.to_vec();
+
+        bytes.extend(value.block_hash);
+
+        bytes.into()
+    }
+}
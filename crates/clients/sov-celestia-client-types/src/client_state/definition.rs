@@ -11,7 +11,7 @@ use tendermint_light_client_verifier::options::Options;
 
 use super::TendermintClientParams;
 use crate::proto::v1::ClientState as RawClientState;
-use crate::sovereign::{CodeCommitment, Error, Root, SovereignClientParams, UpgradePath};
+use crate::sovereign::{CodeCommitment, Error, Root, SovereignClientParams, UpgradePath, ZkVm};
 
 pub const SOV_TENDERMINT_CLIENT_STATE_TYPE_URL: &str =
     "/ibc.lightclients.sovereign.tendermint.v1.ClientState";
@@ -44,6 +44,10 @@ impl<Da> ClientState<Da> {
         &self.sovereign_params.code_commitment
     }
 
+    pub fn zk_vm(&self) -> ZkVm {
+        self.sovereign_params.zk_vm
+    }
+
     pub fn trusting_period(&self) -> Duration {
         self.sovereign_params.trusting_period
     }
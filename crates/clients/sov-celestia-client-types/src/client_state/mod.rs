@@ -107,7 +107,7 @@ pub mod test_util {
     }
 
     pub fn dummy_sov_consensus_state(timestamp: Timestamp) -> SovTmConsensusState {
-        let sovereign_params = SovereignConsensusParams::new(vec![0].into());
+        let sovereign_params = SovereignConsensusParams::new(vec![0; 32].into());
 
         let tendermint_params = TmConsensusParams::new(
             timestamp.into_tm_time().expect("Time exists"),
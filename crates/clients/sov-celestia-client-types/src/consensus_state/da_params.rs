@@ -12,6 +12,9 @@ use crate::sovereign::Error;
 pub struct TmConsensusParams {
     pub timestamp: Time,
     pub next_validators_hash: Hash,
+    /// The hash of the DA block, to which the aggregated proofs of the rollup
+    /// are anchored. Unknown for the consensus state stored upon an upgrade.
+    pub block_hash: Option<Hash>,
 }
 
 impl TmConsensusParams {
@@ -19,8 +22,15 @@ impl TmConsensusParams {
         Self {
             timestamp,
             next_validators_hash,
+            block_hash: None,
         }
     }
+
+    /// Records the hash of the DA block of the consensus state.
+    pub fn with_block_hash(mut self, block_hash: Hash) -> Self {
+        self.block_hash = Some(block_hash);
+        self
+    }
 }
 
 impl Protobuf<RawTmConsensusParams> for TmConsensusParams {}
@@ -40,7 +50,20 @@ impl TryFrom<RawTmConsensusParams> for TmConsensusParams {
         let next_validators_hash = Hash::from_bytes(Algorithm::Sha256, &raw.next_validators_hash)
             .map_err(|_| Error::invalid("invalid next validators hash"))?;
 
-        Ok(Self::new(timestamp, next_validators_hash))
+        let block_hash = if raw.block_hash.is_empty() {
+            None
+        } else {
+            Some(
+                Hash::from_bytes(Algorithm::Sha256, &raw.block_hash)
+                    .map_err(|_| Error::invalid("invalid block hash"))?,
+            )
+        };
+
+        Ok(Self {
+            timestamp,
+            next_validators_hash,
+            block_hash,
+        })
     }
 }
 
@@ -52,6 +75,10 @@ impl From<TmConsensusParams> for RawTmConsensusParams {
         Self {
             timestamp: Some(timestamp),
             next_validators_hash: value.next_validators_hash.as_bytes().to_vec(),
+            block_hash: value
+                .block_hash
+                .map(|hash| hash.as_bytes().to_vec())
+                .unwrap_or_default(),
         }
     }
 }
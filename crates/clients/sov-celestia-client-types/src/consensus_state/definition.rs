@@ -3,7 +3,8 @@
 use ibc_core::client::types::error::ClientError;
 use ibc_core::commitment_types::commitment::CommitmentRoot;
 use ibc_core::primitives::proto::{Any, Protobuf};
-use tendermint::Time;
+use tendermint::hash::Algorithm;
+use tendermint::{Hash, Time};
 
 use super::TmConsensusParams;
 use crate::client_message::SovTmHeader;
@@ -110,9 +111,18 @@ impl From<SovTmHeader> for SovTmConsensusState {
     fn from(header: SovTmHeader) -> Self {
         let tm_header = header.da_header.signed_header.header;
 
+        let mut da_params = TmConsensusParams::new(tm_header.time, tm_header.next_validators_hash);
+
+        // The final slot hash of a verified header is the hash of its DA block
+        if let Ok(block_hash @ Hash::Sha256(_)) =
+            Hash::from_bytes(Algorithm::Sha256, header.aggregated_proof.final_slot_hash())
+        {
+            da_params = da_params.with_block_hash(block_hash);
+        }
+
         Self::new(
             CommitmentRoot::from_bytes(header.aggregated_proof.final_state_root().as_ref()).into(),
-            TmConsensusParams::new(tm_header.time, tm_header.next_validators_hash),
+            da_params,
         )
     }
 }
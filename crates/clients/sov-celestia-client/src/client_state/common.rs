@@ -111,6 +111,10 @@ pub fn verify_upgrade_client(
     // Make sure that the consensus type is of Tendermint type `ConsensusState`
     ConsensusState::try_from(upgraded_consensus_state.clone())?;
 
+    // Make sure that the aggregated proofs of the upgraded rollup are
+    // verifiable in this build
+    upgraded_tm_client_state.inner().zk_vm().verifier()?;
+
     let latest_height = client_state.latest_height_in_sov();
 
     let upgraded_tm_client_state_height = upgraded_tm_client_state.latest_height();
@@ -102,6 +102,9 @@ where
     let host_timestamp = ExtClientValidationContext::host_timestamp(ctx)?;
     let host_height = ExtClientValidationContext::host_height(ctx)?;
 
+    // The aggregated proofs of the client must be verifiable in this build
+    client_state.zk_vm().verifier()?;
+
     let sov_consensus_state: SovTmConsensusState = consensus_state.try_into()?;
 
     let latest_height = client_state.latest_height_in_sov();
@@ -259,10 +262,14 @@ where
     // the block time and `next_validators_hash` of the last block committed by
     // the old chain. This will allow the first block of the new chain to be
     // verified against the last validators of the old chain so long as it is
-    // submitted within the DA `trusting_period` of this client.
+    // submitted within the DA `trusting_period` of this client. The validity
+    // conditions of the first header extend the hash of that block, so an
+    // upgraded consensus state not recording it leaves the client unable to
+    // update until it is recovered.
     let new_tm_consensus_params = TmConsensusParams {
         timestamp: upgraded_consensus_state.timestamp(),
         next_validators_hash: upgraded_consensus_state.next_validators_hash(),
+        block_hash: upgraded_consensus_state.inner().da_params.block_hash,
     };
 
     let new_consensus_state =
@@ -297,13 +304,18 @@ where
 /// Update the client's chain ID, trusting period, latest height, processed
 /// height, and processed time metadata values to those values provided by a
 /// verified substitute client state in response to a successful client
-/// recovery.
+/// recovery. The substitute consensus state is stored at the new latest
+/// height, so that the recovered client can be updated from there on.
+///
+/// As the client only sees the store of the subject, it records the host time
+/// and height of the recovery as the update metadata, which the hosts then
+/// replace with the processed time and height of the substitute.
 pub fn update_on_recovery<E>(
     subject_client_state: SovTmClientState,
     ctx: &mut E,
     subject_client_id: &ClientId,
     substitute_client_state: Any,
-    _substitute_consensus_state: Any,
+    substitute_consensus_state: Any,
 ) -> Result<(), ClientError>
 where
     E: ExtClientExecutionContext,
@@ -311,6 +323,7 @@ where
     SovTmConsensusState: Convertible<E::ConsensusStateRef>,
 {
     let substitute_client_state = ClientState::try_from(substitute_client_state)?.into_inner();
+    let substitute_consensus_state = SovTmConsensusState::try_from(substitute_consensus_state)?;
 
     let latest_height = substitute_client_state.latest_height_in_sov();
 
@@ -332,8 +345,14 @@ where
         new_client_state.into(),
     )?;
 
-    // TODO(rano): add consensus state update
-
+    ctx.store_consensus_state(
+        ClientConsensusStatePath::new(
+            subject_client_id.clone(),
+            latest_height.revision_number(),
+            latest_height.revision_height(),
+        ),
+        substitute_consensus_state.into(),
+    )?;
     ctx.store_update_meta(
         subject_client_id.clone(),
         latest_height,
@@ -1,17 +1,26 @@
 use ibc_client_tendermint::client_state::verify_misbehaviour_header;
 use ibc_core::client::context::{Convertible, ExtClientValidationContext};
 use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
 use ibc_core::host::types::identifiers::ClientId;
 use ibc_core::host::types::path::ClientConsensusStatePath;
-use sov_celestia_client_types::client_message::SovTmMisbehaviour;
+use sov_celestia_client_types::client_message::{
+    MisbehaviourEvidence, SovTmHeader, SovTmMisbehaviour,
+};
 use sov_celestia_client_types::client_state::SovTmClientState;
 use sov_celestia_client_types::consensus_state::SovTmConsensusState;
+use sov_celestia_client_types::sovereign::AggregatedProof;
 use tendermint::crypto::Sha256;
 use tendermint::merkle::MerkleHash;
 use tendermint_light_client_verifier::Verifier as TmVerifier;
 
-/// Determines whether two conflicting headers at the same height would
-/// have convinced the light client.
+use crate::client_state::{
+    trusted_da_block_hash, verify_aggregated_proof, verify_validity_conditions,
+};
+
+/// Determines whether the evidence of a misbehaviour would have convinced the
+/// light client, that is, either two conflicting headers at the same height or
+/// two aggregated proofs of a fork of the rollup.
 pub fn verify_misbehaviour<V, H>(
     ctx: &V,
     client_state: &SovTmClientState,
@@ -27,7 +36,145 @@ where
 {
     misbehaviour.validate_basic::<H>()?;
 
-    let header_1 = misbehaviour.header_1();
+    match misbehaviour.evidence() {
+        MisbehaviourEvidence::Headers { header_1, header_2 } => verify_da_misbehaviour::<V, H>(
+            ctx,
+            client_state,
+            header_1,
+            header_2,
+            client_id,
+            verifier,
+        ),
+        MisbehaviourEvidence::AggregatedProofs {
+            aggregated_proof_1,
+            aggregated_proof_2,
+        } => verify_rollup_fork(
+            ctx,
+            client_state,
+            aggregated_proof_1,
+            aggregated_proof_2,
+            client_id,
+        ),
+    }
+}
+
+/// Determines whether two aggregated proofs of a fork of the rollup would have
+/// convinced the light client. Both proofs must end at the DA block of a
+/// consensus state the client already stored, and start from another stored
+/// consensus state, so that they only differ in how the rollup processed the
+/// DA blocks the client vouches for.
+fn verify_rollup_fork<V>(
+    ctx: &V,
+    client_state: &SovTmClientState,
+    aggregated_proof_1: &AggregatedProof,
+    aggregated_proof_2: &AggregatedProof,
+    client_id: &ClientId,
+) -> Result<(), ClientError>
+where
+    V: ExtClientValidationContext,
+    SovTmConsensusState: Convertible<V::ConsensusStateRef>,
+    ClientError: From<<SovTmConsensusState as TryFrom<V::ConsensusStateRef>>::Error>,
+{
+    if aggregated_proof_1.final_slot_hash() != aggregated_proof_2.final_slot_hash() {
+        return Err(ClientError::Other {
+            description: "aggregated proofs must end at the same final slot hash".to_string(),
+        });
+    }
+
+    let revision_number = client_state.latest_height_in_sov().revision_number();
+
+    let consensus_state_at = |slot_number: u64| -> Result<SovTmConsensusState, ClientError> {
+        let consensus_state_path =
+            ClientConsensusStatePath::new(client_id.clone(), revision_number, slot_number);
+
+        SovTmConsensusState::try_from(ctx.consensus_state(&consensus_state_path)?)
+            .map_err(ClientError::from)
+    };
+
+    let final_slot_number = aggregated_proof_1.final_slot_number();
+
+    let Some(da_block_hash) = consensus_state_at(final_slot_number)?.da_params.block_hash else {
+        return Err(ClientError::Other {
+            description: format!(
+                "consensus state at the final slot number {final_slot_number} does not record \
+                its DA block hash"
+            ),
+        });
+    };
+
+    if aggregated_proof_1.final_slot_hash() != da_block_hash.as_bytes() {
+        return Err(ClientError::Other {
+            description: format!(
+                "final slot hash of the aggregated proofs does not match the DA block hash \
+                {da_block_hash} of the consensus state at the final slot number \
+                {final_slot_number}"
+            ),
+        });
+    }
+
+    for aggregated_proof in [aggregated_proof_1, aggregated_proof_2] {
+        let initial_slot_number = aggregated_proof.initial_slot_number();
+
+        let trusted_slot_number = initial_slot_number
+            .checked_sub(1)
+            .ok_or(ClientError::Other {
+                description: "initial slot number of the aggregated proof must be positive"
+                    .to_string(),
+            })?;
+
+        let trusted_consensus_state = consensus_state_at(trusted_slot_number)?;
+
+        if trusted_consensus_state.sovereign_params.as_bytes()
+            != aggregated_proof.initial_state_root().as_ref()
+        {
+            return Err(ClientError::Other {
+                description: format!(
+                    "initial state root of the aggregated proof does not match the root of \
+                    the consensus state at slot number {trusted_slot_number}"
+                ),
+            });
+        }
+
+        let trusted_da_block_hash = trusted_da_block_hash(
+            &trusted_consensus_state,
+            Height::new(revision_number, trusted_slot_number)?,
+        )?;
+
+        verify_validity_conditions(
+            aggregated_proof,
+            trusted_da_block_hash,
+            &da_block_hash,
+            final_slot_number.saturating_sub(trusted_slot_number),
+        )?;
+
+        verify_aggregated_proof(
+            ctx,
+            client_state.genesis_state_root(),
+            client_state.code_commitment(),
+            client_state.zk_vm(),
+            aggregated_proof,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Determines whether two conflicting DA headers at the same height would have
+/// convinced the light client.
+fn verify_da_misbehaviour<V, H>(
+    ctx: &V,
+    client_state: &SovTmClientState,
+    header_1: &SovTmHeader,
+    header_2: &SovTmHeader,
+    client_id: &ClientId,
+    verifier: &impl TmVerifier,
+) -> Result<(), ClientError>
+where
+    V: ExtClientValidationContext,
+    SovTmConsensusState: Convertible<V::ConsensusStateRef>,
+    ClientError: From<<SovTmConsensusState as TryFrom<V::ConsensusStateRef>>::Error>,
+    H: MerkleHash + Sha256 + Default,
+{
     let trusted_consensus_state_1 = {
         let consensus_state_path = ClientConsensusStatePath::new(
             client_id.clone(),
@@ -39,7 +186,6 @@ where
         SovTmConsensusState::try_from(consensus_state)?
     };
 
-    let header_2 = misbehaviour.header_2();
     let trusted_consensus_state_2 = {
         let consensus_state_path = ClientConsensusStatePath::new(
             client_id.clone(),
@@ -73,8 +219,5 @@ where
         verifier,
     )?;
 
-    // TODO: Determine what sort of checks we need to carry out for detecting
-    // `AggregatedProof` misbehaviour.
-
     Ok(())
 }
@@ -5,12 +5,13 @@ use ibc_core::client::types::error::ClientError;
 use ibc_core::client::types::Height;
 use ibc_core::host::types::identifiers::ClientId;
 use ibc_core::host::types::path::ClientConsensusStatePath;
-use sov_celestia_client_types::client_message::SovTmHeader;
+use sov_celestia_client_types::client_message::{CelestiaValidityCondition, SovTmHeader};
 use sov_celestia_client_types::client_state::SovTmClientState;
 use sov_celestia_client_types::consensus_state::SovTmConsensusState;
-use sov_celestia_client_types::sovereign::{AggregatedProof, CodeCommitment, Root};
+use sov_celestia_client_types::sovereign::{AggregatedProof, CodeCommitment, Root, ZkVm};
 use tendermint::crypto::Sha256;
 use tendermint::merkle::MerkleHash;
+use tendermint::Hash;
 use tendermint_light_client_verifier::types::{TrustedBlockState, UntrustedBlockState};
 use tendermint_light_client_verifier::Verifier as TmVerifier;
 
@@ -39,10 +40,15 @@ where
 
     verify_da_header::<V, H>(ctx, client_state, &header.da_header, client_id, verifier)?;
 
+    verify_aggregated_proof_continuity::<V, H>(ctx, client_state, header, client_id)?;
+
+    verify_header_validity_conditions::<V, H>(ctx, client_state, header, client_id)?;
+
     verify_aggregated_proof(
         ctx,
         client_state.genesis_state_root(),
         client_state.code_commitment(),
+        client_state.zk_vm(),
         &header.aggregated_proof,
     )?;
 
@@ -136,10 +142,224 @@ where
     Ok(())
 }
 
+/// Verifies that the aggregated proof of the header continues from the
+/// trusted consensus state, starting at the slot right after the trusted
+/// height from its state root, and ends at the DA block of the header, so that
+/// consecutive updates chain up without gaps. Its final slot number is matched
+/// against the height of the DA header by
+/// [`SovTmHeader::validate_da_height_offset`].
+pub fn verify_aggregated_proof_continuity<V, H>(
+    ctx: &V,
+    client_state: &SovTmClientState,
+    header: &SovTmHeader,
+    client_id: &ClientId,
+) -> Result<(), ClientError>
+where
+    V: ExtClientValidationContext,
+    SovTmConsensusState: Convertible<V::ConsensusStateRef>,
+    ClientError: From<<SovTmConsensusState as TryFrom<V::ConsensusStateRef>>::Error>,
+    H: MerkleHash + Sha256 + Default,
+{
+    let trusted_height = client_state.latest_height_in_sov();
+
+    let trusted_client_cons_state_path = ClientConsensusStatePath::new(
+        client_id.clone(),
+        trusted_height.revision_number(),
+        trusted_height.revision_height(),
+    );
+
+    let trusted_consensus_state =
+        SovTmConsensusState::try_from(ctx.consensus_state(&trusted_client_cons_state_path)?)?;
+
+    let initial_slot_number = header.aggregated_proof.initial_slot_number();
+
+    if initial_slot_number != trusted_height.revision_height() + 1 {
+        return Err(ClientError::Other {
+            description: format!(
+                "initial slot number {initial_slot_number} of the aggregated proof does not \
+                follow the trusted height {trusted_height}",
+            ),
+        });
+    }
+
+    let initial_state_root = header.aggregated_proof.initial_state_root();
+
+    if trusted_consensus_state.sovereign_params.as_bytes() != initial_state_root.as_ref() {
+        return Err(ClientError::Other {
+            description: format!(
+                "initial state root of the aggregated proof does not match the root of \
+                the trusted consensus state at height {trusted_height}",
+            ),
+        });
+    }
+
+    let da_block_hash = header.da_header.signed_header.header().hash_with::<H>();
+
+    if header.aggregated_proof.final_slot_hash() != da_block_hash.as_bytes() {
+        return Err(ClientError::Other {
+            description: format!(
+                "final slot hash of the aggregated proof does not match the DA block hash \
+                {da_block_hash}",
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+/// Verifies the validity conditions of the aggregated proof of the header
+/// against the DA blocks the client vouches for, that is, from the block of
+/// the trusted consensus state up to the verified DA header.
+pub fn verify_header_validity_conditions<V, H>(
+    ctx: &V,
+    client_state: &SovTmClientState,
+    header: &SovTmHeader,
+    client_id: &ClientId,
+) -> Result<(), ClientError>
+where
+    V: ExtClientValidationContext,
+    SovTmConsensusState: Convertible<V::ConsensusStateRef>,
+    ClientError: From<<SovTmConsensusState as TryFrom<V::ConsensusStateRef>>::Error>,
+    H: MerkleHash + Sha256 + Default,
+{
+    let trusted_height = client_state.latest_height_in_sov();
+
+    let trusted_client_cons_state_path = ClientConsensusStatePath::new(
+        client_id.clone(),
+        trusted_height.revision_number(),
+        trusted_height.revision_height(),
+    );
+
+    let trusted_consensus_state =
+        SovTmConsensusState::try_from(ctx.consensus_state(&trusted_client_cons_state_path)?)?;
+
+    let da_height = header.da_header.height().revision_height();
+
+    let trusted_da_height = header.trusted_height().revision_height();
+
+    let da_block_hash = header.da_header.signed_header.header().hash_with::<H>();
+
+    let trusted_da_block_hash = trusted_da_block_hash(&trusted_consensus_state, trusted_height)?;
+
+    let last_validity_condition = verify_validity_conditions(
+        &header.aggregated_proof,
+        trusted_da_block_hash,
+        &da_block_hash,
+        da_height.saturating_sub(trusted_da_height),
+    )?;
+
+    if let Some(last_block_id) = &header.da_header.signed_header.header().last_block_id {
+        if last_validity_condition.prev_hash != last_block_id.hash.as_bytes() {
+            return Err(ClientError::Other {
+                description: format!(
+                    "last validity condition does not refer to the parent DA block hash {}",
+                    last_block_id.hash
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the hash of the DA block recorded by the trusted consensus state at
+/// the given height, which the validity conditions of aggregated proofs must
+/// extend. Consensus states not recording it, such as the ones of upgraded
+/// clients, cannot be trusted to verify aggregated proofs.
+pub fn trusted_da_block_hash(
+    trusted_consensus_state: &SovTmConsensusState,
+    trusted_height: Height,
+) -> Result<&Hash, ClientError> {
+    trusted_consensus_state
+        .da_params
+        .block_hash
+        .as_ref()
+        .ok_or(ClientError::Other {
+            description: format!(
+                "trusted consensus state at height {trusted_height} does not record its DA \
+                block hash"
+            ),
+        })
+}
+
+/// Verifies the validity conditions of the aggregated proof, which the
+/// Celestia adapter emits for each DA block the rollup relied on. They must
+/// form a chain of blocks extending the trusted DA block and ending at the
+/// given DA block. They may not refer to more
+/// blocks than the given number of verified ones, as the blocks in between
+/// are the only ones the client vouches for. Returns the last validity
+/// condition.
+pub fn verify_validity_conditions(
+    aggregated_proof: &AggregatedProof,
+    trusted_da_block_hash: &Hash,
+    da_block_hash: &Hash,
+    verified_blocks: u64,
+) -> Result<CelestiaValidityCondition, ClientError> {
+    let validity_conditions = aggregated_proof
+        .public_data()
+        .validity_conditions
+        .iter()
+        .map(CelestiaValidityCondition::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if validity_conditions.len() as u64 > verified_blocks {
+        return Err(ClientError::Other {
+            description: format!(
+                "validity conditions refer to {} DA blocks, but only {verified_blocks} \
+                blocks are verified",
+                validity_conditions.len(),
+            ),
+        });
+    }
+
+    if let Some(pos) = validity_conditions
+        .windows(2)
+        .position(|pair| !pair[1].extends(&pair[0]))
+    {
+        return Err(ClientError::Other {
+            description: format!(
+                "validity condition at index {} does not extend the previous DA block",
+                pos + 1
+            ),
+        });
+    }
+
+    let (Some(first_validity_condition), Some(last_validity_condition)) =
+        (validity_conditions.first(), validity_conditions.last())
+    else {
+        return Err(ClientError::Other {
+            description: "aggregated proof has no validity conditions".to_string(),
+        });
+    };
+
+    if first_validity_condition.prev_hash != trusted_da_block_hash.as_bytes() {
+        return Err(ClientError::Other {
+            description: format!(
+                "first validity condition does not extend the trusted DA block hash \
+                {trusted_da_block_hash}",
+            ),
+        });
+    }
+
+    if last_validity_condition.block_hash != da_block_hash.as_bytes() {
+        return Err(ClientError::Other {
+            description: format!(
+                "last validity condition does not refer to the DA block hash {da_block_hash}",
+            ),
+        });
+    }
+
+    Ok(*last_validity_condition)
+}
+
+/// Verifies the aggregated proof against the genesis state root and the code
+/// commitment tracked by the client, using the verifier of the zkVM the client
+/// state selects.
 pub fn verify_aggregated_proof<V>(
     _ctx: &V,
     genesis_state_root: &Root,
     code_commitment: &CodeCommitment,
+    zk_vm: ZkVm,
     aggregated_proof: &AggregatedProof,
 ) -> Result<(), ClientError>
 where
@@ -158,8 +378,12 @@ where
         });
     }
 
-    // TODO: Implement the verification of the `AggregatedProof`.
-    // aggregated_proof.verify()?;
+    zk_vm
+        .verifier()?
+        .verify(code_commitment, aggregated_proof)
+        .map_err(|e| ClientError::Other {
+            description: format!("failed to verify aggregated proof: {e}"),
+        })?;
 
     Ok(())
 }
@@ -8,7 +8,7 @@ use ibc_core::host::types::path::ClientConsensusStatePath;
 use ibc_core::primitives::prelude::*;
 use ibc_core::primitives::proto::Any;
 use sov_celestia_client_types::client_message::{
-    SovTmHeader, SovTmMisbehaviour, SOV_TENDERMINT_HEADER_TYPE_URL,
+    MisbehaviourEvidence, SovTmHeader, SovTmMisbehaviour, SOV_TENDERMINT_HEADER_TYPE_URL,
     SOV_TENDERMINT_MISBEHAVIOUR_TYPE_URL,
 };
 use sov_celestia_client_types::client_state::SovTmClientState;
@@ -113,13 +113,21 @@ where
         }
         SOV_TENDERMINT_MISBEHAVIOUR_TYPE_URL => {
             let misbehaviour = SovTmMisbehaviour::try_from(client_message)?;
-            check_for_misbehaviour_on_misbehavior(
-                &misbehaviour.header_1().da_header,
-                &misbehaviour.header_2().da_header,
-            )
 
-            // TODO: Determine if we need any sort of misbehaviour check for the
-            // rollup (aggregated proof) part.
+            match misbehaviour.evidence() {
+                MisbehaviourEvidence::Headers { header_1, header_2 } => {
+                    check_for_misbehaviour_on_misbehavior(&header_1.da_header, &header_2.da_header)
+                }
+                // Both proofs ending at the same verified DA block is checked
+                // upon verification, so the rollup forked if their state roots
+                // differ.
+                MisbehaviourEvidence::AggregatedProofs {
+                    aggregated_proof_1,
+                    aggregated_proof_2,
+                } => Ok(!aggregated_proof_1
+                    .final_state_root()
+                    .matches(aggregated_proof_2.final_state_root())),
+            }
         }
         _ => Err(ClientError::InvalidUpdateClientMessage),
     }
@@ -71,10 +71,18 @@ impl AggregatedProof {
         &self.public_data.genesis_state_root
     }
 
+    pub fn initial_state_root(&self) -> &Root {
+        &self.public_data.initial_state_root
+    }
+
     pub fn final_state_root(&self) -> &Root {
         &self.public_data.final_state_root
     }
 
+    pub fn final_slot_hash(&self) -> &[u8] {
+        &self.public_data.final_slot_hash
+    }
+
     pub fn code_commitment(&self) -> &CodeCommitment {
         &self.public_data.code_commitment
     }
@@ -511,6 +519,7 @@ impl From<Root> for Vec<u8> {
 #[cfg(feature = "test-util")]
 pub mod test_util {
     use super::*;
+    use crate::verifier::MockVerifier;
 
     // -------------------------------------------------------------------------
     // NOTE: Vectors default to 32-byte arrays as empty vectors aren't valid.
@@ -520,15 +529,22 @@ pub mod test_util {
     #[builder(build_method(into = AggregatedProof))]
     pub struct AggregatedProofConfig {
         pub public_data: PublicDataConfig,
-        #[builder(default = vec![0; 32].into())]
-        pub serialized_proof: SerializedAggregatedProof,
+        /// Defaults to the proof accepted by the [`MockVerifier`].
+        #[builder(default, setter(strip_option))]
+        pub serialized_proof: Option<SerializedAggregatedProof>,
     }
 
     impl From<AggregatedProofConfig> for AggregatedProof {
         fn from(config: AggregatedProofConfig) -> Self {
+            let public_data = config.public_data.into();
+
+            let serialized_proof = config
+                .serialized_proof
+                .unwrap_or_else(|| MockVerifier::prove(&public_data));
+
             Self {
-                public_data: config.public_data.into(),
-                serialized_proof: config.serialized_proof,
+                public_data,
+                serialized_proof,
             }
         }
     }
@@ -9,6 +9,7 @@ use ibc_core::primitives::proto::Protobuf;
 use crate::aggregated_proof::{CodeCommitment, Root};
 use crate::error::Error;
 use crate::proto::SovereignClientParams as RawSovereignClientParams;
+use crate::verifier::ZkVm;
 
 /// Defines the Sovereign SDK rollup-specific client parameters.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -46,6 +47,9 @@ pub struct SovereignClientParams {
     /// The upgrade path is the path to the location on rollup where the
     /// upgraded client and consensus states are stored.
     pub upgrade_path: UpgradePath,
+    /// The zkVM the aggregated proofs of the rollup are generated with, which
+    /// selects the verifier of the aggregated proofs on client updates.
+    pub zk_vm: ZkVm,
 }
 
 impl SovereignClientParams {
@@ -57,6 +61,7 @@ impl SovereignClientParams {
         frozen_height: Option<Height>,
         latest_height: Height,
         upgrade_path: UpgradePath,
+        zk_vm: ZkVm,
     ) -> Self {
         Self {
             genesis_da_height,
@@ -66,6 +71,7 @@ impl SovereignClientParams {
             frozen_height,
             latest_height,
             upgrade_path,
+            zk_vm,
         }
     }
 
@@ -76,6 +82,7 @@ impl SovereignClientParams {
             && self.genesis_state_root == substitute.genesis_state_root
             && self.code_commitment == substitute.code_commitment
             && self.upgrade_path == substitute.upgrade_path
+            && self.zk_vm == substitute.zk_vm
     }
 
     /// Updates the `SovereignClientParams` on the client recovery process with
@@ -98,6 +105,7 @@ impl SovereignClientParams {
             latest_height: upgraded.latest_height,
             frozen_height: None,
             upgrade_path: upgraded.upgrade_path,
+            zk_vm: upgraded.zk_vm,
             ..self
         }
     }
@@ -136,6 +144,13 @@ impl TryFrom<RawSovereignClientParams> for SovereignClientParams {
 
         let upgrade_path = raw.upgrade_path.try_into()?;
 
+        // Client states encoded before the zkVM was recorded leave it empty
+        let zk_vm = if raw.zk_vm.is_empty() {
+            ZkVm::default()
+        } else {
+            raw.zk_vm.try_into()?
+        };
+
         Ok(Self::new(
             genesis_da_height,
             genesis_state_root,
@@ -144,6 +159,7 @@ impl TryFrom<RawSovereignClientParams> for SovereignClientParams {
             frozen_height,
             latest_height,
             upgrade_path,
+            zk_vm,
         ))
     }
 }
@@ -158,6 +174,7 @@ impl From<SovereignClientParams> for RawSovereignClientParams {
             frozen_height: value.frozen_height.map(Into::into),
             latest_height: Some(value.latest_height.into()),
             upgrade_path: value.upgrade_path.0,
+            zk_vm: value.zk_vm.into(),
         }
     }
 }
@@ -235,6 +252,8 @@ pub mod test_util {
         pub latest_height: Height,
         #[builder(default)]
         pub upgrade_path: UpgradePath,
+        #[builder(default = ZkVm::Mock)]
+        pub zk_vm: ZkVm,
     }
 
     impl From<SovereignParamsConfig> for SovereignClientParams {
@@ -247,6 +266,7 @@ pub mod test_util {
                 config.frozen_height,
                 config.latest_height,
                 config.upgrade_path,
+                config.zk_vm,
             )
         }
     }
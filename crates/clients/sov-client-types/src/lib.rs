@@ -2,6 +2,7 @@ mod aggregated_proof;
 mod client_params;
 mod consensus_params;
 mod error;
+mod verifier;
 
 #[cfg(feature = "test-util")]
 pub use aggregated_proof::test_util::*;
@@ -14,6 +15,7 @@ pub use client_params::test_util::*;
 pub use client_params::{SovereignClientParams, UpgradePath};
 pub use consensus_params::SovereignConsensusParams;
 pub use error::*;
+pub use verifier::*;
 
 /// Re-exports Sovereign SDK light clients proto types from `sov-ibc-proto`
 /// crate.
@@ -0,0 +1,46 @@
+use ibc_core::primitives::prelude::*;
+use ibc_core::primitives::proto::Protobuf;
+
+use super::AggregatedProofVerifier;
+use crate::aggregated_proof::{
+    AggregatedProof, AggregatedProofPublicData, CodeCommitment, SerializedAggregatedProof,
+};
+use crate::error::Error;
+
+/// A deterministic verifier for testing, which accepts the proofs consisting
+/// of the code commitment followed by the Protobuf-encoded public data, as
+/// produced by [`MockVerifier::prove`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MockVerifier;
+
+impl MockVerifier {
+    /// Produces the mock proof of the given public data, generated by the
+    /// circuit of the code commitment the public data carries.
+    pub fn prove(public_data: &AggregatedProofPublicData) -> SerializedAggregatedProof {
+        let mut proof = public_data.code_commitment.as_slice().to_vec();
+
+        proof.extend(public_data.clone().encode_vec());
+
+        proof.into()
+    }
+}
+
+impl AggregatedProofVerifier for MockVerifier {
+    fn verify(
+        &self,
+        code_commitment: &CodeCommitment,
+        aggregated_proof: &AggregatedProof,
+    ) -> Result<(), Error> {
+        let proof = aggregated_proof.serialized_proof().as_slice();
+
+        let Some(public_data) = proof.strip_prefix(code_commitment.as_slice()) else {
+            return Err(Error::mismatch("mock proof code commitment"));
+        };
+
+        if public_data != aggregated_proof.public_data().clone().encode_vec() {
+            return Err(Error::mismatch("mock proof public data"));
+        }
+
+        Ok(())
+    }
+}
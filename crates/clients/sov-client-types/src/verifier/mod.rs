@@ -0,0 +1,105 @@
+//! Defines the verification of the aggregated proofs, which is delegated to a
+//! verifier selected by the zkVM the rollup proves its state transitions with.
+//!
+//! Each verifier checks that the serialized proof was generated by the circuit
+//! of the code commitment tracked by the client, and that it commits to the
+//! Protobuf-encoded public data carried alongside it.
+
+#[cfg(any(test, feature = "test-util", feature = "mock-verifier"))]
+mod mock;
+#[cfg(feature = "risc0")]
+mod risc0;
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use ibc_core::primitives::prelude::*;
+#[cfg(any(test, feature = "test-util", feature = "mock-verifier"))]
+pub use mock::MockVerifier;
+#[cfg(feature = "risc0")]
+pub use risc0::Risc0Verifier;
+
+use crate::aggregated_proof::{AggregatedProof, CodeCommitment};
+use crate::error::Error;
+
+/// Verifies the aggregated proofs generated with a given zkVM.
+pub trait AggregatedProofVerifier {
+    /// Verifies that the aggregated proof was generated by the circuit of the
+    /// given code commitment, and that it commits to its public data.
+    fn verify(
+        &self,
+        code_commitment: &CodeCommitment,
+        aggregated_proof: &AggregatedProof,
+    ) -> Result<(), Error>;
+}
+
+/// Identifies the zkVM the aggregated proofs of a rollup are generated with,
+/// which selects the verifier used on client updates. Defaults to RISC Zero,
+/// which the clients created before the zkVM was recorded are proven with.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZkVm {
+    /// The RISC Zero zkVM, whose proofs are serialized receipts.
+    #[default]
+    Risc0,
+    /// A deterministic mock zkVM, only meant for testing. Its proofs are only
+    /// accepted by builds enabling the `mock-verifier` feature.
+    Mock,
+}
+
+impl ZkVm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Risc0 => "risc0",
+            Self::Mock => "mock",
+        }
+    }
+
+    /// Returns the verifier of the aggregated proofs generated with the zkVM,
+    /// provided it is enabled in this build. Clients are only created with,
+    /// or upgraded to, a zkVM whose verifier is enabled.
+    pub fn verifier(&self) -> Result<&'static dyn AggregatedProofVerifier, Error> {
+        match self {
+            #[cfg(feature = "risc0")]
+            Self::Risc0 => Ok(&Risc0Verifier),
+            #[cfg(any(test, feature = "mock-verifier"))]
+            Self::Mock => Ok(&MockVerifier),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::not_supported("aggregated proof verifier").given(self)),
+        }
+    }
+}
+
+impl Display for ZkVm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ZkVm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "risc0" => Ok(Self::Risc0),
+            "mock" => Ok(Self::Mock),
+            "" => Err(Error::empty("zk_vm")),
+            _ => Err(Error::invalid("zk_vm").given(&s)),
+        }
+    }
+}
+
+impl TryFrom<String> for ZkVm {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_str(&value)
+    }
+}
+
+impl From<ZkVm> for String {
+    fn from(value: ZkVm) -> Self {
+        value.as_str().to_string()
+    }
+}
@@ -0,0 +1,37 @@
+use ibc_core::primitives::prelude::*;
+use ibc_core::primitives::proto::Protobuf;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::Receipt;
+
+use super::AggregatedProofVerifier;
+use crate::aggregated_proof::{AggregatedProof, CodeCommitment};
+use crate::error::Error;
+
+/// Verifies the aggregated proofs generated with the RISC Zero zkVM, which are
+/// `bincode`-serialized receipts of the aggregation circuit. The code
+/// commitment is the image ID of the circuit, and the journal of the receipt
+/// is the Protobuf-encoded public data of the proof.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Risc0Verifier;
+
+impl AggregatedProofVerifier for Risc0Verifier {
+    fn verify(
+        &self,
+        code_commitment: &CodeCommitment,
+        aggregated_proof: &AggregatedProof,
+    ) -> Result<(), Error> {
+        let image_id = Digest::try_from(code_commitment.as_slice())
+            .map_err(|_| Error::invalid("RISC Zero image ID").given(code_commitment))?;
+
+        let receipt: Receipt = bincode::deserialize(aggregated_proof.serialized_proof().as_slice())
+            .map_err(Error::source)?;
+
+        receipt.verify(image_id).map_err(Error::source)?;
+
+        if receipt.journal.bytes != aggregated_proof.public_data().clone().encode_vec() {
+            return Err(Error::mismatch("RISC Zero receipt journal"));
+        }
+
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use sov_celestia_client::consensus_state::ConsensusState as HostConsensusState;
 use sov_celestia_client::types::consensus_state::{
     ConsensusState as SovConsensusState, TmConsensusParams,
 };
+use sov_rollup_interface::da::BlockHeaderTrait;
 
 use crate::HasConsensusState;
 
@@ -25,7 +26,8 @@ impl HasConsensusState for CelestiaSpec {
             .expect("Could not obtain timestamp from header"),
             tendermint::Hash::decode_vec(&header.header.next_validators_hash)
                 .expect("Could not decode next validator hash from header"),
-        );
+        )
+        .with_block_hash(tendermint::Hash::Sha256(header.hash().into()));
 
         SovConsensusState {
             sovereign_params,
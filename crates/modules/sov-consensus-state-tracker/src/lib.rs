@@ -130,23 +130,35 @@ where
 
         let visible_slot_number = kernel_working_set.current_slot();
 
+        let visible_hash = S::VisibleHash::from(pre_state_root.clone());
+
+        // The first slot starts from the genesis state root, which the rollup
+        // client parameters may be configured without.
+        self.ibc
+            .record_genesis_state_root(visible_hash.clone().into(), kernel_working_set.inner);
+
         // Workaround the fact that zero is not a valid height (No DA block produced and processed yet)
         if visible_slot_number > 0 {
             let height =
                 Height::new(HOST_REVISION_NUMBER, visible_slot_number).expect("valid height");
 
+            let consensus_state = Da::consensus_state(slot_header, visible_hash.into());
+
+            let timestamp = consensus_state.timestamp().into();
+
+            // Observes the block time of the DA layer, against which the
+            // delays of connections are enforced, before the host height and
+            // timestamp get updated.
+            self.ibc
+                .track_block_time(&height, &timestamp, kernel_working_set.inner);
+
             self.ibc
                 .host_height_map
                 .set(&height, kernel_working_set.inner);
 
-            let visible_hash = S::VisibleHash::from(pre_state_root.clone());
-
-            let consensus_state = Da::consensus_state(slot_header, visible_hash.into());
-
-            self.ibc.host_timestamp_map.set(
-                &consensus_state.timestamp().into(),
-                kernel_working_set.inner,
-            );
+            self.ibc
+                .host_timestamp_map
+                .set(&timestamp, kernel_working_set.inner);
 
             self.ibc.host_consensus_state_map.set(
                 &height,
@@ -154,6 +166,16 @@ where
                 kernel_working_set.inner,
             );
 
+            // Prunes the host consensus states that fell out of the
+            // retention window, if any.
+            self.ibc
+                .prune_host_consensus_states(&height, &timestamp, kernel_working_set.inner);
+
+            // Writes the upgraded client and consensus states once the slot
+            // before a scheduled upgrade height arrives.
+            self.ibc
+                .apply_upgrade_plan(&height, &consensus_state, kernel_working_set.inner);
+
             let da_height = slot_header.height();
 
             info!("Host ConsensusState is stored at rollup {height} and DA {da_height}: {consensus_state:?}");
@@ -0,0 +1,37 @@
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use thiserror::Error;
+
+/// Errors raised by the fee middleware.
+#[derive(Debug, Error)]
+pub enum FeeError {
+    #[error("fee is not enabled on port {port_id} and channel {channel_id}")]
+    FeeNotEnabled { port_id: String, channel_id: String },
+    #[error("invalid fee version: {0}")]
+    InvalidVersion(String),
+    #[error("invalid fee: {0}")]
+    InvalidFee(String),
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("invalid acknowledgement: {0}")]
+    InvalidAcknowledgement(String),
+    #[error("failed to escrow fee: {0}")]
+    EscrowFailed(String),
+    #[error("failed to distribute fee: {0}")]
+    DistributionFailed(String),
+}
+
+impl From<FeeError> for ChannelError {
+    fn from(e: FeeError) -> Self {
+        ChannelError::AppModule {
+            description: e.to_string(),
+        }
+    }
+}
+
+impl From<FeeError> for PacketError {
+    fn from(e: FeeError) -> Self {
+        PacketError::AppModule {
+            description: e.to_string(),
+        }
+    }
+}
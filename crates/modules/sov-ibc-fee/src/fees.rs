@@ -0,0 +1,450 @@
+//! Defines the escrow and the distribution of the packet fees, which are held
+//! by the `IbcFee` module account until the packets are acknowledged, timed
+//! out or their channel closes.
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use sov_bank::{Coins, IntoPayable};
+use sov_modules_api::{Spec, TxState};
+
+use crate::error::FeeError;
+use crate::types::Fee;
+use crate::IbcFee;
+
+impl<S: Spec> IbcFee<S> {
+    /// Returns whether the given channel negotiated the fee version.
+    pub fn is_fee_enabled(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        working_set: &mut impl TxState<S>,
+    ) -> bool {
+        self.fee_enabled_channels
+            .get(&(port_id.clone(), channel_id.clone()), working_set)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn enable_fee(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        working_set: &mut impl TxState<S>,
+    ) {
+        self.fee_enabled_channels
+            .set(&(port_id.clone(), channel_id.clone()), &true, working_set);
+    }
+
+    /// Escrows the given fee, paid by the refund address, for the given
+    /// packet. A packet can be incentivized several times, by different
+    /// payers, in which case the fees add up.
+    pub fn escrow_packet_fee(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        fee: Fee,
+        refund_address: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), FeeError> {
+        if !self.is_fee_enabled(port_id, channel_id, working_set) {
+            return Err(FeeError::FeeNotEnabled {
+                port_id: port_id.to_string(),
+                channel_id: channel_id.to_string(),
+            });
+        }
+
+        fee.validate()?;
+
+        for (token_id, amount) in fee.total()?.into_values() {
+            if amount == 0 {
+                continue;
+            }
+
+            self.bank
+                .transfer_from(
+                    refund_address,
+                    self.id.to_payable(),
+                    Coins { amount, token_id },
+                    working_set,
+                )
+                .map_err(|e| FeeError::EscrowFailed(e.to_string()))?;
+        }
+
+        let key = (port_id.clone(), channel_id.clone(), sequence);
+
+        let mut packet_fees = self.packet_fees.get(&key, working_set).unwrap_or_default();
+
+        if packet_fees.is_empty() {
+            self.index_incentivized_packet(port_id, channel_id, sequence, working_set);
+        }
+
+        packet_fees.push((fee, refund_address.clone()));
+
+        self.packet_fees.set(&key, &packet_fees, working_set);
+
+        Ok(())
+    }
+
+    /// Registers the address the acknowledgement and timeout fees of the given
+    /// relayer are paid to on the given channel.
+    pub fn register_payee(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        relayer: &S::Address,
+        payee: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let key = (port_id.clone(), channel_id.clone(), relayer.clone());
+
+        if self.payees.get(&key, working_set).is_none() {
+            self.payee_keys_vec.push(&key, working_set);
+        }
+
+        self.payees.set(&key, payee, working_set);
+    }
+
+    /// Registers the counterparty address the receive fees of the given
+    /// relayer are paid to on the given channel.
+    pub fn register_counterparty_payee(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        relayer: &S::Address,
+        counterparty_payee: &str,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), FeeError> {
+        if counterparty_payee.trim().is_empty() {
+            return Err(FeeError::InvalidAddress(
+                "counterparty payee cannot be empty".to_string(),
+            ));
+        }
+
+        let key = (port_id.clone(), channel_id.clone(), relayer.clone());
+
+        if self.counterparty_payees.get(&key, working_set).is_none() {
+            self.counterparty_payee_keys_vec.push(&key, working_set);
+        }
+
+        self.counterparty_payees
+            .set(&key, &counterparty_payee.to_string(), working_set);
+
+        Ok(())
+    }
+
+    /// Returns the counterparty address the receive fees of the given relayer
+    /// are paid to, if it registered one.
+    pub fn counterparty_payee(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        relayer: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Option<String> {
+        self.counterparty_payees.get(
+            &(port_id.clone(), channel_id.clone(), relayer.clone()),
+            working_set,
+        )
+    }
+
+    /// Pays out the fees of an acknowledged packet: the receive fee goes to the
+    /// forward relayer carried in the acknowledgement, the acknowledgement fee
+    /// to the payee of the relayer of the acknowledgement, and the timeout fee
+    /// is refunded. Any fee that cannot be paid out is refunded.
+    pub fn distribute_fees_on_acknowledgement(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        forward_relayer: &str,
+        relayer: Option<&S::Address>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), FeeError> {
+        let forward_relayer = forward_relayer.parse::<S::Address>().ok();
+
+        let payee = relayer.map(|relayer| self.payee(port_id, channel_id, relayer, working_set));
+
+        for (fee, refund_address) in
+            self.take_packet_fees(port_id, channel_id, sequence, working_set)
+        {
+            self.distribute_coins(
+                &fee.recv_fee,
+                forward_relayer.as_ref(),
+                &refund_address,
+                working_set,
+            )?;
+            self.distribute_coins(&fee.ack_fee, payee.as_ref(), &refund_address, working_set)?;
+            self.distribute_coins(&fee.timeout_fee, None, &refund_address, working_set)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pays out the fees of a timed out packet: the timeout fee goes to the
+    /// payee of the relayer of the timeout, while the receive and
+    /// acknowledgement fees are refunded.
+    pub fn distribute_fees_on_timeout(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        relayer: Option<&S::Address>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), FeeError> {
+        let payee = relayer.map(|relayer| self.payee(port_id, channel_id, relayer, working_set));
+
+        for (fee, refund_address) in
+            self.take_packet_fees(port_id, channel_id, sequence, working_set)
+        {
+            self.distribute_coins(&fee.recv_fee, None, &refund_address, working_set)?;
+            self.distribute_coins(&fee.ack_fee, None, &refund_address, working_set)?;
+            self.distribute_coins(
+                &fee.timeout_fee,
+                payee.as_ref(),
+                &refund_address,
+                working_set,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Refunds all the fees escrowed for the packets of the given channel,
+    /// which happens when it closes.
+    pub fn refund_channel_fees(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), FeeError> {
+        let channel_key = (port_id.clone(), channel_id.clone());
+
+        let Some((first, _)) = self.incentivized_ends.get(&channel_key, working_set) else {
+            return Ok(());
+        };
+
+        // The list is unlinked as it is walked rather than packet by packet,
+        // as the whole channel gets refunded.
+        self.incentivized_ends.delete(&channel_key, working_set);
+
+        let mut next = Some(first);
+
+        while let Some(sequence) = next {
+            let key = (port_id.clone(), channel_id.clone(), sequence);
+
+            next = self
+                .incentivized_links
+                .get(&key, working_set)
+                .and_then(|(_, next)| next);
+
+            self.incentivized_links.delete(&key, working_set);
+
+            let packet_fees = self.packet_fees.get(&key, working_set).unwrap_or_default();
+
+            self.packet_fees.delete(&key, working_set);
+
+            for (fee, refund_address) in packet_fees {
+                for coins in [fee.recv_fee, fee.ack_fee, fee.timeout_fee] {
+                    self.distribute_coins(&coins, None, &refund_address, working_set)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the address the acknowledgement and timeout fees of the given
+    /// relayer are paid to, which defaults to the relayer itself.
+    fn payee(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        relayer: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> S::Address {
+        self.payees
+            .get(
+                &(port_id.clone(), channel_id.clone(), relayer.clone()),
+                working_set,
+            )
+            .unwrap_or_else(|| relayer.clone())
+    }
+
+    /// Removes and returns the fees escrowed for the given packet.
+    fn take_packet_fees(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> Vec<(Fee, S::Address)> {
+        let key = (port_id.clone(), channel_id.clone(), sequence);
+
+        let Some(packet_fees) = self.packet_fees.get(&key, working_set) else {
+            return vec![];
+        };
+
+        self.packet_fees.delete(&key, working_set);
+
+        self.unindex_incentivized_packet(port_id, channel_id, sequence, working_set);
+
+        packet_fees
+    }
+
+    /// Returns the sequences of the packets with escrowed fees on the given
+    /// channel, in the order they were first incentivized.
+    pub fn incentivized_sequences(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        working_set: &mut impl TxState<S>,
+    ) -> Vec<Sequence> {
+        let mut sequences = Vec::new();
+
+        let mut next = self
+            .incentivized_ends
+            .get(&(port_id.clone(), channel_id.clone()), working_set)
+            .map(|(first, _)| first);
+
+        while let Some(sequence) = next {
+            sequences.push(sequence);
+
+            next = self
+                .incentivized_links
+                .get(
+                    &(port_id.clone(), channel_id.clone(), sequence),
+                    working_set,
+                )
+                .and_then(|(_, next)| next);
+        }
+
+        sequences
+    }
+
+    /// Appends the given packet to the list of the packets with escrowed fees
+    /// on its channel.
+    pub(crate) fn index_incentivized_packet(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let channel_key = (port_id.clone(), channel_id.clone());
+
+        let ends = match self.incentivized_ends.get(&channel_key, working_set) {
+            Some((first, last)) => {
+                let last_key = (port_id.clone(), channel_id.clone(), last);
+
+                let (prev, _) = self
+                    .incentivized_links
+                    .get(&last_key, working_set)
+                    .unwrap_or_default();
+
+                self.incentivized_links
+                    .set(&last_key, &(prev, Some(sequence)), working_set);
+
+                self.incentivized_links.set(
+                    &(port_id.clone(), channel_id.clone(), sequence),
+                    &(Some(last), None),
+                    working_set,
+                );
+
+                (first, sequence)
+            }
+            None => {
+                self.incentivized_links.set(
+                    &(port_id.clone(), channel_id.clone(), sequence),
+                    &(None, None),
+                    working_set,
+                );
+
+                (sequence, sequence)
+            }
+        };
+
+        self.incentivized_ends.set(&channel_key, &ends, working_set);
+    }
+
+    /// Unlinks the given packet from the list of the packets with escrowed
+    /// fees on its channel.
+    fn unindex_incentivized_packet(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let key = (port_id.clone(), channel_id.clone(), sequence);
+
+        let Some((prev, next)) = self.incentivized_links.get(&key, working_set) else {
+            return;
+        };
+
+        self.incentivized_links.delete(&key, working_set);
+
+        if let Some(prev) = prev {
+            let prev_key = (port_id.clone(), channel_id.clone(), prev);
+
+            if let Some((prev_prev, _)) = self.incentivized_links.get(&prev_key, working_set) {
+                self.incentivized_links
+                    .set(&prev_key, &(prev_prev, next), working_set);
+            }
+        }
+
+        if let Some(next) = next {
+            let next_key = (port_id.clone(), channel_id.clone(), next);
+
+            if let Some((_, next_next)) = self.incentivized_links.get(&next_key, working_set) {
+                self.incentivized_links
+                    .set(&next_key, &(prev, next_next), working_set);
+            }
+        }
+
+        let channel_key = (port_id.clone(), channel_id.clone());
+
+        match (prev, next) {
+            // An inner packet, the ends of the list are unchanged.
+            (Some(_), Some(_)) => {}
+            (None, None) => self.incentivized_ends.delete(&channel_key, working_set),
+            _ => {
+                if let Some((first, last)) = self.incentivized_ends.get(&channel_key, working_set) {
+                    let ends = (next.unwrap_or(first), prev.unwrap_or(last));
+
+                    self.incentivized_ends.set(&channel_key, &ends, working_set);
+                }
+            }
+        }
+    }
+
+    /// Pays the given coins out of the escrow to the recipient, or refunds
+    /// them if there is no recipient or the payment fails.
+    fn distribute_coins(
+        &self,
+        coins: &[Coins],
+        recipient: Option<&S::Address>,
+        refund_address: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), FeeError> {
+        for coin in coins.iter().filter(|coin| coin.amount > 0) {
+            let paid = match recipient {
+                Some(recipient) => self
+                    .bank
+                    .transfer_from(self.id.to_payable(), recipient, coin.clone(), working_set)
+                    .is_ok(),
+                None => false,
+            };
+
+            if !paid {
+                self.bank
+                    .transfer_from(
+                        self.id.to_payable(),
+                        refund_address,
+                        coin.clone(),
+                        working_set,
+                    )
+                    .map_err(|e| FeeError::DistributionFailed(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
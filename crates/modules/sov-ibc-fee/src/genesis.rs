@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use ibc_core::channel::types::channel::ChannelEnd;
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use sov_bank::{IntoPayable, TokenId};
+use sov_modules_api::{GenesisState, Module, Spec, TxState};
+
+use super::{
+    FeeConfig, IbcFee, IdentifiedPacketFees, PacketFee, RegisteredCounterpartyPayee,
+    RegisteredPayee,
+};
+use crate::types::Metadata;
+
+impl<S: Spec> IbcFee<S> {
+    /// Imports the payees and the escrowed fees of the fee-enabled channels.
+    /// The escrowed fees must add up to at most what the module account holds.
+    pub(crate) fn init_module(
+        &self,
+        config: &<Self as Module>::Config,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        for registered in &config.payees {
+            self.ensure_fee_enabled(&registered.port_id, &registered.channel_id, working_set)?;
+
+            let relayer = parse_address::<S>(&registered.relayer)?;
+            let payee = parse_address::<S>(&registered.payee)?;
+
+            let key = (
+                registered.port_id.clone(),
+                registered.channel_id.clone(),
+                relayer,
+            );
+
+            if self.payees.get(&key, working_set).is_some() {
+                bail!("Payee of relayer {} is imported more than once", key.2);
+            }
+
+            self.payees.set(&key, &payee, working_set);
+            self.payee_keys_vec.push(&key, working_set);
+        }
+
+        for registered in &config.counterparty_payees {
+            self.ensure_fee_enabled(&registered.port_id, &registered.channel_id, working_set)?;
+
+            let relayer = parse_address::<S>(&registered.relayer)?;
+
+            if registered.counterparty_payee.trim().is_empty() {
+                bail!("Counterparty payee of relayer {relayer} cannot be empty");
+            }
+
+            let key = (
+                registered.port_id.clone(),
+                registered.channel_id.clone(),
+                relayer,
+            );
+
+            if self.counterparty_payees.get(&key, working_set).is_some() {
+                bail!(
+                    "Counterparty payee of relayer {} is imported more than once",
+                    key.2
+                );
+            }
+
+            self.counterparty_payees
+                .set(&key, &registered.counterparty_payee, working_set);
+            self.counterparty_payee_keys_vec.push(&key, working_set);
+        }
+
+        let mut escrowed: BTreeMap<String, (TokenId, u64)> = BTreeMap::new();
+
+        for identified in &config.packet_fees {
+            let (port_id, channel_id) = (&identified.port_id, &identified.channel_id);
+
+            self.ensure_fee_enabled(port_id, channel_id, working_set)?;
+
+            let key = (port_id.clone(), channel_id.clone(), identified.sequence);
+
+            if identified.packet_fees.is_empty()
+                || self.packet_fees.get(&key, working_set).is_some()
+            {
+                bail!(
+                    "Fees of packet {} on channel {channel_id} are empty or imported more than once",
+                    identified.sequence
+                );
+            }
+
+            let mut packet_fees = Vec::new();
+
+            for packet_fee in &identified.packet_fees {
+                packet_fee.fee.validate()?;
+
+                for (denom, (token_id, amount)) in packet_fee.fee.total()? {
+                    let (_, total) = escrowed.entry(denom).or_insert((token_id, 0));
+
+                    *total = total
+                        .checked_add(amount)
+                        .ok_or(anyhow!("Escrowed amount of token {token_id} overflows"))?;
+                }
+
+                packet_fees.push((
+                    packet_fee.fee.clone(),
+                    parse_address::<S>(&packet_fee.refund_address)?,
+                ));
+            }
+
+            self.packet_fees.set(&key, &packet_fees, working_set);
+
+            self.index_incentivized_packet(port_id, channel_id, identified.sequence, working_set);
+        }
+
+        for (token_id, amount) in escrowed.into_values() {
+            let balance = self
+                .bank
+                .get_balance_of(self.id.to_payable(), token_id, working_set)
+                .unwrap_or_default();
+
+            if balance < amount {
+                bail!(
+                    "Escrowed fees of token {token_id} amount to {amount}, while the module account holds {balance}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Enables the fees on a channel imported by the genesis of the `Ibc`
+    /// module if its version wraps the one of the underlying application.
+    pub fn import_channel(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        channel_end: &ChannelEnd,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        let metadata = Metadata::from_version(channel_end.version())
+            .map_err(|e| anyhow!("Invalid version of channel {channel_id}: {e}"))?;
+
+        if metadata.is_some() {
+            self.fee_enabled_channels.set(
+                &(port_id.clone(), channel_id.clone()),
+                &true,
+                working_set,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Exports the module state as a configuration that its genesis imports
+    /// back. The escrowed fees are looked up for the given channels, as the
+    /// fees are not kept track of across channels.
+    pub fn export_config(
+        &self,
+        channels: &[(PortId, ChannelId)],
+        working_set: &mut impl TxState<S>,
+    ) -> Result<FeeConfig> {
+        let payees = self
+            .payee_keys_vec
+            .iter(working_set)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|(port_id, channel_id, relayer)| {
+                let key = (port_id, channel_id, relayer);
+
+                self.payees
+                    .get(&key, working_set)
+                    .map(|payee| RegisteredPayee {
+                        port_id: key.0,
+                        channel_id: key.1,
+                        relayer: key.2.to_string(),
+                        payee: payee.to_string(),
+                    })
+            })
+            .collect();
+
+        let counterparty_payees = self
+            .counterparty_payee_keys_vec
+            .iter(working_set)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|(port_id, channel_id, relayer)| {
+                let key = (port_id, channel_id, relayer);
+
+                self.counterparty_payees
+                    .get(&key, working_set)
+                    .map(|counterparty_payee| RegisteredCounterpartyPayee {
+                        port_id: key.0,
+                        channel_id: key.1,
+                        relayer: key.2.to_string(),
+                        counterparty_payee,
+                    })
+            })
+            .collect();
+
+        let mut packet_fees = Vec::new();
+
+        for (port_id, channel_id) in channels {
+            let sequences = self.incentivized_sequences(&port_id, &channel_id, working_set);
+
+            for sequence in sequences {
+                let Some(fees) = self.packet_fees.get(
+                    &(port_id.clone(), channel_id.clone(), sequence),
+                    working_set,
+                ) else {
+                    continue;
+                };
+
+                packet_fees.push(IdentifiedPacketFees {
+                    port_id: port_id.clone(),
+                    channel_id: channel_id.clone(),
+                    sequence,
+                    packet_fees: fees
+                        .into_iter()
+                        .map(|(fee, refund_address)| PacketFee {
+                            fee,
+                            refund_address: refund_address.to_string(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        Ok(FeeConfig {
+            payees,
+            counterparty_payees,
+            packet_fees,
+        })
+    }
+
+    /// Registers the keys of the payees and counterparty payees that predate
+    /// the registries walked through by the export. Keys already registered
+    /// are skipped, and keys under which no payee is registered are rejected.
+    pub fn backfill_payees(
+        &self,
+        keys: &[(PortId, ChannelId, S::Address)],
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        let mut payee_keys = self.payee_keys_vec.iter(working_set).collect::<Vec<_>>();
+
+        let mut counterparty_payee_keys = self
+            .counterparty_payee_keys_vec
+            .iter(working_set)
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            let has_payee = self.payees.get(key, working_set).is_some();
+
+            let has_counterparty_payee = self.counterparty_payees.get(key, working_set).is_some();
+
+            if !has_payee && !has_counterparty_payee {
+                bail!(
+                    "Relayer {} registered no payee on channel {} and port {}",
+                    key.2,
+                    key.1,
+                    key.0
+                );
+            }
+
+            if has_payee && !payee_keys.contains(key) {
+                self.payee_keys_vec.push(key, working_set);
+                payee_keys.push(key.clone());
+            }
+
+            if has_counterparty_payee && !counterparty_payee_keys.contains(key) {
+                self.counterparty_payee_keys_vec.push(key, working_set);
+                counterparty_payee_keys.push(key.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn ensure_fee_enabled(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        if !self
+            .fee_enabled_channels
+            .get(&(port_id.clone(), channel_id.clone()), working_set)
+            .unwrap_or_default()
+        {
+            bail!("Fee is not enabled on channel {channel_id} and port {port_id}");
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_address<S: Spec>(address: &str) -> Result<S::Address> {
+    address
+        .parse()
+        .map_err(|_| anyhow!("Invalid address: {address}"))
+}
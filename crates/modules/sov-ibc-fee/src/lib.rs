@@ -0,0 +1,158 @@
+pub mod error;
+pub mod fees;
+mod genesis;
+pub mod middleware;
+pub mod types;
+
+use anyhow::anyhow;
+use ibc_core::handler::types::events::IbcEvent;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{
+    Context, Error, GenesisState, Module, ModuleId, ModuleInfo, Spec, StateMap, StateVec, TxState,
+};
+
+use crate::types::Fee;
+
+/// The genesis configuration of the `IbcFee` module, which may import the
+/// payees and the escrowed fees of the fee-enabled channels imported by the
+/// `Ibc` module, whose genesis must run first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FeeConfig {
+    /// The payees registered by the relayers, imported when relaunching or
+    /// forking a rollup.
+    #[serde(default)]
+    pub payees: Vec<RegisteredPayee>,
+    /// The counterparty payees registered by the relayers, imported when
+    /// relaunching or forking a rollup.
+    #[serde(default)]
+    pub counterparty_payees: Vec<RegisteredCounterpartyPayee>,
+    /// The fees escrowed for the packets in flight, imported when relaunching
+    /// or forking a rollup. The module account must hold them.
+    #[serde(default)]
+    pub packet_fees: Vec<IdentifiedPacketFees>,
+}
+
+/// The payee of the acknowledgement and timeout fees of a relayer.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RegisteredPayee {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub relayer: String,
+    pub payee: String,
+}
+
+/// The counterparty payee of the receive fees of a relayer.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RegisteredCounterpartyPayee {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub relayer: String,
+    pub counterparty_payee: String,
+}
+
+/// The fees escrowed for a packet, along with their refund addresses.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdentifiedPacketFees {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub sequence: Sequence,
+    pub packet_fees: Vec<PacketFee>,
+}
+
+/// A fee escrowed for a packet, refunded to the given address.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PacketFee {
+    pub fee: Fee,
+    pub refund_address: String,
+}
+
+#[derive(ModuleInfo, Clone)]
+pub struct IbcFee<S: Spec> {
+    /// Id of the module, whose account holds the escrowed fees.
+    #[id]
+    pub id: ModuleId,
+
+    /// Reference to the Bank module.
+    #[module]
+    bank: sov_bank::Bank<S>,
+
+    /// Keeps track of the channels that negotiated the fee version during
+    /// their handshake, and hence wrap their acknowledgements.
+    #[state]
+    pub fee_enabled_channels: StateMap<(PortId, ChannelId), bool>,
+
+    /// Maps the packets to the fees escrowed for them, along with the refund
+    /// address of each fee.
+    #[state]
+    pub packet_fees: StateMap<(PortId, ChannelId, Sequence), Vec<(Fee, S::Address)>>,
+
+    /// Links every packet with escrowed fees to the previous and next ones
+    /// incentivized on its channel, so that a packet is added to or removed
+    /// from the list of its channel in constant time.
+    #[state]
+    pub incentivized_links:
+        StateMap<(PortId, ChannelId, Sequence), (Option<Sequence>, Option<Sequence>)>,
+
+    /// Maps every channel to the first and last packets of its list of
+    /// packets with escrowed fees, which is walked to refund them when the
+    /// channel closes.
+    #[state]
+    pub incentivized_ends: StateMap<(PortId, ChannelId), (Sequence, Sequence)>,
+
+    /// Maps the relayers to the payee addresses they registered for the
+    /// acknowledgement and timeout fees on the given port and channel.
+    #[state]
+    pub payees: StateMap<(PortId, ChannelId, S::Address), S::Address>,
+
+    /// Maps the relayers to the counterparty addresses they registered for
+    /// the receive fees on the given port and channel. These addresses are
+    /// carried in the acknowledgements as the forward relayer addresses.
+    #[state]
+    pub counterparty_payees: StateMap<(PortId, ChannelId, S::Address), String>,
+
+    /// Keeps track of the keys of the registered payees, which is used to
+    /// export them.
+    #[state]
+    pub payee_keys_vec: StateVec<(PortId, ChannelId, S::Address)>,
+
+    /// Keeps track of the keys of the registered counterparty payees, which
+    /// is used to export them.
+    #[state]
+    pub counterparty_payee_keys_vec: StateVec<(PortId, ChannelId, S::Address)>,
+}
+
+impl<S: Spec> Module for IbcFee<S> {
+    type Spec = S;
+
+    type Config = FeeConfig;
+
+    type CallMessage = ();
+
+    type Event = IbcEvent;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut impl GenesisState<Self::Spec>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
+    }
+
+    fn call(
+        &self,
+        _msg: Self::CallMessage,
+        _context: &Context<Self::Spec>,
+        _working_set: &mut impl TxState<Self::Spec>,
+    ) -> Result<sov_modules_api::CallResponse, Error> {
+        Err(Error::ModuleError(anyhow!(
+            "Cannot call sov-ibc-fee; use sov-ibc instead"
+        )))
+    }
+}
+
+impl<S: Spec> core::fmt::Debug for IbcFee<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Fee").field("id", &self.id).finish()
+    }
+}
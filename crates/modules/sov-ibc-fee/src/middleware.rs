@@ -0,0 +1,484 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_core::channel::types::acknowledgement::{Acknowledgement, AcknowledgementStatus};
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::types::module::ModuleExtras;
+use sov_modules_api::{Spec, TxState};
+
+use crate::error::FeeError;
+use crate::types::{IncentivizedAcknowledgement, Metadata};
+use crate::IbcFee;
+
+/// The ICS-29 fee middleware, which wraps any application module to let the
+/// rollup users incentivize the relaying of its packets.
+///
+/// Channels opt in during their handshake by wrapping the application version
+/// into the fee version metadata. On such channels, the middleware wraps the
+/// application acknowledgements with the address the receive fee must be paid
+/// to, and pays out the escrowed fees upon acknowledgement or timeout. Any
+/// other channel is passed through to the application untouched.
+pub struct FeeMiddleware<'ws, S: Spec, TS: TxState<S>, M: Module> {
+    pub ibc_fee: IbcFee<S>,
+    pub app: M,
+    pub working_set: Rc<RefCell<&'ws mut TS>>,
+}
+
+impl<'ws, S: Spec, TS: TxState<S>, M: Module> FeeMiddleware<'ws, S, TS, M> {
+    pub fn new(ibc_fee: IbcFee<S>, app: M, working_set: Rc<RefCell<&'ws mut TS>>) -> Self {
+        Self {
+            ibc_fee,
+            app,
+            working_set,
+        }
+    }
+
+    fn is_fee_enabled(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
+        self.ibc_fee
+            .is_fee_enabled(port_id, channel_id, *self.working_set.borrow_mut())
+    }
+
+    /// Parses the counterparty fee version of a fee-enabled channel.
+    fn counterparty_metadata(
+        &self,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<Metadata, FeeError> {
+        Metadata::from_version(counterparty_version)?.ok_or(FeeError::InvalidVersion(format!(
+            "expected the counterparty to agree on the fee version, got {counterparty_version}"
+        )))
+    }
+
+    /// Decodes the incentivized acknowledgement of a fee-enabled channel into
+    /// the acknowledgement of the application.
+    fn decode_acknowledgement(
+        &self,
+        acknowledgement: &Acknowledgement,
+    ) -> Result<(IncentivizedAcknowledgement, Acknowledgement), FeeError> {
+        let incentivized_ack = IncentivizedAcknowledgement::from_bytes(acknowledgement.as_ref())?;
+
+        let app_ack = Acknowledgement::try_from(incentivized_ack.app_acknowledgement.clone())
+            .map_err(|e| FeeError::InvalidAcknowledgement(e.to_string()))?;
+
+        Ok((incentivized_ack, app_ack))
+    }
+}
+
+impl<S: Spec> IbcFee<S> {
+    /// Wraps the application acknowledgement of a packet received on a
+    /// fee-enabled channel with the counterparty payee the relayer registered,
+    /// so that the sending chain pays it the receive fee. Hosts writing
+    /// acknowledgements asynchronously must wrap them the same way.
+    pub fn incentivize_acknowledgement(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        relayer: &Signer,
+        app_ack: Acknowledgement,
+        working_set: &mut impl TxState<S>,
+    ) -> Acknowledgement {
+        let forward_relayer_address = relayer_address::<S>(relayer)
+            .and_then(|relayer| self.counterparty_payee(port_id, channel_id, &relayer, working_set))
+            .unwrap_or_default();
+
+        let underlying_app_success =
+            serde_json::from_slice::<AcknowledgementStatus>(app_ack.as_ref())
+                .map(|status| status.is_successful())
+                .unwrap_or(false);
+
+        let incentivized_ack = IncentivizedAcknowledgement {
+            app_acknowledgement: app_ack.as_ref().to_vec(),
+            forward_relayer_address,
+            underlying_app_success,
+        };
+
+        Acknowledgement::try_from(incentivized_ack.to_bytes())
+            .expect("never fails as the acknowledgement is not empty")
+    }
+}
+
+/// Parses a relayer signer as a rollup address. Fees owed to an unparsable
+/// relayer are refunded.
+fn relayer_address<S: Spec>(relayer: &Signer) -> Option<S::Address> {
+    relayer.as_ref().parse().ok()
+}
+
+impl<'ws, S, TS, M> core::fmt::Debug for FeeMiddleware<'ws, S, TS, M>
+where
+    S: Spec,
+    TS: TxState<S>,
+    M: Module,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeeMiddleware")
+            .field("fee_mod", &self.ibc_fee)
+            .field("app", &self.app)
+            .finish()
+    }
+}
+
+impl<'ws, S: Spec, TS: TxState<S>, M: Module> Module for FeeMiddleware<'ws, S, TS, M> {
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        let Some(metadata) = Metadata::from_version(version)? else {
+            return self.app.on_chan_open_init_validate(
+                order,
+                connection_hops,
+                port_id,
+                channel_id,
+                counterparty,
+                version,
+            );
+        };
+
+        let app_version = self.app.on_chan_open_init_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &ChannelVersion::new(metadata.app_version),
+        )?;
+
+        Ok(Metadata::new(app_version.to_string()).to_version())
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        let Some(metadata) = Metadata::from_version(version)? else {
+            return self.app.on_chan_open_init_execute(
+                order,
+                connection_hops,
+                port_id,
+                channel_id,
+                counterparty,
+                version,
+            );
+        };
+
+        let (extras, app_version) = self.app.on_chan_open_init_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &ChannelVersion::new(metadata.app_version),
+        )?;
+
+        self.ibc_fee
+            .enable_fee(port_id, channel_id, *self.working_set.borrow_mut());
+
+        Ok((extras, Metadata::new(app_version.to_string()).to_version()))
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        let Some(metadata) = Metadata::from_version(counterparty_version)? else {
+            return self.app.on_chan_open_try_validate(
+                order,
+                connection_hops,
+                port_id,
+                channel_id,
+                counterparty,
+                counterparty_version,
+            );
+        };
+
+        let app_version = self.app.on_chan_open_try_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &ChannelVersion::new(metadata.app_version),
+        )?;
+
+        Ok(Metadata::new(app_version.to_string()).to_version())
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        let Some(metadata) = Metadata::from_version(counterparty_version)? else {
+            return self.app.on_chan_open_try_execute(
+                order,
+                connection_hops,
+                port_id,
+                channel_id,
+                counterparty,
+                counterparty_version,
+            );
+        };
+
+        let (extras, app_version) = self.app.on_chan_open_try_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &ChannelVersion::new(metadata.app_version),
+        )?;
+
+        self.ibc_fee
+            .enable_fee(port_id, channel_id, *self.working_set.borrow_mut());
+
+        Ok((extras, Metadata::new(app_version.to_string()).to_version()))
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(), ChannelError> {
+        if !self.is_fee_enabled(port_id, channel_id) {
+            return self
+                .app
+                .on_chan_open_ack_validate(port_id, channel_id, counterparty_version);
+        }
+
+        let metadata = self.counterparty_metadata(counterparty_version)?;
+
+        self.app.on_chan_open_ack_validate(
+            port_id,
+            channel_id,
+            &ChannelVersion::new(metadata.app_version),
+        )
+    }
+
+    fn on_chan_open_ack_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ModuleExtras, ChannelError> {
+        if !self.is_fee_enabled(port_id, channel_id) {
+            return self
+                .app
+                .on_chan_open_ack_execute(port_id, channel_id, counterparty_version);
+        }
+
+        let metadata = self.counterparty_metadata(counterparty_version)?;
+
+        self.app.on_chan_open_ack_execute(
+            port_id,
+            channel_id,
+            &ChannelVersion::new(metadata.app_version),
+        )
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.app.on_chan_open_confirm_validate(port_id, channel_id)
+    }
+
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.app.on_chan_open_confirm_execute(port_id, channel_id)
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.app.on_chan_close_init_validate(port_id, channel_id)
+    }
+
+    /// Refunds the fees of the in-flight packets, as they cannot be relayed
+    /// anymore once the channel is closed.
+    fn on_chan_close_init_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        let extras = self.app.on_chan_close_init_execute(port_id, channel_id)?;
+
+        self.ibc_fee
+            .refund_channel_fees(port_id, channel_id, *self.working_set.borrow_mut())?;
+
+        Ok(extras)
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.app.on_chan_close_confirm_validate(port_id, channel_id)
+    }
+
+    /// Refunds the fees of the in-flight packets, as they cannot be relayed
+    /// anymore once the channel is closed.
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        let extras = self
+            .app
+            .on_chan_close_confirm_execute(port_id, channel_id)?;
+
+        self.ibc_fee
+            .refund_channel_fees(port_id, channel_id, *self.working_set.borrow_mut())?;
+
+        Ok(extras)
+    }
+
+    /// Wraps the application acknowledgement with the counterparty payee the
+    /// relayer registered, so that the sending chain pays it the receive fee.
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let (extras, app_ack) = self.app.on_recv_packet_execute(packet, relayer);
+
+        if !self.is_fee_enabled(&packet.port_id_on_b, &packet.chan_id_on_b) {
+            return (extras, app_ack);
+        }
+
+        let ack = self.ibc_fee.incentivize_acknowledgement(
+            &packet.port_id_on_b,
+            &packet.chan_id_on_b,
+            relayer,
+            app_ack,
+            *self.working_set.borrow_mut(),
+        );
+
+        (extras, ack)
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        if !self.is_fee_enabled(&packet.port_id_on_a, &packet.chan_id_on_a) {
+            return self
+                .app
+                .on_acknowledgement_packet_validate(packet, acknowledgement, relayer);
+        }
+
+        let (_, app_ack) = self.decode_acknowledgement(acknowledgement)?;
+
+        self.app
+            .on_acknowledgement_packet_validate(packet, &app_ack, relayer)
+    }
+
+    /// Pays out the fees escrowed for the packet, once the application
+    /// processed its acknowledgement.
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        if !self.is_fee_enabled(&packet.port_id_on_a, &packet.chan_id_on_a) {
+            return self
+                .app
+                .on_acknowledgement_packet_execute(packet, acknowledgement, relayer);
+        }
+
+        let (incentivized_ack, app_ack) = match self.decode_acknowledgement(acknowledgement) {
+            Ok(acks) => acks,
+            Err(e) => return (ModuleExtras::empty(), Err(e.into())),
+        };
+
+        let (extras, res) = self
+            .app
+            .on_acknowledgement_packet_execute(packet, &app_ack, relayer);
+
+        if res.is_err() {
+            return (extras, res);
+        }
+
+        let res = self
+            .ibc_fee
+            .distribute_fees_on_acknowledgement(
+                &packet.port_id_on_a,
+                &packet.chan_id_on_a,
+                packet.seq_on_a,
+                &incentivized_ack.forward_relayer_address,
+                relayer_address::<S>(relayer).as_ref(),
+                *self.working_set.borrow_mut(),
+            )
+            .map_err(PacketError::from);
+
+        (extras, res)
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        self.app.on_timeout_packet_validate(packet, relayer)
+    }
+
+    /// Pays out the fees escrowed for the packet, once the application
+    /// processed its timeout.
+    fn on_timeout_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let (extras, res) = self.app.on_timeout_packet_execute(packet, relayer);
+
+        if res.is_err() || !self.is_fee_enabled(&packet.port_id_on_a, &packet.chan_id_on_a) {
+            return (extras, res);
+        }
+
+        let res = self
+            .ibc_fee
+            .distribute_fees_on_timeout(
+                &packet.port_id_on_a,
+                &packet.chan_id_on_a,
+                packet.seq_on_a,
+                relayer_address::<S>(relayer).as_ref(),
+                *self.working_set.borrow_mut(),
+            )
+            .map_err(PacketError::from);
+
+        (extras, res)
+    }
+}
@@ -0,0 +1,279 @@
+//! Defines the ICS-29 domain types exchanged over fee-enabled channels and
+//! submitted by the rollup users through the `sov-ibc` module.
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use serde::{Deserialize, Serialize};
+use sov_bank::{Coins, TokenId};
+
+use crate::error::FeeError;
+
+/// The ICS-29 fee version.
+pub const VERSION: &str = "ics29-1";
+
+/// The channel version metadata of fee-enabled channels, which wraps the
+/// version of the underlying application.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub fee_version: String,
+    pub app_version: String,
+}
+
+impl Metadata {
+    pub fn new(app_version: String) -> Self {
+        Self {
+            fee_version: VERSION.to_string(),
+            app_version,
+        }
+    }
+
+    /// Parses the metadata out of a channel version. Returns `None` if the
+    /// version does not wrap an application version, meaning the channel is
+    /// not fee-enabled, and fails if it does so with an unsupported fee
+    /// version.
+    pub fn from_version(version: &ChannelVersion) -> Result<Option<Self>, FeeError> {
+        let Ok(metadata) = serde_json::from_str::<Self>(&version.to_string()) else {
+            return Ok(None);
+        };
+
+        if metadata.fee_version != VERSION {
+            return Err(FeeError::InvalidVersion(format!(
+                "expected {VERSION}, got {}",
+                metadata.fee_version
+            )));
+        }
+
+        Ok(Some(metadata))
+    }
+
+    /// Encodes the metadata as a channel version.
+    pub fn to_version(&self) -> ChannelVersion {
+        ChannelVersion::new(
+            serde_json::to_string(self).expect("never fails as the metadata is serializable"),
+        )
+    }
+}
+
+/// The fees paid to the relayers of a packet. The receive fee goes to the
+/// relayer of the packet to the counterparty chain, the acknowledgement fee to
+/// the relayer of the acknowledgement back, and the timeout fee to the relayer
+/// of the timeout, if any. Whatever is not paid out is refunded.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fee {
+    pub recv_fee: Vec<Coins>,
+    pub ack_fee: Vec<Coins>,
+    pub timeout_fee: Vec<Coins>,
+}
+
+impl Fee {
+    /// Checks the fee pays out something.
+    pub fn validate(&self) -> Result<(), FeeError> {
+        if self.total()?.values().all(|(_, amount)| *amount == 0) {
+            return Err(FeeError::InvalidFee(
+                "at least one fee must be non-zero".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the total amount of every token the fee consists of, which is
+    /// what must be escrowed.
+    pub fn total(&self) -> Result<BTreeMap<String, (TokenId, u64)>, FeeError> {
+        let mut total: BTreeMap<String, (TokenId, u64)> = BTreeMap::new();
+
+        for coins in self
+            .recv_fee
+            .iter()
+            .chain(&self.ack_fee)
+            .chain(&self.timeout_fee)
+        {
+            let (_, amount) = total
+                .entry(coins.token_id.to_string())
+                .or_insert((coins.token_id, 0));
+
+            *amount = amount
+                .checked_add(coins.amount)
+                .ok_or(FeeError::InvalidFee("amount overflow".to_string()))?;
+        }
+
+        Ok(total)
+    }
+}
+
+/// The acknowledgement written on fee-enabled channels, JSON-encoded exactly
+/// as `ibc-go` does.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncentivizedAcknowledgement {
+    /// The acknowledgement of the underlying application.
+    #[serde(with = "sov_ibc_utils::base64_bytes")]
+    pub app_acknowledgement: Vec<u8>,
+    /// The address, on the packet sending chain, the receive fee is paid to.
+    pub forward_relayer_address: String,
+    pub underlying_app_success: bool,
+}
+
+impl IncentivizedAcknowledgement {
+    /// Decodes the acknowledgement out of the raw acknowledgement bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FeeError> {
+        serde_json::from_slice(bytes).map_err(|e| FeeError::InvalidAcknowledgement(e.to_string()))
+    }
+
+    /// Encodes the acknowledgement as raw acknowledgement bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("never fails as the acknowledgement is serializable")
+    }
+}
+
+/// Escrows a fee, paid by the sender, for a packet sent on the given channel
+/// that is still in flight and not incentivized yet. Meant to be submitted in
+/// the same batch as the message sending the packet, after it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MsgPayPacketFee {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub sequence: Sequence,
+    pub fee: Fee,
+    /// The payer of the fee, which must be the sender.
+    pub signer: String,
+}
+
+/// Escrows a fee, paid by the sender, for an already sent packet that is not
+/// yet acknowledged nor timed out.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MsgPayPacketFeeAsync {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub sequence: Sequence,
+    pub fee: Fee,
+    /// The payer of the fee, which must be the sender.
+    pub signer: String,
+}
+
+/// Registers, for the sender relayer, the rollup address its acknowledgement
+/// and timeout fees are paid to on the given channel.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgRegisterPayee {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub payee: String,
+    /// The relayer registering the payee, which must be the sender.
+    pub relayer: String,
+}
+
+/// Registers, for the sender relayer, the counterparty address its receive
+/// fees are paid to for the packets it relays to the rollup on the given
+/// channel.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgRegisterCounterpartyPayee {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub counterparty_payee: String,
+    /// The relayer registering the payee, which must be the sender.
+    pub relayer: String,
+}
+
+/// Request of the `feeEnabledChannel` query.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryFeeEnabledChannelRequest {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+}
+
+/// Response of the `feeEnabledChannel` query.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryFeeEnabledChannelResponse {
+    pub fee_enabled: bool,
+}
+
+/// Request of the `incentivizedPackets` query.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryIncentivizedPacketsRequest {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+}
+
+/// Response of the `incentivizedPackets` query.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryIncentivizedPacketsResponse {
+    pub incentivized_packets: Vec<IdentifiedPacketFees>,
+}
+
+/// The fees escrowed for a packet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdentifiedPacketFees {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub sequence: Sequence,
+    pub packet_fees: Vec<PacketFee>,
+}
+
+/// A fee escrowed for a packet, along with the address it is refunded to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PacketFee {
+    pub fee: Fee,
+    pub refund_address: String,
+}
+
+/// Request of the `payee` query.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryPayeeRequest {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub relayer: String,
+}
+
+/// Response of the `payee` query.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryPayeeResponse {
+    pub payee_address: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_version() {
+        let version =
+            ChannelVersion::new(r#"{"fee_version":"ics29-1","app_version":"ics20-1"}"#.to_string());
+
+        let metadata = Metadata::from_version(&version).unwrap();
+
+        assert_eq!(metadata, Some(Metadata::new("ics20-1".to_string())));
+        assert_eq!(metadata.unwrap().to_version(), version);
+
+        assert_eq!(
+            Metadata::from_version(&ChannelVersion::new("ics20-1".to_string())).unwrap(),
+            None
+        );
+        assert!(Metadata::from_version(&ChannelVersion::new(
+            r#"{"fee_version":"ics29-2","app_version":"ics20-1"}"#.to_string()
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_incentivized_ack_json_encoding() {
+        let bytes = br#"{"app_acknowledgement":"eyJyZXN1bHQiOiJBUT09In0=","forward_relayer_address":"relayer","underlying_app_success":true}"#;
+
+        let ack = IncentivizedAcknowledgement::from_bytes(bytes).unwrap();
+
+        assert_eq!(
+            ack,
+            IncentivizedAcknowledgement {
+                app_acknowledgement: br#"{"result":"AQ=="}"#.to_vec(),
+                forward_relayer_address: "relayer".to_string(),
+                underlying_app_success: true,
+            }
+        );
+        assert_eq!(ack.to_bytes(), bytes.to_vec());
+    }
+}
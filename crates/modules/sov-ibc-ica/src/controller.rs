@@ -0,0 +1,454 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_core::channel::types::acknowledgement::{
+    Acknowledgement, AcknowledgementStatus, StatusValue,
+};
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::types::module::ModuleExtras;
+use sov_modules_api::{Spec, TxState};
+
+use crate::error::InterchainAccountError;
+use crate::types::{host_port_id, Metadata, CONTROLLER_PORT_PREFIX};
+use crate::IbcInterchainAccounts;
+
+/// The controller side of the interchain accounts, which lets the rollup users
+/// register accounts on the counterparty host chains and control them.
+pub struct IcaControllerContext<'ws, S: Spec, TS: TxState<S>> {
+    pub ibc_ica: IbcInterchainAccounts<S>,
+    pub working_set: Rc<RefCell<&'ws mut TS>>,
+}
+
+impl<'ws, S: Spec, TS: TxState<S>> IcaControllerContext<'ws, S, TS> {
+    pub fn new(ibc_ica: IbcInterchainAccounts<S>, working_set: Rc<RefCell<&'ws mut TS>>) -> Self {
+        Self {
+            ibc_ica,
+            working_set,
+        }
+    }
+
+    pub fn ensure_controller_enabled(&self) -> Result<(), InterchainAccountError> {
+        let controller_enabled = self
+            .ibc_ica
+            .controller_enabled
+            .get(*self.working_set.borrow_mut())
+            .unwrap_or_default();
+
+        if !controller_enabled {
+            return Err(InterchainAccountError::ControllerDisabled);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the active channel of the given controller port over the given
+    /// connection, if any.
+    pub fn active_channel(
+        &self,
+        connection_id: &ConnectionId,
+        port_id: &PortId,
+    ) -> Option<ChannelId> {
+        self.ibc_ica.controller_active_channels.get(
+            &(connection_id.clone(), port_id.clone()),
+            *self.working_set.borrow_mut(),
+        )
+    }
+
+    /// Validates a channel opening attempt of a rollup user, and returns the
+    /// proposed channel version metadata.
+    fn validate_open_init(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<Metadata, InterchainAccountError> {
+        self.ensure_controller_enabled()?;
+
+        if !port_id.as_str().starts_with(CONTROLLER_PORT_PREFIX) {
+            return Err(InterchainAccountError::InvalidPort(format!(
+                "{port_id} is not a controller port"
+            )));
+        }
+
+        if counterparty.port_id() != &host_port_id() {
+            return Err(InterchainAccountError::InvalidPort(format!(
+                "expected counterparty port {}, got {}",
+                host_port_id(),
+                counterparty.port_id()
+            )));
+        }
+
+        if order != Order::Ordered {
+            return Err(InterchainAccountError::InvalidOrdering);
+        }
+
+        let [connection_id] = connection_hops else {
+            return Err(InterchainAccountError::InvalidConnectionHops(format!(
+                "expected exactly one connection hop, got {}",
+                connection_hops.len()
+            )));
+        };
+
+        let metadata = Metadata::from_version(&version.to_string())?;
+
+        metadata.validate(Some(connection_id), None)?;
+
+        if self.active_channel(connection_id, port_id).is_some() {
+            return Err(InterchainAccountError::ActiveChannelExists {
+                connection_id: connection_id.to_string(),
+                port_id: port_id.to_string(),
+            });
+        }
+
+        Ok(metadata)
+    }
+
+    /// Validates the channel version metadata returned by the counterparty
+    /// host chain against the one proposed upon opening the channel, which
+    /// was recorded along with the channel connection hop. The returned
+    /// metadata must carry the registered interchain account address.
+    fn validate_open_ack(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(ConnectionId, Metadata), InterchainAccountError> {
+        self.ensure_controller_enabled()?;
+
+        let (connection_id, proposed_version) = {
+            let mut working_set = self.working_set.borrow_mut();
+
+            let connection_id = self
+                .ibc_ica
+                .controller_channel_connections
+                .get(channel_id, *working_set);
+
+            let proposed_version = self
+                .ibc_ica
+                .controller_channel_versions
+                .get(channel_id, *working_set);
+
+            match (connection_id, proposed_version) {
+                (Some(connection_id), Some(proposed_version)) => (connection_id, proposed_version),
+                _ => {
+                    return Err(InterchainAccountError::InvalidMetadata(format!(
+                        "channel {channel_id} was not opened by the controller"
+                    )))
+                }
+            }
+        };
+
+        let proposed_metadata = Metadata::from_version(&proposed_version)?;
+
+        let metadata = Metadata::from_version(&counterparty_version.to_string())?;
+
+        metadata.validate(
+            Some(&connection_id),
+            Some(&proposed_metadata.host_connection_id),
+        )?;
+
+        if metadata.address.is_empty() {
+            return Err(InterchainAccountError::InvalidMetadata(
+                "the host did not provide an interchain account address".to_string(),
+            ));
+        }
+
+        if let Some(active_channel_id) = self.active_channel(&connection_id, port_id) {
+            if &active_channel_id != channel_id {
+                return Err(InterchainAccountError::ActiveChannelExists {
+                    connection_id: connection_id.to_string(),
+                    port_id: port_id.to_string(),
+                });
+            }
+        }
+
+        Ok((connection_id, metadata))
+    }
+
+    /// Deactivates the given channel, which happens when it gets closed, so
+    /// that the owner can reopen a new one for the same interchain account.
+    fn deactivate_channel(&self, port_id: &PortId, channel_id: &ChannelId) {
+        let mut working_set = self.working_set.borrow_mut();
+
+        let Some(connection_id) = self
+            .ibc_ica
+            .controller_channel_connections
+            .get(channel_id, *working_set)
+        else {
+            return;
+        };
+
+        let key = (connection_id, port_id.clone());
+
+        if self
+            .ibc_ica
+            .controller_active_channels
+            .get(&key, *working_set)
+            .as_ref()
+            == Some(channel_id)
+        {
+            self.ibc_ica
+                .controller_active_channels
+                .delete(&key, *working_set);
+        }
+    }
+}
+
+impl<'ws, S, TS> core::fmt::Debug for IcaControllerContext<'ws, S, TS>
+where
+    S: Spec,
+    TS: TxState<S>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IcaControllerContext")
+            .field("ica_mod", &self.ibc_ica)
+            .finish()
+    }
+}
+
+impl<'ws, S: Spec, TS: TxState<S>> Module for IcaControllerContext<'ws, S, TS> {
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        _channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        let metadata =
+            self.validate_open_init(order, connection_hops, port_id, counterparty, version)?;
+
+        Ok(ChannelVersion::new(metadata.to_version()))
+    }
+
+    /// Records the connection hop and the proposed version of the channel,
+    /// which the counterparty host chain's version gets checked against upon
+    /// `ChanOpenAck`.
+    fn on_chan_open_init_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        let metadata =
+            self.validate_open_init(order, connection_hops, port_id, counterparty, version)?;
+
+        let version = metadata.to_version();
+
+        self.ibc_ica.controller_channel_connections.set(
+            channel_id,
+            &metadata.controller_connection_id,
+            *self.working_set.borrow_mut(),
+        );
+
+        self.ibc_ica.controller_channel_versions.set(
+            channel_id,
+            &version,
+            *self.working_set.borrow_mut(),
+        );
+
+        Ok((ModuleExtras::empty(), ChannelVersion::new(version)))
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        _counterparty_version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "the controller does not accept channel handshakes".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        _counterparty_version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "the controller does not accept channel handshakes".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(), ChannelError> {
+        self.validate_open_ack(port_id, channel_id, counterparty_version)?;
+
+        Ok(())
+    }
+
+    /// Records the interchain account address registered by the host chain,
+    /// and marks the channel as the active channel of the owner over the
+    /// channel connection hop.
+    fn on_chan_open_ack_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ModuleExtras, ChannelError> {
+        let (connection_id, metadata) =
+            self.validate_open_ack(port_id, channel_id, counterparty_version)?;
+
+        let key = (connection_id, port_id.clone());
+
+        self.ibc_ica.controller_accounts.set(
+            &key,
+            &metadata.address,
+            *self.working_set.borrow_mut(),
+        );
+
+        self.ibc_ica.controller_active_channels.set(
+            &key,
+            channel_id,
+            *self.working_set.borrow_mut(),
+        );
+
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "the controller does not accept channel handshakes".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "the controller does not accept channel handshakes".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "user cannot close interchain accounts channels".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_close_init_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "user cannot close interchain accounts channels".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Ok(())
+    }
+
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.deactivate_channel(port_id, channel_id);
+
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_recv_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let ack_status = AcknowledgementStatus::error(
+            StatusValue::new(
+                InterchainAccountError::UnsupportedAction(
+                    "the controller does not receive packets".to_string(),
+                )
+                .to_string(),
+            )
+            .expect("never fails as the error message is not empty"),
+        );
+
+        (ModuleExtras::empty(), ack_status.into())
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Ok(())
+    }
+
+    /// The outcome of the executed transaction is carried by the
+    /// acknowledgement itself, so there is nothing to do here.
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (ModuleExtras::empty(), Ok(()))
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Ok(())
+    }
+
+    /// Interchain accounts channels are ordered, hence a timeout closes the
+    /// channel, which must be deactivated.
+    fn on_timeout_packet_execute(
+        &mut self,
+        packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        self.deactivate_channel(&packet.port_id_on_a, &packet.chan_id_on_a);
+
+        (ModuleExtras::empty(), Ok(()))
+    }
+}
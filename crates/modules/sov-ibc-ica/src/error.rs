@@ -0,0 +1,62 @@
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use thiserror::Error;
+
+/// Errors raised by the interchain accounts module.
+#[derive(Debug, Error)]
+pub enum InterchainAccountError {
+    #[error("interchain accounts host is disabled")]
+    HostDisabled,
+    #[error("interchain accounts controller is disabled")]
+    ControllerDisabled,
+    #[error("invalid owner: {0}")]
+    InvalidOwner(String),
+    #[error("invalid port: {0}")]
+    InvalidPort(String),
+    #[error("invalid channel ordering: interchain accounts channels must be ordered")]
+    InvalidOrdering,
+    #[error("invalid connection hops: {0}")]
+    InvalidConnectionHops(String),
+    #[error("invalid channel version metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("invalid packet data: {0}")]
+    InvalidPacketData(String),
+    #[error("message type `{0}` is not allowed on the host")]
+    MessageNotAllowed(String),
+    #[error("invalid message: {0}")]
+    InvalidMessage(String),
+    #[error("no interchain account found for port {port_id} on connection {connection_id}")]
+    AccountNotFound {
+        connection_id: String,
+        port_id: String,
+    },
+    #[error("an active channel already exists for port {port_id} on connection {connection_id}")]
+    ActiveChannelExists {
+        connection_id: String,
+        port_id: String,
+    },
+    #[error("no active channel found for port {port_id} on connection {connection_id}")]
+    ActiveChannelNotFound {
+        connection_id: String,
+        port_id: String,
+    },
+    #[error("unsupported action: {0}")]
+    UnsupportedAction(String),
+    #[error("execution failed: {0}")]
+    ExecutionFailed(String),
+}
+
+impl From<InterchainAccountError> for ChannelError {
+    fn from(e: InterchainAccountError) -> Self {
+        ChannelError::AppModule {
+            description: e.to_string(),
+        }
+    }
+}
+
+impl From<InterchainAccountError> for PacketError {
+    fn from(e: InterchainAccountError) -> Self {
+        PacketError::AppModule {
+            description: e.to_string(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::{anyhow, bail, Result};
+use ibc_core::channel::types::channel::ChannelEnd;
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use sov_modules_api::{GenesisState, Module, Spec, TxState};
+
+use super::{IbcInterchainAccounts, InterchainAccountsConfig};
+use crate::types::Metadata;
+use crate::utils::compute_interchain_account_address;
+
+impl<S: Spec> IbcInterchainAccounts<S> {
+    pub(crate) fn init_module(
+        &self,
+        config: &<Self as Module>::Config,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        self.host_enabled.set(&config.host_enabled, working_set);
+        self.controller_enabled
+            .set(&config.controller_enabled, working_set);
+        self.allow_messages.set(&config.allow_messages, working_set);
+
+        Ok(())
+    }
+
+    /// Exports the module parameters as a configuration that its genesis
+    /// imports back. The registries of the accounts are not exported, as the
+    /// genesis of the `Ibc` module restores them out of the channels.
+    pub fn export_config(&self, working_set: &mut impl TxState<S>) -> InterchainAccountsConfig {
+        InterchainAccountsConfig {
+            host_enabled: self.host_enabled.get(working_set).unwrap_or_default(),
+            controller_enabled: self.controller_enabled.get(working_set).unwrap_or_default(),
+            allow_messages: self.allow_messages.get(working_set).unwrap_or_default(),
+        }
+    }
+
+    /// Restores the registries of a host channel imported by the genesis of
+    /// the `Ibc` module, out of the version metadata it was opened with. The
+    /// interchain account it names must be the one derived for its owner.
+    pub fn import_host_channel(
+        &self,
+        channel_id: &ChannelId,
+        channel_end: &ChannelEnd,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        let metadata = channel_metadata(channel_id, channel_end, true)?;
+
+        let owner = (
+            metadata.host_connection_id.clone(),
+            channel_end.counterparty().port_id().clone(),
+        );
+
+        let account = compute_interchain_account_address::<S>(&owner.0, &owner.1);
+
+        if metadata.address != account.to_string() {
+            bail!(
+                "Interchain account {} of host channel {channel_id} is not the one derived for it, {account}",
+                metadata.address
+            );
+        }
+
+        self.interchain_accounts.set(&owner, &account, working_set);
+        self.host_channel_owners
+            .set(channel_id, &owner, working_set);
+
+        if channel_end.is_open() {
+            if self.host_active_channels.get(&owner, working_set).is_some() {
+                bail!(
+                    "Interchain account {account} has more than one open host channel, including {channel_id}"
+                );
+            }
+
+            self.host_active_channels
+                .set(&owner, channel_id, working_set);
+        }
+
+        Ok(())
+    }
+
+    /// Restores the registries of a controller channel imported by the
+    /// genesis of the `Ibc` module, out of the version metadata it was opened
+    /// with.
+    pub fn import_controller_channel(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        channel_end: &ChannelEnd,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        let metadata = channel_metadata(channel_id, channel_end, false)?;
+
+        let key = (metadata.controller_connection_id.clone(), port_id.clone());
+
+        self.controller_channel_connections.set(
+            channel_id,
+            &metadata.controller_connection_id,
+            working_set,
+        );
+        self.controller_channel_versions
+            .set(channel_id, &metadata.to_version(), working_set);
+
+        if channel_end.is_open() {
+            if self
+                .controller_active_channels
+                .get(&key, working_set)
+                .is_some()
+            {
+                bail!(
+                    "Controller port {port_id} has more than one open channel over connection {}, including {channel_id}",
+                    key.0
+                );
+            }
+
+            if metadata.address.is_empty() {
+                bail!("Open controller channel {channel_id} names no interchain account");
+            }
+
+            self.controller_accounts
+                .set(&key, &metadata.address, working_set);
+            self.controller_active_channels
+                .set(&key, channel_id, working_set);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the version metadata of an imported channel, checking that its
+/// connection on the rollup side, the host or the controller one, is the
+/// connection hop of the channel.
+fn channel_metadata(
+    channel_id: &ChannelId,
+    channel_end: &ChannelEnd,
+    on_host: bool,
+) -> Result<Metadata> {
+    let metadata = Metadata::from_version(&channel_end.version().to_string())
+        .map_err(|e| anyhow!("Invalid version of channel {channel_id}: {e}"))?;
+
+    let connection_id = channel_end
+        .connection_hops()
+        .first()
+        .ok_or_else(|| anyhow!("Channel {channel_id} has no connection hop"))?;
+
+    let (controller_connection_id, host_connection_id) = if on_host {
+        (None, Some(connection_id))
+    } else {
+        (Some(connection_id), None)
+    };
+
+    metadata
+        .validate(controller_connection_id, host_connection_id)
+        .map_err(|e| anyhow!("Invalid version of channel {channel_id}: {e}"))?;
+
+    Ok(metadata)
+}
@@ -0,0 +1,616 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use ibc_core::channel::types::acknowledgement::{
+    Acknowledgement, AcknowledgementStatus, StatusValue,
+};
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::types::module::ModuleExtras;
+use ibc_proto::cosmos::base::abci::v1beta1::TxMsgData;
+use ibc_proto::google::protobuf::Any;
+use ibc_proto::ibc::applications::interchain_accounts::v1::CosmosTx;
+use prost::Message;
+use sov_modules_api::{Spec, TxState};
+
+use crate::error::InterchainAccountError;
+use crate::types::{
+    host_port_id, InterchainAccountPacketData, Metadata, PacketType, CONTROLLER_PORT_PREFIX,
+    RUNTIME_CALL_RESPONSE_TYPE_URL, RUNTIME_CALL_TYPE_URL,
+};
+use crate::utils::compute_interchain_account_address;
+use crate::IbcInterchainAccounts;
+
+/// Executes the transactions of the interchain accounts on the rollup.
+///
+/// The interchain accounts module cannot decode runtime calls by itself, as
+/// it does not know the runtime it is part of. Rollups let counterparty
+/// controller chains execute transactions by handing an executor over to the
+/// host with [`IcaHostContext::with_executor`], which usually deserializes the
+/// calls into the runtime call type and dispatches them.
+pub trait InterchainAccountExecutor<S: Spec, TS: TxState<S>> {
+    /// Executes the given JSON-encoded runtime calls, in order, with the
+    /// interchain account as their sender. Implementations must execute
+    /// either all the calls or none of them, leaving the state untouched if
+    /// any of them fails, as the failure only results in an error
+    /// acknowledgement.
+    fn execute_tx(
+        &self,
+        account: &S::Address,
+        calls: &[serde_json::Value],
+        working_set: &mut TS,
+    ) -> anyhow::Result<()>;
+}
+
+/// The host side of the interchain accounts, which registers accounts for the
+/// counterparty controller chains and executes the transactions they send.
+///
+/// Similar to `IbcTransferContext`, it wraps the module and the `WorkingSet`,
+/// as the latter is only available at call-time. Without an executor, the
+/// transactions are acknowledged with an error.
+pub struct IcaHostContext<'ws, S: Spec, TS: TxState<S>> {
+    pub ibc_ica: IbcInterchainAccounts<S>,
+    pub executor: Option<Box<dyn InterchainAccountExecutor<S, TS> + 'ws>>,
+    pub working_set: Rc<RefCell<&'ws mut TS>>,
+}
+
+impl<'ws, S: Spec, TS: TxState<S>> IcaHostContext<'ws, S, TS> {
+    pub fn new(ibc_ica: IbcInterchainAccounts<S>, working_set: Rc<RefCell<&'ws mut TS>>) -> Self {
+        Self {
+            ibc_ica,
+            executor: None,
+            working_set,
+        }
+    }
+
+    /// Sets the executor of the interchain account transactions.
+    pub fn with_executor(mut self, executor: impl InterchainAccountExecutor<S, TS> + 'ws) -> Self {
+        self.executor = Some(Box::new(executor));
+        self
+    }
+
+    fn ensure_host_enabled(&self) -> Result<(), InterchainAccountError> {
+        let host_enabled = self
+            .ibc_ica
+            .host_enabled
+            .get(*self.working_set.borrow_mut())
+            .unwrap_or_default();
+
+        if !host_enabled {
+            return Err(InterchainAccountError::HostDisabled);
+        }
+
+        Ok(())
+    }
+
+    /// Validates the channel opening attempt of a counterparty controller
+    /// chain, and returns the proposed channel version metadata.
+    fn validate_open_try(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<Metadata, InterchainAccountError> {
+        self.ensure_host_enabled()?;
+
+        if port_id != &host_port_id() {
+            return Err(InterchainAccountError::InvalidPort(format!(
+                "expected {}, got {port_id}",
+                host_port_id()
+            )));
+        }
+
+        if !counterparty
+            .port_id()
+            .as_str()
+            .starts_with(CONTROLLER_PORT_PREFIX)
+        {
+            return Err(InterchainAccountError::InvalidPort(format!(
+                "counterparty port {} is not a controller port",
+                counterparty.port_id()
+            )));
+        }
+
+        if order != Order::Ordered {
+            return Err(InterchainAccountError::InvalidOrdering);
+        }
+
+        let [connection_id] = connection_hops else {
+            return Err(InterchainAccountError::InvalidConnectionHops(format!(
+                "expected exactly one connection hop, got {}",
+                connection_hops.len()
+            )));
+        };
+
+        let metadata = Metadata::from_version(&counterparty_version.to_string())?;
+
+        metadata.validate(None, Some(connection_id))?;
+
+        if !metadata.address.is_empty() {
+            return Err(InterchainAccountError::InvalidMetadata(
+                "address must be empty when opening a channel".to_string(),
+            ));
+        }
+
+        if self
+            .ibc_ica
+            .host_active_channels
+            .get(
+                &(connection_id.clone(), counterparty.port_id().clone()),
+                *self.working_set.borrow_mut(),
+            )
+            .is_some()
+        {
+            return Err(InterchainAccountError::ActiveChannelExists {
+                connection_id: connection_id.to_string(),
+                port_id: counterparty.port_id().to_string(),
+            });
+        }
+
+        Ok(metadata)
+    }
+
+    /// Returns the interchain account registered for the given connection and
+    /// controller port, registering a new one if needed.
+    fn obtain_interchain_account(
+        &self,
+        connection_id: &ConnectionId,
+        controller_port_id: &PortId,
+    ) -> S::Address {
+        let mut working_set = self.working_set.borrow_mut();
+
+        let key = (connection_id.clone(), controller_port_id.clone());
+
+        self.ibc_ica
+            .interchain_accounts
+            .get(&key, *working_set)
+            .unwrap_or_else(|| {
+                let account =
+                    compute_interchain_account_address::<S>(connection_id, controller_port_id);
+                self.ibc_ica
+                    .interchain_accounts
+                    .set(&key, &account, *working_set);
+                account
+            })
+    }
+
+    /// Decodes the received packet and executes the runtime calls carried by
+    /// its transaction on behalf of the interchain account, through the
+    /// executor of the rollup. Returns the responses of the executed messages.
+    fn execute_packet(&self, packet: &Packet) -> Result<Vec<Any>, InterchainAccountError> {
+        self.ensure_host_enabled()?;
+
+        let executor = self
+            .executor
+            .as_ref()
+            .ok_or(InterchainAccountError::UnsupportedAction(
+                "the rollup does not execute interchain account transactions".to_string(),
+            ))?;
+
+        let packet_data = InterchainAccountPacketData::from_packet_bytes(&packet.data)?;
+
+        if packet_data.packet_type != PacketType::ExecuteTx {
+            return Err(InterchainAccountError::InvalidPacketData(
+                "unsupported packet type".to_string(),
+            ));
+        }
+
+        let (connection_id, controller_port_id) = self
+            .ibc_ica
+            .host_channel_owners
+            .get(&packet.chan_id_on_b, *self.working_set.borrow_mut())
+            .ok_or(InterchainAccountError::InvalidPacketData(format!(
+                "no interchain account registered for {}",
+                packet.chan_id_on_b
+            )))?;
+
+        if controller_port_id != packet.port_id_on_a {
+            return Err(InterchainAccountError::InvalidPort(format!(
+                "packet sent from {}, but the channel belongs to {controller_port_id}",
+                packet.port_id_on_a
+            )));
+        }
+
+        let account = self
+            .ibc_ica
+            .interchain_accounts
+            .get(
+                &(connection_id.clone(), controller_port_id.clone()),
+                *self.working_set.borrow_mut(),
+            )
+            .ok_or(InterchainAccountError::AccountNotFound {
+                connection_id: connection_id.to_string(),
+                port_id: controller_port_id.to_string(),
+            })?;
+
+        let cosmos_tx = CosmosTx::decode(packet_data.data.as_slice())
+            .map_err(|e| InterchainAccountError::InvalidPacketData(e.to_string()))?;
+
+        if cosmos_tx.messages.is_empty() {
+            return Err(InterchainAccountError::InvalidPacketData(
+                "no messages to execute".to_string(),
+            ));
+        }
+
+        let calls = cosmos_tx
+            .messages
+            .into_iter()
+            .map(|msg| self.to_runtime_call(msg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        executor
+            .execute_tx(&account, &calls, *self.working_set.borrow_mut())
+            .map_err(|e| InterchainAccountError::ExecutionFailed(e.to_string()))?;
+
+        let msg_responses = calls
+            .iter()
+            .map(|_| Any {
+                type_url: RUNTIME_CALL_RESPONSE_TYPE_URL.to_string(),
+                value: vec![],
+            })
+            .collect();
+
+        Ok(msg_responses)
+    }
+
+    /// Decodes the JSON-encoded runtime call carried by a message of an
+    /// interchain account transaction, and checks the runtime module it
+    /// targets is allowed on the host.
+    fn to_runtime_call(&self, msg: Any) -> Result<serde_json::Value, InterchainAccountError> {
+        if msg.type_url != RUNTIME_CALL_TYPE_URL {
+            return Err(InterchainAccountError::MessageNotAllowed(msg.type_url));
+        }
+
+        let call: serde_json::Value = serde_json::from_slice(&msg.value)
+            .map_err(|e| InterchainAccountError::InvalidMessage(e.to_string()))?;
+
+        let module_name = match &call {
+            serde_json::Value::Object(call) if call.len() == 1 => call
+                .keys()
+                .next()
+                .expect("never fails as the call has exactly one key")
+                .clone(),
+            _ => {
+                return Err(InterchainAccountError::InvalidMessage(
+                    "a runtime call must target exactly one module".to_string(),
+                ))
+            }
+        };
+
+        let allow_messages = self
+            .ibc_ica
+            .allow_messages
+            .get(*self.working_set.borrow_mut())
+            .unwrap_or_default();
+
+        if !allow_messages.iter().any(|allowed| allowed == &module_name) {
+            return Err(InterchainAccountError::MessageNotAllowed(module_name));
+        }
+
+        Ok(call)
+    }
+}
+
+impl<'ws, S, TS> core::fmt::Debug for IcaHostContext<'ws, S, TS>
+where
+    S: Spec,
+    TS: TxState<S>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IcaHostContext")
+            .field("ica_mod", &self.ibc_ica)
+            .finish()
+    }
+}
+
+impl<'ws, S: Spec, TS: TxState<S>> Module for IcaHostContext<'ws, S, TS> {
+    fn on_chan_open_init_validate(
+        &self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        _version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "channel handshake must be initiated by the controller chain".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        _version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "channel handshake must be initiated by the controller chain".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        _channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        let metadata = self.validate_open_try(
+            order,
+            connection_hops,
+            port_id,
+            counterparty,
+            counterparty_version,
+        )?;
+
+        Ok(ChannelVersion::new(metadata.to_version()))
+    }
+
+    /// Registers the interchain account, if not already registered (e.g. when
+    /// reopening a channel after the previous one got closed), and returns the
+    /// channel version metadata carrying its address.
+    fn on_chan_open_try_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        let mut metadata = self.validate_open_try(
+            order,
+            connection_hops,
+            port_id,
+            counterparty,
+            counterparty_version,
+        )?;
+
+        let connection_id = &metadata.host_connection_id;
+
+        let account = self.obtain_interchain_account(connection_id, counterparty.port_id());
+
+        self.ibc_ica.host_channel_owners.set(
+            channel_id,
+            &(connection_id.clone(), counterparty.port_id().clone()),
+            *self.working_set.borrow_mut(),
+        );
+
+        metadata.address = account.to_string();
+
+        Ok((
+            ModuleExtras::empty(),
+            ChannelVersion::new(metadata.to_version()),
+        ))
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty_version: &ChannelVersion,
+    ) -> Result<(), ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "channel handshake must be initiated by the controller chain".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_ack_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty_version: &ChannelVersion,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "channel handshake must be initiated by the controller chain".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.ensure_host_enabled()?;
+
+        let owner = self
+            .ibc_ica
+            .host_channel_owners
+            .get(channel_id, *self.working_set.borrow_mut())
+            .ok_or(ChannelError::AppModule {
+                description: format!("no interchain account registered for {channel_id}"),
+            })?;
+
+        // Another channel may have been opened for the same interchain
+        // account since this one was tried.
+        if self
+            .ibc_ica
+            .host_active_channels
+            .get(&owner, *self.working_set.borrow_mut())
+            .is_some()
+        {
+            return Err(InterchainAccountError::ActiveChannelExists {
+                connection_id: owner.0.to_string(),
+                port_id: owner.1.to_string(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Marks the channel as the active channel of the interchain account.
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        let owner = self
+            .ibc_ica
+            .host_channel_owners
+            .get(channel_id, *self.working_set.borrow_mut())
+            .ok_or(ChannelError::AppModule {
+                description: format!("no interchain account registered for {channel_id}"),
+            })?;
+
+        self.ibc_ica
+            .host_active_channels
+            .set(&owner, channel_id, *self.working_set.borrow_mut());
+
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "user cannot close interchain accounts channels".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_close_init_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Err(InterchainAccountError::UnsupportedAction(
+            "user cannot close interchain accounts channels".to_string(),
+        )
+        .into())
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Ok(())
+    }
+
+    /// Deactivates the channel, so that the controller chain can reopen a new
+    /// one for the same interchain account.
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        let mut working_set = self.working_set.borrow_mut();
+
+        if let Some(owner) = self
+            .ibc_ica
+            .host_channel_owners
+            .get(channel_id, *working_set)
+        {
+            if self.ibc_ica.host_active_channels.get(&owner, *working_set)
+                == Some(channel_id.clone())
+            {
+                self.ibc_ica
+                    .host_active_channels
+                    .delete(&owner, *working_set);
+            }
+        }
+
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let ack_status = match self.execute_packet(packet) {
+            Ok(msg_responses) => {
+                #[allow(deprecated)]
+                let tx_msg_data = TxMsgData {
+                    data: vec![],
+                    msg_responses,
+                };
+
+                AcknowledgementStatus::success(
+                    StatusValue::new(BASE64_STANDARD.encode(tx_msg_data.encode_to_vec()))
+                        .expect("never fails as the encoded value is not empty"),
+                )
+            }
+            Err(e) => AcknowledgementStatus::error(
+                StatusValue::new(format!("error handling packet: {e}"))
+                    .expect("never fails as the error message is not empty"),
+            ),
+        };
+
+        (ModuleExtras::empty(), ack_status.into())
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Err(
+            InterchainAccountError::UnsupportedAction("the host does not send packets".to_string())
+                .into(),
+        )
+    }
+
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (
+            ModuleExtras::empty(),
+            Err(InterchainAccountError::UnsupportedAction(
+                "the host does not send packets".to_string(),
+            )
+            .into()),
+        )
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Err(
+            InterchainAccountError::UnsupportedAction("the host does not send packets".to_string())
+                .into(),
+        )
+    }
+
+    fn on_timeout_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (
+            ModuleExtras::empty(),
+            Err(InterchainAccountError::UnsupportedAction(
+                "the host does not send packets".to_string(),
+            )
+            .into()),
+        )
+    }
+}
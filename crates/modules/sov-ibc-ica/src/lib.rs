@@ -0,0 +1,120 @@
+pub mod controller;
+pub mod error;
+mod genesis;
+pub mod host;
+pub mod types;
+pub mod utils;
+
+use anyhow::anyhow;
+use ibc_core::handler::types::events::IbcEvent;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{
+    Context, Error, GenesisState, Module, ModuleId, ModuleInfo, Spec, StateMap, StateValue, TxState,
+};
+
+#[cfg(feature = "native")]
+mod rpc;
+#[cfg(feature = "native")]
+pub use rpc::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct InterchainAccountsConfig {
+    /// Whether the rollup accepts interchain accounts registered by
+    /// counterparty controller chains.
+    pub host_enabled: bool,
+    /// Whether the rollup users can register interchain accounts on
+    /// counterparty host chains.
+    pub controller_enabled: bool,
+    /// The names of the runtime modules the host side is allowed to call on
+    /// behalf of the interchain accounts, as they appear in runtime calls.
+    pub allow_messages: Vec<String>,
+}
+
+#[derive(ModuleInfo, Clone)]
+pub struct IbcInterchainAccounts<S: Spec> {
+    /// Id of the module.
+    #[id]
+    pub id: ModuleId,
+
+    #[state]
+    host_enabled: StateValue<bool>,
+
+    #[state]
+    controller_enabled: StateValue<bool>,
+
+    #[state]
+    allow_messages: StateValue<Vec<String>>,
+
+    /// Maps the host connection and the counterparty controller port to the
+    /// interchain account address registered on the rollup.
+    #[state]
+    interchain_accounts: StateMap<(ConnectionId, PortId), S::Address>,
+
+    /// Maps the channels opened on the host port to the host connection and
+    /// the counterparty controller port they were opened for. This is used to
+    /// find out the executing interchain account upon packet receipt.
+    #[state]
+    host_channel_owners: StateMap<ChannelId, (ConnectionId, PortId)>,
+
+    /// Keeps track of the active host channel of every interchain account.
+    #[state]
+    host_active_channels: StateMap<(ConnectionId, PortId), ChannelId>,
+
+    /// Maps the controller connection and port to the interchain account
+    /// address the counterparty host chain registered for it.
+    #[state]
+    controller_accounts: StateMap<(ConnectionId, PortId), String>,
+
+    /// Keeps track of the active controller channel of every owner.
+    #[state]
+    controller_active_channels: StateMap<(ConnectionId, PortId), ChannelId>,
+
+    /// Maps the controller channels to their connection hop, recorded upon
+    /// `ChanOpenInit`, which keys the controller registries and is needed to
+    /// deactivate the channels when they get closed.
+    #[state]
+    controller_channel_connections: StateMap<ChannelId, ConnectionId>,
+
+    /// Maps the controller channels to the version metadata proposed upon
+    /// `ChanOpenInit`, which the counterparty host chain must stick to.
+    #[state]
+    controller_channel_versions: StateMap<ChannelId, String>,
+}
+
+impl<S: Spec> Module for IbcInterchainAccounts<S> {
+    type Spec = S;
+
+    type Config = InterchainAccountsConfig;
+
+    type CallMessage = ();
+
+    type Event = IbcEvent;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut impl GenesisState<Self::Spec>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
+    }
+
+    fn call(
+        &self,
+        _msg: Self::CallMessage,
+        _context: &Context<Self::Spec>,
+        _working_set: &mut impl TxState<Self::Spec>,
+    ) -> Result<sov_modules_api::CallResponse, Error> {
+        Err(Error::ModuleError(anyhow!(
+            "Cannot call sov-ibc-ica; use sov-ibc instead"
+        )))
+    }
+}
+
+impl<S: Spec> core::fmt::Debug for IbcInterchainAccounts<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterchainAccounts")
+            .field("id", &self.id)
+            .finish()
+    }
+}
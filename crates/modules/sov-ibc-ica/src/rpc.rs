@@ -0,0 +1,77 @@
+//! Defines JSON RPC methods exposed by the interchain accounts module
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use jsonrpsee::core::RpcResult;
+use sov_ibc_utils::to_jsonrpsee_error;
+use sov_modules_api::macros::rpc_gen;
+use sov_modules_api::{Spec, WorkingSet};
+
+use super::IbcInterchainAccounts;
+use crate::types::controller_port_id;
+
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+pub struct InterchainAccountResponse {
+    pub connection_id: ConnectionId,
+    pub controller_port_id: PortId,
+    pub address: String,
+    pub active_channel_id: Option<ChannelId>,
+}
+
+#[rpc_gen(client, server, namespace = "ica")]
+impl<S> IbcInterchainAccounts<S>
+where
+    S: Spec,
+{
+    /// Queries the interchain account registered on the rollup for the given
+    /// host connection and counterparty controller port.
+    #[rpc_method(name = "hostAccount")]
+    pub fn host_account(
+        &self,
+        connection_id: ConnectionId,
+        controller_port_id: PortId,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<InterchainAccountResponse> {
+        let key = (connection_id.clone(), controller_port_id.clone());
+
+        let address = self
+            .interchain_accounts
+            .get(&key, working_set)
+            .ok_or(to_jsonrpsee_error(format!(
+                "No interchain account found for port '{controller_port_id}' on connection '{connection_id}'"
+            )))?;
+
+        Ok(InterchainAccountResponse {
+            active_channel_id: self.host_active_channels.get(&key, working_set),
+            connection_id,
+            controller_port_id,
+            address: address.to_string(),
+        })
+    }
+
+    /// Queries the interchain account a counterparty host chain registered for
+    /// the given rollup owner over the given connection.
+    #[rpc_method(name = "controllerAccount")]
+    pub fn controller_account(
+        &self,
+        connection_id: ConnectionId,
+        owner: String,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<InterchainAccountResponse> {
+        let controller_port_id = controller_port_id(&owner).map_err(to_jsonrpsee_error)?;
+
+        let key = (connection_id.clone(), controller_port_id.clone());
+
+        let address = self
+            .controller_accounts
+            .get(&key, working_set)
+            .ok_or(to_jsonrpsee_error(format!(
+                "No interchain account found for owner '{owner}' on connection '{connection_id}'"
+            )))?;
+
+        Ok(InterchainAccountResponse {
+            active_channel_id: self.controller_active_channels.get(&key, working_set),
+            connection_id,
+            controller_port_id,
+            address,
+        })
+    }
+}
@@ -0,0 +1,286 @@
+//! Defines the ICS-27 domain types exchanged over interchain accounts channels
+//! and submitted by the rollup users through the `sov-ibc` module.
+use borsh::{BorshDeserialize, BorshSerialize};
+use ibc_core::host::types::identifiers::{ConnectionId, PortId};
+use ibc_proto::google::protobuf::Any;
+use ibc_proto::ibc::applications::interchain_accounts::v1::CosmosTx;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::error::InterchainAccountError;
+
+/// The ICS-27 application version.
+pub const VERSION: &str = "ics27-1";
+
+/// The port on which the host side of the interchain accounts listens.
+pub const HOST_PORT_ID_STR: &str = "icahost";
+
+/// The prefix of every controller port, which is followed by the owner address.
+pub const CONTROLLER_PORT_PREFIX: &str = "icacontroller-";
+
+/// The router module ID of the host side of the interchain accounts.
+pub const HOST_MODULE_ID_STR: &str = "icahost";
+
+/// The router module ID of the controller side of the interchain accounts.
+pub const CONTROLLER_MODULE_ID_STR: &str = "icacontroller";
+
+/// The only supported encoding of the interchain account transactions.
+pub const ENCODING_PROTOBUF: &str = "proto3";
+
+/// The only supported interchain account transaction type.
+pub const TX_TYPE_SDK_MULTI_MSG: &str = "sdk_multi_msg";
+
+/// The type URL of the interchain account messages executed on the rollup,
+/// whose value is a JSON-encoded runtime call.
+pub const RUNTIME_CALL_TYPE_URL: &str = "/sovereign.ibc.ica.v1.RuntimeCall";
+
+/// The type URL of the responses acknowledged for the executed runtime calls.
+pub const RUNTIME_CALL_RESPONSE_TYPE_URL: &str = "/sovereign.ibc.ica.v1.RuntimeCallResponse";
+
+/// Returns the port ID of the host side of the interchain accounts.
+pub fn host_port_id() -> PortId {
+    PortId::new(HOST_PORT_ID_STR.to_string()).expect("never fails as the port ID is valid")
+}
+
+/// Returns the controller port ID owned by the given owner.
+pub fn controller_port_id(owner: &str) -> Result<PortId, InterchainAccountError> {
+    PortId::new(format!("{CONTROLLER_PORT_PREFIX}{owner}"))
+        .map_err(|e| InterchainAccountError::InvalidOwner(e.to_string()))
+}
+
+/// The channel version metadata negotiated during the interchain accounts
+/// channel handshake, as defined by the ICS-27 specification.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub version: String,
+    pub controller_connection_id: ConnectionId,
+    pub host_connection_id: ConnectionId,
+    /// The interchain account address, which is filled in by the host chain
+    /// during the `ChanOpenTry` step.
+    #[serde(default)]
+    pub address: String,
+    pub encoding: String,
+    pub tx_type: String,
+}
+
+impl Metadata {
+    /// Creates a new metadata with the default encoding and transaction type.
+    pub fn new(controller_connection_id: ConnectionId, host_connection_id: ConnectionId) -> Self {
+        Self {
+            version: VERSION.to_string(),
+            controller_connection_id,
+            host_connection_id,
+            address: String::new(),
+            encoding: ENCODING_PROTOBUF.to_string(),
+            tx_type: TX_TYPE_SDK_MULTI_MSG.to_string(),
+        }
+    }
+
+    /// Parses the metadata out of a channel version string.
+    pub fn from_version(version: &str) -> Result<Self, InterchainAccountError> {
+        serde_json::from_str(version)
+            .map_err(|e| InterchainAccountError::InvalidMetadata(e.to_string()))
+    }
+
+    /// Encodes the metadata as a channel version string.
+    pub fn to_version(&self) -> String {
+        serde_json::to_string(self).expect("never fails as the metadata is serializable")
+    }
+
+    /// Checks the version, encoding and transaction type are supported, and the
+    /// connection IDs match the expected ones.
+    pub fn validate(
+        &self,
+        controller_connection_id: Option<&ConnectionId>,
+        host_connection_id: Option<&ConnectionId>,
+    ) -> Result<(), InterchainAccountError> {
+        if self.version != VERSION {
+            return Err(InterchainAccountError::InvalidMetadata(format!(
+                "unsupported version: expected {VERSION}, got {}",
+                self.version
+            )));
+        }
+
+        if self.encoding != ENCODING_PROTOBUF {
+            return Err(InterchainAccountError::InvalidMetadata(format!(
+                "unsupported encoding: {}",
+                self.encoding
+            )));
+        }
+
+        if self.tx_type != TX_TYPE_SDK_MULTI_MSG {
+            return Err(InterchainAccountError::InvalidMetadata(format!(
+                "unsupported transaction type: {}",
+                self.tx_type
+            )));
+        }
+
+        if let Some(conn_id) = controller_connection_id {
+            if &self.controller_connection_id != conn_id {
+                return Err(InterchainAccountError::InvalidMetadata(format!(
+                    "controller connection mismatch: expected {conn_id}, got {}",
+                    self.controller_connection_id
+                )));
+            }
+        }
+
+        if let Some(conn_id) = host_connection_id {
+            if &self.host_connection_id != conn_id {
+                return Err(InterchainAccountError::InvalidMetadata(format!(
+                    "host connection mismatch: expected {conn_id}, got {}",
+                    self.host_connection_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The type of an interchain account packet.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum PacketType {
+    #[serde(rename = "TYPE_UNSPECIFIED")]
+    Unspecified,
+    #[serde(rename = "TYPE_EXECUTE_TX")]
+    ExecuteTx,
+}
+
+/// The interchain account packet data, JSON-encoded in the packets exactly as
+/// `ibc-go` does.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchainAccountPacketData {
+    #[serde(rename = "type")]
+    pub packet_type: PacketType,
+    /// The Protobuf-encoded `CosmosTx` to be executed on the host chain.
+    #[serde(with = "sov_ibc_utils::base64_bytes")]
+    #[cfg_attr(feature = "native", schemars(with = "String"))]
+    pub data: Vec<u8>,
+    #[serde(default)]
+    pub memo: String,
+}
+
+impl InterchainAccountPacketData {
+    /// Decodes the packet data out of the raw packet bytes.
+    pub fn from_packet_bytes(bytes: &[u8]) -> Result<Self, InterchainAccountError> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| InterchainAccountError::InvalidPacketData(e.to_string()))
+    }
+
+    /// Encodes the packet data as raw packet bytes.
+    pub fn to_packet_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("never fails as the packet data is serializable")
+    }
+
+    /// Creates the packet data of a transaction executing the given
+    /// JSON-encoded runtime calls on a Sovereign rollup host.
+    pub fn from_runtime_calls(calls: &[serde_json::Value], memo: String) -> Self {
+        let cosmos_tx = CosmosTx {
+            messages: calls
+                .iter()
+                .map(|call| Any {
+                    type_url: RUNTIME_CALL_TYPE_URL.to_string(),
+                    value: serde_json::to_vec(call).expect("never fails as the call is valid JSON"),
+                })
+                .collect(),
+        };
+
+        Self {
+            packet_type: PacketType::ExecuteTx,
+            data: cosmos_tx.encode_to_vec(),
+            memo,
+        }
+    }
+}
+
+/// Registers an interchain account on the host chain for the sender over the
+/// given connection.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgRegisterInterchainAccount {
+    /// The owner of the interchain account, which must be the sender.
+    pub owner: String,
+    pub connection_id: ConnectionId,
+}
+
+/// Sends an interchain account transaction to the host chain on behalf of the
+/// sender over the given connection.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgSendTx {
+    /// The owner of the interchain account, which must be the sender.
+    pub owner: String,
+    pub connection_id: ConnectionId,
+    pub packet_data: InterchainAccountPacketData,
+    /// The timeout, in nanoseconds, relative to the current host timestamp.
+    pub relative_timeout: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_json_encoding() {
+        let version = r#"{"version":"ics27-1","controller_connection_id":"connection-0","host_connection_id":"connection-1","address":"","encoding":"proto3","tx_type":"sdk_multi_msg"}"#;
+
+        let metadata = Metadata::from_version(version).unwrap();
+
+        assert_eq!(
+            metadata,
+            Metadata::new(ConnectionId::new(0), ConnectionId::new(1))
+        );
+        assert_eq!(metadata.to_version(), version);
+        assert!(metadata
+            .validate(Some(&ConnectionId::new(0)), Some(&ConnectionId::new(1)))
+            .is_ok());
+        assert!(metadata
+            .validate(Some(&ConnectionId::new(1)), None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_packet_data_json_encoding() {
+        let bytes = br#"{"type":"TYPE_EXECUTE_TX","data":"AQID","memo":"memo"}"#;
+
+        let packet_data = InterchainAccountPacketData::from_packet_bytes(bytes).unwrap();
+
+        assert_eq!(
+            packet_data,
+            InterchainAccountPacketData {
+                packet_type: PacketType::ExecuteTx,
+                data: vec![1, 2, 3],
+                memo: "memo".to_string(),
+            }
+        );
+        assert_eq!(packet_data.to_packet_bytes(), bytes.to_vec());
+    }
+
+    #[test]
+    fn test_packet_data_from_runtime_calls() {
+        let calls = vec![
+            serde_json::json!({"bank": {"transfer": {}}}),
+            serde_json::json!({"bank": {"burn": {}}}),
+        ];
+
+        let packet_data = InterchainAccountPacketData::from_runtime_calls(&calls, String::new());
+
+        assert_eq!(packet_data.packet_type, PacketType::ExecuteTx);
+
+        let cosmos_tx = CosmosTx::decode(packet_data.data.as_slice()).unwrap();
+
+        assert_eq!(cosmos_tx.messages.len(), 2);
+
+        for (msg, call) in cosmos_tx.messages.iter().zip(calls) {
+            assert_eq!(msg.type_url, RUNTIME_CALL_TYPE_URL);
+            assert_eq!(
+                serde_json::from_slice::<serde_json::Value>(&msg.value).unwrap(),
+                call
+            );
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use ibc_core::host::types::identifiers::{ConnectionId, PortId};
+use sov_ibc_utils::derive_module_address;
+use sov_modules_api::Spec;
+
+use crate::types::VERSION;
+
+/// The interchain account address is derived from the ICS-27 version along
+/// with the host connection and the controller port the account is registered
+/// for.
+pub fn compute_interchain_account_address<S: Spec>(
+    connection_id: &ConnectionId,
+    controller_port_id: &PortId,
+) -> S::Address {
+    derive_module_address::<S, _>(VERSION, &format!("{connection_id}/{controller_port_id}"))
+}
@@ -0,0 +1,622 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_app_nft_transfer::context::{NftTransferExecutionContext, NftTransferValidationContext};
+use ibc_app_nft_transfer::module::{
+    on_acknowledgement_packet_execute, on_acknowledgement_packet_validate,
+    on_chan_open_ack_validate, on_chan_open_confirm_validate, on_chan_open_init_execute,
+    on_chan_open_init_validate, on_chan_open_try_execute, on_chan_open_try_validate,
+    on_recv_packet_execute, on_timeout_packet_execute, on_timeout_packet_validate,
+};
+use ibc_app_nft_transfer::types::error::NftTransferError;
+use ibc_app_nft_transfer::types::{
+    ClassData, ClassUri, Memo, PrefixedClassId, TokenData, TokenId, TokenUri, PORT_ID_STR, VERSION,
+};
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::types::module::ModuleExtras;
+use sov_modules_api::{Spec, TxState};
+
+use super::IbcNftTransfer;
+use crate::types::{Nft, NftClass, NftClassRecord, NftRecord};
+use crate::utils::compute_escrow_address;
+
+/// We need to create a wrapper around the `NftTransfer` module and
+/// `WorkingSet`, for the same reasons as the `IbcTransferContext` of
+/// `sov-ibc-transfer`: the `WorkingSet` is only available at call-time.
+pub struct IbcNftTransferContext<'ws, S: Spec, TS: TxState<S>> {
+    pub ibc_nft_transfer: IbcNftTransfer<S>,
+    pub working_set: Rc<RefCell<&'ws mut TS>>,
+}
+
+impl<'ws, S: Spec, TS: TxState<S>> IbcNftTransferContext<'ws, S, TS> {
+    pub fn new(ibc_nft_transfer: IbcNftTransfer<S>, working_set: Rc<RefCell<&'ws mut TS>>) -> Self {
+        Self {
+            ibc_nft_transfer,
+            working_set,
+        }
+    }
+
+    /// Returns the current owner of the given NFT, if it exists.
+    fn owner_of(&self, class_id: &PrefixedClassId, token_id: &TokenId) -> Option<S::Address> {
+        self.ibc_nft_transfer.nft_owners.get(
+            &(class_id.to_string(), token_id.to_string()),
+            *self.working_set.borrow_mut(),
+        )
+    }
+
+    /// Validates that the given NFT is owned by the expected owner.
+    fn validate_owner(
+        &self,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        expected_owner: &S::Address,
+    ) -> Result<(), NftTransferError> {
+        let owner = self
+            .owner_of(class_id, token_id)
+            .ok_or(NftTransferError::Other(format!(
+                "NFT {token_id} of class {class_id} not found"
+            )))?;
+
+        if &owner != expected_owner {
+            return Err(NftTransferError::Other(format!(
+                "NFT {token_id} of class {class_id} is not owned by {expected_owner}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn set_owner(&self, class_id: &PrefixedClassId, token_id: &TokenId, owner: &S::Address) {
+        self.ibc_nft_transfer.nft_owners.set(
+            &(class_id.to_string(), token_id.to_string()),
+            owner,
+            *self.working_set.borrow_mut(),
+        );
+    }
+}
+
+impl<'ws, S, TS> core::fmt::Debug for IbcNftTransferContext<'ws, S, TS>
+where
+    S: Spec,
+    TS: TxState<S>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NftTransferContext")
+            .field("nft_transfer_mod", &self.ibc_nft_transfer)
+            .finish()
+    }
+}
+
+impl<'ws, S, TS> NftTransferValidationContext for IbcNftTransferContext<'ws, S, TS>
+where
+    S: Spec,
+    TS: TxState<S>,
+{
+    type AccountId = Address<S>;
+    type Nft = Nft;
+    type NftClass = NftClass;
+
+    fn get_port(&self) -> Result<PortId, NftTransferError> {
+        PortId::new(PORT_ID_STR.to_string())
+            .map_err(|e| NftTransferError::Other(format!("invalid port: {e}")))
+    }
+
+    fn can_send_nft(&self) -> Result<(), NftTransferError> {
+        Ok(())
+    }
+
+    fn can_receive_nft(&self) -> Result<(), NftTransferError> {
+        Ok(())
+    }
+
+    fn create_or_update_class_validate(
+        &self,
+        _class_id: &PrefixedClassId,
+        _class_uri: Option<&ClassUri>,
+        _class_data: Option<&ClassData>,
+    ) -> Result<(), NftTransferError> {
+        Ok(())
+    }
+
+    /// This is called in a `send_nft_transfer()` in the case where we are the
+    /// NFT source, and checks the sender owns the NFT.
+    fn escrow_nft_validate(
+        &self,
+        from_account: &Self::AccountId,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        _memo: &Memo,
+    ) -> Result<(), NftTransferError> {
+        self.validate_owner(class_id, token_id, &from_account.address)
+    }
+
+    /// This is called in a `recv_packet()` in the case where we are the NFT
+    /// source, and checks the NFT is held by the escrow of the channel.
+    fn unescrow_nft_validate(
+        &self,
+        _to_account: &Self::AccountId,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+    ) -> Result<(), NftTransferError> {
+        let escrow_address = compute_escrow_address::<S>(port_id, channel_id);
+
+        self.validate_owner(class_id, token_id, &escrow_address)
+    }
+
+    fn mint_nft_validate(
+        &self,
+        _account: &Self::AccountId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        _token_uri: Option<&TokenUri>,
+        _token_data: Option<&TokenData>,
+    ) -> Result<(), NftTransferError> {
+        if self.owner_of(class_id, token_id).is_some() {
+            return Err(NftTransferError::Other(format!(
+                "NFT {token_id} of class {class_id} already exists"
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn burn_nft_validate(
+        &self,
+        account: &Self::AccountId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        _memo: &Memo,
+    ) -> Result<(), NftTransferError> {
+        self.validate_owner(class_id, token_id, &account.address)
+    }
+
+    fn get_nft(
+        &self,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+    ) -> Result<Self::Nft, NftTransferError> {
+        let class_id = class_id.to_string();
+        let token_id = token_id.to_string();
+
+        let record = self
+            .ibc_nft_transfer
+            .nfts
+            .get(
+                &(class_id.clone(), token_id.clone()),
+                *self.working_set.borrow_mut(),
+            )
+            .ok_or(NftTransferError::Other(format!(
+                "NFT {token_id} of class {class_id} not found"
+            )))?;
+
+        Nft::try_new(&class_id, &token_id, record)
+    }
+
+    fn get_nft_class(
+        &self,
+        class_id: &PrefixedClassId,
+    ) -> Result<Self::NftClass, NftTransferError> {
+        self.ibc_nft_transfer
+            .classes
+            .get(&class_id.to_string(), *self.working_set.borrow_mut())
+            .ok_or(NftTransferError::Other(format!(
+                "NFT class {class_id} not found"
+            )))?
+            .try_into()
+    }
+}
+
+impl<'ws, S, TS> NftTransferExecutionContext for IbcNftTransferContext<'ws, S, TS>
+where
+    S: Spec,
+    TS: TxState<S>,
+{
+    /// Records the class upon receiving its first NFT, or updates its metadata
+    /// with the one carried in the packet.
+    fn create_or_update_class_execute(
+        &self,
+        class_id: &PrefixedClassId,
+        class_uri: Option<&ClassUri>,
+        class_data: Option<&ClassData>,
+    ) -> Result<(), NftTransferError> {
+        let class_id = class_id.to_string();
+
+        let mut working_set = self.working_set.borrow_mut();
+
+        if self
+            .ibc_nft_transfer
+            .classes
+            .get(&class_id, *working_set)
+            .is_none()
+        {
+            self.ibc_nft_transfer
+                .ibc_class_ids_vec
+                .push(&class_id, *working_set);
+        }
+
+        self.ibc_nft_transfer.classes.set(
+            &class_id,
+            &NftClassRecord::new(class_id.clone(), class_uri, class_data),
+            *working_set,
+        );
+
+        Ok(())
+    }
+
+    /// This is called in a `send_nft_transfer()` in the case where we are the
+    /// NFT source
+    fn escrow_nft_execute(
+        &mut self,
+        _from_account: &Self::AccountId,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        _memo: &Memo,
+    ) -> Result<(), NftTransferError> {
+        let escrow_address = compute_escrow_address::<S>(port_id, channel_id);
+
+        self.set_owner(class_id, token_id, &escrow_address);
+
+        Ok(())
+    }
+
+    /// This is called in a `recv_packet()` in the case where we are the NFT
+    /// source
+    fn unescrow_nft_execute(
+        &mut self,
+        to_account: &Self::AccountId,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+    ) -> Result<(), NftTransferError> {
+        self.set_owner(class_id, token_id, &to_account.address);
+
+        Ok(())
+    }
+
+    /// This is called in a `recv_packet()` in the case where the NFT comes
+    /// from the counterparty chain, and hence its class ID is prefixed with
+    /// the destination port and channel.
+    fn mint_nft_execute(
+        &mut self,
+        account: &Self::AccountId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        token_uri: Option<&TokenUri>,
+        token_data: Option<&TokenData>,
+    ) -> Result<(), NftTransferError> {
+        let (class_id_str, token_id_str) = (class_id.to_string(), token_id.to_string());
+
+        {
+            let mut working_set = self.working_set.borrow_mut();
+
+            self.ibc_nft_transfer.nfts.set(
+                &(class_id_str.clone(), token_id_str.clone()),
+                &NftRecord::new(token_uri, token_data),
+                *working_set,
+            );
+
+            let mut token_ids = self
+                .ibc_nft_transfer
+                .class_token_ids
+                .get(&class_id_str, *working_set)
+                .unwrap_or_default();
+
+            if !token_ids.contains(&token_id_str) {
+                token_ids.push(token_id_str);
+
+                self.ibc_nft_transfer
+                    .class_token_ids
+                    .set(&class_id_str, &token_ids, *working_set);
+            }
+        }
+
+        self.set_owner(class_id, token_id, &account.address);
+
+        Ok(())
+    }
+
+    /// This is called in a `send_nft_transfer()` in the case where the NFT
+    /// goes back to the chain it came from.
+    fn burn_nft_execute(
+        &mut self,
+        _account: &Self::AccountId,
+        class_id: &PrefixedClassId,
+        token_id: &TokenId,
+        _memo: &Memo,
+    ) -> Result<(), NftTransferError> {
+        let key = (class_id.to_string(), token_id.to_string());
+
+        let mut working_set = self.working_set.borrow_mut();
+
+        self.ibc_nft_transfer.nfts.delete(&key, *working_set);
+        self.ibc_nft_transfer.nft_owners.delete(&key, *working_set);
+
+        let mut token_ids = self
+            .ibc_nft_transfer
+            .class_token_ids
+            .get(&key.0, *working_set)
+            .unwrap_or_default();
+
+        token_ids.retain(|id| id != &key.1);
+
+        self.ibc_nft_transfer
+            .class_token_ids
+            .set(&key.0, &token_ids, *working_set);
+
+        Ok(())
+    }
+}
+
+/// Address type, which wraps S::Address. This is needed to implement
+/// `TryFrom<Signer>` (circumventing the orphan rule).
+pub struct Address<S: Spec> {
+    pub address: S::Address,
+}
+
+impl<S: Spec> PartialEq for Address<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl<S: Spec> TryFrom<Signer> for Address<S> {
+    type Error = anyhow::Error;
+
+    fn try_from(signer: Signer) -> Result<Self, Self::Error> {
+        Ok(Address {
+            address: signer.as_ref().parse().map_err(|_| {
+                anyhow::anyhow!("Failed to parse signer address: {}", signer.as_ref())
+            })?,
+        })
+    }
+}
+
+impl<'ws, S: Spec, TS: TxState<S>> Module for IbcNftTransferContext<'ws, S, TS> {
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        on_chan_open_init_validate(
+            self,
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            version,
+        )
+        .map_err(|e: NftTransferError| ChannelError::AppModule {
+            description: e.to_string(),
+        })?;
+
+        Ok(ChannelVersion::new(VERSION.to_string()))
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        on_chan_open_init_execute(
+            self,
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            version,
+        )
+        .map_err(|e: NftTransferError| ChannelError::AppModule {
+            description: e.to_string(),
+        })
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        on_chan_open_try_validate(
+            self,
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            counterparty_version,
+        )
+        .map_err(|e: NftTransferError| ChannelError::AppModule {
+            description: e.to_string(),
+        })?;
+        Ok(ChannelVersion::new(VERSION.to_string()))
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        on_chan_open_try_execute(
+            self,
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            counterparty_version,
+        )
+        .map_err(|e: NftTransferError| ChannelError::AppModule {
+            description: e.to_string(),
+        })
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(), ChannelError> {
+        on_chan_open_ack_validate(self, port_id, channel_id, counterparty_version).map_err(
+            |e: NftTransferError| ChannelError::AppModule {
+                description: e.to_string(),
+            },
+        )
+    }
+
+    fn on_chan_open_ack_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty_version: &ChannelVersion,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        on_chan_open_confirm_validate(self, port_id, channel_id).map_err(|e: NftTransferError| {
+            ChannelError::AppModule {
+                description: e.to_string(),
+            }
+        })
+    }
+
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Ok(())
+    }
+
+    fn on_chan_close_init_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Ok(())
+    }
+
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        on_recv_packet_execute(self, packet)
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        on_acknowledgement_packet_validate(self, packet, acknowledgement, relayer).map_err(
+            |e: NftTransferError| PacketError::AppModule {
+                description: e.to_string(),
+            },
+        )
+    }
+
+    /// Gives the NFTs back to the sender upon an error acknowledgement.
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let res = on_acknowledgement_packet_execute(self, packet, acknowledgement, relayer);
+        (
+            res.0,
+            res.1.map_err(|e: NftTransferError| PacketError::AppModule {
+                description: e.to_string(),
+            }),
+        )
+    }
+
+    /// Note: `MsgTimeout` and `MsgTimeoutOnClose` use the same callback
+    fn on_timeout_packet_validate(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        on_timeout_packet_validate(self, packet, relayer).map_err(|e: NftTransferError| {
+            PacketError::AppModule {
+                description: e.to_string(),
+            }
+        })
+    }
+
+    /// Note: `MsgTimeout` and `MsgTimeoutOnClose` use the same callback
+    fn on_timeout_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let res = on_timeout_packet_execute(self, packet, relayer);
+        (
+            res.0,
+            res.1.map_err(|e: NftTransferError| PacketError::AppModule {
+                description: e.to_string(),
+            }),
+        )
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, bail, Result};
+use ibc_app_nft_transfer::types::{
+    ClassData, ClassId, ClassUri, PrefixedClassId, TokenData, TokenId, TokenUri,
+};
+use sov_modules_api::{GenesisState, Module, Spec, TxState};
+
+use super::{IbcNftTransfer, NftClassConfig, NftConfig, NftTransferConfig};
+use crate::types::{NftClassRecord, NftRecord};
+
+impl<S: Spec> IbcNftTransfer<S> {
+    /// Issues the NFT classes and tokens of the rollup, after checking they
+    /// are valid ICS-721 classes and tokens. Trace-prefixed class IDs are
+    /// rejected, as they are reserved for the classes created through IBC,
+    /// which are imported separately and must be trace-prefixed.
+    pub(crate) fn init_module(
+        &self,
+        config: &<Self as Module>::Config,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        for class in &config.classes {
+            self.import_class(class, false, working_set)?;
+        }
+
+        for class in &config.ibc_classes {
+            self.import_class(class, true, working_set)?;
+
+            self.ibc_class_ids_vec.push(&class.class_id, working_set);
+        }
+
+        Ok(())
+    }
+
+    fn import_class(
+        &self,
+        class: &NftClassConfig,
+        is_ibc_class: bool,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        let class_id: ClassId = class
+            .class_id
+            .parse()
+            .map_err(|e| anyhow!("Invalid class ID {}: {e}", class.class_id))?;
+
+        let prefixed_class_id: PrefixedClassId = class
+            .class_id
+            .parse()
+            .map_err(|e| anyhow!("Invalid class ID {}: {e}", class.class_id))?;
+
+        if prefixed_class_id.trace_path.is_empty() == is_ibc_class {
+            if is_ibc_class {
+                bail!("Class ID {class_id} of an IBC class is not trace-prefixed");
+            }
+
+            bail!("Class ID {class_id} is trace-prefixed, which is reserved for IBC classes");
+        }
+
+        if self.classes.get(&class.class_id, working_set).is_some() {
+            bail!("Class {class_id} is configured more than once");
+        }
+
+        let class_uri = class
+            .class_uri
+            .as_deref()
+            .map(str::parse::<ClassUri>)
+            .transpose()
+            .map_err(|e| anyhow!("Invalid URI of class {class_id}: {e}"))?;
+
+        let class_data = class
+            .class_data
+            .as_deref()
+            .map(str::parse::<ClassData>)
+            .transpose()
+            .map_err(|e| anyhow!("Invalid data of class {class_id}: {e}"))?;
+
+        self.classes.set(
+            &class.class_id,
+            &NftClassRecord::new(
+                class.class_id.clone(),
+                class_uri.as_ref(),
+                class_data.as_ref(),
+            ),
+            working_set,
+        );
+
+        if !is_ibc_class {
+            self.native_class_ids_vec.push(&class.class_id, working_set);
+        }
+
+        for nft in &class.nfts {
+            let token_id: TokenId = nft
+                .token_id
+                .parse()
+                .map_err(|e| anyhow!("Invalid token ID {}: {e}", nft.token_id))?;
+
+            let key = (class.class_id.clone(), nft.token_id.clone());
+
+            if self.nfts.get(&key, working_set).is_some() {
+                bail!("Token {token_id} of class {class_id} is configured more than once");
+            }
+
+            let token_uri = nft
+                .token_uri
+                .as_deref()
+                .map(str::parse::<TokenUri>)
+                .transpose()
+                .map_err(|e| anyhow!("Invalid URI of token {token_id}: {e}"))?;
+
+            let token_data = nft
+                .token_data
+                .as_deref()
+                .map(str::parse::<TokenData>)
+                .transpose()
+                .map_err(|e| anyhow!("Invalid data of token {token_id}: {e}"))?;
+
+            let owner: S::Address = nft
+                .owner
+                .parse()
+                .map_err(|_| anyhow!("Invalid owner of token {token_id}: {}", nft.owner))?;
+
+            self.nfts.set(
+                &key,
+                &NftRecord::new(token_uri.as_ref(), token_data.as_ref()),
+                working_set,
+            );
+            self.nft_owners.set(&key, &owner, working_set);
+        }
+
+        let token_ids = class
+            .nfts
+            .iter()
+            .map(|nft| nft.token_id.clone())
+            .collect::<Vec<_>>();
+
+        self.class_token_ids
+            .set(&class.class_id, &token_ids, working_set);
+
+        Ok(())
+    }
+
+    /// Exports the module state as a configuration that its genesis imports
+    /// back. The NFTs are exported along with their current owners, which
+    /// are the escrow addresses of the channels for the NFTs sent away.
+    pub fn export_config(&self, working_set: &mut impl TxState<S>) -> Result<NftTransferConfig> {
+        let native_class_ids = self
+            .native_class_ids_vec
+            .iter(working_set)
+            .collect::<Vec<_>>();
+
+        let ibc_class_ids = self.ibc_class_ids_vec.iter(working_set).collect::<Vec<_>>();
+
+        let mut classes = Vec::new();
+
+        for class_id in &native_class_ids {
+            classes.push(self.export_class(class_id, working_set)?);
+        }
+
+        let mut ibc_classes = Vec::new();
+
+        for class_id in &ibc_class_ids {
+            ibc_classes.push(self.export_class(class_id, working_set)?);
+        }
+
+        Ok(NftTransferConfig {
+            classes,
+            ibc_classes,
+        })
+    }
+
+    fn export_class(
+        &self,
+        class_id: &str,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<NftClassConfig> {
+        let class = self
+            .classes
+            .get(class_id, working_set)
+            .ok_or_else(|| anyhow!("Class {class_id} not found"))?;
+
+        let mut nfts = Vec::new();
+
+        for token_id in self
+            .class_token_ids
+            .get(class_id, working_set)
+            .unwrap_or_default()
+        {
+            let key = (class_id.to_string(), token_id.clone());
+
+            let nft = self
+                .nfts
+                .get(&key, working_set)
+                .ok_or_else(|| anyhow!("Token {token_id} of class {class_id} not found"))?;
+
+            let owner = self.nft_owners.get(&key, working_set).ok_or_else(|| {
+                anyhow!("Owner of token {token_id} of class {class_id} not found")
+            })?;
+
+            nfts.push(NftConfig {
+                token_id,
+                token_uri: nft.token_uri,
+                token_data: nft.token_data,
+                owner: owner.to_string(),
+            });
+        }
+
+        Ok(NftClassConfig {
+            class_id: class.class_id,
+            class_uri: class.class_uri,
+            class_data: class.class_data,
+            nfts,
+        })
+    }
+
+    /// Registers the classes issued on the rollup and the tokens held on it
+    /// that predate the registries walked through by the export. Entries
+    /// already registered are skipped, and entries that do not exist are
+    /// rejected.
+    pub fn backfill_registries(
+        &self,
+        native_class_ids: &[String],
+        tokens: &[(String, String)],
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        let mut registered = self
+            .native_class_ids_vec
+            .iter(working_set)
+            .collect::<BTreeSet<_>>();
+
+        for class_id in native_class_ids {
+            let prefixed_class_id: PrefixedClassId = class_id
+                .parse()
+                .map_err(|e| anyhow!("Invalid class ID {class_id}: {e}"))?;
+
+            if !prefixed_class_id.trace_path.is_empty()
+                || self.classes.get(class_id, working_set).is_none()
+            {
+                bail!("Class {class_id} is not a class issued on the rollup");
+            }
+
+            if registered.insert(class_id.clone()) {
+                self.native_class_ids_vec.push(class_id, working_set);
+            }
+        }
+
+        for (class_id, token_id) in tokens {
+            if self
+                .nfts
+                .get(&(class_id.clone(), token_id.clone()), working_set)
+                .is_none()
+            {
+                bail!("Token {token_id} of class {class_id} not found");
+            }
+
+            let mut token_ids = self
+                .class_token_ids
+                .get(class_id, working_set)
+                .unwrap_or_default();
+
+            if !token_ids.contains(token_id) {
+                token_ids.push(token_id.clone());
+
+                self.class_token_ids.set(class_id, &token_ids, working_set);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+pub mod context;
+mod genesis;
+pub mod types;
+pub mod utils;
+
+use anyhow::anyhow;
+use ibc_core::handler::types::events::IbcEvent;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{
+    Context, Error, GenesisState, Module, ModuleId, ModuleInfo, Spec, StateMap, StateVec, TxState,
+};
+use types::{NftClassRecord, NftRecord};
+
+#[cfg(feature = "native")]
+mod rpc;
+#[cfg(feature = "native")]
+pub use rpc::*;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct NftTransferConfig {
+    /// The NFT classes issued on the rollup, along with their tokens.
+    pub classes: Vec<NftClassConfig>,
+    /// The NFT classes previously created through IBC, keyed by their
+    /// trace-prefixed class IDs, along with their tokens. Imported when
+    /// relaunching or forking a rollup.
+    #[serde(default)]
+    pub ibc_classes: Vec<NftClassConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct NftClassConfig {
+    pub class_id: String,
+    pub class_uri: Option<String>,
+    pub class_data: Option<String>,
+    pub nfts: Vec<NftConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct NftConfig {
+    pub token_id: String,
+    pub token_uri: Option<String>,
+    pub token_data: Option<String>,
+    /// The rollup address owning the NFT.
+    pub owner: String,
+}
+
+#[derive(ModuleInfo, Clone)]
+pub struct IbcNftTransfer<S: Spec> {
+    /// Id of the module.
+    #[id]
+    pub id: ModuleId,
+
+    /// Maps the class IDs to their metadata. Classes issued on the rollup are
+    /// keyed by their base class ID, while classes created through IBC are
+    /// keyed by their trace-prefixed class ID, which is guaranteed to be
+    /// unique.
+    #[state]
+    classes: StateMap<String, NftClassRecord>,
+
+    /// Maps the class and token IDs to the metadata of the NFTs.
+    #[state]
+    nfts: StateMap<(String, String), NftRecord>,
+
+    /// Maps the class and token IDs to the current owners of the NFTs. An NFT
+    /// sent over IBC from the rollup is owned by the escrow address of its
+    /// channel until it comes back.
+    #[state]
+    nft_owners: StateMap<(String, String), S::Address>,
+
+    /// Keeps track of the trace-prefixed class IDs of the classes created
+    /// through IBC, which is used to serve the class traces.
+    #[state]
+    ibc_class_ids_vec: StateVec<String>,
+
+    /// Keeps track of the class IDs of the classes issued on the rollup, which
+    /// is used to export them.
+    #[state]
+    native_class_ids_vec: StateVec<String>,
+
+    /// Maps the class IDs to the IDs of the tokens currently held on the
+    /// rollup, which is used to export them.
+    #[state]
+    class_token_ids: StateMap<String, Vec<String>>,
+}
+
+impl<S: Spec> Module for IbcNftTransfer<S> {
+    type Spec = S;
+
+    type Config = NftTransferConfig;
+
+    type CallMessage = ();
+
+    type Event = IbcEvent;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut impl GenesisState<Self::Spec>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
+    }
+
+    fn call(
+        &self,
+        _msg: Self::CallMessage,
+        _context: &Context<Self::Spec>,
+        _working_set: &mut impl TxState<Self::Spec>,
+    ) -> Result<sov_modules_api::CallResponse, Error> {
+        Err(Error::ModuleError(anyhow!(
+            "Cannot call sov-ibc-nft-transfer; use sov-ibc instead"
+        )))
+    }
+}
+
+impl<S: Spec> core::fmt::Debug for IbcNftTransfer<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NftTransfer").field("id", &self.id).finish()
+    }
+}
@@ -0,0 +1,103 @@
+//! Defines JSON RPC methods exposed by the ibc nft transfer module
+use ibc_app_nft_transfer::types::PrefixedClassId;
+use jsonrpsee::core::RpcResult;
+use sov_ibc_utils::to_jsonrpsee_error;
+use sov_modules_api::macros::rpc_gen;
+use sov_modules_api::{Spec, WorkingSet};
+
+use super::IbcNftTransfer;
+
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+pub struct ClassTraceResponse {
+    pub class_id: String,
+    pub trace_path: String,
+    pub base_class_id: String,
+    pub class_uri: Option<String>,
+    pub class_data: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+pub struct NftOwnerResponse {
+    pub class_id: String,
+    pub token_id: String,
+    pub owner: String,
+}
+
+#[rpc_gen(client, server, namespace = "nftTransfer")]
+impl<S> IbcNftTransfer<S>
+where
+    S: Spec,
+{
+    /// Queries the trace of a class created through IBC by its trace-prefixed
+    /// class ID.
+    #[rpc_method(name = "classTrace")]
+    pub fn class_trace(
+        &self,
+        class_id: String,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<ClassTraceResponse> {
+        self.class_trace_of(&class_id, working_set)
+    }
+
+    /// Queries the traces of all the classes created through IBC.
+    #[rpc_method(name = "classTraces")]
+    pub fn class_traces(
+        &self,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<Vec<ClassTraceResponse>> {
+        self.ibc_class_ids_vec
+            .iter(working_set)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|class_id| self.class_trace_of(&class_id, working_set))
+            .collect()
+    }
+
+    /// Queries the current owner of an NFT, which is the escrow address of a
+    /// channel if the NFT was sent over it.
+    #[rpc_method(name = "nftOwner")]
+    pub fn nft_owner(
+        &self,
+        class_id: String,
+        token_id: String,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<NftOwnerResponse> {
+        let owner = self
+            .nft_owners
+            .get(&(class_id.clone(), token_id.clone()), working_set)
+            .ok_or(to_jsonrpsee_error(format!(
+                "No NFT found for class ID '{class_id}' and token ID '{token_id}'"
+            )))?;
+
+        Ok(NftOwnerResponse {
+            class_id,
+            token_id,
+            owner: owner.to_string(),
+        })
+    }
+}
+
+impl<S: Spec> IbcNftTransfer<S> {
+    fn class_trace_of(
+        &self,
+        class_id: &str,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<ClassTraceResponse> {
+        let class =
+            self.classes
+                .get(&class_id.to_string(), working_set)
+                .ok_or(to_jsonrpsee_error(format!(
+                    "No NFT class found for ID: '{class_id}'"
+                )))?;
+
+        let prefixed_class_id: PrefixedClassId = class_id.parse().map_err(to_jsonrpsee_error)?;
+
+        Ok(ClassTraceResponse {
+            class_id: class.class_id,
+            trace_path: prefixed_class_id.trace_path.to_string(),
+            base_class_id: prefixed_class_id.base_class_id.to_string(),
+            class_uri: class.class_uri,
+            class_data: class.class_data,
+        })
+    }
+}
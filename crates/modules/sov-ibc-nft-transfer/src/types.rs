@@ -0,0 +1,128 @@
+//! Defines the NFT classes and tokens held by the `IbcNftTransfer` module.
+use borsh::{BorshDeserialize, BorshSerialize};
+use ibc_app_nft_transfer::context::{NftClassContext, NftContext};
+use ibc_app_nft_transfer::types::error::NftTransferError;
+use ibc_app_nft_transfer::types::{ClassData, ClassId, ClassUri, TokenData, TokenId, TokenUri};
+use serde::{Deserialize, Serialize};
+
+/// The stored metadata of an NFT class, kept in its string form as received
+/// in the packets.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftClassRecord {
+    pub class_id: String,
+    pub class_uri: Option<String>,
+    pub class_data: Option<String>,
+}
+
+impl NftClassRecord {
+    pub fn new(
+        class_id: String,
+        class_uri: Option<&ClassUri>,
+        class_data: Option<&ClassData>,
+    ) -> Self {
+        Self {
+            class_id,
+            class_uri: class_uri.map(ToString::to_string),
+            class_data: class_data.map(ToString::to_string),
+        }
+    }
+}
+
+/// The stored metadata of an NFT, kept in its string form as received in the
+/// packets.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftRecord {
+    pub token_uri: Option<String>,
+    pub token_data: Option<String>,
+}
+
+impl NftRecord {
+    pub fn new(token_uri: Option<&TokenUri>, token_data: Option<&TokenData>) -> Self {
+        Self {
+            token_uri: token_uri.map(ToString::to_string),
+            token_data: token_data.map(ToString::to_string),
+        }
+    }
+}
+
+/// An NFT class, as handed over to the ICS-721 handlers.
+#[derive(Clone, Debug)]
+pub struct NftClass {
+    pub class_id: ClassId,
+    pub class_uri: Option<ClassUri>,
+    pub class_data: Option<ClassData>,
+}
+
+impl TryFrom<NftClassRecord> for NftClass {
+    type Error = NftTransferError;
+
+    fn try_from(record: NftClassRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            class_id: parse(&record.class_id)?,
+            class_uri: record.class_uri.as_deref().map(parse).transpose()?,
+            class_data: record.class_data.as_deref().map(parse).transpose()?,
+        })
+    }
+}
+
+impl NftClassContext for NftClass {
+    fn get_id(&self) -> &ClassId {
+        &self.class_id
+    }
+
+    fn get_uri(&self) -> Option<&ClassUri> {
+        self.class_uri.as_ref()
+    }
+
+    fn get_data(&self) -> Option<&ClassData> {
+        self.class_data.as_ref()
+    }
+}
+
+/// An NFT, as handed over to the ICS-721 handlers.
+#[derive(Clone, Debug)]
+pub struct Nft {
+    pub class_id: ClassId,
+    pub token_id: TokenId,
+    pub token_uri: Option<TokenUri>,
+    pub token_data: Option<TokenData>,
+}
+
+impl Nft {
+    pub fn try_new(
+        class_id: &str,
+        token_id: &str,
+        record: NftRecord,
+    ) -> Result<Self, NftTransferError> {
+        Ok(Self {
+            class_id: parse(class_id)?,
+            token_id: parse(token_id)?,
+            token_uri: record.token_uri.as_deref().map(parse).transpose()?,
+            token_data: record.token_data.as_deref().map(parse).transpose()?,
+        })
+    }
+}
+
+impl NftContext for Nft {
+    fn get_class_id(&self) -> &ClassId {
+        &self.class_id
+    }
+
+    fn get_id(&self) -> &TokenId {
+        &self.token_id
+    }
+
+    fn get_uri(&self) -> Option<&TokenUri> {
+        self.token_uri.as_ref()
+    }
+
+    fn get_data(&self) -> Option<&TokenData> {
+        self.token_data.as_ref()
+    }
+}
+
+fn parse<T: core::str::FromStr>(value: &str) -> Result<T, NftTransferError> {
+    value
+        .parse()
+        .map_err(|_| NftTransferError::Other(format!("invalid stored NFT value: {value}")))
+}
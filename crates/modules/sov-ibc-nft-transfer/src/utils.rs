@@ -0,0 +1,10 @@
+use ibc_app_nft_transfer::types::VERSION;
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use sov_ibc_utils::derive_module_address;
+use sov_modules_api::Spec;
+
+/// The escrow address of a channel is derived from the ICS-721 version, so
+/// that the NFT and the fungible token escrows of a channel never collide.
+pub fn compute_escrow_address<S: Spec>(port_id: &PortId, channel_id: &ChannelId) -> S::Address {
+    derive_module_address::<S, _>(VERSION, &format!("{port_id}/{channel_id}"))
+}
@@ -0,0 +1,27 @@
+use ibc_core::channel::types::error::PacketError;
+use thiserror::Error;
+
+/// Errors raised by the packet-forward middleware.
+#[derive(Debug, Error)]
+pub enum PacketForwardError {
+    #[error("invalid forward metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("invalid packet data: {0}")]
+    InvalidPacketData(String),
+    #[error("failed to forward packet: {0}")]
+    ForwardFailed(String),
+    #[error("failed to refund forwarded packet: {0}")]
+    RefundFailed(String),
+    #[error("failed to write acknowledgement: {0}")]
+    WriteAcknowledgementFailed(String),
+    #[error("acknowledgement of packet {0} is held back and was already withheld")]
+    AcknowledgementAlreadyWithheld(String),
+}
+
+impl From<PacketForwardError> for PacketError {
+    fn from(e: PacketForwardError) -> Self {
+        PacketError::AppModule {
+            description: e.to_string(),
+        }
+    }
+}
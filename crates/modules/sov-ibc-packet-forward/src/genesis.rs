@@ -0,0 +1,109 @@
+use anyhow::{bail, Result};
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use sov_modules_api::{GenesisState, Module, Spec, TxState};
+
+use super::{ForwardedPacket, IbcPacketForward, PacketForwardConfig};
+use crate::types::HeldAcknowledgement;
+
+impl<S: Spec> IbcPacketForward<S> {
+    /// Imports the packets in flight through the rollup, holding back the
+    /// acknowledgements of the inbound packets they originate from.
+    pub(crate) fn init_module(
+        &self,
+        config: &<Self as Module>::Config,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        for forwarded in &config.forwarded_packets {
+            let forwarded_packet = (
+                forwarded.port_id.clone(),
+                forwarded.channel_id.clone(),
+                forwarded.sequence,
+            );
+
+            let inbound_packet = &forwarded.in_flight_packet.packet;
+
+            let inbound_key = (
+                inbound_packet.port_id_on_b.clone(),
+                inbound_packet.chan_id_on_b.clone(),
+                inbound_packet.seq_on_a,
+            );
+
+            if self
+                .in_flight_packets
+                .get(&forwarded_packet, working_set)
+                .is_some()
+                || self
+                    .held_acknowledgements
+                    .get(&inbound_key, working_set)
+                    .is_some()
+            {
+                bail!(
+                    "Forwarded packet {} on channel {} or the packet it originates from is imported more than once",
+                    forwarded.sequence,
+                    forwarded.channel_id
+                );
+            }
+
+            self.in_flight_packets
+                .set(&forwarded_packet, &forwarded.in_flight_packet, working_set);
+
+            self.held_acknowledgements.set(
+                &inbound_key,
+                &HeldAcknowledgement {
+                    forwarded_packet,
+                    ack_withheld: forwarded.ack_withheld,
+                    event_withheld: forwarded.event_withheld,
+                },
+                working_set,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Exports the module state as a configuration that its genesis imports
+    /// back. The in-flight packets are looked up among the given packets sent
+    /// by the rollup, as they are not kept track of across channels.
+    pub fn export_config(
+        &self,
+        sent_packets: &[(PortId, ChannelId, Sequence)],
+        working_set: &mut impl TxState<S>,
+    ) -> Result<PacketForwardConfig> {
+        let mut forwarded_packets = Vec::new();
+
+        for (port_id, channel_id, sequence) in sent_packets {
+            let forwarded_packet = (port_id.clone(), channel_id.clone(), *sequence);
+
+            let Some(in_flight_packet) = self.in_flight_packets.get(&forwarded_packet, working_set)
+            else {
+                continue;
+            };
+
+            let inbound_packet = &in_flight_packet.packet;
+
+            let Some(held_ack) = self.held_acknowledgements.get(
+                &(
+                    inbound_packet.port_id_on_b.clone(),
+                    inbound_packet.chan_id_on_b.clone(),
+                    inbound_packet.seq_on_a,
+                ),
+                working_set,
+            ) else {
+                bail!(
+                    "Held acknowledgement of the packet forwarded as {sequence} on channel {channel_id} not found"
+                );
+            };
+
+            forwarded_packets.push(ForwardedPacket {
+                port_id: port_id.clone(),
+                channel_id: channel_id.clone(),
+                sequence: *sequence,
+                in_flight_packet,
+                ack_withheld: held_ack.ack_withheld,
+                event_withheld: held_ack.event_withheld,
+            });
+        }
+
+        Ok(PacketForwardConfig { forwarded_packets })
+    }
+}
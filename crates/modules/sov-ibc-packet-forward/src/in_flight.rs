@@ -0,0 +1,150 @@
+//! Defines the bookkeeping of the packets in flight through the rollup, whose
+//! inbound acknowledgements are held back by the `IbcPacketForward` module.
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::primitives::Signer;
+use sov_ibc_transfer::rate_limit::FlowInstant;
+use sov_modules_api::{Spec, TxState};
+
+use crate::error::PacketForwardError;
+use crate::types::{HeldAcknowledgement, InFlightPacket};
+use crate::IbcPacketForward;
+
+impl<S: Spec> IbcPacketForward<S> {
+    /// Returns whether the acknowledgement of the given inbound packet is held
+    /// back until the packet it was forwarded as settles.
+    pub fn is_acknowledgement_held(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> bool {
+        self.held_acknowledgements
+            .get(
+                &(port_id.clone(), channel_id.clone(), sequence),
+                working_set,
+            )
+            .is_some()
+    }
+
+    /// Withholds the acknowledgement the application returned upon receipt of
+    /// the given inbound packet, if its acknowledgement is held back. Hosts
+    /// must not store the acknowledgement when this returns `true`. Fails if
+    /// the acknowledgement of the packet was already withheld, as the
+    /// acknowledgement to be stored would then be lost.
+    pub fn withhold_acknowledgement(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<bool, PacketForwardError> {
+        self.withhold(port_id, channel_id, sequence, working_set, |held| {
+            &mut held.ack_withheld
+        })
+    }
+
+    /// Withholds the event of the acknowledgement the application returned
+    /// upon receipt of the given inbound packet, in the same way as
+    /// [`Self::withhold_acknowledgement`].
+    pub fn withhold_acknowledgement_event(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<bool, PacketForwardError> {
+        self.withhold(port_id, channel_id, sequence, working_set, |held| {
+            &mut held.event_withheld
+        })
+    }
+
+    fn withhold(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+        withheld: impl FnOnce(&mut HeldAcknowledgement) -> &mut bool,
+    ) -> Result<bool, PacketForwardError> {
+        let key = (port_id.clone(), channel_id.clone(), sequence);
+
+        let Some(mut held_ack) = self.held_acknowledgements.get(&key, working_set) else {
+            return Ok(false);
+        };
+
+        let withheld = withheld(&mut held_ack);
+
+        if *withheld {
+            return Err(PacketForwardError::AcknowledgementAlreadyWithheld(format!(
+                "{sequence} on channel {channel_id} and port {port_id}"
+            )));
+        }
+
+        *withheld = true;
+
+        self.held_acknowledgements.set(&key, &held_ack, working_set);
+
+        Ok(true)
+    }
+
+    /// Records that the given inbound packet, received at the given instant,
+    /// was forwarded as the given packet, and holds its acknowledgement back.
+    pub(crate) fn hold_acknowledgement(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+        received_at: FlowInstant,
+        forwarded_packet: (PortId, ChannelId, Sequence),
+        working_set: &mut impl TxState<S>,
+    ) {
+        self.held_acknowledgements.set(
+            &(
+                packet.port_id_on_b.clone(),
+                packet.chan_id_on_b.clone(),
+                packet.seq_on_a,
+            ),
+            &HeldAcknowledgement::new(forwarded_packet.clone()),
+            working_set,
+        );
+
+        self.in_flight_packets.set(
+            &forwarded_packet,
+            &InFlightPacket {
+                packet: packet.clone(),
+                relayer: relayer.clone(),
+                received_at,
+            },
+            working_set,
+        );
+    }
+
+    /// Removes the inbound packet the given packet was forwarded from, if any,
+    /// and releases its acknowledgement so that it can be written.
+    pub(crate) fn release_acknowledgement(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> Option<InFlightPacket> {
+        let forwarded_packet = (port_id.clone(), channel_id.clone(), sequence);
+
+        let in_flight_packet = self.in_flight_packets.get(&forwarded_packet, working_set)?;
+
+        self.in_flight_packets
+            .delete(&forwarded_packet, working_set);
+
+        self.held_acknowledgements.delete(
+            &(
+                in_flight_packet.packet.port_id_on_b.clone(),
+                in_flight_packet.packet.chan_id_on_b.clone(),
+                in_flight_packet.packet.seq_on_a,
+            ),
+            working_set,
+        );
+
+        Some(in_flight_packet)
+    }
+}
@@ -0,0 +1,101 @@
+pub mod error;
+mod genesis;
+pub mod in_flight;
+pub mod middleware;
+pub mod types;
+pub mod utils;
+
+use anyhow::anyhow;
+use ibc_core::handler::types::events::IbcEvent;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{
+    Context, Error, GenesisState, Module, ModuleId, ModuleInfo, Spec, StateMap, TxState,
+};
+
+use crate::types::{HeldAcknowledgement, InFlightPacket};
+
+/// The genesis configuration of the `IbcPacketForward` module, which may
+/// import the packets in flight through the rollup. The forwarded packets are
+/// those imported by the `Ibc` module, whose genesis must run first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct PacketForwardConfig {
+    /// The packets forwarded by the rollup that are still in flight, imported
+    /// when relaunching or forking a rollup.
+    #[serde(default)]
+    pub forwarded_packets: Vec<ForwardedPacket>,
+}
+
+/// A packet forwarded by the rollup, along with the inbound packet it
+/// originates from, whose acknowledgement is held back.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ForwardedPacket {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub sequence: Sequence,
+    pub in_flight_packet: InFlightPacket,
+    /// Whether the acknowledgement of the inbound packet was withheld from
+    /// the store.
+    pub ack_withheld: bool,
+    /// Whether the event of the acknowledgement of the inbound packet was
+    /// withheld.
+    pub event_withheld: bool,
+}
+
+#[derive(ModuleInfo, Clone)]
+pub struct IbcPacketForward<S: Spec> {
+    /// Id of the module.
+    #[id]
+    pub id: ModuleId,
+
+    /// Maps the packets forwarded by the rollup to the inbound packets they
+    /// originate from.
+    #[state]
+    pub in_flight_packets: StateMap<(PortId, ChannelId, Sequence), InFlightPacket>,
+
+    /// Maps the inbound packets whose acknowledgement is held back to the
+    /// packets they were forwarded as. The acknowledgements of these packets
+    /// are only written once the forwarded packets settle.
+    #[state]
+    pub held_acknowledgements: StateMap<(PortId, ChannelId, Sequence), HeldAcknowledgement>,
+
+    #[phantom]
+    _phantom: core::marker::PhantomData<S>,
+}
+
+impl<S: Spec> Module for IbcPacketForward<S> {
+    type Spec = S;
+
+    type Config = PacketForwardConfig;
+
+    type CallMessage = ();
+
+    type Event = IbcEvent;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut impl GenesisState<Self::Spec>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
+    }
+
+    fn call(
+        &self,
+        _msg: Self::CallMessage,
+        _context: &Context<Self::Spec>,
+        _working_set: &mut impl TxState<Self::Spec>,
+    ) -> Result<sov_modules_api::CallResponse, Error> {
+        Err(Error::ModuleError(anyhow!(
+            "Cannot call sov-ibc-packet-forward; use sov-ibc instead"
+        )))
+    }
+}
+
+impl<S: Spec> core::fmt::Debug for IbcPacketForward<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketForward")
+            .field("id", &self.id)
+            .finish()
+    }
+}
@@ -0,0 +1,506 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_app_transfer::types::msgs::transfer::MsgTransfer;
+use ibc_app_transfer::types::packet::PacketData;
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::handler::types::error::ContextError;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::host::types::path::SeqSendPath;
+use ibc_core::host::ExecutionContext;
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::types::module::ModuleExtras;
+use sov_ibc_transfer::context::IbcTransferContext;
+use sov_ibc_transfer::utils::{error_acknowledgement, is_ack_successful, received_coin};
+use sov_modules_api::{Spec, TxState};
+
+use crate::error::PacketForwardError;
+use crate::types::ForwardMetadata;
+use crate::utils::compute_intermediate_address;
+use crate::IbcPacketForward;
+
+/// Writes the acknowledgements of the inbound packets held back by the
+/// packet-forward middleware, once the packets they were forwarded as settle.
+///
+/// Implementors must write the acknowledgement the same way the core handlers
+/// would have upon receipt, including any wrapping by the middlewares above the
+/// packet-forward one.
+pub trait AcknowledgementWriter {
+    fn write_acknowledgement(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+        acknowledgement: Acknowledgement,
+    ) -> Result<(), ContextError>;
+}
+
+/// The packet-forward middleware, which wraps the ICS-20 transfer application
+/// to let tokens hop through the rollup, following the `forward` memo format of
+/// `ibc-go`'s packet-forward middleware.
+///
+/// The tokens of an inbound packet carrying forwarding instructions are
+/// received by an intermediate account, which sends them onwards. The
+/// acknowledgement of the inbound packet is held back until the forwarded
+/// packet is acknowledged or timed out. If the forwarded packet fails, the
+/// tokens refunded to the intermediate account are taken back the way they
+/// came in, and an error acknowledgement is written for the inbound packet, so
+/// that the sending chain refunds the original sender in turn.
+pub struct PacketForwardMiddleware<'ws, S: Spec, TS: TxState<S>, C> {
+    pub ibc_packet_forward: IbcPacketForward<S>,
+    pub transfer_ctx: IbcTransferContext<'ws, S, TS>,
+    pub ibc_ctx: C,
+    pub working_set: Rc<RefCell<&'ws mut TS>>,
+}
+
+impl<'ws, S, TS, C> PacketForwardMiddleware<'ws, S, TS, C>
+where
+    S: Spec,
+    TS: TxState<S>,
+    C: ExecutionContext + AcknowledgementWriter,
+{
+    pub fn new(
+        ibc_packet_forward: IbcPacketForward<S>,
+        transfer_ctx: IbcTransferContext<'ws, S, TS>,
+        ibc_ctx: C,
+        working_set: Rc<RefCell<&'ws mut TS>>,
+    ) -> Self {
+        Self {
+            ibc_packet_forward,
+            transfer_ctx,
+            ibc_ctx,
+            working_set,
+        }
+    }
+
+    /// Sends the tokens an intermediate account received onwards, and holds
+    /// the acknowledgement of the inbound packet back.
+    fn forward_packet(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+        data: &PacketData,
+        forward: &ForwardMetadata,
+        intermediate_address: &S::Address,
+    ) -> Result<(), PacketForwardError> {
+        let port_id_on_a = forward.port_id()?;
+        let chan_id_on_a = forward.channel_id()?;
+
+        let seq_on_a = self
+            .ibc_ctx
+            .get_next_sequence_send(&SeqSendPath::new(&port_id_on_a, &chan_id_on_a))
+            .map_err(|e| PacketForwardError::ForwardFailed(e.to_string()))?;
+
+        let timeout_timestamp_on_b = (self
+            .ibc_ctx
+            .host_timestamp()
+            .map_err(|e| PacketForwardError::ForwardFailed(e.to_string()))?
+            + forward.timeout())
+        .map_err(|e| PacketForwardError::ForwardFailed(e.to_string()))?;
+
+        let msg_transfer = MsgTransfer {
+            port_id_on_a: port_id_on_a.clone(),
+            chan_id_on_a: chan_id_on_a.clone(),
+            packet_data: PacketData {
+                token: received_coin(packet, data),
+                sender: Signer::from(intermediate_address.to_string()),
+                receiver: Signer::from(forward.receiver.clone()),
+                memo: forward.next_memo(),
+            },
+            timeout_height_on_b: TimeoutHeight::Never,
+            timeout_timestamp_on_b,
+        };
+
+        self.transfer_ctx
+            .send_transfer(&mut self.ibc_ctx, msg_transfer)
+            .map_err(|e| PacketForwardError::ForwardFailed(e.to_string()))?;
+
+        self.ibc_packet_forward.hold_acknowledgement(
+            packet,
+            relayer,
+            self.transfer_ctx.flow_instant(),
+            (port_id_on_a, chan_id_on_a, seq_on_a),
+            *self.working_set.borrow_mut(),
+        );
+
+        Ok(())
+    }
+
+    /// Writes the acknowledgement of the inbound packet the given packet was
+    /// forwarded from, if any. The acknowledgement of the forwarded packet is
+    /// passed on if it is successful. Otherwise, which includes timeouts
+    /// (`None`), the inbound tokens are taken back and an error is written.
+    ///
+    /// Only forwarded packets acknowledged with an error are refunded, to the
+    /// intermediate account, before their tokens are taken back, as the
+    /// transfer application does not act upon acknowledgements. Timed out
+    /// packets were already refunded by the transfer application.
+    fn settle_forwarded_packet(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: Option<&Acknowledgement>,
+        relayer: &Signer,
+    ) -> Result<ModuleExtras, PacketForwardError> {
+        let Some(in_flight_packet) = self.ibc_packet_forward.release_acknowledgement(
+            &packet.port_id_on_a,
+            &packet.chan_id_on_a,
+            packet.seq_on_a,
+            *self.working_set.borrow_mut(),
+        ) else {
+            return Ok(ModuleExtras::empty());
+        };
+
+        let mut extras = ModuleExtras::empty();
+
+        let inbound_ack = match acknowledgement {
+            Some(ack) if is_ack_successful(ack) => ack.clone(),
+            _ => {
+                if let Some(ack) = acknowledgement {
+                    extras = self
+                        .transfer_ctx
+                        .refund_on_error_acknowledgement(packet, ack, relayer)
+                        .map_err(|e| PacketForwardError::RefundFailed(e.to_string()))?;
+                }
+
+                let data = decode_packet_data(&in_flight_packet.packet)?;
+
+                let intermediate_address = compute_intermediate_address::<S>(
+                    &in_flight_packet.packet.chan_id_on_b,
+                    data.sender.as_ref(),
+                );
+
+                self.transfer_ctx
+                    .revert_receipt_at(
+                        &in_flight_packet.packet,
+                        &data,
+                        &intermediate_address,
+                        in_flight_packet.received_at,
+                    )
+                    .map_err(|e| PacketForwardError::RefundFailed(e.to_string()))?;
+
+                match acknowledgement {
+                    Some(ack) => error_acknowledgement(format!(
+                        "forwarded packet failed: {}",
+                        String::from_utf8_lossy(ack.as_ref())
+                    )),
+                    None => error_acknowledgement("forwarded packet timed out"),
+                }
+            }
+        };
+
+        self.ibc_ctx
+            .write_acknowledgement(
+                &in_flight_packet.packet,
+                &in_flight_packet.relayer,
+                inbound_ack,
+            )
+            .map_err(|e| PacketForwardError::WriteAcknowledgementFailed(e.to_string()))?;
+
+        Ok(extras)
+    }
+}
+
+fn decode_packet_data(packet: &Packet) -> Result<PacketData, PacketForwardError> {
+    serde_json::from_slice(&packet.data)
+        .map_err(|e| PacketForwardError::InvalidPacketData(e.to_string()))
+}
+
+impl<'ws, S, TS, C> core::fmt::Debug for PacketForwardMiddleware<'ws, S, TS, C>
+where
+    S: Spec,
+    TS: TxState<S>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketForwardMiddleware")
+            .field("packet_forward_mod", &self.ibc_packet_forward)
+            .field("transfer_ctx", &self.transfer_ctx)
+            .finish()
+    }
+}
+
+impl<'ws, S, TS, C> Module for PacketForwardMiddleware<'ws, S, TS, C>
+where
+    S: Spec,
+    TS: TxState<S>,
+    C: ExecutionContext + AcknowledgementWriter,
+{
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        self.transfer_ctx.on_chan_open_init_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            version,
+        )
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        self.transfer_ctx.on_chan_open_init_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            version,
+        )
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        self.transfer_ctx.on_chan_open_try_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            counterparty_version,
+        )
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        self.transfer_ctx.on_chan_open_try_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            counterparty_version,
+        )
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(), ChannelError> {
+        self.transfer_ctx
+            .on_chan_open_ack_validate(port_id, channel_id, counterparty_version)
+    }
+
+    fn on_chan_open_ack_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.transfer_ctx
+            .on_chan_open_ack_execute(port_id, channel_id, counterparty_version)
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.transfer_ctx
+            .on_chan_open_confirm_validate(port_id, channel_id)
+    }
+
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.transfer_ctx
+            .on_chan_open_confirm_execute(port_id, channel_id)
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.transfer_ctx
+            .on_chan_close_init_validate(port_id, channel_id)
+    }
+
+    fn on_chan_close_init_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.transfer_ctx
+            .on_chan_close_init_execute(port_id, channel_id)
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.transfer_ctx
+            .on_chan_close_confirm_validate(port_id, channel_id)
+    }
+
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.transfer_ctx
+            .on_chan_close_confirm_execute(port_id, channel_id)
+    }
+
+    /// Forwards the tokens of a packet carrying forwarding instructions in its
+    /// memo, by having an intermediate account receive them and send them
+    /// onwards. The returned acknowledgement is then held back by the host
+    /// until the forwarded packet settles. Any other packet is passed through
+    /// to the transfer application.
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let Ok(data) = decode_packet_data(packet) else {
+            return self.transfer_ctx.on_recv_packet_execute(packet, relayer);
+        };
+
+        let forward = match ForwardMetadata::from_memo(&data.memo) {
+            Ok(Some(forward)) => forward,
+            Ok(None) => return self.transfer_ctx.on_recv_packet_execute(packet, relayer),
+            Err(e) => return (ModuleExtras::empty(), error_acknowledgement(e)),
+        };
+
+        let intermediate_address =
+            compute_intermediate_address::<S>(&packet.chan_id_on_b, data.sender.as_ref());
+
+        let intermediate_data = PacketData {
+            receiver: Signer::from(intermediate_address.to_string()),
+            ..data.clone()
+        };
+
+        let intermediate_packet = Packet {
+            data: serde_json::to_vec(&intermediate_data)
+                .expect("never fails as the packet data was just decoded"),
+            ..packet.clone()
+        };
+
+        let (extras, ack) = self
+            .transfer_ctx
+            .on_recv_packet_execute(&intermediate_packet, relayer);
+
+        if !is_ack_successful(&ack) {
+            return (extras, ack);
+        }
+
+        if let Err(forward_err) =
+            self.forward_packet(packet, relayer, &data, &forward, &intermediate_address)
+        {
+            let revert_res = self
+                .transfer_ctx
+                .revert_receipt(packet, &data, &intermediate_address);
+
+            let description = match revert_res {
+                Ok(()) => forward_err.to_string(),
+                Err(revert_err) => format!("{forward_err}; {revert_err}"),
+            };
+
+            return (ModuleExtras::empty(), error_acknowledgement(description));
+        }
+
+        (extras, ack)
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        self.transfer_ctx
+            .on_acknowledgement_packet_validate(packet, acknowledgement, relayer)
+    }
+
+    /// Writes the acknowledgement of the inbound packet, if the packet was
+    /// forwarded, before passing the acknowledgement on to the transfer
+    /// application.
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let mut extras = match self.settle_forwarded_packet(packet, Some(acknowledgement), relayer)
+        {
+            Ok(extras) => extras,
+            Err(e) => return (ModuleExtras::empty(), Err(PacketError::from(e))),
+        };
+
+        let (app_extras, res) =
+            self.transfer_ctx
+                .on_acknowledgement_packet_execute(packet, acknowledgement, relayer);
+
+        extras.events.extend(app_extras.events);
+        extras.log.extend(app_extras.log);
+
+        (extras, res)
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        self.transfer_ctx
+            .on_timeout_packet_validate(packet, relayer)
+    }
+
+    /// Writes an error acknowledgement for the inbound packet, if the packet
+    /// was forwarded, once the transfer application refunded it.
+    fn on_timeout_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let (extras, res) = self.transfer_ctx.on_timeout_packet_execute(packet, relayer);
+
+        if res.is_err() {
+            return (extras, res);
+        }
+
+        let res = self
+            .settle_forwarded_packet(packet, None, relayer)
+            .map(|_| ())
+            .map_err(PacketError::from);
+
+        (extras, res)
+    }
+}
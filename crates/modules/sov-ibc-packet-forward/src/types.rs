@@ -0,0 +1,205 @@
+//! Defines the forwarding instructions carried in the ICS-20 memos, following
+//! the format of `ibc-go`'s packet-forward middleware, and the packets in
+//! flight through the rollup.
+use core::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use ibc_app_transfer::types::Memo;
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::primitives::Signer;
+use serde::{Deserialize, Serialize};
+use sov_ibc_transfer::rate_limit::FlowInstant;
+
+use crate::error::PacketForwardError;
+
+/// The key of the forwarding instructions in the ICS-20 memos.
+pub const FORWARD_MEMO_KEY: &str = "forward";
+
+/// The relative timeout of the forwarded packets when the memo does not set
+/// one, which is the default of `ibc-go`'s packet-forward middleware.
+pub const DEFAULT_FORWARD_TIMEOUT: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+
+/// The forwarding instructions of an inbound transfer, found under the
+/// `forward` key of its memo.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardMetadata {
+    /// The receiver of the tokens on the next chain.
+    pub receiver: String,
+    /// The port the tokens are forwarded over.
+    pub port: String,
+    /// The channel the tokens are forwarded over.
+    pub channel: String,
+    /// The timeout of the forwarded packet in nanoseconds, relative to the
+    /// rollup timestamp at which it is sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// The memo of the forwarded packet, which may carry the forwarding
+    /// instructions of the next hop.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<serde_json::Value>,
+}
+
+impl ForwardMetadata {
+    /// Parses the forwarding instructions out of an ICS-20 memo. Returns
+    /// `None` if the memo does not carry any, meaning the transfer is meant
+    /// for the rollup, and fails if it carries malformed ones.
+    pub fn from_memo(memo: &Memo) -> Result<Option<Self>, PacketForwardError> {
+        let Ok(serde_json::Value::Object(memo)) = serde_json::from_str(memo.as_ref()) else {
+            return Ok(None);
+        };
+
+        let Some(forward) = memo.get(FORWARD_MEMO_KEY) else {
+            return Ok(None);
+        };
+
+        let metadata: Self = serde_json::from_value(forward.clone())
+            .map_err(|e| PacketForwardError::InvalidMetadata(e.to_string()))?;
+
+        if metadata.receiver.is_empty() {
+            return Err(PacketForwardError::InvalidMetadata(
+                "receiver cannot be empty".to_string(),
+            ));
+        }
+
+        metadata.port_id()?;
+        metadata.channel_id()?;
+
+        Ok(Some(metadata))
+    }
+
+    pub fn port_id(&self) -> Result<PortId, PacketForwardError> {
+        self.port
+            .parse()
+            .map_err(|e| PacketForwardError::InvalidMetadata(format!("invalid port: {e}")))
+    }
+
+    pub fn channel_id(&self) -> Result<ChannelId, PacketForwardError> {
+        self.channel
+            .parse()
+            .map_err(|e| PacketForwardError::InvalidMetadata(format!("invalid channel: {e}")))
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+            .map(Duration::from_nanos)
+            .unwrap_or(DEFAULT_FORWARD_TIMEOUT)
+    }
+
+    /// Returns the memo of the forwarded packet. The next hop may be given
+    /// either as a JSON object or as its string encoding.
+    pub fn next_memo(&self) -> Memo {
+        match &self.next {
+            None => Memo::from(String::new()),
+            Some(serde_json::Value::String(next)) => Memo::from(next.clone()),
+            Some(next) => Memo::from(next.to_string()),
+        }
+    }
+}
+
+/// An inbound packet whose tokens were forwarded, and whose acknowledgement is
+/// held back until the forwarded packet gets acknowledged or timed out.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightPacket {
+    /// The inbound packet.
+    pub packet: Packet,
+    /// The relayer of the inbound packet, which the acknowledgement is
+    /// eventually written on behalf of.
+    pub relayer: Signer,
+    /// The instant the inbound packet was received at, which its inflow is
+    /// taken back from the window of if the forward fails.
+    pub received_at: FlowInstant,
+}
+
+/// The acknowledgement of an inbound packet held back until the packet it was
+/// forwarded as settles.
+///
+/// The core handlers store and emit the acknowledgement the application
+/// returns upon receipt exactly once each, which the host withholds. Any
+/// further attempt to store or emit an acknowledgement for the packet while it
+/// is held back is an error rather than being dropped.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HeldAcknowledgement {
+    /// The packet the inbound packet was forwarded as.
+    pub forwarded_packet: (PortId, ChannelId, Sequence),
+    /// Whether the acknowledgement returned upon receipt was withheld from
+    /// the store.
+    pub ack_withheld: bool,
+    /// Whether the event of the acknowledgement returned upon receipt was
+    /// withheld.
+    pub event_withheld: bool,
+}
+
+impl HeldAcknowledgement {
+    pub fn new(forwarded_packet: (PortId, ChannelId, Sequence)) -> Self {
+        Self {
+            forwarded_packet,
+            ack_withheld: false,
+            event_withheld: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_metadata_from_memo() {
+        let memo = Memo::from(
+            r#"{"forward":{"receiver":"cosmos1receiver","port":"transfer","channel":"channel-1","timeout":600000000000,"next":{"forward":{"receiver":"osmo1receiver","port":"transfer","channel":"channel-7"}}}}"#
+                .to_string(),
+        );
+
+        let metadata = ForwardMetadata::from_memo(&memo).unwrap().unwrap();
+
+        assert_eq!(metadata.receiver, "cosmos1receiver");
+        assert_eq!(metadata.port_id().unwrap(), PortId::transfer());
+        assert_eq!(metadata.channel_id().unwrap(), ChannelId::new(1));
+        assert_eq!(metadata.timeout(), Duration::from_secs(600));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(metadata.next_memo().as_ref()).unwrap(),
+            serde_json::json!({
+                "forward": {
+                    "receiver": "osmo1receiver",
+                    "port": "transfer",
+                    "channel": "channel-7"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_forward_metadata_defaults() {
+        let memo = Memo::from(
+            r#"{"forward":{"receiver":"cosmos1receiver","port":"transfer","channel":"channel-1","next":"{\"wasm\":{}}"}}"#
+                .to_string(),
+        );
+
+        let metadata = ForwardMetadata::from_memo(&memo).unwrap().unwrap();
+
+        assert_eq!(metadata.timeout(), DEFAULT_FORWARD_TIMEOUT);
+        assert_eq!(metadata.next_memo().as_ref(), r#"{"wasm":{}}"#);
+    }
+
+    #[test]
+    fn test_memo_without_forward_metadata() {
+        for memo in ["", "a plain memo", r#"{"wasm":{}}"#, "[1,2,3]"] {
+            assert_eq!(
+                ForwardMetadata::from_memo(&Memo::from(memo.to_string())).unwrap(),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_forward_metadata() {
+        for memo in [
+            r#"{"forward":{"receiver":"cosmos1receiver","port":"transfer"}}"#,
+            r#"{"forward":{"receiver":"","port":"transfer","channel":"channel-1"}}"#,
+            r#"{"forward":{"receiver":"cosmos1receiver","port":"transfer","channel":"chan"}}"#,
+        ] {
+            assert!(ForwardMetadata::from_memo(&Memo::from(memo.to_string())).is_err());
+        }
+    }
+}
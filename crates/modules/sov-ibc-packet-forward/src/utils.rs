@@ -0,0 +1,20 @@
+use ibc_core::host::types::identifiers::ChannelId;
+use sov_ibc_utils::derive_module_address;
+use sov_modules_api::Spec;
+
+/// Domain separator of the intermediate addresses, matching the one of
+/// `ibc-go`'s packet-forward middleware.
+const INTERMEDIATE_ADDRESS_PREFIX: &str = "pfm";
+
+/// The intermediate address receives the tokens of an inbound packet to be
+/// forwarded, and sends them onwards. As in `ibc-go`'s packet-forward
+/// middleware, it is derived from the inbound channel and the original sender.
+pub fn compute_intermediate_address<S: Spec>(
+    channel_id: &ChannelId,
+    original_sender: &str,
+) -> S::Address {
+    derive_module_address::<S, _>(
+        INTERMEDIATE_ADDRESS_PREFIX,
+        &format!("{channel_id}/{original_sender}"),
+    )
+}
@@ -3,23 +3,29 @@ use std::rc::Rc;
 use std::str::FromStr;
 
 use ibc_app_transfer::context::{TokenTransferExecutionContext, TokenTransferValidationContext};
+use ibc_app_transfer::handler::send_transfer;
 use ibc_app_transfer::module::{
-    on_acknowledgement_packet_validate, on_chan_open_ack_validate, on_chan_open_confirm_validate,
-    on_chan_open_init_execute, on_chan_open_init_validate, on_chan_open_try_execute,
-    on_chan_open_try_validate, on_recv_packet_execute, on_timeout_packet_execute,
-    on_timeout_packet_validate,
+    on_acknowledgement_packet_execute, on_acknowledgement_packet_validate,
+    on_chan_open_ack_validate, on_chan_open_confirm_validate, on_chan_open_init_execute,
+    on_chan_open_init_validate, on_chan_open_try_execute, on_chan_open_try_validate,
+    on_recv_packet_execute, on_timeout_packet_execute, on_timeout_packet_validate,
 };
 use ibc_app_transfer::types::error::TokenTransferError;
+use ibc_app_transfer::types::msgs::transfer::MsgTransfer;
+use ibc_app_transfer::types::packet::PacketData;
 use ibc_app_transfer::types::{
-    Amount, Memo, PrefixedCoin, PrefixedDenom, TracePrefix, PORT_ID_STR, VERSION,
+    is_receiver_chain_source, Amount, Memo, PrefixedCoin, PrefixedDenom, TracePrefix, PORT_ID_STR,
+    VERSION,
 };
+use ibc_core::channel::context::SendPacketExecutionContext;
 use ibc_core::channel::types::acknowledgement::Acknowledgement;
 use ibc_core::channel::types::channel::{Counterparty, Order};
 use ibc_core::channel::types::error::{ChannelError, PacketError};
 use ibc_core::channel::types::packet::Packet;
 use ibc_core::channel::types::Version as ChannelVersion;
 use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
-use ibc_core::primitives::Signer;
+use ibc_core::host::types::path::SeqSendPath;
+use ibc_core::primitives::{Signer, Timestamp};
 use ibc_core::router::module::Module;
 use ibc_core::router::types::module::ModuleExtras;
 use sov_bank::{Coins, IntoPayable, Payable, TokenId};
@@ -27,7 +33,10 @@ use sov_modules_api::{Context, ModuleId, Spec, TxState};
 use uint::FromDecStrErr;
 
 use super::IbcTransfer;
-use crate::utils::compute_escrow_address;
+use crate::rate_limit::{voucher_channel, FlowDirection, FlowInstant};
+use crate::utils::{
+    compute_escrow_address, error_acknowledgement, is_ack_successful, received_coin,
+};
 
 /// Using a different salt will result in a different token address. Since
 /// ICS-20 tokens coming from other chains are guaranteed to have unique names,
@@ -45,6 +54,9 @@ const MAXIMUM_MEMO_LENGTH: usize = 32768; // 1 << 15
 pub struct IbcTransferContext<'ws, S: Spec, TS: TxState<S>> {
     pub ibc_transfer: IbcTransfer<S>,
     pub sdk_context: Context<S>,
+    /// The host timestamp, against which the rate limits measured in seconds
+    /// are enforced.
+    pub host_timestamp: Option<Timestamp>,
     pub working_set: Rc<RefCell<&'ws mut TS>>,
 }
 
@@ -57,10 +69,204 @@ impl<'ws, S: Spec, TS: TxState<S>> IbcTransferContext<'ws, S, TS> {
         Self {
             ibc_transfer,
             sdk_context,
+            host_timestamp: None,
             working_set,
         }
     }
 
+    /// Sets the host timestamp. Transfers of denoms whose rate limit is
+    /// measured in seconds are rejected without it.
+    pub fn with_host_timestamp(mut self, host_timestamp: Timestamp) -> Self {
+        self.host_timestamp = Some(host_timestamp);
+        self
+    }
+
+    /// Sends a transfer through the ICS-20 handler, and tags its outflow with
+    /// the instant it is recorded at, so that a refund only takes the outflow
+    /// back from the window it counts in.
+    pub fn send_transfer<C: SendPacketExecutionContext>(
+        &mut self,
+        send_packet_ctx: &mut C,
+        msg: MsgTransfer,
+    ) -> Result<(), TokenTransferError> {
+        let port_id = msg.port_id_on_a.clone();
+        let channel_id = msg.chan_id_on_a.clone();
+        let denom = msg.packet_data.token.denom.to_string();
+
+        let sequence = send_packet_ctx
+            .get_next_sequence_send(&SeqSendPath::new(&port_id, &channel_id))
+            .map_err(TokenTransferError::ContextError)?;
+
+        send_transfer(send_packet_ctx, self, msg)?;
+
+        let mut working_set = self.working_set.borrow_mut();
+
+        if self
+            .ibc_transfer
+            .rate_limit(&channel_id, &denom, *working_set)
+            .is_some()
+        {
+            self.ibc_transfer.outflow_instants.set(
+                &(port_id, channel_id, sequence),
+                &self.flow_instant(),
+                *working_set,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Takes back the tokens the given account received for an inbound packet
+    /// the way they came in: tokens native to the rollup go back to the escrow
+    /// of the inbound channel, while vouchers are burnt. This is used by the
+    /// middlewares that redirect inbound transfers to intermediate accounts,
+    /// before acknowledging them with an error.
+    pub fn revert_receipt(
+        &mut self,
+        packet: &Packet,
+        data: &PacketData,
+        account: &S::Address,
+    ) -> Result<(), TokenTransferError> {
+        let received_at = self.flow_instant();
+
+        self.revert_receipt_at(packet, data, account, received_at)
+    }
+
+    /// Same as [`IbcTransferContext::revert_receipt`], for a packet received
+    /// in an earlier call, whose inflow is taken back from the window of the
+    /// instant it was received at.
+    pub fn revert_receipt_at(
+        &mut self,
+        packet: &Packet,
+        data: &PacketData,
+        account: &S::Address,
+        received_at: FlowInstant,
+    ) -> Result<(), TokenTransferError> {
+        let coin = received_coin(packet, data);
+
+        let account = Address {
+            address: account.clone(),
+        };
+
+        if is_receiver_chain_source(
+            packet.port_id_on_a.clone(),
+            packet.chan_id_on_a.clone(),
+            &data.token.denom,
+        ) {
+            self.escrow_coins(&account, &packet.port_id_on_b, &packet.chan_id_on_b, &coin)?;
+        } else {
+            self.burn_coins(&account, &coin)?;
+        }
+
+        if let Some((denom, amount)) = received_flow(packet) {
+            self.ibc_transfer.undo_flow(
+                &packet.chan_id_on_b,
+                &denom,
+                FlowDirection::Inflow,
+                amount,
+                received_at,
+                *self.working_set.borrow_mut(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the instant the flows of the current call are recorded at.
+    pub fn flow_instant(&self) -> FlowInstant {
+        FlowInstant {
+            slot: self.sdk_context.visible_slot_number(),
+            timestamp: self
+                .host_timestamp
+                .map(|timestamp| timestamp.nanoseconds() / 1_000_000_000),
+        }
+    }
+
+    /// Records a flow of the given coin over the given channel against its
+    /// rate limit, if any.
+    fn record_flow(
+        &self,
+        channel_id: &ChannelId,
+        coin: &PrefixedCoin,
+        direction: FlowDirection,
+    ) -> Result<(), TokenTransferError> {
+        let amount: sov_bank::Amount = (*coin.amount.as_ref())
+            .try_into()
+            .map_err(|_| TokenTransferError::InvalidAmount(FromDecStrErr::InvalidLength))?;
+
+        self.ibc_transfer.record_flow(
+            channel_id,
+            &coin.denom.to_string(),
+            direction,
+            amount,
+            self.flow_instant(),
+            *self.working_set.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Refunds the sender of the given packet if it was acknowledged with an
+    /// error, taking its outflow back.
+    pub fn refund_on_error_acknowledgement(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> Result<ModuleExtras, TokenTransferError> {
+        if is_ack_successful(acknowledgement) {
+            return Ok(ModuleExtras::empty());
+        }
+
+        let (extras, res) =
+            on_acknowledgement_packet_execute(self, packet, acknowledgement, relayer);
+
+        res?;
+
+        self.undo_outflow(packet);
+
+        Ok(extras)
+    }
+
+    /// Takes back the outflow recorded for a sent packet whose tokens are
+    /// refunded, from the window it was recorded in.
+    fn undo_outflow(&self, packet: &Packet) {
+        let Some(sent_at) = self.take_outflow_instant(packet) else {
+            return;
+        };
+
+        if let Some((denom, amount)) = sent_flow(packet) {
+            self.ibc_transfer.undo_flow(
+                &packet.chan_id_on_a,
+                &denom,
+                FlowDirection::Outflow,
+                amount,
+                sent_at,
+                *self.working_set.borrow_mut(),
+            );
+        }
+    }
+
+    /// Removes and returns the instant the outflow of a sent packet was
+    /// recorded at, if it was rate-limited.
+    fn take_outflow_instant(&self, packet: &Packet) -> Option<FlowInstant> {
+        let key = (
+            packet.port_id_on_a.clone(),
+            packet.chan_id_on_a.clone(),
+            packet.seq_on_a,
+        );
+
+        let mut working_set = self.working_set.borrow_mut();
+
+        let sent_at = self.ibc_transfer.outflow_instants.get(&key, *working_set)?;
+
+        self.ibc_transfer
+            .outflow_instants
+            .delete(&key, *working_set);
+
+        Some(sent_at)
+    }
+
     /// Stores mapping from "denom to token ID" and vice versa for an
     /// IBC-created token.
     fn record_minted_token(&self, token_id: TokenId, token_name: String) {
@@ -75,6 +281,10 @@ impl<'ws, S: Spec, TS: TxState<S>> IbcTransferContext<'ws, S, TS> {
             &token_id,
             *self.working_set.borrow_mut(),
         );
+
+        self.ibc_transfer
+            .minted_token_vec
+            .push(&token_id, *self.working_set.borrow_mut());
     }
 
     /// Validate that the token is native and **not** an IBC-created token by
@@ -243,6 +453,61 @@ impl<'ws, S: Spec, TS: TxState<S>> IbcTransferContext<'ws, S, TS> {
 
         Ok(())
     }
+
+    /// Burns the given vouchers of the account.
+    fn burn_coins(
+        &self,
+        account: &Address<S>,
+        coin: &PrefixedCoin,
+    ) -> Result<(), TokenTransferError> {
+        // The token was created by the IBC module, and the ICS-20 denom was
+        // stored in the token name. Hence, we need to use the token name as
+        // denom.
+        let token_id = self.get_ibc_token_id(coin)?;
+
+        let amount: sov_bank::Amount = (*coin.amount.as_ref())
+            .try_into()
+            .map_err(|_| TokenTransferError::InvalidAmount(FromDecStrErr::InvalidLength))?;
+        let sdk_coins = Coins { amount, token_id };
+
+        self.ibc_transfer
+            .bank
+            .burn(sdk_coins, &account.address, *self.working_set.borrow_mut())
+            .map_err(|err| TokenTransferError::Other(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Transfers the given native tokens of the account to the escrow of the
+    /// given channel.
+    fn escrow_coins(
+        &self,
+        from_account: &Address<S>,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        coin: &PrefixedCoin,
+    ) -> Result<(), TokenTransferError> {
+        // The token name on the Sovereign SDK chains is not guaranteed to be
+        // unique, and hence we must use the token ID (which is guaranteed to be
+        // unique) as the ICS-20 denom to ensure uniqueness.
+        let token_id = TokenId::from_str(&coin.denom.to_string()).map_err(|_| {
+            TokenTransferError::InvalidCoin {
+                coin: coin.to_string(),
+            }
+        })?;
+
+        let escrow_account = self.obtain_escrow_address(port_id, channel_id);
+
+        // transfer coins to escrow account
+        self.transfer(
+            token_id,
+            &from_account.address,
+            escrow_account.to_payable(),
+            &coin.amount,
+        )?;
+
+        Ok(())
+    }
 }
 
 impl<'ws, S, TS> core::fmt::Debug for IbcTransferContext<'ws, S, TS>
@@ -401,29 +666,19 @@ impl<'ws, S: Spec, TS: TxState<S>> TokenTransferExecutionContext
     }
 
     /// This is called in a `send_transfer()` in the case where we are NOT the
-    /// token source
+    /// token source. The outflow is recorded on the channel the voucher was
+    /// received over, which is the one it is sent back over.
     fn burn_coins_execute(
         &mut self,
         account: &Self::AccountId,
         coin: &PrefixedCoin,
         _memo: &Memo,
     ) -> Result<(), TokenTransferError> {
-        // The token was created by the IBC module, and the ICS-20 denom was
-        // stored in the token name. Hence, we need to use the token name as
-        // denom.
-        let token_id = self.get_ibc_token_id(coin)?;
-
-        let amount: sov_bank::Amount = (*coin.amount.as_ref())
-            .try_into()
-            .map_err(|_| TokenTransferError::InvalidAmount(FromDecStrErr::InvalidLength))?;
-        let sdk_coins = Coins { amount, token_id };
-
-        self.ibc_transfer
-            .bank
-            .burn(sdk_coins, &account.address, *self.working_set.borrow_mut())
-            .map_err(|err| TokenTransferError::Other(err.to_string()))?;
+        if let Some(channel_id) = voucher_channel(&coin.denom) {
+            self.record_flow(&channel_id, coin, FlowDirection::Outflow)?;
+        }
 
-        Ok(())
+        self.burn_coins(account, coin)
     }
 
     /// This is called in a `send_transfer()` in the case where we are the token source
@@ -435,26 +690,9 @@ impl<'ws, S: Spec, TS: TxState<S>> TokenTransferExecutionContext
         coin: &PrefixedCoin,
         _memo: &Memo,
     ) -> Result<(), TokenTransferError> {
-        // The token name on the Sovereign SDK chains is not guaranteed to be
-        // unique, and hence we must use the token ID (which is guaranteed to be
-        // unique) as the ICS-20 denom to ensure uniqueness.
-        let token_id = TokenId::from_str(&coin.denom.to_string()).map_err(|_| {
-            TokenTransferError::InvalidCoin {
-                coin: coin.to_string(),
-            }
-        })?;
-
-        let escrow_account = self.obtain_escrow_address(port_id, channel_id);
-
-        // transfer coins to escrow account
-        self.transfer(
-            token_id,
-            &from_account.address,
-            escrow_account.to_payable(),
-            &coin.amount,
-        )?;
+        self.record_flow(channel_id, coin, FlowDirection::Outflow)?;
 
-        Ok(())
+        self.escrow_coins(from_account, port_id, channel_id, coin)
     }
 
     /// This is called in a `recv_packet()` in the case where we are the token source.
@@ -675,12 +913,42 @@ impl<'ws, S: Spec, TS: TxState<S>> Module for IbcTransferContext<'ws, S, TS> {
         Ok(ModuleExtras::empty())
     }
 
+    /// Rejects the packet with an error acknowledgement if its tokens take
+    /// the net inflow of their denom over the channel quota.
     fn on_recv_packet_execute(
         &mut self,
         packet: &Packet,
         _relayer: &Signer,
     ) -> (ModuleExtras, Acknowledgement) {
-        on_recv_packet_execute(self, packet)
+        let inflow = received_flow(packet);
+
+        if let Some((denom, amount)) = &inflow {
+            if let Err(e) = self.ibc_transfer.record_flow(
+                &packet.chan_id_on_b,
+                denom,
+                FlowDirection::Inflow,
+                *amount,
+                self.flow_instant(),
+                *self.working_set.borrow_mut(),
+            ) {
+                return (ModuleExtras::empty(), error_acknowledgement(e));
+            }
+        }
+
+        let (extras, ack) = on_recv_packet_execute(self, packet);
+
+        if let Some((denom, amount)) = inflow.filter(|_| !is_ack_successful(&ack)) {
+            self.ibc_transfer.undo_flow(
+                &packet.chan_id_on_b,
+                &denom,
+                FlowDirection::Inflow,
+                amount,
+                self.flow_instant(),
+                *self.working_set.borrow_mut(),
+            );
+        }
+
+        (extras, ack)
     }
 
     fn on_acknowledgement_packet_validate(
@@ -696,12 +964,18 @@ impl<'ws, S: Spec, TS: TxState<S>> Module for IbcTransferContext<'ws, S, TS> {
         )
     }
 
+    /// Acknowledgements are not acted upon, except for the packets forwarded
+    /// by the rollup, which the packet-forward middleware refunds through
+    /// [`IbcTransferContext::refund_on_error_acknowledgement`] beforehand. The
+    /// packet settles either way, so its outflow can no longer be taken back.
     fn on_acknowledgement_packet_execute(
         &mut self,
-        _packet: &Packet,
+        packet: &Packet,
         _acknowledgement: &Acknowledgement,
         _relayer: &Signer,
     ) -> (ModuleExtras, Result<(), PacketError>) {
+        self.take_outflow_instant(packet);
+
         (ModuleExtras::empty(), Ok(()))
     }
 
@@ -725,6 +999,11 @@ impl<'ws, S: Spec, TS: TxState<S>> Module for IbcTransferContext<'ws, S, TS> {
         relayer: &Signer,
     ) -> (ModuleExtras, Result<(), PacketError>) {
         let res = on_timeout_packet_execute(self, packet, relayer);
+
+        if res.1.is_ok() {
+            self.undo_outflow(packet);
+        }
+
         (
             res.0,
             res.1
@@ -734,3 +1013,21 @@ impl<'ws, S: Spec, TS: TxState<S>> Module for IbcTransferContext<'ws, S, TS> {
         )
     }
 }
+
+/// Returns the rollup denom and the amount an inbound packet credits, if its
+/// data is valid.
+fn received_flow(packet: &Packet) -> Option<(String, sov_bank::Amount)> {
+    let data = serde_json::from_slice::<PacketData>(&packet.data).ok()?;
+    let amount = (*data.token.amount.as_ref()).try_into().ok()?;
+
+    Some((received_coin(packet, &data).denom.to_string(), amount))
+}
+
+/// Returns the rollup denom and the amount an outbound packet debited, if its
+/// data is valid.
+fn sent_flow(packet: &Packet) -> Option<(String, sov_bank::Amount)> {
+    let data = serde_json::from_slice::<PacketData>(&packet.data).ok()?;
+    let amount = (*data.token.amount.as_ref()).try_into().ok()?;
+
+    Some((data.token.denom.to_string(), amount))
+}
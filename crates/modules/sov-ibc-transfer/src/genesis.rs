@@ -1,14 +1,239 @@
-use anyhow::Result;
-use sov_modules_api::{GenesisState, Module, Spec};
+use std::str::FromStr;
 
-use super::IbcTransfer;
+use anyhow::{anyhow, bail, Result};
+use ibc_app_transfer::types::PrefixedDenom;
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use sov_bank::TokenId;
+use sov_modules_api::{GenesisState, Module, Spec, TxState};
+
+use super::{EscrowAccount, IbcTransfer, MintedToken, TransferConfig};
+use crate::utils::compute_escrow_address;
 
 impl<S: Spec> IbcTransfer<S> {
+    /// Stores the rate limit admin and the quotas the rollup starts with,
+    /// along with the imported minted tokens and escrow accounts. Minted
+    /// tokens must already exist in the bank, and escrow accounts must be the
+    /// ones derived for their channels.
     pub(crate) fn init_module(
         &self,
-        _config: &<Self as Module>::Config,
-        _working_set: &mut impl GenesisState<S>,
+        config: &<Self as Module>::Config,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        if let Some(admin) = &config.rate_limit_admin {
+            let admin: S::Address = admin
+                .parse()
+                .map_err(|_| anyhow!("Invalid rate limit admin address: {admin}"))?;
+
+            self.rate_limit_admin.set(&admin, working_set);
+        }
+
+        for rate_limit in &config.rate_limits {
+            rate_limit.validate()?;
+
+            let key = (rate_limit.channel_id.clone(), rate_limit.denom.clone());
+
+            if self.rate_limits.get(&key, working_set).is_some() {
+                bail!(
+                    "Rate limit of denom {} on channel {} is configured more than once",
+                    rate_limit.denom,
+                    rate_limit.channel_id
+                );
+            }
+
+            self.rate_limits.set(&key, rate_limit, working_set);
+            self.rate_limit_vec.push(&key, working_set);
+        }
+
+        for minted_token in &config.minted_tokens {
+            let denom = PrefixedDenom::from_str(&minted_token.token_name)
+                .map_err(|e| anyhow!("Invalid minted token {}: {e}", minted_token.token_name))?;
+
+            if denom.trace_path.is_empty() {
+                bail!("Minted token {} has no trace path", minted_token.token_name);
+            }
+
+            // The vouchers themselves are held by the bank, whose genesis
+            // must have created them.
+            if self
+                .bank
+                .get_total_supply_of(&minted_token.token_id, working_set)
+                .is_none()
+            {
+                bail!(
+                    "Minted token {} has no token {} in the bank",
+                    minted_token.token_name,
+                    minted_token.token_id
+                );
+            }
+
+            if self
+                .minted_token_name_to_id
+                .get(&minted_token.token_name, working_set)
+                .is_some()
+                || self
+                    .minted_token_id_to_name
+                    .get(&minted_token.token_id, working_set)
+                    .is_some()
+            {
+                bail!(
+                    "Minted token {} or its ID {} is imported more than once",
+                    minted_token.token_name,
+                    minted_token.token_id
+                );
+            }
+
+            self.minted_token_name_to_id.set(
+                &minted_token.token_name,
+                &minted_token.token_id,
+                working_set,
+            );
+            self.minted_token_id_to_name.set(
+                &minted_token.token_id,
+                &minted_token.token_name,
+                working_set,
+            );
+            self.minted_token_vec
+                .push(&minted_token.token_id, working_set);
+        }
+
+        for escrow_account in &config.escrow_accounts {
+            let key = (
+                escrow_account.port_id.clone(),
+                escrow_account.channel_id.clone(),
+            );
+
+            if self.escrow_address_cache.get(&key, working_set).is_some() {
+                bail!(
+                    "Escrow account of channel {} on port {} is imported more than once",
+                    escrow_account.channel_id,
+                    escrow_account.port_id
+                );
+            }
+
+            let expected_escrow_id =
+                compute_escrow_address::<S>(&escrow_account.port_id, &escrow_account.channel_id);
+
+            if escrow_account.escrow_id != expected_escrow_id {
+                bail!(
+                    "Escrow account {} of channel {} on port {} is not the one derived for it, {expected_escrow_id}",
+                    escrow_account.escrow_id,
+                    escrow_account.channel_id,
+                    escrow_account.port_id
+                );
+            }
+
+            self.escrow_address_cache
+                .set(&key, &escrow_account.escrow_id, working_set);
+        }
+
+        Ok(())
+    }
+
+    /// Exports the module state as a configuration that its genesis imports
+    /// back. The escrow accounts are looked up for the given channels, as the
+    /// escrow cache cannot be walked through. The flows accumulated within
+    /// the current windows of the quotas are not exported, so the imported
+    /// quotas start with fresh windows.
+    pub fn export_config(
+        &self,
+        channels: &[(PortId, ChannelId)],
+        working_set: &mut impl TxState<S>,
+    ) -> Result<TransferConfig> {
+        let rate_limit_admin = self
+            .rate_limit_admin
+            .get(working_set)
+            .map(|admin| admin.to_string());
+
+        let rate_limits = self
+            .rate_limit_vec
+            .iter(working_set)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|key| self.rate_limits.get(&key, working_set))
+            .collect();
+
+        let minted_tokens = self
+            .minted_token_vec
+            .iter(working_set)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|token_id| {
+                let token_name = self
+                    .minted_token_id_to_name
+                    .get(&token_id, working_set)
+                    .ok_or_else(|| anyhow!("Name of minted token {token_id} not found"))?;
+
+                Ok(MintedToken {
+                    token_name,
+                    token_id,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let escrow_accounts = channels
+            .iter()
+            .filter_map(|(port_id, channel_id)| {
+                self.escrow_address_cache
+                    .get(&(port_id.clone(), channel_id.clone()), working_set)
+                    .map(|escrow_id| EscrowAccount {
+                        port_id: port_id.clone(),
+                        channel_id: channel_id.clone(),
+                        escrow_id,
+                    })
+            })
+            .collect();
+
+        Ok(TransferConfig {
+            rate_limit_admin,
+            rate_limits,
+            minted_tokens,
+            escrow_accounts,
+        })
+    }
+
+    /// Registers the minted tokens and the rate limits that predate the
+    /// registries walked through by the export. Entries already registered
+    /// are skipped, and entries that do not exist are rejected.
+    pub fn backfill_registries(
+        &self,
+        minted_token_ids: &[TokenId],
+        rate_limit_keys: &[(ChannelId, String)],
+        working_set: &mut impl TxState<S>,
     ) -> Result<()> {
+        let mut registered_token_ids = self.minted_token_vec.iter(working_set).collect::<Vec<_>>();
+
+        for token_id in minted_token_ids {
+            if self
+                .minted_token_id_to_name
+                .get(token_id, working_set)
+                .is_none()
+            {
+                bail!("Token {token_id} was not minted through IBC");
+            }
+
+            if !registered_token_ids.contains(token_id) {
+                self.minted_token_vec.push(token_id, working_set);
+                registered_token_ids.push(*token_id);
+            }
+        }
+
+        let mut registered_keys = self.rate_limit_vec.iter(working_set).collect::<Vec<_>>();
+
+        for key in rate_limit_keys {
+            if self.rate_limits.get(key, working_set).is_none() {
+                bail!(
+                    "No rate limit of denom {} is set on channel {}",
+                    key.1,
+                    key.0
+                );
+            }
+
+            if !registered_keys.contains(key) {
+                self.rate_limit_vec.push(key, working_set);
+                registered_keys.push(key.clone());
+            }
+        }
+
         Ok(())
     }
 }
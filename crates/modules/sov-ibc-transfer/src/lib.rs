@@ -1,23 +1,57 @@
 pub mod context;
 mod genesis;
+pub mod rate_limit;
 pub mod utils;
 
 use anyhow::anyhow;
 use ibc_core::handler::types::events::IbcEvent;
-use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
 use serde::{Deserialize, Serialize};
 use sov_bank::TokenId;
 use sov_modules_api::{
-    Context, Error, GenesisState, Module, ModuleId, ModuleInfo, Spec, StateMap, TxState,
+    Context, Error, GenesisState, Module, ModuleId, ModuleInfo, Spec, StateMap, StateValue,
+    StateVec, TxState,
 };
 
+use crate::rate_limit::{FlowInstant, RateLimit, RateLimitUsage};
+
 #[cfg(feature = "native")]
 mod rpc;
 #[cfg(feature = "native")]
 pub use rpc::*;
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TransferConfig {
+    /// The rollup address allowed to set and lift the rate limits, if any.
+    #[serde(default)]
+    pub rate_limit_admin: Option<String>,
+    /// The rate limits the rollup starts with.
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimit>,
+    /// The tokens previously minted through IBC, imported when relaunching
+    /// or forking a rollup.
+    #[serde(default)]
+    pub minted_tokens: Vec<MintedToken>,
+    /// The escrow accounts previously used by the channels, imported when
+    /// relaunching or forking a rollup.
+    #[serde(default)]
+    pub escrow_accounts: Vec<EscrowAccount>,
+}
+
+/// A token minted through IBC, known by the full trace path of its denom.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct MintedToken {
+    pub token_name: String,
+    pub token_id: TokenId,
+}
+
+/// The account escrowing the native tokens sent over a channel.
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
-pub struct TransferConfig {}
+pub struct EscrowAccount {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub escrow_id: ModuleId,
+}
 
 #[derive(ModuleInfo, Clone)]
 pub struct IbcTransfer<S: Spec> {
@@ -43,11 +77,39 @@ pub struct IbcTransfer<S: Spec> {
     #[state]
     minted_token_id_to_name: StateMap<TokenId, String>,
 
+    /// Lists the IDs of the tokens minted through IBC, so that the minted
+    /// tokens can be walked through when exporting the module state.
+    #[state]
+    minted_token_vec: StateVec<TokenId>,
+
     /// Keeps track of escrow addresses associated with a specific port and
     /// channel pair, offering an efficient means to access these addresses
     /// without the need for re-computation during every packet processing.
     #[state]
     escrow_address_cache: StateMap<(PortId, ChannelId), ModuleId>,
+
+    /// The address allowed to set and lift the rate limits.
+    #[state]
+    rate_limit_admin: StateValue<S::Address>,
+
+    /// Maps a channel and a rollup denom to the quota of their flows.
+    #[state]
+    rate_limits: StateMap<(ChannelId, String), RateLimit>,
+
+    /// Lists the channel and rollup denom pairs having a quota, so that the
+    /// quotas can be walked through when exporting the module state.
+    #[state]
+    rate_limit_vec: StateVec<(ChannelId, String)>,
+
+    /// Maps a channel and a rollup denom to their flows accumulated within the
+    /// current window of their quota.
+    #[state]
+    rate_limit_usage: StateMap<(ChannelId, String), RateLimitUsage>,
+
+    /// Maps the sent packets whose outflow is rate-limited to the instant it
+    /// was recorded at, until they get acknowledged or timed out.
+    #[state]
+    outflow_instants: StateMap<(PortId, ChannelId, Sequence), FlowInstant>,
 }
 
 impl<S: Spec> Module for IbcTransfer<S> {
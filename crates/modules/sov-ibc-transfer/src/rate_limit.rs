@@ -0,0 +1,512 @@
+//! Defines the rate limits of the ICS-20 transfers, which bound the net flow of
+//! each denom over each channel within a sliding window of slots or seconds, as
+//! a circuit breaker against bridge exploits.
+use borsh::{BorshDeserialize, BorshSerialize};
+use ibc_app_transfer::types::error::TokenTransferError;
+use ibc_app_transfer::types::PrefixedDenom;
+use ibc_core::host::types::identifiers::ChannelId;
+use serde::{Deserialize, Serialize};
+use sov_bank::Amount;
+use sov_modules_api::{Spec, TxState};
+use thiserror::Error;
+
+use crate::IbcTransfer;
+
+/// The length of the sliding window over which the flows of a rate-limited
+/// denom are accumulated.
+///
+/// The sliding window is approximated with two buckets: the flows are
+/// accumulated over fixed windows aligned on multiples of the length, and the
+/// flows of the previous fixed window count in proportion to how much of it
+/// the sliding window ending at the current instant still covers. Unlike
+/// fixed windows alone, this does not let twice the quota through around the
+/// boundary of two windows.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitWindow {
+    /// A window measured in rollup slots.
+    Slots(u64),
+    /// A window measured in seconds of host time.
+    Seconds(u64),
+}
+
+impl RateLimitWindow {
+    pub fn length(&self) -> u64 {
+        match self {
+            Self::Slots(length) | Self::Seconds(length) => *length,
+        }
+    }
+
+    /// Returns the slot or the host time in seconds of the given instant,
+    /// depending on the unit of the window, if known.
+    pub fn position(&self, instant: FlowInstant) -> Option<u64> {
+        match self {
+            Self::Slots(_) => Some(instant.slot),
+            Self::Seconds(_) => instant.timestamp,
+        }
+    }
+
+    /// Returns the start of the fixed window the given position falls within.
+    pub fn start_of(&self, position: u64) -> u64 {
+        position - position % self.length()
+    }
+}
+
+/// The quota of a denom over a channel. The denom is the one known on the
+/// rollup, that is the token ID for the tokens native to the rollup and the
+/// full trace path for the vouchers.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub channel_id: ChannelId,
+    pub denom: String,
+    pub window: RateLimitWindow,
+    /// The maximum net amount received over the channel within a window.
+    pub max_inflow: Amount,
+    /// The maximum net amount sent over the channel within a window.
+    pub max_outflow: Amount,
+}
+
+impl RateLimit {
+    pub fn validate(&self) -> Result<(), RateLimitError> {
+        if self.window.length() == 0 {
+            return Err(RateLimitError::EmptyWindow {
+                channel_id: self.channel_id.to_string(),
+                denom: self.denom.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// The flows of a rate-limited denom accumulated over the current fixed window
+/// of its quota and the previous one. See [`RateLimitWindow`].
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct RateLimitUsage {
+    /// The slot or the host time in seconds, depending on the window, at
+    /// which the current fixed window started.
+    pub window_start: u64,
+    pub inflow: Amount,
+    pub outflow: Amount,
+    #[serde(default)]
+    pub previous_inflow: Amount,
+    #[serde(default)]
+    pub previous_outflow: Amount,
+}
+
+impl RateLimitUsage {
+    /// Returns the usage as of the given position, moving on to the fixed
+    /// window it falls within if the current one has elapsed.
+    pub fn at(self, window: &RateLimitWindow, position: u64) -> Self {
+        let window_start = window.start_of(position);
+
+        if window_start == self.window_start {
+            return self;
+        }
+
+        if window_start == self.window_start.saturating_add(window.length()) {
+            return Self {
+                window_start,
+                previous_inflow: self.inflow,
+                previous_outflow: self.outflow,
+                ..Default::default()
+            };
+        }
+
+        Self {
+            window_start,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the inflow and the outflow within the sliding window ending at
+    /// the given position, which must fall within the current fixed window.
+    pub fn sliding_flows(&self, window: &RateLimitWindow, position: u64) -> (Amount, Amount) {
+        let length = u128::from(window.length());
+
+        let covered = length.saturating_sub(u128::from(position.saturating_sub(self.window_start)));
+
+        let weigh = |previous: Amount| (u128::from(previous) * covered / length) as Amount;
+
+        (
+            self.inflow.saturating_add(weigh(self.previous_inflow)),
+            self.outflow.saturating_add(weigh(self.previous_outflow)),
+        )
+    }
+
+    pub fn net_inflow(&self, window: &RateLimitWindow, position: u64) -> Amount {
+        let (inflow, outflow) = self.sliding_flows(window, position);
+
+        inflow.saturating_sub(outflow)
+    }
+
+    pub fn net_outflow(&self, window: &RateLimitWindow, position: u64) -> Amount {
+        let (inflow, outflow) = self.sliding_flows(window, position);
+
+        outflow.saturating_sub(inflow)
+    }
+}
+
+/// The direction of a flow of tokens over a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlowDirection {
+    Inflow,
+    Outflow,
+}
+
+/// The slot and the host time at which a flow is recorded. Flows are tagged
+/// with it, so that they can only be taken back from the fixed window they
+/// were recorded in.
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct FlowInstant {
+    pub slot: u64,
+    /// The host time in seconds, if known.
+    pub timestamp: Option<u64>,
+}
+
+/// Sets the quota of a denom over a channel, restarting its window. Only the
+/// rate limit admin may send it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgSetRateLimit {
+    pub rate_limit: RateLimit,
+}
+
+/// Lifts the quota of a denom over a channel. Only the rate limit admin may
+/// send it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgRemoveRateLimit {
+    pub channel_id: ChannelId,
+    pub denom: String,
+}
+
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    #[error("rate limit window of denom {denom} on channel {channel_id} cannot be empty")]
+    EmptyWindow { channel_id: String, denom: String },
+    #[error("{direction:?} quota of denom {denom} on channel {channel_id} exceeded: {attempted} over {quota}")]
+    QuotaExceeded {
+        channel_id: String,
+        denom: String,
+        direction: FlowDirection,
+        attempted: Amount,
+        quota: Amount,
+    },
+    #[error("host time is unknown; cannot measure the rate limit window of denom {denom} on channel {channel_id}")]
+    UnknownHostTime { channel_id: String, denom: String },
+    #[error("amount {amount} of denom {denom} overflows the rate limit bookkeeping")]
+    InvalidAmount { amount: String, denom: String },
+    #[error("no rate limit admin is configured")]
+    NoAdmin,
+    #[error("sender {sender} is not the rate limit admin")]
+    Unauthorized { sender: String },
+    #[error("no rate limit set for denom {denom} on channel {channel_id}")]
+    RateLimitNotFound { channel_id: String, denom: String },
+}
+
+impl From<RateLimitError> for TokenTransferError {
+    fn from(e: RateLimitError) -> Self {
+        TokenTransferError::Other(e.to_string())
+    }
+}
+
+/// Returns the rollup-side channel of a voucher denom, which is the one its
+/// outermost trace prefix names. The tokens the rollup mints are prefixed with
+/// their receiving channel, and the ones it burns with their sending channel.
+pub fn voucher_channel(denom: &PrefixedDenom) -> Option<ChannelId> {
+    denom.trace_path.to_string().split('/').nth(1)?.parse().ok()
+}
+
+impl<S: Spec> IbcTransfer<S> {
+    /// Returns the quota of the given denom over the given channel, if any.
+    pub fn rate_limit(
+        &self,
+        channel_id: &ChannelId,
+        denom: &str,
+        working_set: &mut impl TxState<S>,
+    ) -> Option<RateLimit> {
+        self.rate_limits
+            .get(&(channel_id.clone(), denom.to_string()), working_set)
+    }
+
+    /// Returns the flows of the given denom over the given channel accumulated
+    /// within its current window, if any were recorded.
+    pub fn rate_limit_usage(
+        &self,
+        channel_id: &ChannelId,
+        denom: &str,
+        working_set: &mut impl TxState<S>,
+    ) -> Option<RateLimitUsage> {
+        self.rate_limit_usage
+            .get(&(channel_id.clone(), denom.to_string()), working_set)
+    }
+
+    /// Sets a quota on behalf of the given sender, which must be the rate
+    /// limit admin.
+    pub fn set_rate_limit(
+        &self,
+        msg: MsgSetRateLimit,
+        sender: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), RateLimitError> {
+        self.ensure_rate_limit_admin(sender, working_set)?;
+
+        let rate_limit = msg.rate_limit;
+
+        rate_limit.validate()?;
+
+        let key = (rate_limit.channel_id.clone(), rate_limit.denom.clone());
+
+        if self.rate_limits.get(&key, working_set).is_none() {
+            self.rate_limit_vec.push(&key, working_set);
+        }
+
+        self.rate_limits.set(&key, &rate_limit, working_set);
+        self.rate_limit_usage.delete(&key, working_set);
+
+        Ok(())
+    }
+
+    /// Lifts a quota on behalf of the given sender, which must be the rate
+    /// limit admin.
+    pub fn remove_rate_limit(
+        &self,
+        msg: MsgRemoveRateLimit,
+        sender: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), RateLimitError> {
+        self.ensure_rate_limit_admin(sender, working_set)?;
+
+        let key = (msg.channel_id, msg.denom);
+
+        if self.rate_limits.get(&key, working_set).is_none() {
+            return Err(RateLimitError::RateLimitNotFound {
+                channel_id: key.0.to_string(),
+                denom: key.1,
+            });
+        }
+
+        let remaining_keys = self
+            .rate_limit_vec
+            .iter(working_set)
+            .filter(|k| k != &key)
+            .collect::<Vec<_>>();
+
+        self.rate_limit_vec.set_all(remaining_keys, working_set);
+
+        self.rate_limits.delete(&key, working_set);
+        self.rate_limit_usage.delete(&key, working_set);
+
+        Ok(())
+    }
+
+    /// Records a flow of the given denom over the given channel, failing if
+    /// it takes the net flow in that direction over the quota. Denoms without
+    /// a quota are not tracked.
+    pub(crate) fn record_flow(
+        &self,
+        channel_id: &ChannelId,
+        denom: &str,
+        direction: FlowDirection,
+        amount: Amount,
+        now: FlowInstant,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), RateLimitError> {
+        let key = (channel_id.clone(), denom.to_string());
+
+        let Some(rate_limit) = self.rate_limits.get(&key, working_set) else {
+            return Ok(());
+        };
+
+        let window = rate_limit.window;
+
+        let now = window
+            .position(now)
+            .ok_or(RateLimitError::UnknownHostTime {
+                channel_id: channel_id.to_string(),
+                denom: denom.to_string(),
+            })?;
+
+        let mut usage = self
+            .rate_limit_usage
+            .get(&key, working_set)
+            .unwrap_or_default()
+            .at(&window, now);
+
+        let (net_flow, quota) = match direction {
+            FlowDirection::Inflow => (usage.net_inflow(&window, now), rate_limit.max_inflow),
+            FlowDirection::Outflow => (usage.net_outflow(&window, now), rate_limit.max_outflow),
+        };
+
+        let attempted = net_flow
+            .checked_add(amount)
+            .ok_or(RateLimitError::InvalidAmount {
+                amount: amount.to_string(),
+                denom: denom.to_string(),
+            })?;
+
+        if attempted > quota {
+            return Err(RateLimitError::QuotaExceeded {
+                channel_id: channel_id.to_string(),
+                denom: denom.to_string(),
+                direction,
+                attempted,
+                quota,
+            });
+        }
+
+        match direction {
+            FlowDirection::Inflow => usage.inflow = usage.inflow.saturating_add(amount),
+            FlowDirection::Outflow => usage.outflow = usage.outflow.saturating_add(amount),
+        }
+
+        self.rate_limit_usage.set(&key, &usage, working_set);
+
+        Ok(())
+    }
+
+    /// Takes back a flow recorded at the given instant, as when a sent
+    /// transfer gets refunded or a received one gets reverted. The flow is
+    /// only taken back from the fixed window it was recorded in, if that
+    /// window still counts; flows of elapsed windows were already dropped, and
+    /// taking them back from a later window would free up quota there.
+    pub(crate) fn undo_flow(
+        &self,
+        channel_id: &ChannelId,
+        denom: &str,
+        direction: FlowDirection,
+        amount: Amount,
+        recorded_at: FlowInstant,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let key = (channel_id.clone(), denom.to_string());
+
+        let Some(rate_limit) = self.rate_limits.get(&key, working_set) else {
+            return;
+        };
+
+        let Some(mut usage) = self.rate_limit_usage.get(&key, working_set) else {
+            return;
+        };
+
+        let window = rate_limit.window;
+
+        let Some(recorded_at) = window.position(recorded_at) else {
+            return;
+        };
+
+        let recorded_window_start = window.start_of(recorded_at);
+
+        let flow = if recorded_window_start == usage.window_start {
+            match direction {
+                FlowDirection::Inflow => &mut usage.inflow,
+                FlowDirection::Outflow => &mut usage.outflow,
+            }
+        } else if recorded_window_start.saturating_add(window.length()) == usage.window_start {
+            match direction {
+                FlowDirection::Inflow => &mut usage.previous_inflow,
+                FlowDirection::Outflow => &mut usage.previous_outflow,
+            }
+        } else {
+            return;
+        };
+
+        *flow = flow.saturating_sub(amount);
+
+        self.rate_limit_usage.set(&key, &usage, working_set);
+    }
+
+    fn ensure_rate_limit_admin(
+        &self,
+        sender: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), RateLimitError> {
+        let admin = self
+            .rate_limit_admin
+            .get(working_set)
+            .ok_or(RateLimitError::NoAdmin)?;
+
+        if &admin != sender {
+            return Err(RateLimitError::Unauthorized {
+                sender: sender.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_voucher_channel() {
+        let denom = PrefixedDenom::from_str("transfer/channel-3/transfer/channel-0/uatom").unwrap();
+        assert_eq!(voucher_channel(&denom), Some(ChannelId::new(3)));
+
+        let denom = PrefixedDenom::from_str("uatom").unwrap();
+        assert_eq!(voucher_channel(&denom), None);
+    }
+
+    #[test]
+    fn test_net_flows() {
+        let window = RateLimitWindow::Slots(10);
+
+        let usage = RateLimitUsage {
+            window_start: 0,
+            inflow: 100,
+            outflow: 30,
+            ..Default::default()
+        };
+
+        assert_eq!(usage.net_inflow(&window, 5), 70);
+        assert_eq!(usage.net_outflow(&window, 5), 0);
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let window = RateLimitWindow::Slots(10);
+
+        let usage = RateLimitUsage {
+            window_start: 10,
+            inflow: 100,
+            outflow: 0,
+            ..Default::default()
+        };
+
+        // Within the same fixed window, the usage is left as is
+        assert_eq!(usage.clone().at(&window, 19), usage);
+
+        // The next fixed window carries the flows over as the previous ones,
+        // which count less and less as the sliding window moves on
+        let next = usage.clone().at(&window, 22);
+
+        assert_eq!(next.window_start, 20);
+        assert_eq!(next.previous_inflow, 100);
+        assert_eq!(next.net_inflow(&window, 20), 100);
+        assert_eq!(next.net_inflow(&window, 22), 80);
+        assert_eq!(next.net_inflow(&window, 29), 10);
+
+        // Once a whole fixed window elapsed, the flows are dropped
+        assert_eq!(
+            usage.at(&window, 30),
+            RateLimitUsage {
+                window_start: 30,
+                ..Default::default()
+            }
+        );
+    }
+}
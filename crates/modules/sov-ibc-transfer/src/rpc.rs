@@ -1,11 +1,13 @@
 //! Defines JSON RPC methods exposed by the ibc transfer module
+use ibc_core::host::types::identifiers::ChannelId;
 use jsonrpsee::core::RpcResult;
-use jsonrpsee::types::ErrorObjectOwned;
 use sov_bank::TokenId;
+use sov_ibc_utils::to_jsonrpsee_error;
 use sov_modules_api::macros::rpc_gen;
 use sov_modules_api::{Spec, WorkingSet};
 
 use super::IbcTransfer;
+use crate::rate_limit::{RateLimit, RateLimitUsage};
 
 #[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
 pub struct MintedTokenResponse {
@@ -13,6 +15,14 @@ pub struct MintedTokenResponse {
     pub token_id: TokenId,
 }
 
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+pub struct RateLimitResponse {
+    pub rate_limit: RateLimit,
+    /// The flows accumulated within the last recorded window, which may have
+    /// elapsed since.
+    pub usage: RateLimitUsage,
+}
+
 #[rpc_gen(client, server, namespace = "transfer")]
 impl<S> IbcTransfer<S>
 where
@@ -55,13 +65,29 @@ where
             token_id,
         })
     }
-}
 
-/// Creates a jsonrpsee error object
-pub fn to_jsonrpsee_error(err: impl ToString) -> ErrorObjectOwned {
-    ErrorObjectOwned::owned(
-        jsonrpsee::types::error::UNKNOWN_ERROR_CODE,
-        err.to_string(),
-        None::<String>,
-    )
+    #[rpc_method(name = "rateLimit")]
+    pub fn rate_limit_query(
+        &self,
+        channel_id: ChannelId,
+        denom: String,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<RateLimitResponse> {
+        let key = (channel_id, denom);
+
+        let rate_limit = self
+            .rate_limits
+            .get(&key, working_set)
+            .ok_or(to_jsonrpsee_error(format!(
+                "No rate limit found for denom '{}' on channel '{}'",
+                key.1, key.0
+            )))?;
+
+        let usage = self
+            .rate_limit_usage
+            .get(&key, working_set)
+            .unwrap_or_default();
+
+        Ok(RateLimitResponse { rate_limit, usage })
+    }
 }
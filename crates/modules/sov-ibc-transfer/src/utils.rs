@@ -1,22 +1,54 @@
-use ibc_app_transfer::types::VERSION;
+use ibc_app_transfer::types::error::TokenTransferError;
+use ibc_app_transfer::types::packet::PacketData;
+use ibc_app_transfer::types::{is_receiver_chain_source, PrefixedCoin, TracePrefix, VERSION};
+use ibc_core::channel::types::acknowledgement::{Acknowledgement, AcknowledgementStatus};
+use ibc_core::channel::types::packet::Packet;
 use ibc_core::host::types::identifiers::{ChannelId, PortId};
-use sov_modules_api::digest::Digest;
-use sov_modules_api::{CryptoSpec, ModuleId, Spec};
+use sov_ibc_utils::derive_module_address;
+use sov_modules_api::{ModuleId, Spec};
 
-/// The escrow address follows the format as outlined in Cosmos SDK's ADR 028:
-/// <https://github.com/cosmos/cosmos-sdk/blob/master/docs/architecture/adr-028-public-key-addresses.md/>
-/// except that the `Hasher` function mandated by the `CryptoSpec` trait in the
-/// rollup implementation.
+/// The escrow address of a channel is derived from the ICS-20 version and the
+/// port and channel it escrows tokens for.
 pub fn compute_escrow_address<S: Spec>(port_id: &PortId, channel_id: &ChannelId) -> ModuleId {
-    let escrow_account_bytes: [u8; 32] = {
-        let mut hasher = <S::CryptoSpec as CryptoSpec>::Hasher::new();
-        hasher.update(VERSION);
-        hasher.update([0]);
-        hasher.update(format!("{port_id}/{channel_id}"));
+    derive_module_address::<S, _>(VERSION, &format!("{port_id}/{channel_id}"))
+}
+
+/// Returns the coin an inbound packet credits on the rollup, with the trace
+/// prefix of its denom updated the way the transfer application does upon
+/// receipt.
+pub fn received_coin(packet: &Packet, data: &PacketData) -> PrefixedCoin {
+    let mut denom = data.token.denom.clone();
+
+    if is_receiver_chain_source(
+        packet.port_id_on_a.clone(),
+        packet.chan_id_on_a.clone(),
+        &denom,
+    ) {
+        denom.remove_trace_prefix(&TracePrefix::new(
+            packet.port_id_on_a.clone(),
+            packet.chan_id_on_a.clone(),
+        ));
+    } else {
+        denom.add_trace_prefix(TracePrefix::new(
+            packet.port_id_on_b.clone(),
+            packet.chan_id_on_b.clone(),
+        ));
+    }
 
-        let hash = hasher.finalize();
-        *hash.as_ref()
-    };
+    PrefixedCoin {
+        denom,
+        amount: data.token.amount,
+    }
+}
+
+/// Returns whether the given ICS-20 acknowledgement is successful.
+pub fn is_ack_successful(acknowledgement: &Acknowledgement) -> bool {
+    serde_json::from_slice::<AcknowledgementStatus>(acknowledgement.as_ref())
+        .map(|status| status.is_successful())
+        .unwrap_or(false)
+}
 
-    escrow_account_bytes.into()
+/// Creates an ICS-20 error acknowledgement with the given description.
+pub fn error_acknowledgement(description: impl ToString) -> Acknowledgement {
+    AcknowledgementStatus::error(TokenTransferError::Other(description.to_string()).into()).into()
 }
@@ -0,0 +1,26 @@
+use sov_modules_api::digest::Digest;
+use sov_modules_api::{CryptoSpec, Spec};
+
+/// Derives the address of an account owned by an IBC module, following the
+/// format outlined in Cosmos SDK's ADR 028:
+/// <https://github.com/cosmos/cosmos-sdk/blob/master/docs/architecture/adr-028-public-key-addresses.md/>
+/// except that the `Hasher` function mandated by the `CryptoSpec` trait in the
+/// rollup implementation is used.
+///
+/// The domain separates the accounts of the different modules, such as the
+/// escrows of the fungible and the non-fungible token transfers, while the key
+/// identifies the account within its domain. No rollup user controls the
+/// derived addresses.
+pub fn derive_module_address<S: Spec, A: From<[u8; 32]>>(domain: &str, key: &str) -> A {
+    let account_bytes: [u8; 32] = {
+        let mut hasher = <S::CryptoSpec as CryptoSpec>::Hasher::new();
+        hasher.update(domain);
+        hasher.update([0]);
+        hasher.update(key);
+
+        let hash = hasher.finalize();
+        *hash.as_ref()
+    };
+
+    account_bytes.into()
+}
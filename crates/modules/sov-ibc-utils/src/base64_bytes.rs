@@ -0,0 +1,18 @@
+//! Serializes byte fields as base64 strings, as `ibc-go` does for the bytes
+//! carried in JSON-encoded packet data and acknowledgements. Meant to be used
+//! through `#[serde(with = "sov_ibc_utils::base64_bytes")]`.
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<Se: Serializer>(bytes: &[u8], serializer: Se) -> Result<Se::Ok, Se::Error> {
+    serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+}
+
+pub fn deserialize<'de, De: Deserializer<'de>>(deserializer: De) -> Result<Vec<u8>, De::Error> {
+    let encoded = String::deserialize(deserializer)?;
+
+    BASE64_STANDARD
+        .decode(encoded)
+        .map_err(serde::de::Error::custom)
+}
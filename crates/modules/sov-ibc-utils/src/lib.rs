@@ -0,0 +1,10 @@
+//! Helpers shared by the Sovereign IBC modules.
+mod address;
+pub mod base64_bytes;
+
+pub use address::*;
+
+#[cfg(feature = "native")]
+mod rpc;
+#[cfg(feature = "native")]
+pub use rpc::*;
@@ -0,0 +1,10 @@
+use jsonrpsee::types::ErrorObjectOwned;
+
+/// Creates a jsonrpsee error object
+pub fn to_jsonrpsee_error(err: impl ToString) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        jsonrpsee::types::error::UNKNOWN_ERROR_CODE,
+        err.to_string(),
+        None::<String>,
+    )
+}
@@ -0,0 +1,140 @@
+//! Defines the batches of IBC core messages, through which relayers submit
+//! the messages they would bundle in a single Cosmos SDK transaction, such as
+//! a client update followed by the packets it proves, within a single rollup
+//! transaction.
+use anyhow::{anyhow, bail, Result};
+use ibc_core::handler::types::msgs::MsgEnvelope;
+use ibc_core::primitives::proto::Any;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{CallResponse, Context, EventEmitter, Spec, TxState};
+use tracing::info;
+
+use crate::call::{dispatch_core_message, envelope_signer, IBC_SOFTWARE_UPGRADE_TYPE_URL};
+use crate::checkpoint::TxCheckpoint;
+use crate::error::ensure_signer_is_sender;
+use crate::event::{batch_message_event, error_with_logs, IBC_BATCH_EVENT_KEY};
+use crate::router::IbcRouterExtension;
+use crate::Ibc;
+
+/// A batch of IBC core messages, dispatched in order within the same rollup
+/// transaction.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize, borsh::BorshSerialize, Clone, Debug, PartialEq, Serialize, Deserialize,
+)]
+pub struct MsgBatch {
+    pub messages: Vec<Any>,
+    /// Whether a failing message reverts the whole batch. Otherwise, only the
+    /// state changes of the failing message are reverted, and the messages
+    /// after it are still dispatched.
+    pub atomic: bool,
+}
+
+impl MsgBatch {
+    /// Creates an atomic batch, which behaves like a Cosmos SDK multi-message
+    /// transaction.
+    pub fn new(messages: Vec<Any>) -> Self {
+        Self {
+            messages,
+            atomic: true,
+        }
+    }
+
+    /// Creates a batch skipping the messages that fail, such as packets
+    /// already relayed by another relayer.
+    pub fn non_atomic(messages: Vec<Any>) -> Self {
+        Self {
+            messages,
+            atomic: false,
+        }
+    }
+}
+
+impl From<Vec<Any>> for MsgBatch {
+    fn from(messages: Vec<Any>) -> Self {
+        Self::new(messages)
+    }
+}
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Processes a batch of IBC core messages, routed the same way as single
+    /// ones. Every message is dispatched within its own [`TxCheckpoint`],
+    /// which is only committed if it succeeds. The outcome of every message
+    /// is emitted as an event keyed by [`IBC_BATCH_EVENT_KEY`].
+    pub(crate) fn process_batch(
+        &self,
+        msg_batch: MsgBatch,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing IBC batch of {} messages (atomic: {}) at visible_slot_number: {:?}",
+            msg_batch.messages.len(),
+            msg_batch.atomic,
+            context.visible_slot_number()
+        );
+
+        if msg_batch.messages.is_empty() {
+            bail!("IBC batch cannot be empty");
+        }
+
+        for (index, msg) in msg_batch.messages.into_iter().enumerate() {
+            let type_url = msg.type_url.clone();
+
+            let result = {
+                let mut checkpoint = TxCheckpoint::new(working_set);
+
+                let result = self.dispatch_batched_message(msg, &context, &mut checkpoint);
+
+                // Otherwise, dropping the checkpoint reverts the state changes
+                // of the failing message only
+                if result.is_ok() {
+                    checkpoint.commit();
+                }
+
+                result
+            };
+
+            let error = match result {
+                Ok(()) => None,
+                Err(e) if !msg_batch.atomic => Some(e.to_string()),
+                Err(e) => bail!("Message {index} of the IBC batch ({type_url}) failed: {e}"),
+            };
+
+            self.emit_event(
+                working_set,
+                IBC_BATCH_EVENT_KEY,
+                batch_message_event(index, type_url, error),
+            );
+        }
+
+        Ok(CallResponse::default())
+    }
+
+    /// Dispatches a batched message over the checkpoint it runs within,
+    /// attaching the messages it logged to the error it fails with.
+    fn dispatch_batched_message<TS: TxState<S>>(
+        &self,
+        msg: Any,
+        context: &Context<S>,
+        working_set: &mut TS,
+    ) -> Result<()> {
+        // `MsgIbcSoftwareUpgrade` is handled on its own, as it is not an IBC
+        // core message per se.
+        if msg.type_url == IBC_SOFTWARE_UPGRADE_TYPE_URL {
+            bail!("MsgIbcSoftwareUpgrade cannot be batched");
+        }
+
+        let msg_envelope = MsgEnvelope::try_from(msg)
+            .map_err(|e| anyhow!("Failed to convert Any to MsgEnvelope: {e}"))?;
+
+        ensure_signer_is_sender::<S>(envelope_signer(&msg_envelope), context.sender())?;
+
+        self.ensure_core_message_authority(&msg_envelope, context.sender(), working_set)?;
+
+        let (mut ibc_ctx, mut router) = self.core_handlers(context, working_set)?;
+
+        dispatch_core_message(&mut ibc_ctx, &mut router, msg_envelope)
+            .map_err(|e| error_with_logs(e, &ibc_ctx.take_logs()))
+    }
+}
@@ -0,0 +1,208 @@
+//! Defines the tracking of the rollup block time, which the ICS-03 connection
+//! delays are enforced with.
+//!
+//! The rollup advances with the DA layer, one height per DA block, hence its
+//! block time is the one of the DA layer. Since not every DA layer has a
+//! predictable block time, it is observed from the timestamps of the host
+//! consensus states rather than assumed, unless set at genesis.
+use core::time::Duration;
+
+use ibc_core::client::types::Height;
+use ibc_core::primitives::Timestamp;
+use sov_modules_api::{Spec, StateCheckpoint, TxState};
+
+use crate::router::IbcRouterExtension;
+use crate::Ibc;
+
+/// The weight given to each new observation in the moving average of the
+/// block time, as a divisor.
+const BLOCK_TIME_SMOOTHING: u64 = 8;
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Folds the interval between the last host consensus state and the one
+    /// of the given height and timestamp into the observed block time. Must
+    /// be called before the host height and timestamp are updated.
+    pub fn track_block_time(
+        &self,
+        height: &Height,
+        timestamp: &Timestamp,
+        working_set: &mut StateCheckpoint<S>,
+    ) {
+        let (Some(last_height), Some(last_timestamp)) = (
+            self.host_height_map.get(working_set),
+            self.host_timestamp_map.get(working_set),
+        ) else {
+            return;
+        };
+
+        if height.revision_number() != last_height.revision_number() || height <= &last_height {
+            return;
+        }
+
+        let Some(elapsed) = timestamp.duration_since(&last_timestamp) else {
+            return;
+        };
+
+        let blocks = height.revision_height() - last_height.revision_height();
+
+        let interval = u64::try_from(elapsed.as_nanos() / u128::from(blocks)).unwrap_or(u64::MAX);
+
+        let block_time = match self.observed_block_time.get(working_set) {
+            Some(block_time) if block_time <= interval => {
+                block_time + (interval - block_time) / BLOCK_TIME_SMOOTHING
+            }
+            Some(block_time) => block_time - (block_time - interval) / BLOCK_TIME_SMOOTHING,
+            None => interval,
+        };
+
+        self.observed_block_time.set(&block_time, working_set);
+    }
+
+    /// Returns the expected time per block, which is the one set at genesis
+    /// if any, or else the observed block time. It is zero until a block is
+    /// observed, which cancels the block delay checks of connections.
+    pub fn expected_block_time(&self, working_set: &mut impl TxState<S>) -> Duration {
+        self.max_expected_time_per_block
+            .get(working_set)
+            .or_else(|| self.observed_block_time.get(working_set))
+            .map(Duration::from_nanos)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{with_state_checkpoint, TestSpec};
+    use crate::IbcConfig;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// Observes a host consensus state at the given height and time, in
+    /// seconds, the way the slot hooks do.
+    fn observe(
+        ibc: &Ibc<TestSpec>,
+        height: u64,
+        secs: u64,
+        checkpoint: &mut StateCheckpoint<TestSpec>,
+    ) {
+        let height = Height::new(0, height).unwrap();
+        let timestamp = Timestamp::from_nanoseconds(secs * SECOND).unwrap();
+
+        ibc.track_block_time(&height, &timestamp, checkpoint);
+
+        ibc.host_height_map.set(&height, checkpoint);
+        ibc.host_timestamp_map.set(&timestamp, checkpoint);
+    }
+
+    #[test]
+    fn test_track_block_time() {
+        with_state_checkpoint(|mut checkpoint| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            // Nothing is observed before the first host consensus state
+            observe(&ibc, 10, 100, &mut checkpoint);
+
+            assert_eq!(ibc.observed_block_time.get(&mut checkpoint), None);
+
+            // The first interval is taken as is
+            observe(&ibc, 11, 106, &mut checkpoint);
+
+            assert_eq!(
+                ibc.observed_block_time.get(&mut checkpoint),
+                Some(6 * SECOND)
+            );
+
+            // Then each interval moves the average by an eighth of its
+            // distance to it, upwards and downwards
+            observe(&ibc, 12, 120, &mut checkpoint);
+
+            assert_eq!(
+                ibc.observed_block_time.get(&mut checkpoint),
+                Some(7 * SECOND)
+            );
+
+            observe(&ibc, 13, 123, &mut checkpoint);
+
+            assert_eq!(
+                ibc.observed_block_time.get(&mut checkpoint),
+                Some(13 * SECOND / 2)
+            );
+
+            // An interval over several blocks is spread across them
+            observe(&ibc, 15, 136, &mut checkpoint);
+
+            assert_eq!(
+                ibc.observed_block_time.get(&mut checkpoint),
+                Some(13 * SECOND / 2)
+            );
+
+            // A height that does not advance or a timestamp going backwards
+            // is left out
+            for (height, secs) in [(15, 200), (14, 200), (16, 130)] {
+                ibc.track_block_time(
+                    &Height::new(0, height).unwrap(),
+                    &Timestamp::from_nanoseconds(secs * SECOND).unwrap(),
+                    &mut checkpoint,
+                );
+            }
+
+            // As is a height of another revision
+            ibc.track_block_time(
+                &Height::new(1, 16).unwrap(),
+                &Timestamp::from_nanoseconds(200 * SECOND).unwrap(),
+                &mut checkpoint,
+            );
+
+            assert_eq!(
+                ibc.observed_block_time.get(&mut checkpoint),
+                Some(13 * SECOND / 2)
+            );
+
+            assert_eq!(
+                ibc.expected_block_time(&mut checkpoint.to_revertable_unmetered()),
+                Duration::from_nanos(13 * SECOND / 2)
+            );
+        });
+    }
+
+    #[test]
+    fn test_expected_block_time() {
+        with_state_checkpoint(|mut checkpoint| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            observe(&ibc, 10, 100, &mut checkpoint);
+
+            let mut working_set = checkpoint.to_revertable_unmetered();
+
+            // Zero until a block is observed
+            assert_eq!(ibc.expected_block_time(&mut working_set), Duration::ZERO);
+
+            let mut checkpoint = working_set.checkpoint().0;
+
+            observe(&ibc, 11, 106, &mut checkpoint);
+
+            let mut working_set = checkpoint.to_revertable_unmetered();
+
+            assert_eq!(
+                ibc.expected_block_time(&mut working_set),
+                Duration::from_secs(6)
+            );
+
+            // The block time set at genesis overrides the observed one
+            ibc.init_module(
+                &IbcConfig {
+                    max_expected_time_per_block: Some(Duration::from_secs(2)),
+                    ..Default::default()
+                },
+                &mut working_set,
+            )
+            .unwrap();
+
+            assert_eq!(
+                ibc.expected_block_time(&mut working_set),
+                Duration::from_secs(2)
+            );
+        });
+    }
+}
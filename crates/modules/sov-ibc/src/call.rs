@@ -1,24 +1,61 @@
+use core::time::Duration;
 use std::cell::RefCell;
 use std::fmt::Debug;
 use std::rc::Rc;
 
-use anyhow::{bail, Result};
-use ibc_app_transfer::handler::send_transfer;
+use anyhow::{anyhow, bail, Result};
+use ibc_app_nft_transfer::handler::send_nft_transfer;
+use ibc_app_nft_transfer::types::msgs::transfer::MsgTransfer as MsgNftTransfer;
 use ibc_app_transfer::types::msgs::transfer::MsgTransfer;
+use ibc_core::channel::handler::send_packet;
+use ibc_core::channel::types::channel::Order;
+use ibc_core::channel::types::msgs::{ChannelMsg, MsgChannelOpenInit, PacketMsg};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::client::types::msgs::ClientMsg;
+use ibc_core::client::types::proto::v1::MsgIbcSoftwareUpgrade as RawMsgIbcSoftwareUpgrade;
+use ibc_core::connection::types::msgs::ConnectionMsg;
 use ibc_core::entrypoint::dispatch;
+use ibc_core::handler::types::error::ContextError;
 use ibc_core::handler::types::msgs::MsgEnvelope;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::{ChannelEndPath, CommitmentPath, SeqSendPath};
+use ibc_core::host::ValidationContext;
 use ibc_core::primitives::proto::Any;
-use sov_ibc_transfer::context::IbcTransferContext;
+use ibc_core::primitives::Signer;
+use prost::Message;
+use sov_ibc_fee::types::{
+    MsgPayPacketFee, MsgPayPacketFeeAsync, MsgRegisterCounterpartyPayee, MsgRegisterPayee,
+};
+use sov_ibc_ica::controller::IcaControllerContext;
+use sov_ibc_ica::error::InterchainAccountError;
+use sov_ibc_ica::types::{
+    controller_port_id, host_port_id, Metadata, MsgRegisterInterchainAccount, MsgSendTx,
+    PacketType, CONTROLLER_MODULE_ID_STR,
+};
+use sov_ibc_nft_transfer::context::IbcNftTransferContext;
+use sov_ibc_transfer::rate_limit::{MsgRemoveRateLimit, MsgSetRateLimit};
 use sov_modules_api::{CallResponse, Context, Spec, TxState};
 use tracing::info;
 
+use crate::batch::MsgBatch;
 use crate::context::IbcContext;
-use crate::router::IbcRouter;
+use crate::error::ensure_signer_is_sender;
+use crate::event::error_with_logs;
+use crate::export::MsgBackfillRegistries;
+use crate::packet_index::MsgMigratePacketIndex;
+use crate::router::{transfer_context, IbcRouter, IbcRouterExtension};
+use crate::upgrade::{MsgCancelUpgrade, MsgScheduleUpgrade, UpgradePlan};
 use crate::Ibc;
 
+/// The type URL of `MsgIbcSoftwareUpgrade`, through which the authority may
+/// schedule a rollup upgrade the same way as on Cosmos SDK chains.
+pub const IBC_SOFTWARE_UPGRADE_TYPE_URL: &str = "/ibc.core.client.v1.MsgIbcSoftwareUpgrade";
+
 #[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
 #[cfg_attr(
-    feature = "serde",
+    feature = "native",
     derive(serde::Serialize),
     derive(serde::Deserialize)
 )]
@@ -26,16 +63,53 @@ use crate::Ibc;
 pub enum CallMessage {
     Core(Any),
 
+    Batch(MsgBatch),
+
     Transfer(MsgTransfer),
+
+    NftTransfer(MsgNftTransfer),
+
+    RegisterInterchainAccount(MsgRegisterInterchainAccount),
+
+    SendInterchainTx(MsgSendTx),
+
+    PayPacketFee(MsgPayPacketFee),
+
+    PayPacketFeeAsync(MsgPayPacketFeeAsync),
+
+    RegisterPayee(MsgRegisterPayee),
+
+    RegisterCounterpartyPayee(MsgRegisterCounterpartyPayee),
+
+    SetRateLimit(MsgSetRateLimit),
+
+    RemoveRateLimit(MsgRemoveRateLimit),
+
+    ScheduleUpgrade(MsgScheduleUpgrade),
+
+    CancelUpgrade(MsgCancelUpgrade),
+
+    BackfillRegistries(MsgBackfillRegistries),
+
+    MigratePacketIndex(MsgMigratePacketIndex),
 }
 
-impl<S: Spec> Ibc<S> {
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Processes an IBC core message, routing the channel and packet
+    /// callbacks to the application modules shipped with the `Ibc` module as
+    /// well as to the ones registered by the rollup extension.
     pub(crate) fn process_core_message(
         &self,
         msg: Any,
         context: Context<S>,
         working_set: &mut impl TxState<S>,
     ) -> Result<CallResponse> {
+        // `MsgIbcSoftwareUpgrade` is not an IBC core message per se, as it is
+        // handled by the upgrade module on Cosmos SDK chains.
+        if msg.type_url == IBC_SOFTWARE_UPGRADE_TYPE_URL {
+            return self.ibc_software_upgrade(msg, context, working_set);
+        }
+
         let msg_envelope = MsgEnvelope::try_from(msg).map_err(|e| {
             anyhow::anyhow!("Failed to convert Any to MsgEnvelope: {}", e.to_string())
         })?;
@@ -46,21 +120,35 @@ impl<S: Spec> Ibc<S> {
             context.visible_slot_number()
         );
 
+        ensure_signer_is_sender::<S>(envelope_signer(&msg_envelope), context.sender())?;
+
+        self.ensure_core_message_authority(&msg_envelope, context.sender(), working_set)?;
+
+        let (mut ibc_ctx, mut router) = self.core_handlers(&context, working_set)?;
+
+        match dispatch_core_message(&mut ibc_ctx, &mut router, msg_envelope) {
+            Ok(_) => Ok(CallResponse::default()),
+            Err(e) => Err(error_with_logs(e, &ibc_ctx.take_logs())),
+        }
+    }
+
+    /// Sets up the IBC context and the router dispatching an IBC core message
+    /// over the given working set, once the visible slot number of the call
+    /// is checked against the host height.
+    pub(crate) fn core_handlers<'ws, TS: TxState<S>>(
+        &'ws self,
+        context: &Context<S>,
+        working_set: &'ws mut TS,
+    ) -> Result<(IbcContext<'ws, S, TS, R>, IbcRouter<'ws, S, TS, R>)> {
         let shared_working_set = Rc::new(RefCell::new(working_set));
 
-        let mut ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: shared_working_set.clone(),
-        };
+        let ibc_ctx = IbcContext::new(self, shared_working_set.clone());
 
         ibc_ctx.height_sanity_check(context.visible_slot_number())?;
 
-        let mut router = IbcRouter::new(self, context.clone(), shared_working_set);
+        let router = IbcRouter::with_extension(self, context.clone(), shared_working_set)?;
 
-        match dispatch(&mut ibc_ctx, &mut router, msg_envelope) {
-            Ok(_) => Ok(CallResponse::default()),
-            Err(e) => bail!(e.to_string()),
-        }
+        Ok((ibc_ctx, router))
     }
 
     pub(crate) fn transfer(
@@ -75,20 +163,631 @@ impl<S: Spec> Ibc<S> {
             context.visible_slot_number()
         );
 
+        ensure_signer_is_sender::<S>(&msg_transfer.packet_data.sender, context.sender())?;
+
         let shared_working_set = Rc::new(RefCell::new(working_set));
 
-        let mut ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: shared_working_set.clone(),
-        };
+        let mut ibc_ctx = IbcContext::new(self, shared_working_set.clone());
 
         ibc_ctx.height_sanity_check(context.visible_slot_number())?;
 
-        let mut transfer_ctx =
-            IbcTransferContext::new(self.transfer.clone(), context, shared_working_set.clone());
+        let mut transfer_ctx = transfer_context(self, &context, &shared_working_set);
 
-        send_transfer(&mut ibc_ctx, &mut transfer_ctx, msg_transfer)?;
+        transfer_ctx
+            .send_transfer(&mut ibc_ctx, msg_transfer)
+            .map_err(|e| error_with_logs(e, &ibc_ctx.take_logs()))?;
 
         Ok(sov_modules_api::CallResponse::default())
     }
+
+    pub(crate) fn nft_transfer(
+        &self,
+        msg_nft_transfer: MsgNftTransfer,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing IBC NFT transfer message: {:?} at visible_slot_number: {:?}",
+            msg_nft_transfer,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&msg_nft_transfer.packet_data.sender, context.sender())?;
+
+        let shared_working_set = Rc::new(RefCell::new(working_set));
+
+        let mut ibc_ctx = IbcContext::new(self, shared_working_set.clone());
+
+        ibc_ctx.height_sanity_check(context.visible_slot_number())?;
+
+        let mut nft_transfer_ctx =
+            IbcNftTransferContext::new(self.nft_transfer.clone(), shared_working_set.clone());
+
+        send_nft_transfer(&mut ibc_ctx, &mut nft_transfer_ctx, msg_nft_transfer)
+            .map_err(|e| error_with_logs(e, &ibc_ctx.take_logs()))?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Registers an interchain account for the sender on the counterparty host
+    /// chain, by binding the sender's controller port and initiating the
+    /// channel handshake towards the host port.
+    pub(crate) fn register_interchain_account(
+        &self,
+        msg: MsgRegisterInterchainAccount,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing interchain account registration: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.owner.clone()), context.sender())?;
+
+        let owner = context.sender().to_string();
+
+        let port_id = controller_port_id(&owner)?;
+
+        let shared_working_set = Rc::new(RefCell::new(working_set));
+
+        let mut ibc_ctx = IbcContext::new(self, shared_working_set.clone());
+
+        ibc_ctx.height_sanity_check(context.visible_slot_number())?;
+
+        let conn_end = ibc_ctx.connection_end(&msg.connection_id)?;
+
+        let host_connection_id = conn_end
+            .counterparty()
+            .connection_id()
+            .ok_or(anyhow!(
+                "Counterparty connection ID of {} not found",
+                msg.connection_id
+            ))?
+            .clone();
+
+        let metadata = Metadata::new(msg.connection_id.clone(), host_connection_id);
+
+        let mut router = IbcRouter::with_extension(self, context.clone(), shared_working_set)?;
+
+        router.claim_port(&port_id, CONTROLLER_MODULE_ID_STR)?;
+
+        let msg_chan_open_init = MsgChannelOpenInit {
+            port_id_on_a: port_id,
+            connection_hops_on_a: vec![msg.connection_id],
+            port_id_on_b: host_port_id(),
+            ordering: Order::Ordered,
+            signer: Signer::from(owner),
+            version_proposal: ChannelVersion::new(metadata.to_version()),
+        };
+
+        let msg_envelope = MsgEnvelope::Channel(ChannelMsg::OpenInit(msg_chan_open_init));
+
+        match dispatch(&mut ibc_ctx, &mut router, msg_envelope) {
+            Ok(_) => Ok(CallResponse::default()),
+            Err(e) => Err(error_with_logs(e, &ibc_ctx.take_logs())),
+        }
+    }
+
+    /// Sends an interchain account transaction on behalf of the sender over
+    /// the active channel of its controller port.
+    pub(crate) fn send_interchain_tx(
+        &self,
+        msg: MsgSendTx,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing interchain account transaction: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.owner.clone()), context.sender())?;
+
+        if msg.packet_data.packet_type != PacketType::ExecuteTx {
+            bail!("Unsupported interchain account packet type");
+        }
+
+        if msg.packet_data.data.is_empty() {
+            bail!("Interchain account packet data cannot be empty");
+        }
+
+        if msg.relative_timeout == 0 {
+            bail!("Relative timeout must be non-zero");
+        }
+
+        let port_id = controller_port_id(&context.sender().to_string())?;
+
+        let shared_working_set = Rc::new(RefCell::new(working_set));
+
+        let mut ibc_ctx = IbcContext::new(self, shared_working_set.clone());
+
+        ibc_ctx.height_sanity_check(context.visible_slot_number())?;
+
+        let controller_ctx = IcaControllerContext::new(self.ica.clone(), shared_working_set);
+
+        controller_ctx.ensure_controller_enabled()?;
+
+        let chan_id_on_a = controller_ctx
+            .active_channel(&msg.connection_id, &port_id)
+            .ok_or(InterchainAccountError::ActiveChannelNotFound {
+                connection_id: msg.connection_id.to_string(),
+                port_id: port_id.to_string(),
+            })?;
+
+        let chan_end_on_a = ibc_ctx.channel_end(&ChannelEndPath::new(&port_id, &chan_id_on_a))?;
+
+        let port_id_on_b = chan_end_on_a.counterparty().port_id().clone();
+
+        let chan_id_on_b = chan_end_on_a
+            .counterparty()
+            .channel_id()
+            .ok_or(anyhow!(
+                "Counterparty channel ID of {chan_id_on_a} not found"
+            ))?
+            .clone();
+
+        let seq_on_a =
+            ibc_ctx.get_next_sequence_send(&SeqSendPath::new(&port_id, &chan_id_on_a))?;
+
+        let timeout_timestamp_on_b =
+            (ibc_ctx.host_timestamp()? + Duration::from_nanos(msg.relative_timeout))?;
+
+        let packet = Packet {
+            seq_on_a,
+            port_id_on_a: port_id,
+            chan_id_on_a,
+            port_id_on_b,
+            chan_id_on_b,
+            data: msg.packet_data.to_packet_bytes(),
+            timeout_height_on_b: TimeoutHeight::Never,
+            timeout_timestamp_on_b,
+        };
+
+        send_packet(&mut ibc_ctx, packet).map_err(|e| error_with_logs(e, &ibc_ctx.take_logs()))?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Escrows a fee, paid by the sender, for a packet sent on the given
+    /// fee-enabled channel, which must still be in flight and not be
+    /// incentivized yet.
+    pub(crate) fn pay_packet_fee(
+        &self,
+        msg: MsgPayPacketFee,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing packet fee payment: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.signer.clone()), context.sender())?;
+
+        if self
+            .fee
+            .packet_fees
+            .get(
+                &(msg.port_id.clone(), msg.channel_id.clone(), msg.sequence),
+                working_set,
+            )
+            .is_some()
+        {
+            bail!(
+                "Packet {} on channel {} and port {} is already incentivized",
+                msg.sequence,
+                msg.channel_id,
+                msg.port_id
+            );
+        }
+
+        self.ensure_packet_in_flight(&msg.port_id, &msg.channel_id, msg.sequence, working_set)?;
+
+        self.fee.escrow_packet_fee(
+            &msg.port_id,
+            &msg.channel_id,
+            msg.sequence,
+            msg.fee,
+            context.sender(),
+            working_set,
+        )?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Escrows a fee, paid by the sender, for an in-flight packet of the given
+    /// fee-enabled channel.
+    pub(crate) fn pay_packet_fee_async(
+        &self,
+        msg: MsgPayPacketFeeAsync,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing async packet fee payment: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.signer.clone()), context.sender())?;
+
+        self.ensure_packet_in_flight(&msg.port_id, &msg.channel_id, msg.sequence, working_set)?;
+
+        self.fee.escrow_packet_fee(
+            &msg.port_id,
+            &msg.channel_id,
+            msg.sequence,
+            msg.fee,
+            context.sender(),
+            working_set,
+        )?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Checks the given packet was sent and is neither acknowledged nor timed
+    /// out yet, i.e. its commitment exists.
+    fn ensure_packet_in_flight(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        let commitment_path = CommitmentPath::new(port_id, channel_id, sequence);
+
+        if self
+            .packet_commitment_map
+            .get(&commitment_path, working_set)
+            .is_none()
+        {
+            bail!("Packet {sequence} on channel {channel_id} and port {port_id} is not in flight");
+        }
+
+        Ok(())
+    }
+
+    /// Registers the address the acknowledgement and timeout fees of the
+    /// sender relayer are paid to on the given fee-enabled channel.
+    pub(crate) fn register_payee(
+        &self,
+        msg: MsgRegisterPayee,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing payee registration: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.relayer.clone()), context.sender())?;
+
+        if !self
+            .fee
+            .is_fee_enabled(&msg.port_id, &msg.channel_id, working_set)
+        {
+            bail!(
+                "Fee is not enabled on channel {} and port {}",
+                msg.channel_id,
+                msg.port_id
+            );
+        }
+
+        let payee: S::Address = msg
+            .payee
+            .parse()
+            .map_err(|_| anyhow!("Invalid payee address: {}", msg.payee))?;
+
+        self.fee.register_payee(
+            &msg.port_id,
+            &msg.channel_id,
+            context.sender(),
+            &payee,
+            working_set,
+        );
+
+        Ok(CallResponse::default())
+    }
+
+    /// Registers the counterparty address the receive fees of the sender
+    /// relayer are paid to on the given fee-enabled channel.
+    pub(crate) fn register_counterparty_payee(
+        &self,
+        msg: MsgRegisterCounterpartyPayee,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing counterparty payee registration: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.relayer.clone()), context.sender())?;
+
+        if !self
+            .fee
+            .is_fee_enabled(&msg.port_id, &msg.channel_id, working_set)
+        {
+            bail!(
+                "Fee is not enabled on channel {} and port {}",
+                msg.channel_id,
+                msg.port_id
+            );
+        }
+
+        self.fee.register_counterparty_payee(
+            &msg.port_id,
+            &msg.channel_id,
+            context.sender(),
+            &msg.counterparty_payee,
+            working_set,
+        )?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Sets the quota of a denom over a channel on behalf of the rate limit
+    /// admin.
+    pub(crate) fn set_rate_limit(
+        &self,
+        msg: MsgSetRateLimit,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing rate limit update: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        self.transfer
+            .set_rate_limit(msg, context.sender(), working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Lifts the quota of a denom over a channel on behalf of the rate limit
+    /// admin.
+    pub(crate) fn remove_rate_limit(
+        &self,
+        msg: MsgRemoveRateLimit,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing rate limit removal: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        self.transfer
+            .remove_rate_limit(msg, context.sender(), working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Schedules a rollup upgrade on behalf of the authority.
+    pub(crate) fn schedule_upgrade(
+        &self,
+        msg: MsgScheduleUpgrade,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing upgrade scheduling: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        self.ensure_authority(context.sender(), working_set)?;
+
+        self.store_upgrade_plan(msg.plan, context.visible_slot_number(), working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Cancels the pending rollup upgrade on behalf of the authority.
+    pub(crate) fn cancel_upgrade(
+        &self,
+        msg: MsgCancelUpgrade,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing upgrade cancellation: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        self.ensure_authority(context.sender(), working_set)?;
+
+        self.cancel_upgrade_plan(working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Backfills the registries walked through by the export on behalf of
+    /// the authority.
+    pub(crate) fn backfill(
+        &self,
+        msg: MsgBackfillRegistries,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing registries backfill: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        self.ensure_authority(context.sender(), working_set)?;
+
+        self.backfill_registries(msg, working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Migrates the packet vectors into the per-channel packet indexes on
+    /// behalf of the authority.
+    pub(crate) fn migrate_packets(
+        &self,
+        msg: MsgMigratePacketIndex,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        info!(
+            "Processing packet index migration: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        self.ensure_authority(context.sender(), working_set)?;
+
+        self.migrate_packet_index(msg, working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Schedules a rollup upgrade out of a `MsgIbcSoftwareUpgrade`, whose
+    /// signer must be the authority sending it.
+    pub(crate) fn ibc_software_upgrade(
+        &self,
+        msg: Any,
+        context: Context<S>,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<CallResponse> {
+        let msg = RawMsgIbcSoftwareUpgrade::decode(msg.value.as_slice())
+            .map_err(|e| anyhow!("Failed to decode MsgIbcSoftwareUpgrade: {e}"))?;
+
+        info!(
+            "Processing IBC software upgrade: {:?} at visible_slot_number: {:?}",
+            msg,
+            context.visible_slot_number()
+        );
+
+        ensure_signer_is_sender::<S>(&Signer::from(msg.signer.clone()), context.sender())?;
+
+        self.ensure_authority(context.sender(), working_set)?;
+
+        let plan = msg.plan.ok_or(anyhow!("Missing upgrade plan"))?;
+
+        let upgraded_client_state = msg
+            .upgraded_client_state
+            .ok_or(anyhow!("Missing upgraded client state"))?;
+
+        let plan = UpgradePlan {
+            name: plan.name,
+            height: u64::try_from(plan.height)
+                .map_err(|_| anyhow!("Invalid upgrade height: {}", plan.height))?,
+            upgraded_client_state,
+        };
+
+        self.store_upgrade_plan(plan, context.visible_slot_number(), working_set)?;
+
+        Ok(CallResponse::default())
+    }
+
+    /// Fails if the given IBC core message may only be sent by the authority,
+    /// as `MsgRecoverClient` is, and the sender is not the authority. Any other
+    /// core message may be relayed by anyone.
+    pub(crate) fn ensure_core_message_authority(
+        &self,
+        msg_envelope: &MsgEnvelope,
+        sender: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        if let MsgEnvelope::Client(ClientMsg::RecoverClient(_)) = msg_envelope {
+            self.ensure_authority(sender, working_set)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless the given sender is the authority of the module.
+    pub(crate) fn ensure_authority(
+        &self,
+        sender: &S::Address,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        let authority = self
+            .authority
+            .get(working_set)
+            .ok_or(anyhow!("No IBC authority is configured"))?;
+
+        if &authority != sender {
+            bail!("Sender {sender} is not the IBC authority");
+        }
+
+        Ok(())
+    }
+}
+
+/// Dispatches the given IBC core message. Once a client is recovered, the
+/// update metadata of the substitute client is carried over to the subject
+/// one, so that the connection delays of the packets proven against the
+/// recovered consensus state run from when the substitute processed it.
+pub(crate) fn dispatch_core_message<S, TS, R>(
+    ibc_ctx: &mut IbcContext<'_, S, TS, R>,
+    router: &mut IbcRouter<'_, S, TS, R>,
+    msg_envelope: MsgEnvelope,
+) -> Result<(), ContextError>
+where
+    S: Spec,
+    TS: TxState<S>,
+    R: IbcRouterExtension<S>,
+{
+    let recovered_clients = match &msg_envelope {
+        MsgEnvelope::Client(ClientMsg::RecoverClient(msg)) => Some((
+            msg.subject_client_id.clone(),
+            msg.substitute_client_id.clone(),
+        )),
+        _ => None,
+    };
+
+    dispatch(ibc_ctx, router, msg_envelope)?;
+
+    if let Some((subject_client_id, substitute_client_id)) = recovered_clients {
+        ibc_ctx.carry_over_update_meta(&subject_client_id, &substitute_client_id)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the signer of the given IBC core message.
+pub(crate) fn envelope_signer(msg_envelope: &MsgEnvelope) -> &Signer {
+    match msg_envelope {
+        MsgEnvelope::Client(msg) => match msg {
+            ClientMsg::CreateClient(msg) => &msg.signer,
+            ClientMsg::UpdateClient(msg) => &msg.signer,
+            ClientMsg::Misbehaviour(msg) => &msg.signer,
+            ClientMsg::UpgradeClient(msg) => &msg.signer,
+            ClientMsg::RecoverClient(msg) => &msg.signer,
+        },
+        MsgEnvelope::Connection(msg) => match msg {
+            ConnectionMsg::OpenInit(msg) => &msg.signer,
+            ConnectionMsg::OpenTry(msg) => &msg.signer,
+            ConnectionMsg::OpenAck(msg) => &msg.signer,
+            ConnectionMsg::OpenConfirm(msg) => &msg.signer,
+        },
+        MsgEnvelope::Channel(msg) => match msg {
+            ChannelMsg::OpenInit(msg) => &msg.signer,
+            ChannelMsg::OpenTry(msg) => &msg.signer,
+            ChannelMsg::OpenAck(msg) => &msg.signer,
+            ChannelMsg::OpenConfirm(msg) => &msg.signer,
+            ChannelMsg::CloseInit(msg) => &msg.signer,
+            ChannelMsg::CloseConfirm(msg) => &msg.signer,
+        },
+        MsgEnvelope::Packet(msg) => match msg {
+            PacketMsg::Recv(msg) => &msg.signer,
+            PacketMsg::Ack(msg) => &msg.signer,
+            PacketMsg::Timeout(msg) => &msg.signer,
+            PacketMsg::TimeoutOnClose(msg) => &msg.signer,
+        },
+    }
 }
@@ -0,0 +1,123 @@
+//! Defines a nested checkpoint over the state of a rollup transaction, which
+//! lets the `Ibc` module revert the effects of a part of the transaction, such
+//! as a runtime call triggered by a packet, while keeping the rest.
+use std::collections::HashMap;
+
+use sov_modules_api::{EventContainer, GasMeter, Spec, StateReaderAndWriter, TxState};
+use sov_state::namespaces::{Accessory, User};
+use sov_state::{SlotKey, SlotValue};
+
+type PendingEvent<'a, TS> = Box<dyn FnOnce(&mut TS) + 'a>;
+
+/// Buffers the writes and the events of a part of a transaction on top of the
+/// transaction state, which is only modified once the checkpoint gets
+/// committed. Dropping the checkpoint reverts everything done through it.
+///
+/// Reads go through the buffered writes first, so that the buffered part of
+/// the transaction observes its own effects. Gas is charged on the underlying
+/// state right away, as a reverted call must still be paid for.
+pub struct TxCheckpoint<'a, S: Spec, TS: TxState<S>> {
+    inner: &'a mut TS,
+    user_writes: HashMap<SlotKey, Option<SlotValue>>,
+    accessory_writes: HashMap<SlotKey, Option<SlotValue>>,
+    events: Vec<PendingEvent<'a, TS>>,
+    _spec: core::marker::PhantomData<S>,
+}
+
+impl<'a, S: Spec, TS: TxState<S>> TxCheckpoint<'a, S, TS> {
+    pub fn new(inner: &'a mut TS) -> Self {
+        Self {
+            inner,
+            user_writes: HashMap::new(),
+            accessory_writes: HashMap::new(),
+            events: Vec::new(),
+            _spec: core::marker::PhantomData,
+        }
+    }
+
+    /// Applies the buffered writes and emits the buffered events on the
+    /// underlying transaction state.
+    pub fn commit(self) {
+        let Self {
+            inner,
+            user_writes,
+            accessory_writes,
+            events,
+            ..
+        } = self;
+
+        for (key, value) in user_writes {
+            match value {
+                Some(value) => StateReaderAndWriter::<User>::set(inner, &key, value),
+                None => StateReaderAndWriter::<User>::delete(inner, &key),
+            }
+        }
+
+        for (key, value) in accessory_writes {
+            match value {
+                Some(value) => StateReaderAndWriter::<Accessory>::set(inner, &key, value),
+                None => StateReaderAndWriter::<Accessory>::delete(inner, &key),
+            }
+        }
+
+        for emit in events {
+            emit(inner);
+        }
+    }
+}
+
+impl<'a, S: Spec, TS: TxState<S>> StateReaderAndWriter<User> for TxCheckpoint<'a, S, TS> {
+    fn get(&mut self, key: &SlotKey) -> Option<SlotValue> {
+        match self.user_writes.get(key) {
+            Some(value) => value.clone(),
+            None => StateReaderAndWriter::<User>::get(self.inner, key),
+        }
+    }
+
+    fn set(&mut self, key: &SlotKey, value: SlotValue) {
+        self.user_writes.insert(key.clone(), Some(value));
+    }
+
+    fn delete(&mut self, key: &SlotKey) {
+        self.user_writes.insert(key.clone(), None);
+    }
+}
+
+impl<'a, S: Spec, TS: TxState<S>> StateReaderAndWriter<Accessory> for TxCheckpoint<'a, S, TS> {
+    fn get(&mut self, key: &SlotKey) -> Option<SlotValue> {
+        match self.accessory_writes.get(key) {
+            Some(value) => value.clone(),
+            None => StateReaderAndWriter::<Accessory>::get(self.inner, key),
+        }
+    }
+
+    fn set(&mut self, key: &SlotKey, value: SlotValue) {
+        self.accessory_writes.insert(key.clone(), Some(value));
+    }
+
+    fn delete(&mut self, key: &SlotKey) {
+        self.accessory_writes.insert(key.clone(), None);
+    }
+}
+
+impl<'a, S: Spec, TS: TxState<S>> EventContainer for TxCheckpoint<'a, S, TS> {
+    fn add_event<E: 'static + core::marker::Send>(&mut self, event_key: &str, event: E) {
+        let event_key = event_key.to_string();
+
+        self.events.push(Box::new(move |inner: &mut TS| {
+            inner.add_event(&event_key, event)
+        }));
+    }
+}
+
+impl<'a, S: Spec, TS: TxState<S>> GasMeter<S::Gas> for TxCheckpoint<'a, S, TS> {
+    fn charge_gas(&mut self, gas: &S::Gas) -> anyhow::Result<()> {
+        self.inner.charge_gas(gas)
+    }
+
+    fn remaining_funds(&self) -> u64 {
+        self.inner.remaining_funds()
+    }
+}
+
+impl<'a, S: Spec, TS: TxState<S>> TxState<S> for TxCheckpoint<'a, S, TS> {}
@@ -10,8 +10,36 @@ use sov_modules_api::{Spec, TxState};
 
 use super::{AnyClientState, AnyConsensusState};
 use crate::context::IbcContext;
+use crate::router::IbcRouterExtension;
+
+impl<'a, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>> IbcContext<'a, S, TS, R> {
+    /// Carries the update metadata the substitute client recorded at its
+    /// latest height over to the subject client, which took over the
+    /// consensus state at that height upon recovery. The client itself cannot
+    /// read the store of the substitute, so it records the time and height of
+    /// the recovery instead.
+    pub(crate) fn carry_over_update_meta(
+        &mut self,
+        subject_client_id: &ClientId,
+        substitute_client_id: &ClientId,
+    ) -> Result<(), ContextError> {
+        let latest_height = self.client_state(substitute_client_id)?.latest_height();
+
+        let (processed_time, processed_height) =
+            self.client_update_meta(substitute_client_id, &latest_height)?;
+
+        self.store_update_meta(
+            subject_client_id.clone(),
+            latest_height,
+            processed_time,
+            processed_height,
+        )
+    }
+}
 
-impl<'a, S: Spec, TS: TxState<S>> ClientValidationContext for IbcContext<'a, S, TS> {
+impl<'a, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>> ClientValidationContext
+    for IbcContext<'a, S, TS, R>
+{
     type ClientStateRef = AnyClientState;
     type ConsensusStateRef = AnyConsensusState;
 
@@ -70,7 +98,9 @@ impl<'a, S: Spec, TS: TxState<S>> ClientValidationContext for IbcContext<'a, S,
     }
 }
 
-impl<'a, S: Spec, TS: TxState<S>> ClientExecutionContext for IbcContext<'a, S, TS> {
+impl<'a, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>> ClientExecutionContext
+    for IbcContext<'a, S, TS, R>
+{
     type ClientStateMut = AnyClientState;
 
     fn store_client_state(
@@ -78,6 +108,24 @@ impl<'a, S: Spec, TS: TxState<S>> ClientExecutionContext for IbcContext<'a, S, T
         client_state_path: ClientStatePath,
         client_state: Self::ClientStateMut,
     ) -> Result<(), ContextError> {
+        // Registers the client on its creation, which is when its state is
+        // first stored
+        if self
+            .ibc
+            .client_state_map
+            .get(&client_state_path.0, *self.working_set.borrow_mut())
+            .is_none()
+        {
+            self.ibc
+                .client_ids_vec
+                .push(&client_state_path.0, *self.working_set.borrow_mut());
+            self.ibc.client_type_map.set(
+                &client_state_path.0,
+                &client_state.client_type().to_string(),
+                *self.working_set.borrow_mut(),
+            );
+        }
+
         self.ibc.client_state_map.set(
             &client_state_path.0,
             &client_state,
@@ -119,9 +167,11 @@ impl<'a, S: Spec, TS: TxState<S>> ClientExecutionContext for IbcContext<'a, S, T
         host_timestamp: Timestamp,
         host_height: Height,
     ) -> Result<(), ContextError> {
-        self.ibc
-            .client_update_heights_vec
-            .push(&height, *self.working_set.borrow_mut());
+        self.ibc.consensus_height_index().insert(
+            &client_id,
+            height,
+            *self.working_set.borrow_mut(),
+        )?;
 
         self.ibc.client_update_meta_map.set(
             &(client_id.clone(), height),
@@ -137,6 +187,12 @@ impl<'a, S: Spec, TS: TxState<S>> ClientExecutionContext for IbcContext<'a, S, T
         client_id: ClientId,
         height: Height,
     ) -> Result<(), ContextError> {
+        self.ibc.consensus_height_index().remove(
+            &client_id,
+            &height,
+            *self.working_set.borrow_mut(),
+        )?;
+
         self.ibc
             .client_update_meta_map
             .remove(&(client_id.clone(), height), *self.working_set.borrow_mut());
@@ -144,7 +200,9 @@ impl<'a, S: Spec, TS: TxState<S>> ClientExecutionContext for IbcContext<'a, S, T
     }
 }
 
-impl<'a, S: Spec, TS: TxState<S>> ExtClientValidationContext for IbcContext<'a, S, TS> {
+impl<'a, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>> ExtClientValidationContext
+    for IbcContext<'a, S, TS, R>
+{
     fn host_timestamp(&self) -> Result<Timestamp, ContextError> {
         <Self as ValidationContext>::host_timestamp(self)
     }
@@ -153,12 +211,11 @@ impl<'a, S: Spec, TS: TxState<S>> ExtClientValidationContext for IbcContext<'a,
         <Self as ValidationContext>::host_height(self)
     }
 
-    fn consensus_state_heights(&self, _client_id: &ClientId) -> Result<Vec<Height>, ContextError> {
+    fn consensus_state_heights(&self, client_id: &ClientId) -> Result<Vec<Height>, ContextError> {
         let heights = self
             .ibc
-            .client_update_heights_vec
-            .iter(*self.working_set.borrow_mut())
-            .collect::<Vec<_>>();
+            .consensus_height_index()
+            .heights(client_id, *self.working_set.borrow_mut())?;
         Ok(heights)
     }
 
@@ -179,130 +236,44 @@ impl<'a, S: Spec, TS: TxState<S>> ExtClientValidationContext for IbcContext<'a,
     }
 }
 
-fn next_consensus_state<S: Spec, TS: TxState<S>>(
-    ctx: &IbcContext<'_, S, TS>,
+fn next_consensus_state<S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>(
+    ctx: &IbcContext<'_, S, TS, R>,
     client_id: &ClientId,
     height: &Height,
 ) -> Result<Option<AnyConsensusState>, ContextError> {
-    // Searches for the most recent height at which a client has been
-    // updated and a consensus state has been stored.
-    let latest_height = ctx
-        .ibc
-        .client_state_map
-        .get(client_id, *ctx.working_set.borrow_mut())
-        .map(|cs| cs.latest_height())
-        .ok_or(ClientError::ClientStateNotFound {
-            client_id: client_id.clone(),
-        })?;
-
-    if height.revision_number() != latest_height.revision_number() {
-        Err(ClientError::Other {
-            description: "height revision number must match the chain's revision number"
-                .to_string(),
-        })?;
-    }
-
-    // If the height is greater equal than the latest height, there is no
-    // next consensus state
-    if height >= &latest_height {
-        return Ok(None);
-    }
-
-    // Otherwise, we iterate over the heights between the given height and
-    // the latest height, and return the first consensus state we find
-    //
-    // NOTE: this is not the efficient way to do this, but no other way at
-    // the moment as we don't have access to an iterator over the map keys
-
-    let mut target_height = *height;
-
-    while height.revision_height() < latest_height.revision_height() {
-        target_height = target_height.increment();
-
-        let cons_state_path = ClientConsensusStatePath::new(
-            client_id.clone(),
-            target_height.revision_number(),
-            target_height.revision_height(),
-        );
-
-        let next_cons_state = ctx
-            .ibc
-            .consensus_state_map
-            .get(&cons_state_path, *ctx.working_set.borrow_mut());
-
-        if next_cons_state.is_some() {
-            return Ok(next_cons_state);
-        }
-    }
+    let next_height =
+        ctx.ibc
+            .consensus_height_index()
+            .next(client_id, height, *ctx.working_set.borrow_mut())?;
 
-    Ok(None)
+    Ok(next_height.and_then(|next_height| consensus_state_at(ctx, client_id, &next_height)))
 }
 
-fn prev_consensus_state<S: Spec, TS: TxState<S>>(
-    ctx: &IbcContext<'_, S, TS>,
+fn prev_consensus_state<S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>(
+    ctx: &IbcContext<'_, S, TS, R>,
     client_id: &ClientId,
     height: &Height,
 ) -> Result<Option<AnyConsensusState>, ContextError> {
-    // Searches for the most recent height at which a client has been
-    // updated and a consensus state has been stored.
-    let latest_height = ctx
-        .ibc
-        .client_state_map
-        .get(client_id, *ctx.working_set.borrow_mut())
-        .map(|cs| cs.latest_height())
-        .ok_or(ClientError::ClientStateNotFound {
-            client_id: client_id.clone(),
-        })?;
-
-    if height.revision_number() != latest_height.revision_number() {
-        Err(ClientError::Other {
-            description: "height revision number must match the chain's revision number"
-                .to_string(),
-        })?;
-    }
-
-    // If the height is greater equal than the latest height, the previous
-    // consensus state is the latest consensus state
-    if height >= &latest_height {
-        let cons_state_path = ClientConsensusStatePath::new(
-            client_id.clone(),
-            latest_height.revision_number(),
-            latest_height.revision_height(),
-        );
-
-        let prev_cons_state = ctx
-            .ibc
-            .consensus_state_map
-            .get(&cons_state_path, *ctx.working_set.borrow_mut());
-
-        return Ok(prev_cons_state);
-    }
-
-    // Otherwise, we decrement the height until we reach the first consensus
-    // state we find
-    //
-    // NOTE: this is not the efficient way to do this, but no other way at
-    // the moment as we don't have access to an iterator over the map keys
-    let mut target_height = *height;
-
-    while target_height.revision_height() > 0 {
-        let cons_state_path = ClientConsensusStatePath::new(
-            client_id.clone(),
-            target_height.revision_number(),
-            target_height.revision_height(),
-        );
-
-        let prev_cons_state = ctx
-            .ibc
-            .consensus_state_map
-            .get(&cons_state_path, *ctx.working_set.borrow_mut());
+    let prev_height =
+        ctx.ibc
+            .consensus_height_index()
+            .prev(client_id, height, *ctx.working_set.borrow_mut())?;
 
-        if prev_cons_state.is_some() {
-            return Ok(prev_cons_state);
-        }
-
-        target_height = target_height.decrement()?;
-    }
+    Ok(prev_height.and_then(|prev_height| consensus_state_at(ctx, client_id, &prev_height)))
+}
 
-    Ok(None)
+fn consensus_state_at<S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>(
+    ctx: &IbcContext<'_, S, TS, R>,
+    client_id: &ClientId,
+    height: &Height,
+) -> Option<AnyConsensusState> {
+    let cons_state_path = ClientConsensusStatePath::new(
+        client_id.clone(),
+        height.revision_number(),
+        height.revision_height(),
+    );
+
+    ctx.ibc
+        .consensus_state_map
+        .get(&cons_state_path, *ctx.working_set.borrow_mut())
 }
@@ -0,0 +1,351 @@
+//! Defines the per-client index of the heights at which consensus states are
+//! stored, which backs the consensus state queries and the ordered lookups the
+//! light clients make on updates and misbehaviour checks.
+//!
+//! The heights of a client are kept sorted in a window of slots, so that the
+//! next and previous heights are found by binary search. Clients are mostly
+//! updated at increasing heights and pruned from their oldest height, which
+//! respectively extend the window at its end and shrink it at its start.
+//! Inserting or removing any other height shifts the slots that follow it.
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::host::types::identifiers::ClientId;
+use sov_modules_api::{GenesisState, Spec, StateMap, TxState};
+
+/// A view over the state maps backing the consensus height index.
+pub(crate) struct ConsensusHeightIndex<'a> {
+    /// Maps each slot of a client to the height it holds.
+    slots: &'a StateMap<(ClientId, u64), Height>,
+    /// Holds the start, inclusive, and the end, exclusive, of the window of
+    /// slots of each client.
+    bounds: &'a StateMap<ClientId, (u64, u64)>,
+}
+
+impl<'a> ConsensusHeightIndex<'a> {
+    pub(crate) fn new(
+        slots: &'a StateMap<(ClientId, u64), Height>,
+        bounds: &'a StateMap<ClientId, (u64, u64)>,
+    ) -> Self {
+        Self { slots, bounds }
+    }
+
+    /// Indexes the given height of the client, unless it already is.
+    pub(crate) fn insert<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        height: Height,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), ClientError> {
+        let (start, end) = self.bounds(client_id, working_set);
+
+        let Err(position) = self.search(client_id, &height, working_set)? else {
+            return Ok(());
+        };
+
+        for slot in (position..end).rev() {
+            let shifted = self.slot(client_id, slot, working_set)?;
+            self.slots
+                .set(&(client_id.clone(), slot + 1), &shifted, working_set);
+        }
+
+        self.slots
+            .set(&(client_id.clone(), position), &height, working_set);
+        self.bounds.set(client_id, &(start, end + 1), working_set);
+
+        Ok(())
+    }
+
+    /// Removes the given height from the index of the client.
+    pub(crate) fn remove<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(), ClientError> {
+        let (start, end) = self.bounds(client_id, working_set);
+
+        let Ok(position) = self.search(client_id, height, working_set)? else {
+            return Ok(());
+        };
+
+        if position == start {
+            self.slots.delete(&(client_id.clone(), start), working_set);
+            self.set_bounds(client_id, start + 1, end, working_set);
+            return Ok(());
+        }
+
+        for slot in position + 1..end {
+            let shifted = self.slot(client_id, slot, working_set)?;
+            self.slots
+                .set(&(client_id.clone(), slot - 1), &shifted, working_set);
+        }
+
+        self.slots
+            .delete(&(client_id.clone(), end - 1), working_set);
+        self.set_bounds(client_id, start, end - 1, working_set);
+
+        Ok(())
+    }
+
+    /// Returns the indexed heights of the client in ascending order.
+    pub(crate) fn heights<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<Vec<Height>, ClientError> {
+        let (start, end) = self.bounds(client_id, working_set);
+
+        (start..end)
+            .map(|slot| self.slot(client_id, slot, working_set))
+            .collect()
+    }
+
+    /// Returns the lowest indexed height of the client above the given one.
+    pub(crate) fn next<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<Option<Height>, ClientError> {
+        let (_, end) = self.bounds(client_id, working_set);
+
+        let slot = match self.search(client_id, height, working_set)? {
+            Ok(position) => position + 1,
+            Err(position) => position,
+        };
+
+        (slot < end)
+            .then(|| self.slot(client_id, slot, working_set))
+            .transpose()
+    }
+
+    /// Returns the highest indexed height of the client below the given one.
+    pub(crate) fn prev<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<Option<Height>, ClientError> {
+        let (start, _) = self.bounds(client_id, working_set);
+
+        let slot = match self.search(client_id, height, working_set)? {
+            Ok(position) | Err(position) => position,
+        };
+
+        (slot > start)
+            .then(|| self.slot(client_id, slot - 1, working_set))
+            .transpose()
+    }
+
+    /// Indexes the heights of a client imported at genesis, which holds no
+    /// indexed heights yet. The heights are expected to be unique.
+    pub(crate) fn import<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        heights: impl IntoIterator<Item = Height>,
+        working_set: &mut impl GenesisState<S>,
+    ) {
+        let mut heights = heights.into_iter().collect::<Vec<_>>();
+        heights.sort();
+
+        for (slot, height) in (0u64..).zip(&heights) {
+            self.slots
+                .set(&(client_id.clone(), slot), height, working_set);
+        }
+
+        if !heights.is_empty() {
+            self.bounds
+                .set(client_id, &(0, heights.len() as u64), working_set);
+        }
+    }
+
+    /// Binary searches the slots of the client for the given height, returning
+    /// either the slot holding it or the one where it would be inserted.
+    fn search<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<Result<u64, u64>, ClientError> {
+        let (mut low, mut high) = self.bounds(client_id, working_set);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            match self.slot(client_id, mid, working_set)?.cmp(height) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(Ok(mid)),
+            }
+        }
+
+        Ok(Err(low))
+    }
+
+    /// Returns the height held by the given slot of the client, which is set
+    /// as long as the slot lies within the bounds of the client.
+    fn slot<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        slot: u64,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<Height, ClientError> {
+        self.slots
+            .get(&(client_id.clone(), slot), working_set)
+            .ok_or_else(|| ClientError::Other {
+                description: format!(
+                    "Slot {slot} of the consensus heights of client {client_id} is not set"
+                ),
+            })
+    }
+
+    fn bounds<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        working_set: &mut impl TxState<S>,
+    ) -> (u64, u64) {
+        self.bounds.get(client_id, working_set).unwrap_or_default()
+    }
+
+    fn set_bounds<S: Spec>(
+        &self,
+        client_id: &ClientId,
+        start: u64,
+        end: u64,
+        working_set: &mut impl TxState<S>,
+    ) {
+        if start == end {
+            self.bounds.delete(client_id, working_set);
+        } else {
+            self.bounds.set(client_id, &(start, end), working_set);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_modules_api::WorkingSet;
+    use sov_state::Prefix;
+
+    use super::*;
+    use crate::test_utils::{with_working_set, TestSpec};
+
+    fn client_id() -> ClientId {
+        ClientId::new("07-tendermint", 0).unwrap()
+    }
+
+    fn height(revision_height: u64) -> Height {
+        Height::new(0, revision_height).unwrap()
+    }
+
+    fn heights(revision_heights: &[u64]) -> Vec<Height> {
+        revision_heights.iter().copied().map(height).collect()
+    }
+
+    fn with_index(f: impl FnOnce(&ConsensusHeightIndex<'_>, &mut WorkingSet<TestSpec>)) {
+        let slots = StateMap::new(Prefix::new(b"slots".to_vec()));
+        let bounds = StateMap::new(Prefix::new(b"bounds".to_vec()));
+
+        with_working_set(|working_set| f(&ConsensusHeightIndex::new(&slots, &bounds), working_set));
+    }
+
+    #[test]
+    fn test_out_of_order_insert() {
+        with_index(|index, working_set| {
+            for revision_height in [5, 2, 9, 7, 1, 7] {
+                index
+                    .insert(&client_id(), height(revision_height), working_set)
+                    .unwrap();
+            }
+
+            assert_eq!(
+                index.heights(&client_id(), working_set).unwrap(),
+                heights(&[1, 2, 5, 7, 9])
+            );
+
+            // Heights of a later revision come after all those of earlier ones
+            index
+                .insert(&client_id(), Height::new(1, 1).unwrap(), working_set)
+                .unwrap();
+
+            assert_eq!(
+                index.heights(&client_id(), working_set).unwrap().last(),
+                Some(&Height::new(1, 1).unwrap())
+            );
+        });
+    }
+
+    #[test]
+    fn test_lookup() {
+        with_index(|index, working_set| {
+            index.import(&client_id(), heights(&[8, 2, 4, 6]), working_set);
+
+            // Lookups at and between indexed heights are strict
+            assert_eq!(
+                index.next(&client_id(), &height(4), working_set).unwrap(),
+                Some(height(6))
+            );
+            assert_eq!(
+                index.next(&client_id(), &height(5), working_set).unwrap(),
+                Some(height(6))
+            );
+            assert_eq!(
+                index.next(&client_id(), &height(8), working_set).unwrap(),
+                None
+            );
+            assert_eq!(
+                index.prev(&client_id(), &height(4), working_set).unwrap(),
+                Some(height(2))
+            );
+            assert_eq!(
+                index.prev(&client_id(), &height(3), working_set).unwrap(),
+                Some(height(2))
+            );
+            assert_eq!(
+                index.prev(&client_id(), &height(2), working_set).unwrap(),
+                None
+            );
+
+            // Removing the first height shrinks the window at its start, any
+            // other shifts the heights that follow it
+            for revision_height in [2, 6, 3] {
+                index
+                    .remove(&client_id(), &height(revision_height), working_set)
+                    .unwrap();
+            }
+
+            assert_eq!(
+                index.heights(&client_id(), working_set).unwrap(),
+                heights(&[4, 8])
+            );
+            assert_eq!(
+                index.prev(&client_id(), &height(8), working_set).unwrap(),
+                Some(height(4))
+            );
+            assert_eq!(
+                index.next(&client_id(), &height(1), working_set).unwrap(),
+                Some(height(4))
+            );
+
+            // A height below all others shifts them all
+            index.insert(&client_id(), height(3), working_set).unwrap();
+
+            assert_eq!(
+                index.heights(&client_id(), working_set).unwrap(),
+                heights(&[3, 4, 8])
+            );
+        });
+    }
+
+    #[test]
+    fn test_missing_slot() {
+        with_index(|index, working_set| {
+            index.import(&client_id(), heights(&[1, 2, 3]), working_set);
+
+            index.slots.delete(&(client_id(), 1), working_set);
+
+            assert!(index.heights(&client_id(), working_set).is_err());
+            assert!(index.insert(&client_id(), height(4), working_set).is_err());
+        });
+    }
+}
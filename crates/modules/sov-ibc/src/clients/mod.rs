@@ -1,4 +1,5 @@
 pub mod context;
+pub(crate) mod height_index;
 
 use derive_more::{From, TryInto};
 use ibc_client_tendermint::client_state::ClientState as TmClientState;
@@ -21,10 +22,11 @@ use sov_celestia_client::types::consensus_state::{
 use sov_modules_api::{Spec, TxState};
 
 use crate::context::IbcContext;
+use crate::router::IbcRouterExtension;
 
 #[derive(Clone, Debug, From, TryInto, ClientState)]
-#[validation(IbcContext<'a, S: Spec, TS: TxState<S>>)]
-#[execution(IbcContext<'a, S: Spec, TS: TxState<S>>)]
+#[validation(IbcContext<'a, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>)]
+#[execution(IbcContext<'a, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>)]
 pub enum AnyClientState {
     Tendermint(TmClientState),
     Sovereign(SovClientState),
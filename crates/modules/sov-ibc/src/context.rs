@@ -2,10 +2,14 @@ use core::time::Duration;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
 use ibc_core::channel::types::channel::ChannelEnd;
-use ibc_core::channel::types::commitment::{AcknowledgementCommitment, PacketCommitment};
+use ibc_core::channel::types::commitment::{
+    compute_ack_commitment, AcknowledgementCommitment, PacketCommitment,
+};
 use ibc_core::channel::types::error::{ChannelError, PacketError};
-use ibc_core::channel::types::packet::Receipt;
+use ibc_core::channel::types::events::WriteAcknowledgement;
+use ibc_core::channel::types::packet::{Packet, Receipt};
 use ibc_core::client::types::error::ClientError;
 use ibc_core::client::types::Height;
 use ibc_core::commitment_types::commitment::CommitmentPrefix;
@@ -13,7 +17,7 @@ use ibc_core::connection::types::error::ConnectionError;
 use ibc_core::connection::types::ConnectionEnd;
 use ibc_core::handler::types::error::ContextError;
 use ibc_core::handler::types::events::IbcEvent;
-use ibc_core::host::types::identifiers::{ConnectionId, Sequence};
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId, Sequence};
 use ibc_core::host::types::path::{
     AckPath, ChannelEndPath, ClientConnectionPath, CommitmentPath, ConnectionPath, ReceiptPath,
     SeqAckPath, SeqRecvPath, SeqSendPath, UpgradeClientPath,
@@ -22,32 +26,53 @@ use ibc_core::host::{ExecutionContext, ValidationContext};
 use ibc_core::primitives::{Signer, Timestamp};
 use sov_celestia_client::client_state::{ClientState as HostClientState, ClientState};
 use sov_celestia_client::consensus_state::{ConsensusState as HostConsensusState, ConsensusState};
+use sov_ibc_packet_forward::middleware::AcknowledgementWriter;
 use sov_modules_api::{EventEmitter, ModuleInfo, Spec, TxState};
 use sov_state::Prefix;
 
-use crate::event::auxiliary_packet_events;
+use crate::event::{auxiliary_packet_events, log_event, IBC_LOG_EVENT_KEY};
+use crate::router::IbcRouterExtension;
 use crate::Ibc;
 
 /// The SDK doesn't have a concept of a "revision number", so we default to 0
 pub const HOST_REVISION_NUMBER: u64 = 0;
 
 #[derive(Clone)]
-pub struct IbcContext<'a, S, TS>
+pub struct IbcContext<'a, S, TS, R = ()>
 where
     S: Spec,
     TS: TxState<S>,
+    R: IbcRouterExtension<S>,
 {
-    pub ibc: &'a Ibc<S>,
+    pub ibc: &'a Ibc<S, R>,
     pub working_set: Rc<RefCell<&'a mut TS>>,
+    /// The messages logged while handling the current message, which are
+    /// attached to its error should it fail, as the `ibc_log` events get
+    /// reverted along with the rest of the transaction.
+    logs: Rc<RefCell<Vec<String>>>,
 }
 
-impl<'a, S, TS> IbcContext<'a, S, TS>
+impl<'a, S, TS, R> IbcContext<'a, S, TS, R>
 where
     S: Spec,
     TS: TxState<S>,
+    R: IbcRouterExtension<S>,
 {
-    pub fn new(ibc: &'a Ibc<S>, working_set: Rc<RefCell<&'a mut TS>>) -> IbcContext<'a, S, TS> {
-        IbcContext { ibc, working_set }
+    pub fn new(
+        ibc: &'a Ibc<S, R>,
+        working_set: Rc<RefCell<&'a mut TS>>,
+    ) -> IbcContext<'a, S, TS, R> {
+        IbcContext {
+            ibc,
+            working_set,
+            logs: Rc::default(),
+        }
+    }
+
+    /// Takes the messages logged since the last call, so that they can be
+    /// attached to the error of a failing message.
+    pub fn take_logs(&self) -> Vec<String> {
+        self.logs.take()
     }
 
     /// Check that the context slot number matches the host height that IBC modules view.
@@ -65,10 +90,39 @@ where
         Ok(())
     }
 
+    /// Returns whether the acknowledgement returned upon receipt of the given
+    /// packet is withheld from the store, as the packet-forward middleware
+    /// holds it back until the packet it was forwarded as settles.
+    fn withhold_acknowledgement(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+    ) -> Result<bool, ContextError> {
+        let withheld = self
+            .ibc
+            .packet_forward
+            .withhold_acknowledgement(
+                port_id,
+                channel_id,
+                sequence,
+                *self.working_set.borrow_mut(),
+            )
+            .map_err(PacketError::from)?;
+
+        if withheld {
+            self.log_message(format!(
+                "Acknowledgement of packet {sequence} on channel {channel_id} and port {port_id} held back until its forwarded packet settles"
+            ))?;
+        }
+
+        Ok(withheld)
+    }
+
     // ------------------------------------------------------------------------
-    // TODO: Determine who should have upgrade authority for clients, and which
-    // party is responsible for storing upgraded client/consensus states?
-    // <https://github.com/informalsystems/sovereign-ibc/issues/122>
+    // Rollup upgrades are scheduled by the IBC authority, and their upgraded
+    // client/consensus states are stored by the slot hook of the consensus
+    // state tracker. See `Ibc::apply_upgrade_plan`.
     // ------------------------------------------------------------------------
 
     /// Stores the upgraded client state at the specified upgrade path.
@@ -98,10 +152,11 @@ where
     }
 }
 
-impl<'a, S, TS> ValidationContext for IbcContext<'a, S, TS>
+impl<'a, S, TS, R> ValidationContext for IbcContext<'a, S, TS, R>
 where
     S: Spec,
     TS: TxState<S>,
+    R: IbcRouterExtension<S>,
 {
     type V = Self;
     type HostClientState = ClientState;
@@ -139,6 +194,15 @@ where
         &self,
         height: &Height,
     ) -> Result<Self::HostConsensusState, ContextError> {
+        if self
+            .ibc
+            .is_host_consensus_state_pruned(height, *self.working_set.borrow_mut())
+        {
+            Err(ClientError::Other {
+                description: format!("Host consensus state at height {height} has been pruned"),
+            })?;
+        }
+
         let host_consensus_state = self
             .ibc
             .host_consensus_state_map
@@ -179,11 +243,100 @@ where
 
     fn validate_self_client(
         &self,
-        _client_state_of_host_on_counterparty: Self::HostClientState,
+        client_state_of_host_on_counterparty: Self::HostClientState,
     ) -> Result<(), ContextError> {
-        // Note: We can optionally implement this.
-        // It would require having a Protobuf definition of the chain's `ClientState` that other chains would use.
-        // The relayer sends us this `ClientState` as stored on other chains, and we validate it here.
+        let client_state = client_state_of_host_on_counterparty.into_inner();
+
+        let invalid =
+            |reason: String| ContextError::from(ConnectionError::InvalidClientState { reason });
+
+        if client_state.is_frozen() {
+            return Err(invalid("client of the rollup is frozen".to_string()));
+        }
+
+        let host_height = self.host_height()?;
+        let latest_height = client_state.latest_height_in_sov();
+
+        if latest_height.revision_number() != HOST_REVISION_NUMBER || latest_height > host_height {
+            return Err(invalid(format!(
+                "latest height {latest_height} of the client is not a rollup height up to the host height {host_height}"
+            )));
+        }
+
+        let trust_level = client_state.da_params.trust_level;
+
+        // The trust level must lie within [1/3, 1], as for Tendermint clients
+        if trust_level.denominator() == 0
+            || trust_level.numerator() > trust_level.denominator()
+            || trust_level.numerator().saturating_mul(3) < trust_level.denominator()
+        {
+            return Err(invalid(format!(
+                "trust level {trust_level} is out of range"
+            )));
+        }
+
+        if client_state.trusting_period().is_zero() {
+            return Err(invalid("trusting period cannot be zero".to_string()));
+        }
+
+        if client_state.upgrade_path().as_str().as_bytes() != self.commitment_prefix().as_bytes() {
+            return Err(invalid(format!(
+                "upgrade path {} does not match the module prefix",
+                client_state.upgrade_path().as_str()
+            )));
+        }
+
+        // Without the parameters of the rollup, the client cannot be told
+        // apart from a client of another rollup, so the handshake is rejected
+        let Some(params) = self
+            .ibc
+            .self_client_params
+            .get(*self.working_set.borrow_mut())
+        else {
+            return Err(invalid(
+                "parameters of the rollup client are not configured".to_string(),
+            ));
+        };
+
+        let Some(genesis_state_root) = params.genesis_state_root else {
+            return Err(invalid(
+                "genesis state root of the rollup is not recorded yet".to_string(),
+            ));
+        };
+
+        if client_state.genesis_state_root().as_ref() != &genesis_state_root {
+            return Err(invalid("genesis state root does not match".to_string()));
+        }
+
+        if client_state.code_commitment().as_slice() != params.code_commitment.as_slice() {
+            return Err(invalid("code commitment does not match".to_string()));
+        }
+
+        if client_state.genesis_da_height() != params.genesis_da_height {
+            return Err(invalid(format!(
+                "genesis DA height {} does not match {}",
+                client_state.genesis_da_height(),
+                params.genesis_da_height
+            )));
+        }
+
+        if client_state.chain_id() != &params.da_chain_id {
+            return Err(invalid(format!(
+                "DA chain ID {} does not match {}",
+                client_state.chain_id(),
+                params.da_chain_id
+            )));
+        }
+
+        let zk_vm = params.zk_vm().map_err(|e| invalid(e.to_string()))?;
+
+        if client_state.zk_vm() != zk_vm {
+            return Err(invalid(format!(
+                "zkVM {} does not match {zk_vm}",
+                client_state.zk_vm()
+            )));
+        }
+
         Ok(())
     }
 
@@ -320,22 +473,34 @@ where
     }
 
     fn max_expected_time_per_block(&self) -> Duration {
-        // This effectively cancels the check on connection block delays. Not
-        // all DAs have predictable block times (such as Bitcoin and Avalanche),
-        // so we cannot support connection block delays as they are defined
-        // today.
-        Duration::ZERO
-    }
+        // Not all DAs have predictable block times (such as Bitcoin and
+        // Avalanche), so the block time is observed on the DA, unless set at
+        // genesis. The time delay itself is checked against the processed
+        // times and heights recorded upon client updates.
+        self.ibc.expected_block_time(*self.working_set.borrow_mut())
+    }
+
+    /// Checks the signer of the privileged messages, namely
+    /// `MsgRecoverClient`, is the authority of the module.
+    fn validate_message_signer(&self, signer: &Signer) -> Result<(), ContextError> {
+        // The authority required by `MsgRecoverClient` is checked against the
+        // transaction sender before dispatch.
+        signer
+            .as_ref()
+            .parse::<S::Address>()
+            .map_err(|_| ClientError::Other {
+                description: format!("Invalid signer address: {}", signer.as_ref()),
+            })?;
 
-    fn validate_message_signer(&self, _signer: &Signer) -> Result<(), ContextError> {
         Ok(())
     }
 }
 
-impl<'a, S, TS> ExecutionContext for IbcContext<'a, S, TS>
+impl<'a, S, TS, R> ExecutionContext for IbcContext<'a, S, TS, R>
 where
     S: Spec,
     TS: TxState<S>,
+    R: IbcRouterExtension<S>,
 {
     type E = Self;
 
@@ -419,9 +584,11 @@ where
         commitment_path: &CommitmentPath,
         commitment: PacketCommitment,
     ) -> Result<(), ContextError> {
-        self.ibc
-            .packet_commitment_vec
-            .push(commitment_path, *self.working_set.borrow_mut());
+        self.ibc.packet_commitment_index().insert(
+            &ChannelEndPath::new(&commitment_path.port_id, &commitment_path.channel_id),
+            commitment_path.sequence,
+            *self.working_set.borrow_mut(),
+        );
         self.ibc.packet_commitment_map.set(
             commitment_path,
             &commitment,
@@ -434,17 +601,11 @@ where
         &mut self,
         commitment_path: &CommitmentPath,
     ) -> Result<(), ContextError> {
-        let unprocessed_packets = self
-            .ibc
-            .packet_commitment_vec
-            .iter(*self.working_set.borrow_mut())
-            .filter(|path| path != commitment_path)
-            .collect::<Vec<CommitmentPath>>();
-
-        self.ibc
-            .packet_commitment_vec
-            .set_all(unprocessed_packets, *self.working_set.borrow_mut());
-
+        self.ibc.packet_commitment_index().remove(
+            &ChannelEndPath::new(&commitment_path.port_id, &commitment_path.channel_id),
+            commitment_path.sequence,
+            *self.working_set.borrow_mut(),
+        );
         self.ibc
             .packet_commitment_map
             .delete(commitment_path, *self.working_set.borrow_mut());
@@ -456,9 +617,11 @@ where
         receipt_path: &ReceiptPath,
         receipt: Receipt,
     ) -> Result<(), ContextError> {
-        self.ibc
-            .packet_receipt_vec
-            .push(receipt_path, *self.working_set.borrow_mut());
+        self.ibc.packet_receipt_index().insert(
+            &ChannelEndPath::new(&receipt_path.port_id, &receipt_path.channel_id),
+            receipt_path.sequence,
+            *self.working_set.borrow_mut(),
+        );
         self.ibc
             .packet_receipt_map
             .set(receipt_path, &receipt, *self.working_set.borrow_mut());
@@ -470,9 +633,19 @@ where
         ack_path: &AckPath,
         ack_commitment: AcknowledgementCommitment,
     ) -> Result<(), ContextError> {
-        self.ibc
-            .packet_ack_vec
-            .push(ack_path, *self.working_set.borrow_mut());
+        if self.withhold_acknowledgement(
+            &ack_path.port_id,
+            &ack_path.channel_id,
+            ack_path.sequence,
+        )? {
+            return Ok(());
+        }
+
+        self.ibc.packet_ack_index().insert(
+            &ChannelEndPath::new(&ack_path.port_id, &ack_path.channel_id),
+            ack_path.sequence,
+            *self.working_set.borrow_mut(),
+        );
         self.ibc
             .packet_ack_map
             .set(ack_path, &ack_commitment, *self.working_set.borrow_mut());
@@ -480,17 +653,11 @@ where
     }
 
     fn delete_packet_acknowledgement(&mut self, ack_path: &AckPath) -> Result<(), ContextError> {
-        let filtered_acks = self
-            .ibc
-            .packet_ack_vec
-            .iter(*self.working_set.borrow_mut())
-            .filter(|path| path != ack_path)
-            .collect::<Vec<AckPath>>();
-
-        self.ibc
-            .packet_ack_vec
-            .set_all(filtered_acks, *self.working_set.borrow_mut());
-
+        self.ibc.packet_ack_index().remove(
+            &ChannelEndPath::new(&ack_path.port_id, &ack_path.channel_id),
+            ack_path.sequence,
+            *self.working_set.borrow_mut(),
+        );
         self.ibc
             .packet_ack_map
             .delete(ack_path, *self.working_set.borrow_mut());
@@ -507,6 +674,11 @@ where
             &channel_end,
             *self.working_set.borrow_mut(),
         );
+        self.ibc.channel_port_map.set(
+            &channel_end_path.1,
+            &channel_end_path.0,
+            *self.working_set.borrow_mut(),
+        );
         Ok(())
     }
 
@@ -562,6 +734,22 @@ where
     }
 
     fn emit_ibc_event(&mut self, event: IbcEvent) -> Result<(), ContextError> {
+        if let IbcEvent::WriteAcknowledgement(ref e) = event {
+            if self
+                .ibc
+                .packet_forward
+                .withhold_acknowledgement_event(
+                    e.port_id_on_b(),
+                    e.chan_id_on_b(),
+                    *e.seq_on_a(),
+                    *self.working_set.borrow_mut(),
+                )
+                .map_err(PacketError::from)?
+            {
+                return Ok(());
+            }
+        }
+
         self.ibc.emit_event(
             *self.working_set.borrow_mut(),
             event.event_type(),
@@ -580,9 +768,82 @@ where
         Ok(())
     }
 
-    /// FIXME: To implement this method, there should be a way for IBC module to
-    /// insert logs into the transaction receipts upon execution
-    fn log_message(&mut self, _message: String) -> Result<(), ContextError> {
+    /// Attaches the message to the receipt of the transaction being executed,
+    /// as an event keyed by `ibc_log`, and appends it to the logs of the
+    /// transaction, which the `txLogs` RPC method serves by transaction hash.
+    ///
+    /// Note: the events and logs of a reverted transaction are discarded
+    /// along with its state changes, so the message is also kept aside, to be
+    /// attached to the error the receipt keeps. See [`IbcContext::take_logs`].
+    fn log_message(&mut self, message: String) -> Result<(), ContextError> {
+        self.logs.borrow_mut().push(message.clone());
+
+        self.ibc
+            .push_tx_log(message.clone(), *self.working_set.borrow_mut());
+
+        self.ibc.emit_event(
+            *self.working_set.borrow_mut(),
+            IBC_LOG_EVENT_KEY,
+            log_event(message),
+        );
+
         Ok(())
     }
 }
+
+impl<'a, S, TS, R> AcknowledgementWriter for IbcContext<'a, S, TS, R>
+where
+    S: Spec,
+    TS: TxState<S>,
+    R: IbcRouterExtension<S>,
+{
+    /// Writes the acknowledgement of a packet received on the transfer
+    /// application stack, wrapping it first if the channel is fee-enabled, as
+    /// the fee middleware would have upon receipt.
+    fn write_acknowledgement(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+        acknowledgement: Acknowledgement,
+    ) -> Result<(), ContextError> {
+        let acknowledgement = if self.ibc.fee.is_fee_enabled(
+            &packet.port_id_on_b,
+            &packet.chan_id_on_b,
+            *self.working_set.borrow_mut(),
+        ) {
+            self.ibc.fee.incentivize_acknowledgement(
+                &packet.port_id_on_b,
+                &packet.chan_id_on_b,
+                relayer,
+                acknowledgement,
+                *self.working_set.borrow_mut(),
+            )
+        } else {
+            acknowledgement
+        };
+
+        let chan_end_on_b = self.channel_end(&ChannelEndPath::new(
+            &packet.port_id_on_b,
+            &packet.chan_id_on_b,
+        ))?;
+
+        let conn_id_on_b = chan_end_on_b
+            .connection_hops()
+            .first()
+            .ok_or(ChannelError::Other {
+                description: format!("Connection of channel {} not found", packet.chan_id_on_b),
+            })?
+            .clone();
+
+        self.store_packet_acknowledgement(
+            &AckPath::new(&packet.port_id_on_b, &packet.chan_id_on_b, packet.seq_on_a),
+            compute_ack_commitment(&acknowledgement),
+        )?;
+
+        self.emit_ibc_event(IbcEvent::WriteAcknowledgement(WriteAcknowledgement::new(
+            packet.clone(),
+            acknowledgement,
+            conn_id_on_b,
+        )))
+    }
+}
@@ -0,0 +1,39 @@
+use ibc_core::primitives::Signer;
+use sov_modules_api::Spec;
+use thiserror::Error;
+
+/// Errors raised when binding the signer of an IBC message to the sender of
+/// the rollup transaction carrying it.
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("invalid signer address: {signer}")]
+    InvalidSigner { signer: String },
+    #[error("signer {signer} does not match the transaction sender {sender}")]
+    SignerMismatch { signer: String, sender: String },
+}
+
+/// Checks that the given signer, once converted into a rollup address, is the
+/// sender of the transaction. Relayers and transfer senders are thereby
+/// authenticated by the rollup, so that neither the fees paid to relayers nor
+/// the tokens sent can be claimed on behalf of someone else.
+pub fn ensure_signer_is_sender<S: Spec>(
+    signer: &Signer,
+    sender: &S::Address,
+) -> Result<(), SignerError> {
+    let signer_address: S::Address =
+        signer
+            .as_ref()
+            .parse()
+            .map_err(|_| SignerError::InvalidSigner {
+                signer: signer.to_string(),
+            })?;
+
+    if &signer_address != sender {
+        return Err(SignerError::SignerMismatch {
+            signer: signer.to_string(),
+            sender: sender.to_string(),
+        });
+    }
+
+    Ok(())
+}
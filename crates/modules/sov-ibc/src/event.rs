@@ -1,10 +1,21 @@
 //! Contains event processing logic.
 
 use std::collections::HashMap;
+use std::fmt::Display;
 
+use anyhow::anyhow;
 use ibc_core::handler::types::error::ContextError;
 use ibc_core::handler::types::events::IbcEvent;
 use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::router::types::event::ModuleEvent;
+
+/// The key, and the module event kind, of the events carrying the diagnostic
+/// messages ibc-rs logs during handshakes and packet handling.
+pub const IBC_LOG_EVENT_KEY: &str = "ibc_log";
+
+/// The key, and the module event kind, of the events carrying the outcome of
+/// each message of an IBC batch.
+pub const IBC_BATCH_EVENT_KEY: &str = "ibc_batch_message";
 
 /// Processes an IBC event and generates an additional packet event with a hashed
 /// key if the event is of `SendPacket` or `ReceivePacket` type.
@@ -48,6 +59,48 @@ pub(crate) fn auxiliary_packet_events(
     Ok(events)
 }
 
+/// Wraps a message logged by ibc-rs into a module event, so that it lands in
+/// the receipt of the transaction being executed.
+pub fn log_event(message: String) -> IbcEvent {
+    IbcEvent::Module(ModuleEvent {
+        kind: IBC_LOG_EVENT_KEY.to_string(),
+        attributes: vec![("message", message).into()],
+    })
+}
+
+/// Records the outcome of a batched message into a module event, along with
+/// the error it was skipped for, if any.
+pub fn batch_message_event(index: usize, type_url: String, error: Option<String>) -> IbcEvent {
+    let mut attributes = vec![
+        ("index", index.to_string()).into(),
+        ("type_url", type_url).into(),
+    ];
+
+    match error {
+        Some(error) => {
+            attributes.push(("status", "skipped").into());
+            attributes.push(("error", error).into());
+        }
+        None => attributes.push(("status", "executed").into()),
+    }
+
+    IbcEvent::Module(ModuleEvent {
+        kind: IBC_BATCH_EVENT_KEY.to_string(),
+        attributes,
+    })
+}
+
+/// Attaches the messages logged while handling an IBC message to the error
+/// it failed with, so that they land in the receipt of the reverted
+/// transaction, which keeps the error but not the `ibc_log` events.
+pub fn error_with_logs(error: impl Display, logs: &[String]) -> anyhow::Error {
+    if logs.is_empty() {
+        return anyhow!("{error}");
+    }
+
+    anyhow!("{error} ({IBC_LOG_EVENT_KEY}: {})", logs.join("; "))
+}
+
 /// Computes the unique base64-encoded key for either a `SendPacket` or
 /// `ReceivePacket` event. This key is typically utilized to index packet data
 /// required for processing pending packets by IBC relayers.
@@ -121,4 +174,51 @@ mod tests {
             "VQeG65fqa8SLEyLtdjn0nI+ktiVh6U29dsvJiIbYOZU="
         );
     }
+
+    #[test]
+    fn test_log_event() {
+        let IbcEvent::Module(event) = log_event("success: packet receive".to_string()) else {
+            panic!("log event must be a module event");
+        };
+
+        assert_eq!(event.kind, IBC_LOG_EVENT_KEY);
+        assert_eq!(event.attributes.len(), 1);
+        assert_eq!(event.attributes[0].key, "message");
+        assert_eq!(event.attributes[0].value, "success: packet receive");
+    }
+
+    #[test]
+    fn test_error_with_logs() {
+        assert_eq!(
+            error_with_logs("packet receipt failed", &[]).to_string(),
+            "packet receipt failed"
+        );
+
+        let logs = vec![
+            "success: packet receive".to_string(),
+            "success: packet write acknowledgement".to_string(),
+        ];
+
+        assert_eq!(
+            error_with_logs("packet receipt failed", &logs).to_string(),
+            "packet receipt failed (ibc_log: success: packet receive; success: packet write acknowledgement)"
+        );
+    }
+
+    #[test]
+    fn test_batch_message_event() {
+        let IbcEvent::Module(event) = batch_message_event(
+            1,
+            "/ibc.core.channel.v1.MsgRecvPacket".to_string(),
+            Some("packet already received".to_string()),
+        ) else {
+            panic!("batch message event must be a module event");
+        };
+
+        assert_eq!(event.kind, IBC_BATCH_EVENT_KEY);
+        assert_eq!(event.attributes.len(), 4);
+        assert_eq!(event.attributes[0].value, "1");
+        assert_eq!(event.attributes[2].value, "skipped");
+        assert_eq!(event.attributes[3].value, "packet already received");
+    }
 }
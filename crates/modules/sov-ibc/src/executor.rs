@@ -0,0 +1,73 @@
+//! Defines how the `Ibc` module executes the rollup runtime calls carried by
+//! IBC packets, such as the transactions of interchain accounts.
+use anyhow::anyhow;
+use sov_ibc_ica::host::InterchainAccountExecutor;
+use sov_modules_api::{Context, Spec, TxState};
+
+use crate::checkpoint::TxCheckpoint;
+
+/// Executes JSON-encoded runtime calls on behalf of a given sender.
+///
+/// The `Ibc` module cannot decode runtime calls by itself, as it does not know
+/// the runtime it is part of. Rollups hand an executor over to the router
+/// through their [`IbcRouterExtension`], which usually deserializes the call
+/// into the runtime call type and dispatches it.
+///
+/// The executor is called either on the transaction state or on a
+/// [`TxCheckpoint`] over it, hence the generic working set.
+///
+/// [`IbcRouterExtension`]: crate::router::IbcRouterExtension
+pub trait RuntimeCallExecutor<S: Spec> {
+    /// Executes the given JSON-encoded runtime call with `sender` as its
+    /// sender, in the slot of the given SDK context.
+    fn execute<TS: TxState<S>>(
+        &self,
+        call: &serde_json::Value,
+        sender: &S::Address,
+        sdk_context: &Context<S>,
+        working_set: &mut TS,
+    ) -> anyhow::Result<()>;
+}
+
+/// Executes the transactions of interchain accounts as sequences of runtime
+/// calls, within a [`TxCheckpoint`] which is only committed if all the calls
+/// succeed.
+pub struct RuntimeTxExecutor<S: Spec, E: RuntimeCallExecutor<S>> {
+    executor: E,
+    sdk_context: Context<S>,
+}
+
+impl<S: Spec, E: RuntimeCallExecutor<S>> RuntimeTxExecutor<S, E> {
+    pub fn new(executor: E, sdk_context: Context<S>) -> Self {
+        Self {
+            executor,
+            sdk_context,
+        }
+    }
+}
+
+impl<S, TS, E> InterchainAccountExecutor<S, TS> for RuntimeTxExecutor<S, E>
+where
+    S: Spec,
+    TS: TxState<S>,
+    E: RuntimeCallExecutor<S>,
+{
+    fn execute_tx(
+        &self,
+        account: &S::Address,
+        calls: &[serde_json::Value],
+        working_set: &mut TS,
+    ) -> anyhow::Result<()> {
+        let mut checkpoint = TxCheckpoint::new(working_set);
+
+        for (index, call) in calls.iter().enumerate() {
+            self.executor
+                .execute(call, account, &self.sdk_context, &mut checkpoint)
+                .map_err(|e| anyhow!("message {index} failed: {e}"))?;
+        }
+
+        checkpoint.commit();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+//! Defines the export of the IBC state of a rollup, which the genesis of the
+//! `Ibc` module and of the application modules imports back. It serves
+//! audits, hard-fork migrations and the seeding of test networks.
+//!
+//! The host state, that is the host heights, timestamps and consensus states,
+//! is left out, as the slot hooks of the new rollup rebuild it, as is any
+//! pending upgrade plan along with the upgraded states.
+//!
+//! The export walks through registries of clients, minted tokens, rate
+//! limits, payees and NFTs. Rollups holding entries that predate these
+//! registries have the authority backfill them with `MsgBackfillRegistries`
+//! before exporting. Likewise, the packet states are walked through the
+//! per-channel packet indexes, into which `MsgMigratePacketIndex` moves the
+//! packet vectors of older rollups.
+use core::time::Duration;
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, bail, Result};
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::Height;
+use ibc_core::host::types::identifiers::{ChannelId, ClientId, ConnectionId, PortId, Sequence};
+use ibc_core::host::types::path::{
+    AckPath, ChannelEndPath, ClientConsensusStatePath, CommitmentPath, ConnectionPath, ReceiptPath,
+    SeqAckPath, SeqRecvPath, SeqSendPath,
+};
+use serde::{Deserialize, Serialize};
+use sov_bank::TokenId;
+use sov_ibc_fee::FeeConfig;
+use sov_ibc_ica::InterchainAccountsConfig;
+use sov_ibc_nft_transfer::NftTransferConfig;
+use sov_ibc_packet_forward::PacketForwardConfig;
+use sov_ibc_transfer::TransferConfig;
+use sov_modules_api::{Spec, TxState};
+use tracing::info;
+
+use crate::genesis::{
+    ChannelGenesis, ClientGenesis, ConnectionGenesis, ConsensusStateGenesis, PacketStateGenesis,
+    PortBinding,
+};
+use crate::router::{default_port_bindings, IbcRouterExtension};
+use crate::{Ibc, IbcConfig};
+
+/// The IBC state of a rollup at a given height, laid out as the genesis
+/// configurations of the `Ibc` module and of the application modules. Its
+/// clients are ordered by creation and its other entries by identifier and
+/// sequence, so that exporting the same state always yields the same
+/// document.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IbcStateExport {
+    /// The rollup height at which the state was exported.
+    pub height: Height,
+    pub ibc: IbcConfig,
+    pub transfer: TransferConfig,
+    pub ica: InterchainAccountsConfig,
+    pub fee: FeeConfig,
+    pub nft_transfer: NftTransferConfig,
+    pub packet_forward: PacketForwardConfig,
+}
+
+/// Registers the entries that predate the registries walked through by the
+/// export, namely the client IDs, the tokens minted through IBC, the rate
+/// limits, the keys of the registered payees and the NFTs issued on or held
+/// by the rollup. Only the authority may send it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize, borsh::BorshSerialize, Clone, Debug, PartialEq, Serialize, Deserialize,
+)]
+pub struct MsgBackfillRegistries {
+    pub client_ids: Vec<ClientId>,
+    pub minted_token_ids: Vec<TokenId>,
+    /// The channels and denoms of the rate limits.
+    pub rate_limits: Vec<(ChannelId, String)>,
+    /// The ports, channels and relayer addresses under which payees or
+    /// counterparty payees are registered.
+    pub payees: Vec<(PortId, ChannelId, String)>,
+    /// The class IDs of the NFT classes issued on the rollup.
+    pub nft_classes: Vec<String>,
+    /// The class and token IDs of the NFTs held on the rollup.
+    pub nfts: Vec<(String, String)>,
+}
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Exports the IBC state of the rollup held by the given working set.
+    pub fn export_state(&self, working_set: &mut impl TxState<S>) -> Result<IbcStateExport> {
+        let height = self
+            .host_height_map
+            .get(working_set)
+            .ok_or_else(|| anyhow!("Host height not found"))?;
+
+        let ibc = self.export_config(working_set)?;
+
+        let transfer_channels = ibc
+            .channels
+            .iter()
+            .map(|channel| (channel.port_id.clone(), channel.channel_id.clone()))
+            .collect::<Vec<_>>();
+
+        let transfer = self
+            .transfer
+            .export_config(&transfer_channels, working_set)?;
+
+        let ica = self.ica.export_config(working_set);
+
+        let fee = self.fee.export_config(&transfer_channels, working_set)?;
+
+        let nft_transfer = self.nft_transfer.export_config(working_set)?;
+
+        let sent_packets = ibc
+            .channels
+            .iter()
+            .flat_map(|channel| {
+                channel.commitments.iter().map(|commitment| {
+                    (
+                        channel.port_id.clone(),
+                        channel.channel_id.clone(),
+                        commitment.sequence,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let packet_forward = self
+            .packet_forward
+            .export_config(&sent_packets, working_set)?;
+
+        Ok(IbcStateExport {
+            height,
+            ibc,
+            transfer,
+            ica,
+            fee,
+            nft_transfer,
+            packet_forward,
+        })
+    }
+
+    /// Registers the given entries that predate the registries walked through
+    /// by the export. Entries already registered are skipped, and entries
+    /// that do not exist are rejected.
+    pub(crate) fn backfill_registries(
+        &self,
+        msg: MsgBackfillRegistries,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        let mut client_ids = self
+            .client_ids_vec
+            .iter(working_set)
+            .collect::<BTreeSet<_>>();
+
+        for client_id in msg.client_ids {
+            let Some(client_state) = self.client_state_map.get(&client_id, working_set) else {
+                bail!("Client {client_id} not found");
+            };
+
+            if client_ids.insert(client_id.clone()) {
+                self.client_ids_vec.push(&client_id, working_set);
+            }
+
+            self.client_type_map.set(
+                &client_id,
+                &client_state.client_type().to_string(),
+                working_set,
+            );
+        }
+
+        self.transfer
+            .backfill_registries(&msg.minted_token_ids, &msg.rate_limits, working_set)?;
+
+        let payees = msg
+            .payees
+            .into_iter()
+            .map(|(port_id, channel_id, relayer)| {
+                let relayer: S::Address = relayer
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid relayer address: {relayer}"))?;
+
+                Ok((port_id, channel_id, relayer))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.fee.backfill_payees(&payees, working_set)?;
+
+        self.nft_transfer
+            .backfill_registries(&msg.nft_classes, &msg.nfts, working_set)?;
+
+        info!("Backfilled the registries walked through by the export");
+
+        Ok(())
+    }
+
+    /// Exports the `Ibc` module state as a configuration that its genesis
+    /// imports back. Clients are walked through by their registry,
+    /// connections and channels by their counters, and the packet state
+    /// through the per-channel sequence indexes.
+    pub fn export_config(&self, working_set: &mut impl TxState<S>) -> Result<IbcConfig> {
+        if self.packet_index_migration_pending(working_set) {
+            bail!("Packet vectors are pending migration into the per-channel packet indexes");
+        }
+
+        let authority = self
+            .authority
+            .get(working_set)
+            .map(|authority| authority.to_string());
+
+        let self_client = self.self_client_params.get(working_set);
+
+        let max_expected_time_per_block = self
+            .max_expected_time_per_block
+            .get(working_set)
+            .map(Duration::from_nanos);
+
+        let host_state_retention = self.host_state_retention.get(working_set);
+
+        let next_client_sequence = self.client_counter.get(working_set).unwrap_or_default();
+        let next_connection_sequence = self.connection_counter.get(working_set).unwrap_or_default();
+        let next_channel_sequence = self.channel_counter.get(working_set).unwrap_or_default();
+
+        let mut clients = Vec::new();
+
+        for client_id in self.client_ids_vec.iter(working_set).collect::<Vec<_>>() {
+            let client_state = self
+                .client_state_map
+                .get(&client_id, working_set)
+                .ok_or_else(|| anyhow!("Client state of client {client_id} not found"))?;
+
+            let mut consensus_states = Vec::new();
+
+            for height in self
+                .consensus_height_index()
+                .heights(&client_id, working_set)
+                .map_err(|e| anyhow!("{e}"))?
+            {
+                let consensus_state_path = ClientConsensusStatePath::new(
+                    client_id.clone(),
+                    height.revision_number(),
+                    height.revision_height(),
+                );
+
+                let Some(consensus_state) = self
+                    .consensus_state_map
+                    .get(&consensus_state_path, working_set)
+                else {
+                    continue;
+                };
+
+                let (processed_time, processed_height) = self
+                    .client_update_meta_map
+                    .get(&(client_id.clone(), height), working_set)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Update metadata of client {client_id} at height {height} not found"
+                        )
+                    })?;
+
+                consensus_states.push(ConsensusStateGenesis {
+                    height,
+                    consensus_state: consensus_state.into(),
+                    processed_time,
+                    processed_height,
+                });
+            }
+
+            clients.push(ClientGenesis {
+                client_id,
+                client_state: client_state.into(),
+                consensus_states,
+            });
+        }
+
+        let connections = (0..next_connection_sequence)
+            .map(ConnectionId::new)
+            .filter_map(|connection_id| {
+                self.connection_end_map
+                    .get(&ConnectionPath::new(&connection_id), working_set)
+                    .map(|connection_end| ConnectionGenesis {
+                        connection_id,
+                        connection_end,
+                    })
+            })
+            .collect();
+
+        let default_ports = default_port_bindings()
+            .into_iter()
+            .map(|(port_id, _)| port_id)
+            .collect::<BTreeSet<_>>();
+
+        let bound_ports = self
+            .bound_ports_vec
+            .iter(working_set)
+            .collect::<BTreeSet<_>>();
+
+        let mut port_bindings = Vec::new();
+
+        for port_id in bound_ports.difference(&default_ports) {
+            let module_id = self
+                .port_module_map
+                .get(port_id, working_set)
+                .ok_or_else(|| anyhow!("Module bound to port {port_id} not found"))?;
+
+            port_bindings.push(PortBinding {
+                port_id: port_id.clone(),
+                module_id,
+            });
+        }
+
+        let mut channels = Vec::new();
+
+        for i in 0..next_channel_sequence {
+            let channel_id = ChannelId::new(i);
+
+            // Imported channels may leave gaps below the channel counter
+            let Some(port_id) = &self.channel_port_map.get(&channel_id, working_set) else {
+                continue;
+            };
+
+            let channel_end_path = ChannelEndPath::new(port_id, &channel_id);
+
+            let channel_end = self
+                .channel_end_map
+                .get(&channel_end_path, working_set)
+                .ok_or_else(|| anyhow!("Channel {channel_id} on port {port_id} not found"))?;
+
+            let key = (port_id.clone(), channel_id.clone());
+
+            let commitments = self
+                .packet_commitment_index()
+                .sequences(&channel_end_path, working_set)
+                .into_iter()
+                .filter_map(|sequence| {
+                    self.packet_commitment_map
+                        .get(
+                            &CommitmentPath::new(port_id, &channel_id, sequence),
+                            working_set,
+                        )
+                        .map(|commitment| PacketStateGenesis {
+                            sequence,
+                            data: commitment.as_ref().to_vec(),
+                        })
+                })
+                .collect();
+
+            let receipts = self
+                .packet_receipt_index()
+                .sequences(&channel_end_path, working_set)
+                .into_iter()
+                .filter(|sequence| {
+                    self.packet_receipt_map
+                        .get(
+                            &ReceiptPath::new(port_id, &channel_id, *sequence),
+                            working_set,
+                        )
+                        .is_some()
+                })
+                .collect();
+
+            let acknowledgements = self
+                .packet_ack_index()
+                .sequences(&channel_end_path, working_set)
+                .into_iter()
+                .filter_map(|sequence| {
+                    self.packet_ack_map
+                        .get(&AckPath::new(port_id, &channel_id, sequence), working_set)
+                        .map(|ack| PacketStateGenesis {
+                            sequence,
+                            data: ack.as_ref().to_vec(),
+                        })
+                })
+                .collect();
+
+            channels.push(ChannelGenesis {
+                port_id: port_id.clone(),
+                channel_id: channel_id.clone(),
+                channel_end,
+                next_sequence_send: next_sequence(
+                    self.send_sequence_map
+                        .get(&SeqSendPath::new(port_id, &channel_id), working_set),
+                    &key,
+                    "send",
+                )?,
+                next_sequence_recv: next_sequence(
+                    self.recv_sequence_map
+                        .get(&SeqRecvPath::new(port_id, &channel_id), working_set),
+                    &key,
+                    "recv",
+                )?,
+                next_sequence_ack: next_sequence(
+                    self.ack_sequence_map
+                        .get(&SeqAckPath::new(port_id, &channel_id), working_set),
+                    &key,
+                    "ack",
+                )?,
+                commitments,
+                receipts,
+                acknowledgements,
+            });
+        }
+
+        Ok(IbcConfig {
+            authority,
+            self_client,
+            max_expected_time_per_block,
+            host_state_retention,
+            clients,
+            next_client_sequence,
+            connections,
+            next_connection_sequence,
+            port_bindings,
+            channels,
+            next_channel_sequence,
+        })
+    }
+}
+
+fn next_sequence(
+    sequence: Option<Sequence>,
+    (port_id, channel_id): &(PortId, ChannelId),
+    name: &str,
+) -> Result<Sequence> {
+    sequence.ok_or_else(|| {
+        anyhow!("Next {name} sequence of channel {channel_id} on port {port_id} not found")
+    })
+}
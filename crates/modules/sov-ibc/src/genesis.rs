@@ -1,18 +1,672 @@
-use anyhow::Result;
-use sov_modules_api::{GenesisState, Module, Spec};
+//! Defines the genesis of the `Ibc` module, which may import the IBC state of
+//! a previous rollup, so that a rollup can be relaunched or forked without
+//! losing its clients, connections and channels.
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
 
-use crate::Ibc;
+use anyhow::{anyhow, bail, Result};
+use ibc_app_transfer::types::MODULE_ID_STR;
+use ibc_core::channel::types::channel::{ChannelEnd, Order};
+use ibc_core::channel::types::commitment::{AcknowledgementCommitment, PacketCommitment};
+use ibc_core::channel::types::packet::Receipt;
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::Height;
+use ibc_core::connection::types::ConnectionEnd;
+use ibc_core::host::types::identifiers::{
+    ChainId, ChannelId, ClientId, ConnectionId, PortId, Sequence,
+};
+use ibc_core::host::types::path::{
+    AckPath, ChannelEndPath, ClientConnectionPath, ClientConsensusStatePath, CommitmentPath,
+    ConnectionPath, ReceiptPath, SeqAckPath, SeqRecvPath, SeqSendPath,
+};
+use ibc_core::primitives::proto::Any;
+use ibc_core::primitives::Timestamp;
+use serde::{Deserialize, Serialize};
+use sov_celestia_client::types::sovereign::ZkVm;
+use sov_ibc_ica::types::{CONTROLLER_MODULE_ID_STR, HOST_MODULE_ID_STR};
+use sov_modules_api::{GenesisState, Module, Spec, StateCheckpoint};
+use tracing::info;
 
-impl<S: Spec> Ibc<S> {
+use crate::clients::{AnyClientState, AnyConsensusState};
+use crate::router::{default_port_bindings, IbcRouterExtension};
+use crate::{Ibc, IbcConfig};
+
+/// The parameters the clients of the rollup hosted on counterparties must
+/// carry, against which `validate_self_client` checks them upon connection
+/// handshakes.
+#[derive(
+    borsh::BorshDeserialize,
+    borsh::BorshSerialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct SelfClientParams {
+    /// The state root of the rollup right after its genesis. It may be left
+    /// out when unknown in advance, as at the first launch of a rollup, since
+    /// the genesis configuration itself determines it, in which case the
+    /// first slot hook records it.
+    #[serde(default)]
+    pub genesis_state_root: Option<[u8; 32]>,
+    /// The code commitment of the rollup software.
+    pub code_commitment: Vec<u8>,
+    /// The DA height at which the rollup started.
+    pub genesis_da_height: Height,
+    /// The chain ID of the DA layer.
+    pub da_chain_id: ChainId,
+    /// The zkVM the aggregated proofs of the rollup are generated with, such
+    /// as `risc0`, which is also the one assumed when left empty, as for the
+    /// client states themselves.
+    #[serde(default)]
+    pub zk_vm: String,
+}
+
+impl SelfClientParams {
+    fn validate(&self) -> Result<()> {
+        self.zk_vm()?;
+
+        Ok(())
+    }
+
+    /// Returns the zkVM the clients of the rollup must carry.
+    pub fn zk_vm(&self) -> Result<ZkVm> {
+        if self.zk_vm.is_empty() {
+            return Ok(ZkVm::default());
+        }
+
+        ZkVm::from_str(&self.zk_vm).map_err(|e| anyhow!("Invalid zkVM of the rollup client: {e}"))
+    }
+}
+
+/// A client imported at genesis, along with its consensus states.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClientGenesis {
+    pub client_id: ClientId,
+    pub client_state: Any,
+    #[serde(default)]
+    pub consensus_states: Vec<ConsensusStateGenesis>,
+}
+
+/// A consensus state of an imported client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConsensusStateGenesis {
+    pub height: Height,
+    pub consensus_state: Any,
+    /// The host time at which the client was updated to this height, against
+    /// which the connection delays are measured.
+    pub processed_time: Timestamp,
+    /// The host height at which the client was updated to this height.
+    pub processed_height: Height,
+}
+
+/// A connection imported at genesis.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConnectionGenesis {
+    pub connection_id: ConnectionId,
+    pub connection_end: ConnectionEnd,
+}
+
+/// A port bound at genesis on top of the default ones, such as the
+/// controller ports of the interchain accounts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortBinding {
+    pub port_id: PortId,
+    pub module_id: String,
+}
+
+/// A channel imported at genesis, along with its sequences and packet state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChannelGenesis {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub channel_end: ChannelEnd,
+    pub next_sequence_send: Sequence,
+    pub next_sequence_recv: Sequence,
+    pub next_sequence_ack: Sequence,
+    /// The commitments of the sent packets that are neither acknowledged nor
+    /// timed out yet.
+    #[serde(default)]
+    pub commitments: Vec<PacketStateGenesis>,
+    /// The sequences of the received packets, for unordered channels.
+    #[serde(default)]
+    pub receipts: Vec<Sequence>,
+    /// The acknowledgement commitments of the received packets.
+    #[serde(default)]
+    pub acknowledgements: Vec<PacketStateGenesis>,
+}
+
+/// The commitment stored for a packet sequence.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PacketStateGenesis {
+    pub sequence: Sequence,
+    pub data: Vec<u8>,
+}
+
+impl IbcConfig {
+    /// Checks that the imported state is internally consistent: identifiers
+    /// are unique and below the counters, every connection, channel and
+    /// packet refers to an imported client, connection or channel, and the
+    /// packet state is consistent with the channel sequences and ordering.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(retention) = &self.host_state_retention {
+            retention.validate()?;
+        }
+
+        if let Some(self_client) = &self.self_client {
+            self_client.validate()?;
+        }
+
+        let mut client_ids = BTreeSet::new();
+
+        for client in &self.clients {
+            check_below_counter(
+                client.client_id.as_str(),
+                self.next_client_sequence,
+                "client",
+            )?;
+
+            if !client_ids.insert(&client.client_id) {
+                bail!("Client {} is imported more than once", client.client_id);
+            }
+
+            client.validate()?;
+        }
+
+        let mut connection_ids = BTreeSet::new();
+
+        for connection in &self.connections {
+            check_below_counter(
+                connection.connection_id.as_str(),
+                self.next_connection_sequence,
+                "connection",
+            )?;
+
+            if !connection_ids.insert(&connection.connection_id) {
+                bail!(
+                    "Connection {} is imported more than once",
+                    connection.connection_id
+                );
+            }
+
+            let client_id = connection.connection_end.client_id();
+
+            if !client_ids.contains(client_id) {
+                bail!(
+                    "Connection {} refers to client {client_id}, which is not imported",
+                    connection.connection_id
+                );
+            }
+        }
+
+        let mut bound_ports: BTreeSet<PortId> = default_port_bindings()
+            .into_iter()
+            .map(|(port_id, _)| port_id)
+            .collect();
+
+        for binding in &self.port_bindings {
+            if !bound_ports.insert(binding.port_id.clone()) {
+                bail!("Port {} is bound more than once", binding.port_id);
+            }
+        }
+
+        let mut channel_ids = BTreeSet::new();
+
+        for channel in &self.channels {
+            check_below_counter(
+                channel.channel_id.as_str(),
+                self.next_channel_sequence,
+                "channel",
+            )?;
+
+            if !channel_ids.insert((&channel.port_id, &channel.channel_id)) {
+                bail!(
+                    "Channel {} on port {} is imported more than once",
+                    channel.channel_id,
+                    channel.port_id
+                );
+            }
+
+            if !bound_ports.contains(&channel.port_id) {
+                bail!(
+                    "Channel {} is on port {}, which is not bound",
+                    channel.channel_id,
+                    channel.port_id
+                );
+            }
+
+            let connection_id = channel
+                .channel_end
+                .connection_hops()
+                .first()
+                .ok_or_else(|| anyhow!("Channel {} has no connection hop", channel.channel_id))?;
+
+            if !connection_ids.contains(connection_id) {
+                bail!(
+                    "Channel {} refers to connection {connection_id}, which is not imported",
+                    channel.channel_id
+                );
+            }
+
+            channel.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ClientGenesis {
+    fn validate(&self) -> Result<()> {
+        let client_state = AnyClientState::try_from(self.client_state.clone())
+            .map_err(|e| anyhow!("Invalid client state of client {}: {e}", self.client_id))?;
+
+        let mut heights = BTreeSet::new();
+
+        for consensus_state in &self.consensus_states {
+            if !heights.insert(consensus_state.height) {
+                bail!(
+                    "Consensus state of client {} at height {} is imported more than once",
+                    self.client_id,
+                    consensus_state.height
+                );
+            }
+
+            AnyConsensusState::try_from(consensus_state.consensus_state.clone()).map_err(|e| {
+                anyhow!(
+                    "Invalid consensus state of client {} at height {}: {e}",
+                    self.client_id,
+                    consensus_state.height
+                )
+            })?;
+        }
+
+        if !heights.contains(&client_state.latest_height()) {
+            bail!(
+                "Client {} has no consensus state at its latest height {}",
+                self.client_id,
+                client_state.latest_height()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl ChannelGenesis {
+    fn validate(&self) -> Result<()> {
+        for (sequence, name) in [
+            (self.next_sequence_send, "send"),
+            (self.next_sequence_recv, "recv"),
+            (self.next_sequence_ack, "ack"),
+        ] {
+            if sequence.value() == 0 {
+                bail!(
+                    "Next {name} sequence of channel {} cannot be zero",
+                    self.channel_id
+                );
+            }
+        }
+
+        let is_ordered = self.channel_end.ordering() == &Order::Ordered;
+
+        let mut sent = BTreeSet::new();
+
+        for commitment in &self.commitments {
+            self.check_packet_state(commitment, &mut sent, "commitment")?;
+
+            if commitment.sequence >= self.next_sequence_send {
+                bail!(
+                    "Commitment of packet {} on channel {} is not below the next send sequence",
+                    commitment.sequence,
+                    self.channel_id
+                );
+            }
+        }
+
+        if is_ordered && !self.receipts.is_empty() {
+            bail!(
+                "Ordered channel {} cannot hold packet receipts",
+                self.channel_id
+            );
+        }
+
+        let mut received = BTreeSet::new();
+
+        for sequence in &self.receipts {
+            if sequence.value() == 0 || !received.insert(*sequence) {
+                bail!(
+                    "Receipt of packet {sequence} on channel {} is invalid or imported more than once",
+                    self.channel_id
+                );
+            }
+        }
+
+        let mut acknowledged = BTreeSet::new();
+
+        for ack in &self.acknowledgements {
+            self.check_packet_state(ack, &mut acknowledged, "acknowledgement")?;
+
+            let is_received = if is_ordered {
+                ack.sequence < self.next_sequence_recv
+            } else {
+                received.contains(&ack.sequence)
+            };
+
+            if !is_received {
+                bail!(
+                    "Acknowledgement of packet {} on channel {} is for a packet not received",
+                    ack.sequence,
+                    self.channel_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_packet_state(
+        &self,
+        state: &PacketStateGenesis,
+        sequences: &mut BTreeSet<Sequence>,
+        kind: &str,
+    ) -> Result<()> {
+        if state.sequence.value() == 0 || state.data.is_empty() {
+            bail!(
+                "Packet {kind} of sequence {} on channel {} is invalid",
+                state.sequence,
+                self.channel_id
+            );
+        }
+
+        if !sequences.insert(state.sequence) {
+            bail!(
+                "Packet {kind} of sequence {} on channel {} is imported more than once",
+                state.sequence,
+                self.channel_id
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the sequence number ending the given identifier is below the
+/// counter of its kind, so that no identifier gets reused.
+fn check_below_counter(identifier: &str, counter: u64, kind: &str) -> Result<()> {
+    let sequence: u64 = identifier
+        .rsplit_once('-')
+        .and_then(|(_, sequence)| sequence.parse().ok())
+        .ok_or_else(|| anyhow!("Identifier {identifier} does not end with a sequence number"))?;
+
+    if sequence >= counter {
+        bail!("Identifier {identifier} is not below the next {kind} sequence {counter}");
+    }
+
+    Ok(())
+}
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
     pub(crate) fn init_module(
         &self,
-        _config: &<Self as Module>::Config,
+        config: &<Self as Module>::Config,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        config.validate()?;
+
+        if let Some(authority) = &config.authority {
+            let authority: S::Address = authority
+                .parse()
+                .map_err(|_| anyhow!("Invalid IBC authority address: {authority}"))?;
+
+            self.authority.set(&authority, working_set);
+        }
+
+        if let Some(self_client) = &config.self_client {
+            self.self_client_params.set(self_client, working_set);
+        }
+
+        if let Some(block_time) = &config.max_expected_time_per_block {
+            let block_time = u64::try_from(block_time.as_nanos())
+                .map_err(|_| anyhow!("Max expected time per block is too large: {block_time:?}"))?;
+
+            self.max_expected_time_per_block
+                .set(&block_time, working_set);
+        }
+
+        if let Some(retention) = &config.host_state_retention {
+            self.host_state_retention.set(retention, working_set);
+        }
+
+        self.client_counter
+            .set(&config.next_client_sequence, working_set);
+        self.connection_counter
+            .set(&config.next_connection_sequence, working_set);
+        self.channel_counter
+            .set(&config.next_channel_sequence, working_set);
+
+        let port_bindings = default_port_bindings().into_iter().chain(
+            config
+                .port_bindings
+                .iter()
+                .map(|binding| (binding.port_id.clone(), binding.module_id.clone())),
+        );
+
+        for (port_id, module_id) in port_bindings {
+            self.port_module_map.set(&port_id, &module_id, working_set);
+            self.bound_ports_vec.push(&port_id, working_set);
+        }
+
+        for client in &config.clients {
+            self.import_client(client, working_set)?;
+        }
+
+        let mut client_connections: BTreeMap<ClientId, Vec<ConnectionId>> = BTreeMap::new();
+
+        for connection in &config.connections {
+            self.connection_end_map.set(
+                &ConnectionPath::new(&connection.connection_id),
+                &connection.connection_end,
+                working_set,
+            );
+
+            client_connections
+                .entry(connection.connection_end.client_id().clone())
+                .or_default()
+                .push(connection.connection_id.clone());
+        }
+
+        for (client_id, connection_ids) in client_connections {
+            self.client_connections_map.set(
+                &ClientConnectionPath::new(client_id),
+                &connection_ids,
+                working_set,
+            );
+        }
+
+        for channel in &config.channels {
+            self.import_channel(channel, working_set);
+            self.import_app_channel(channel, working_set)?;
+        }
+
+        Ok(())
+    }
+
+    fn import_client(
+        &self,
+        client: &ClientGenesis,
         working_set: &mut impl GenesisState<S>,
     ) -> Result<()> {
-        self.client_counter.set(&0, working_set);
-        self.connection_counter.set(&0, working_set);
-        self.channel_counter.set(&0, working_set);
+        let client_state = AnyClientState::try_from(client.client_state.clone())?;
+
+        self.client_ids_vec.push(&client.client_id, working_set);
+        self.client_type_map.set(
+            &client.client_id,
+            &client_state.client_type().to_string(),
+            working_set,
+        );
+        self.client_state_map
+            .set(&client.client_id, &client_state, working_set);
+
+        for consensus_state in &client.consensus_states {
+            let height = consensus_state.height;
+
+            self.consensus_state_map.set(
+                &ClientConsensusStatePath::new(
+                    client.client_id.clone(),
+                    height.revision_number(),
+                    height.revision_height(),
+                ),
+                &AnyConsensusState::try_from(consensus_state.consensus_state.clone())?,
+                working_set,
+            );
+
+            self.client_update_meta_map.set(
+                &(client.client_id.clone(), height),
+                &(
+                    consensus_state.processed_time,
+                    consensus_state.processed_height,
+                ),
+                working_set,
+            );
+        }
+
+        self.consensus_height_index().import(
+            &client.client_id,
+            client
+                .consensus_states
+                .iter()
+                .map(|consensus_state| consensus_state.height),
+            working_set,
+        );
 
         Ok(())
     }
+
+    /// Restores the per-channel state the application bound to the port of an
+    /// imported channel records during channel handshakes, out of the channel
+    /// version, namely the fee-enabled transfer channels and the registries
+    /// of the interchain accounts.
+    fn import_app_channel(
+        &self,
+        channel: &ChannelGenesis,
+        working_set: &mut impl GenesisState<S>,
+    ) -> Result<()> {
+        let (port_id, channel_id, channel_end) =
+            (&channel.port_id, &channel.channel_id, &channel.channel_end);
+
+        let module_id = self
+            .port_module_map
+            .get(port_id, working_set)
+            .ok_or_else(|| anyhow!("Port {port_id} of channel {channel_id} is not bound"))?;
+
+        match module_id.as_str() {
+            MODULE_ID_STR => self
+                .fee
+                .import_channel(port_id, channel_id, channel_end, working_set),
+            HOST_MODULE_ID_STR => {
+                self.ica
+                    .import_host_channel(channel_id, channel_end, working_set)
+            }
+            CONTROLLER_MODULE_ID_STR => {
+                self.ica
+                    .import_controller_channel(port_id, channel_id, channel_end, working_set)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn import_channel(&self, channel: &ChannelGenesis, working_set: &mut impl GenesisState<S>) {
+        let (port_id, channel_id) = (&channel.port_id, &channel.channel_id);
+
+        let channel_end_path = ChannelEndPath::new(port_id, channel_id);
+
+        self.channel_end_map
+            .set(&channel_end_path, &channel.channel_end, working_set);
+        self.channel_port_map.set(channel_id, port_id, working_set);
+
+        self.send_sequence_map.set(
+            &SeqSendPath::new(port_id, channel_id),
+            &channel.next_sequence_send,
+            working_set,
+        );
+        self.recv_sequence_map.set(
+            &SeqRecvPath::new(port_id, channel_id),
+            &channel.next_sequence_recv,
+            working_set,
+        );
+        self.ack_sequence_map.set(
+            &SeqAckPath::new(port_id, channel_id),
+            &channel.next_sequence_ack,
+            working_set,
+        );
+
+        self.packet_commitment_index().import(
+            &channel_end_path,
+            channel
+                .commitments
+                .iter()
+                .map(|commitment| commitment.sequence),
+            working_set,
+        );
+
+        for commitment in &channel.commitments {
+            let commitment_path = CommitmentPath::new(port_id, channel_id, commitment.sequence);
+
+            self.packet_commitment_map.set(
+                &commitment_path,
+                &PacketCommitment::from(commitment.data.clone()),
+                working_set,
+            );
+        }
+
+        self.packet_receipt_index().import(
+            &channel_end_path,
+            channel.receipts.iter().copied(),
+            working_set,
+        );
+
+        for sequence in &channel.receipts {
+            self.packet_receipt_map.set(
+                &ReceiptPath::new(port_id, channel_id, *sequence),
+                &Receipt::Ok,
+                working_set,
+            );
+        }
+
+        self.packet_ack_index().import(
+            &channel_end_path,
+            channel.acknowledgements.iter().map(|ack| ack.sequence),
+            working_set,
+        );
+
+        for ack in &channel.acknowledgements {
+            self.packet_ack_map.set(
+                &AckPath::new(port_id, channel_id, ack.sequence),
+                &AcknowledgementCommitment::from(ack.data.clone()),
+                working_set,
+            );
+        }
+    }
+
+    /// Records the genesis state root of the rollup in its client parameters
+    /// if they were configured without it, as it is only known once the
+    /// genesis is applied. Called by the slot hooks with the state root the
+    /// slot starts from, which is the genesis one at the first slot.
+    pub fn record_genesis_state_root(&self, root: [u8; 32], working_set: &mut StateCheckpoint<S>) {
+        let Some(mut params) = self.self_client_params.get(working_set) else {
+            return;
+        };
+
+        if params.genesis_state_root.is_some() {
+            return;
+        }
+
+        params.genesis_state_root = Some(root);
+
+        self.self_client_params.set(&params, working_set);
+
+        info!("Genesis state root of the rollup is recorded: {root:?}");
+    }
 }
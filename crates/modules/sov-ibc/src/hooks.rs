@@ -0,0 +1,404 @@
+//! Defines the IBC hooks, which let inbound ICS-20 transfers trigger a rollup
+//! runtime call carried in their memo, similar to `ibc-go`'s wasm hooks.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_app_transfer::types::packet::PacketData;
+use ibc_app_transfer::types::Memo;
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::types::module::ModuleExtras;
+use sov_ibc_packet_forward::types::FORWARD_MEMO_KEY;
+use sov_ibc_transfer::context::IbcTransferContext;
+use sov_ibc_transfer::utils::{error_acknowledgement, is_ack_successful};
+use sov_ibc_utils::derive_module_address;
+use sov_modules_api::{Spec, TxState};
+
+use crate::checkpoint::TxCheckpoint;
+use crate::executor::RuntimeCallExecutor;
+
+/// The key of the runtime call in the ICS-20 memos.
+pub const HOOK_MEMO_KEY: &str = "call";
+
+/// Domain separator of the hook sender addresses.
+const HOOK_SENDER_PREFIX: &str = "ibc-hook-intermediary";
+
+/// Extracts the runtime call out of an ICS-20 memo. Returns `None` if the memo
+/// does not carry any, and fails if it also carries forwarding instructions,
+/// as the tokens cannot be both forwarded and spent on the rollup.
+pub fn hook_call(memo: &Memo) -> Result<Option<serde_json::Value>, String> {
+    let Ok(serde_json::Value::Object(memo)) = serde_json::from_str(memo.as_ref()) else {
+        return Ok(None);
+    };
+
+    let Some(call) = memo.get(HOOK_MEMO_KEY) else {
+        return Ok(None);
+    };
+
+    if memo.contains_key(FORWARD_MEMO_KEY) {
+        return Err(format!(
+            "memo cannot carry both `{HOOK_MEMO_KEY}` and `{FORWARD_MEMO_KEY}` instructions"
+        ));
+    }
+
+    Ok(Some(call.clone()))
+}
+
+/// The hook sender receives the tokens of an inbound transfer carrying a
+/// runtime call, and executes the call with them. It is derived from the
+/// inbound channel and the original sender, so that no counterparty chain can
+/// impersonate another chain's senders.
+pub fn compute_hook_sender_address<S: Spec>(
+    channel_id: &ChannelId,
+    original_sender: &str,
+) -> S::Address {
+    derive_module_address::<S, _>(
+        HOOK_SENDER_PREFIX,
+        &format!("{channel_id}/{original_sender}"),
+    )
+}
+
+/// The IBC hooks middleware, which wraps the ICS-20 transfer application
+/// stack to execute the runtime calls carried in the memos of inbound
+/// transfers.
+///
+/// The receiver of such a transfer is replaced by its hook sender, which then
+/// executes the call once the tokens are minted or unescrowed. The call runs
+/// within a [`TxCheckpoint`], so that all its writes and events, including the
+/// funds it moves, are reverted if it fails. The tokens are then taken back
+/// the way they came in and the packet is acknowledged with an error, so that
+/// the sending chain refunds the original sender.
+pub struct IbcHooksMiddleware<'ws, S: Spec, TS: TxState<S>, M: Module, E: RuntimeCallExecutor<S>> {
+    pub app: M,
+    pub transfer_ctx: IbcTransferContext<'ws, S, TS>,
+    pub executor: E,
+    pub working_set: Rc<RefCell<&'ws mut TS>>,
+}
+
+impl<'ws, S, TS, M, E> IbcHooksMiddleware<'ws, S, TS, M, E>
+where
+    S: Spec,
+    TS: TxState<S>,
+    M: Module,
+    E: RuntimeCallExecutor<S>,
+{
+    pub fn new(
+        app: M,
+        transfer_ctx: IbcTransferContext<'ws, S, TS>,
+        executor: E,
+        working_set: Rc<RefCell<&'ws mut TS>>,
+    ) -> Self {
+        Self {
+            app,
+            transfer_ctx,
+            executor,
+            working_set,
+        }
+    }
+
+    /// Executes the runtime call with the hook sender as its sender, and only
+    /// applies its effects if it succeeds.
+    fn execute_call(
+        &self,
+        call: &serde_json::Value,
+        hook_sender: &S::Address,
+    ) -> anyhow::Result<()> {
+        let mut working_set = self.working_set.borrow_mut();
+
+        let mut checkpoint = TxCheckpoint::new(*working_set);
+
+        self.executor.execute(
+            call,
+            hook_sender,
+            &self.transfer_ctx.sdk_context,
+            &mut checkpoint,
+        )?;
+
+        checkpoint.commit();
+
+        Ok(())
+    }
+}
+
+impl<'ws, S, TS, M, E> core::fmt::Debug for IbcHooksMiddleware<'ws, S, TS, M, E>
+where
+    S: Spec,
+    TS: TxState<S>,
+    M: Module,
+    E: RuntimeCallExecutor<S>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IbcHooksMiddleware")
+            .field("app", &self.app)
+            .finish()
+    }
+}
+
+impl<'ws, S, TS, M, E> Module for IbcHooksMiddleware<'ws, S, TS, M, E>
+where
+    S: Spec,
+    TS: TxState<S>,
+    M: Module,
+    E: RuntimeCallExecutor<S>,
+{
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        self.app.on_chan_open_init_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            version,
+        )
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        self.app.on_chan_open_init_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            version,
+        )
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ChannelVersion, ChannelError> {
+        self.app.on_chan_open_try_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            counterparty_version,
+        )
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(ModuleExtras, ChannelVersion), ChannelError> {
+        self.app.on_chan_open_try_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            counterparty_version,
+        )
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<(), ChannelError> {
+        self.app
+            .on_chan_open_ack_validate(port_id, channel_id, counterparty_version)
+    }
+
+    fn on_chan_open_ack_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &ChannelVersion,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.app
+            .on_chan_open_ack_execute(port_id, channel_id, counterparty_version)
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.app.on_chan_open_confirm_validate(port_id, channel_id)
+    }
+
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.app.on_chan_open_confirm_execute(port_id, channel_id)
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.app.on_chan_close_init_validate(port_id, channel_id)
+    }
+
+    fn on_chan_close_init_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.app.on_chan_close_init_execute(port_id, channel_id)
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        self.app.on_chan_close_confirm_validate(port_id, channel_id)
+    }
+
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        self.app.on_chan_close_confirm_execute(port_id, channel_id)
+    }
+
+    /// Executes the runtime call carried in the memo of the packet, if any,
+    /// once its tokens are received by the hook sender. Any other packet is
+    /// passed through to the application.
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let Ok(data) = serde_json::from_slice::<PacketData>(&packet.data) else {
+            return self.app.on_recv_packet_execute(packet, relayer);
+        };
+
+        let call = match hook_call(&data.memo) {
+            Ok(Some(call)) => call,
+            Ok(None) => return self.app.on_recv_packet_execute(packet, relayer),
+            Err(e) => return (ModuleExtras::empty(), error_acknowledgement(e)),
+        };
+
+        let hook_sender =
+            compute_hook_sender_address::<S>(&packet.chan_id_on_b, data.sender.as_ref());
+
+        let hook_data = PacketData {
+            receiver: Signer::from(hook_sender.to_string()),
+            ..data.clone()
+        };
+
+        let hook_packet = Packet {
+            data: serde_json::to_vec(&hook_data)
+                .expect("never fails as the packet data was just decoded"),
+            ..packet.clone()
+        };
+
+        let (extras, ack) = self.app.on_recv_packet_execute(&hook_packet, relayer);
+
+        if !is_ack_successful(&ack) {
+            return (extras, ack);
+        }
+
+        if let Err(call_err) = self.execute_call(&call, &hook_sender) {
+            let revert_res = self
+                .transfer_ctx
+                .revert_receipt(packet, &data, &hook_sender);
+
+            let description = match revert_res {
+                Ok(()) => format!("runtime call failed: {call_err}"),
+                Err(revert_err) => format!("runtime call failed: {call_err}; {revert_err}"),
+            };
+
+            return (ModuleExtras::empty(), error_acknowledgement(description));
+        }
+
+        (extras, ack)
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        self.app
+            .on_acknowledgement_packet_validate(packet, acknowledgement, relayer)
+    }
+
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        self.app
+            .on_acknowledgement_packet_execute(packet, acknowledgement, relayer)
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        self.app.on_timeout_packet_validate(packet, relayer)
+    }
+
+    fn on_timeout_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        self.app.on_timeout_packet_execute(packet, relayer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_call_from_memo() {
+        let memo = Memo::from(r#"{"call":{"bank":{"transfer":{}}}}"#.to_string());
+
+        assert_eq!(
+            hook_call(&memo).unwrap(),
+            Some(serde_json::json!({"bank": {"transfer": {}}}))
+        );
+
+        for memo in ["", "a plain memo", r#"{"forward":{}}"#] {
+            assert_eq!(hook_call(&Memo::from(memo.to_string())).unwrap(), None);
+        }
+
+        assert!(hook_call(&Memo::from(r#"{"call":{},"forward":{}}"#.to_string())).is_err());
+    }
+}
@@ -1,8 +1,16 @@
+pub mod batch;
+pub mod block_time;
 pub mod call;
+pub mod checkpoint;
 pub mod clients;
 pub mod codec;
+pub mod error;
 pub mod event;
+pub mod executor;
+pub mod export;
 pub mod genesis;
+pub mod hooks;
+pub mod packet_index;
 
 #[cfg(feature = "native")]
 mod rpc;
@@ -11,8 +19,18 @@ use ibc_core::handler::types::events::IbcEvent;
 pub use rpc::*;
 
 pub mod context;
-mod router;
+pub mod retention;
+pub mod router;
+pub mod tx_logs;
+pub mod upgrade;
 
+#[cfg(test)]
+mod test_utils;
+
+use core::marker::PhantomData;
+use core::time::Duration;
+
+use clients::height_index::ConsensusHeightIndex;
 use clients::{AnyClientState, AnyConsensusState};
 use codec::{AcknowledgementCommitmentCodec, PacketCommitmentCodec, ProtobufCodec};
 use ibc_core::channel::types::channel::ChannelEnd;
@@ -22,24 +40,71 @@ use ibc_core::channel::types::proto::v1::Channel as RawChannelEnd;
 use ibc_core::client::types::Height;
 use ibc_core::connection::types::proto::v1::ConnectionEnd as RawConnectionEnd;
 use ibc_core::connection::types::ConnectionEnd;
-use ibc_core::host::types::identifiers::{ClientId, ConnectionId, Sequence};
+use ibc_core::host::types::identifiers::{ChannelId, ClientId, ConnectionId, PortId, Sequence};
 use ibc_core::host::types::path::{
     AckPath, ChannelEndPath, ClientConnectionPath, ClientConsensusStatePath, CommitmentPath,
     ConnectionPath, ReceiptPath, SeqAckPath, SeqRecvPath, SeqSendPath, UpgradeClientPath,
 };
 use ibc_core::primitives::proto::Any;
 use ibc_core::primitives::Timestamp;
+use packet_index::SequenceIndex;
+use router::IbcRouterExtension;
 use serde::{Deserialize, Serialize};
 use sov_celestia_client::client_state::ClientState as HostClientState;
 use sov_celestia_client::consensus_state::ConsensusState as HostConsensusState;
+use sov_ibc_fee::IbcFee;
+use sov_ibc_ica::IbcInterchainAccounts;
+use sov_ibc_nft_transfer::IbcNftTransfer;
+use sov_ibc_packet_forward::IbcPacketForward;
 use sov_ibc_transfer::IbcTransfer;
 use sov_modules_api::{
     Context, Error, GenesisState, ModuleId, ModuleInfo, Spec, StateMap, StateValue, StateVec,
     TxState,
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
-pub struct ExampleModuleConfig {}
+/// The genesis configuration of the `Ibc` module. Besides the authority, it
+/// may import the IBC state of a previous rollup, which is checked for
+/// internal consistency before being stored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IbcConfig {
+    /// The rollup address allowed to send the privileged IBC messages, such
+    /// as scheduling rollup upgrades, if any.
+    #[serde(default)]
+    pub authority: Option<String>,
+    /// The parameters the clients of the rollup hosted on counterparties
+    /// must carry. Without them, the clients of the rollup cannot be told
+    /// apart from the clients of another rollup, so the connection handshakes
+    /// are rejected.
+    #[serde(default)]
+    pub self_client: Option<genesis::SelfClientParams>,
+    /// The expected time per block, which connection delays are enforced
+    /// with, overriding the one observed on the DA layer, if any.
+    #[serde(default)]
+    pub max_expected_time_per_block: Option<Duration>,
+    /// The retention policy of the host consensus states, which are kept
+    /// forever if none.
+    #[serde(default)]
+    pub host_state_retention: Option<retention::HostStateRetention>,
+    #[serde(default)]
+    pub clients: Vec<genesis::ClientGenesis>,
+    /// The sequence of the next created client, above every imported one.
+    #[serde(default)]
+    pub next_client_sequence: u64,
+    #[serde(default)]
+    pub connections: Vec<genesis::ConnectionGenesis>,
+    /// The sequence of the next created connection, above every imported
+    /// one.
+    #[serde(default)]
+    pub next_connection_sequence: u64,
+    /// The ports bound on top of the default ones.
+    #[serde(default)]
+    pub port_bindings: Vec<genesis::PortBinding>,
+    #[serde(default)]
+    pub channels: Vec<genesis::ChannelGenesis>,
+    /// The sequence of the next created channel, above every imported one.
+    #[serde(default)]
+    pub next_channel_sequence: u64,
+}
 
 /// the sov-ibc module that manages all IBC-related states
 ///
@@ -47,14 +112,54 @@ pub struct ExampleModuleConfig {}
 /// utilized to form prefixes for `state` fields. This naming adheres to the
 /// module naming convention used throughout the codebase, ensuring created
 /// prefixes by modules are in harmony.
+///
+/// The `R` parameter lets the rollup extend the IBC router with its own
+/// application modules. It does not affect the state of the module.
 #[derive(ModuleInfo, Clone)]
-pub struct Ibc<S: Spec> {
+pub struct Ibc<S: Spec, R: IbcRouterExtension<S> = ()> {
     #[id]
     pub id: ModuleId,
 
+    #[phantom]
+    _extension: PhantomData<R>,
+
     #[module]
     transfer: IbcTransfer<S>,
 
+    #[module]
+    ica: IbcInterchainAccounts<S>,
+
+    #[module]
+    fee: IbcFee<S>,
+
+    #[module]
+    nft_transfer: IbcNftTransfer<S>,
+
+    #[module]
+    packet_forward: IbcPacketForward<S>,
+
+    /// The address allowed to send the privileged IBC messages.
+    #[state]
+    authority: StateValue<S::Address>,
+
+    /// The parameters the clients of the rollup hosted on counterparties
+    /// must carry.
+    #[state]
+    self_client_params: StateValue<genesis::SelfClientParams>,
+
+    /// The rollup upgrade pending at a future rollup height, if any.
+    #[state]
+    upgrade_plan: StateValue<upgrade::UpgradePlan>,
+
+    /// The expected time per block set at genesis, in nanoseconds, if any.
+    #[state]
+    max_expected_time_per_block: StateValue<u64>,
+
+    /// The moving average of the interval between the host consensus states,
+    /// in nanoseconds.
+    #[state]
+    observed_block_time: StateValue<u64>,
+
     // ----------- IBC core host state maps -------------
     #[state]
     pub host_height_map: StateValue<Height>,
@@ -65,18 +170,39 @@ pub struct Ibc<S: Spec> {
     #[state]
     pub host_consensus_state_map: StateMap<Height, HostConsensusState, ProtobufCodec<Any>>,
 
+    /// The retention policy of the host consensus states, if any.
+    #[state]
+    host_state_retention: StateValue<retention::HostStateRetention>,
+
+    /// The rollup height below which the host consensus states are pruned.
+    #[state]
+    host_pruned_height: StateValue<u64>,
+
     // ----------- IBC core client state maps -------------
     #[state]
     client_counter: StateValue<u64>,
 
+    /// The identifiers of the clients hosted on the rollup, of every client
+    /// type, in the order they were created or imported.
+    #[state]
+    client_ids_vec: StateVec<ClientId>,
+
+    /// Maps each client of the registry to its client type.
+    #[state]
+    client_type_map: StateMap<ClientId, String>,
+
     #[state]
     client_state_map: StateMap<ClientId, AnyClientState, ProtobufCodec<Any>>,
 
     #[state]
     consensus_state_map: StateMap<ClientConsensusStatePath, AnyConsensusState, ProtobufCodec<Any>>,
 
+    /// Indexes the heights of the consensus states stored per client.
+    #[state]
+    consensus_height_index_slots: StateMap<(ClientId, u64), Height>,
+
     #[state]
-    client_update_heights_vec: StateVec<Height>,
+    consensus_height_index_bounds: StateMap<ClientId, (u64, u64)>,
 
     #[state]
     client_update_meta_map: StateMap<(ClientId, Height), (Timestamp, Height)>,
@@ -98,6 +224,17 @@ pub struct Ibc<S: Spec> {
     #[state]
     client_connections_map: StateMap<ClientConnectionPath, Vec<ConnectionId>>,
 
+    // ----------- IBC core port state maps -------------
+    #[state]
+    port_module_map: StateMap<PortId, String>,
+
+    #[state]
+    bound_ports_vec: StateVec<PortId>,
+
+    /// Maps every channel to the port it was opened on.
+    #[state]
+    channel_port_map: StateMap<ChannelId, PortId>,
+
     // ----------- IBC core channel state maps -------------
     #[state]
     channel_counter: StateValue<u64>,
@@ -114,29 +251,119 @@ pub struct Ibc<S: Spec> {
     #[state]
     ack_sequence_map: StateMap<SeqAckPath, Sequence>,
 
+    /// Indexes the sequences of the packet commitments stored per channel.
+    #[state]
+    packet_commitment_index_links:
+        StateMap<(ChannelEndPath, Sequence), (Option<Sequence>, Option<Sequence>)>,
+
+    #[state]
+    packet_commitment_index_ends: StateMap<ChannelEndPath, (Sequence, Sequence, u64)>,
+
+    /// Holds the packet commitments stored before the per-channel index, until
+    /// `MsgMigratePacketIndex` moves them into it.
     #[state]
     packet_commitment_vec: StateVec<CommitmentPath>,
 
     #[state]
     packet_commitment_map: StateMap<CommitmentPath, PacketCommitment, PacketCommitmentCodec>,
 
+    /// Indexes the sequences of the packet receipts stored per channel.
+    #[state]
+    packet_receipt_index_links:
+        StateMap<(ChannelEndPath, Sequence), (Option<Sequence>, Option<Sequence>)>,
+
+    #[state]
+    packet_receipt_index_ends: StateMap<ChannelEndPath, (Sequence, Sequence, u64)>,
+
+    /// Holds the packet receipts stored before the per-channel index, until
+    /// `MsgMigratePacketIndex` moves them into it.
     #[state]
     packet_receipt_vec: StateVec<ReceiptPath>,
 
     #[state]
     packet_receipt_map: StateMap<ReceiptPath, Receipt>,
 
+    /// Indexes the sequences of the packet acknowledgements stored per channel.
+    #[state]
+    packet_ack_index_links:
+        StateMap<(ChannelEndPath, Sequence), (Option<Sequence>, Option<Sequence>)>,
+
+    #[state]
+    packet_ack_index_ends: StateMap<ChannelEndPath, (Sequence, Sequence, u64)>,
+
+    /// Holds the packet acknowledgements stored before the per-channel index, until
+    /// `MsgMigratePacketIndex` moves them into it.
     #[state]
     packet_ack_vec: StateVec<AckPath>,
 
     #[state]
     packet_ack_map: StateMap<AckPath, AcknowledgementCommitment, AcknowledgementCommitmentCodec>,
+
+    /// Holds the position in the packet vector being migrated up to which
+    /// its entries are indexed.
+    #[state]
+    packet_vec_migration_cursor: StateValue<u64>,
+
+    // ----------- Transaction logs -------------
+    /// The messages `ibc-rs` logged while executing the current transaction,
+    /// until the runtime records them under its hash.
+    #[state]
+    pending_tx_logs: StateValue<Vec<String>>,
+
+    /// Maps the hashes of the latest transactions that logged messages to
+    /// these messages.
+    #[state]
+    tx_logs_map: StateMap<tx_logs::TxHash, Vec<String>>,
+
+    /// Holds the hashes of the transactions of `tx_logs_map` by the order
+    /// they were recorded in, so that the oldest ones can be dropped.
+    #[state]
+    tx_log_hashes: StateMap<u64, tx_logs::TxHash>,
+
+    #[state]
+    tx_log_count: StateValue<u64>,
 }
 
-impl<S: Spec> sov_modules_api::Module for Ibc<S> {
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Returns the type of the given client if it is registered.
+    pub fn registered_client_type(
+        &self,
+        client_id: &ClientId,
+        working_set: &mut impl TxState<S>,
+    ) -> Option<String> {
+        self.client_type_map.get(client_id, working_set)
+    }
+
+    pub(crate) fn consensus_height_index(&self) -> ConsensusHeightIndex<'_> {
+        ConsensusHeightIndex::new(
+            &self.consensus_height_index_slots,
+            &self.consensus_height_index_bounds,
+        )
+    }
+
+    pub(crate) fn packet_commitment_index(&self) -> SequenceIndex<'_> {
+        SequenceIndex::new(
+            &self.packet_commitment_index_links,
+            &self.packet_commitment_index_ends,
+        )
+    }
+
+    pub(crate) fn packet_receipt_index(&self) -> SequenceIndex<'_> {
+        SequenceIndex::new(
+            &self.packet_receipt_index_links,
+            &self.packet_receipt_index_ends,
+        )
+    }
+
+    pub(crate) fn packet_ack_index(&self) -> SequenceIndex<'_> {
+        SequenceIndex::new(&self.packet_ack_index_links, &self.packet_ack_index_ends)
+    }
+}
+
+impl<S: Spec, R: IbcRouterExtension<S>> sov_modules_api::Module for Ibc<S, R> {
     type Spec = S;
 
-    type Config = ExampleModuleConfig;
+    type Config = IbcConfig;
 
     type CallMessage = call::CallMessage;
 
@@ -160,9 +387,51 @@ impl<S: Spec> sov_modules_api::Module for Ibc<S> {
             call::CallMessage::Core(msg_envelope) => {
                 Ok(self.process_core_message(msg_envelope, context.clone(), working_set)?)
             }
+            call::CallMessage::Batch(msg_batch) => {
+                Ok(self.process_batch(msg_batch, context.clone(), working_set)?)
+            }
             call::CallMessage::Transfer(sdk_token_transfer) => {
                 Ok(self.transfer(sdk_token_transfer, context.clone(), working_set)?)
             }
+            call::CallMessage::NftTransfer(msg_nft_transfer) => {
+                Ok(self.nft_transfer(msg_nft_transfer, context.clone(), working_set)?)
+            }
+            call::CallMessage::RegisterInterchainAccount(msg_register) => {
+                Ok(self.register_interchain_account(msg_register, context.clone(), working_set)?)
+            }
+            call::CallMessage::SendInterchainTx(msg_send_tx) => {
+                Ok(self.send_interchain_tx(msg_send_tx, context.clone(), working_set)?)
+            }
+            call::CallMessage::PayPacketFee(msg_pay_fee) => {
+                Ok(self.pay_packet_fee(msg_pay_fee, context.clone(), working_set)?)
+            }
+            call::CallMessage::PayPacketFeeAsync(msg_pay_fee) => {
+                Ok(self.pay_packet_fee_async(msg_pay_fee, context.clone(), working_set)?)
+            }
+            call::CallMessage::RegisterPayee(msg_register) => {
+                Ok(self.register_payee(msg_register, context.clone(), working_set)?)
+            }
+            call::CallMessage::RegisterCounterpartyPayee(msg_register) => {
+                Ok(self.register_counterparty_payee(msg_register, context.clone(), working_set)?)
+            }
+            call::CallMessage::SetRateLimit(msg_set) => {
+                Ok(self.set_rate_limit(msg_set, context.clone(), working_set)?)
+            }
+            call::CallMessage::RemoveRateLimit(msg_remove) => {
+                Ok(self.remove_rate_limit(msg_remove, context.clone(), working_set)?)
+            }
+            call::CallMessage::ScheduleUpgrade(msg_schedule) => {
+                Ok(self.schedule_upgrade(msg_schedule, context.clone(), working_set)?)
+            }
+            call::CallMessage::CancelUpgrade(msg_cancel) => {
+                Ok(self.cancel_upgrade(msg_cancel, context.clone(), working_set)?)
+            }
+            call::CallMessage::BackfillRegistries(msg_backfill) => {
+                Ok(self.backfill(msg_backfill, context.clone(), working_set)?)
+            }
+            call::CallMessage::MigratePacketIndex(msg_migrate) => {
+                Ok(self.migrate_packets(msg_migrate, context.clone(), working_set)?)
+            }
         }
     }
 }
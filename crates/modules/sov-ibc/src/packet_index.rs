@@ -0,0 +1,690 @@
+//! Defines the per-channel index of the sequences under which packet
+//! commitments, receipts or acknowledgements are stored, along with the
+//! migration of the packet vectors it replaces.
+//!
+//! The sequences of a channel are kept in a list linked in ascending order,
+//! so that queries and state exports walk through them in order, one page at
+//! a time, without loading the whole channel. Removing a sequence only
+//! relinks its neighbours. Inserting one walks back from the highest indexed
+//! sequence to its place, which takes a single step for commitments, as the
+//! send sequence only grows, and for receipts and acknowledgements relayed in
+//! order, while packets relayed out of order only walk past the ones relayed
+//! ahead of them. The walk reads state within the transaction inserting the
+//! sequence, so its cost is charged to the sender of the transaction.
+use anyhow::{bail, Result};
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::ChannelEndPath;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{GenesisState, Spec, StateMap, TxState};
+use tracing::info;
+
+use crate::router::IbcRouterExtension;
+use crate::Ibc;
+
+/// The neighbours of an indexed sequence, the previous one first.
+type Links = (Option<Sequence>, Option<Sequence>);
+
+/// The lowest and highest indexed sequences of a channel, followed by their
+/// number.
+type Ends = (Sequence, Sequence, u64);
+
+/// The maximum number of sequences served in a page of the `packetSequences`
+/// query, larger limits being capped to it.
+pub const MAX_PACKET_SEQUENCES_PAGE_LIMIT: u64 = 1_000;
+
+/// A view over the state maps backing the sequence index of one kind of
+/// packet state.
+pub(crate) struct SequenceIndex<'a> {
+    /// Maps each indexed sequence of a channel to its neighbours.
+    links: &'a StateMap<(ChannelEndPath, Sequence), Links>,
+    /// Holds the ends of the list of each channel holding any sequence.
+    ends: &'a StateMap<ChannelEndPath, Ends>,
+}
+
+impl<'a> SequenceIndex<'a> {
+    pub(crate) fn new(
+        links: &'a StateMap<(ChannelEndPath, Sequence), Links>,
+        ends: &'a StateMap<ChannelEndPath, Ends>,
+    ) -> Self {
+        Self { links, ends }
+    }
+
+    /// Indexes the given sequence of the channel, unless it already is.
+    pub(crate) fn insert<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let Some((first, last, len)) = self.ends.get(channel_end_path, working_set) else {
+            self.set_links(channel_end_path, sequence, (None, None), working_set);
+            self.ends
+                .set(channel_end_path, &(sequence, sequence, 1), working_set);
+            return;
+        };
+
+        if self.contains(channel_end_path, sequence, working_set) {
+            return;
+        }
+
+        // Walks back to the highest sequence below the inserted one
+        let mut prev = Some(last);
+
+        while let Some(candidate) = prev.filter(|candidate| *candidate > sequence) {
+            prev = self.links(channel_end_path, candidate, working_set).0;
+        }
+
+        let next = match prev {
+            Some(prev) => self.links(channel_end_path, prev, working_set).1,
+            None => Some(first),
+        };
+
+        if let Some(prev) = prev {
+            self.set_next(channel_end_path, prev, Some(sequence), working_set);
+        }
+
+        if let Some(next) = next {
+            self.set_prev(channel_end_path, next, Some(sequence), working_set);
+        }
+
+        self.set_links(channel_end_path, sequence, (prev, next), working_set);
+
+        self.ends.set(
+            channel_end_path,
+            &(first.min(sequence), last.max(sequence), len + 1),
+            working_set,
+        );
+    }
+
+    /// Removes the given sequence from the index of the channel, linking its
+    /// neighbours to each other.
+    pub(crate) fn remove<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let Some((prev, next)) = self
+            .links
+            .remove(&(channel_end_path.clone(), sequence), working_set)
+        else {
+            return;
+        };
+
+        let Some((first, last, len)) = self.ends.get(channel_end_path, working_set) else {
+            return;
+        };
+
+        if len <= 1 {
+            self.ends.delete(channel_end_path, working_set);
+            return;
+        }
+
+        if let Some(prev) = prev {
+            self.set_next(channel_end_path, prev, next, working_set);
+        }
+
+        if let Some(next) = next {
+            self.set_prev(channel_end_path, next, prev, working_set);
+        }
+
+        let first = if first == sequence {
+            next.unwrap_or(first)
+        } else {
+            first
+        };
+        let last = if last == sequence {
+            prev.unwrap_or(last)
+        } else {
+            last
+        };
+
+        self.ends
+            .set(channel_end_path, &(first, last, len - 1), working_set);
+    }
+
+    /// Returns at most `limit` indexed sequences of the channel in ascending
+    /// order, starting after the given one if any, along with the sequence
+    /// to start the next page after if more sequences follow.
+    ///
+    /// A page starting after a sequence that is no longer indexed walks from
+    /// the lowest indexed sequence to its place, unless no indexed sequence
+    /// follows it. The limit must be positive, as an empty page would not
+    /// tell where the next one starts.
+    pub(crate) fn page<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        start_after: Option<Sequence>,
+        limit: u64,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<(Vec<Sequence>, Option<Sequence>)> {
+        if limit == 0 {
+            bail!("The page limit must be positive");
+        }
+
+        let Some((first, last, _)) = self.ends.get(channel_end_path, working_set) else {
+            return Ok((Vec::new(), None));
+        };
+
+        let mut cursor = match start_after {
+            None => Some(first),
+            Some(start_after) if start_after >= last => None,
+            Some(start_after) => match self
+                .links
+                .get(&(channel_end_path.clone(), start_after), working_set)
+            {
+                Some((_, next)) => next,
+                None => {
+                    let mut cursor = Some(first);
+
+                    while let Some(sequence) = cursor.filter(|sequence| *sequence < start_after) {
+                        cursor = self.links(channel_end_path, sequence, working_set).1;
+                    }
+
+                    cursor
+                }
+            },
+        };
+
+        let mut sequences = Vec::new();
+
+        while let Some(sequence) = cursor {
+            if sequences.len() as u64 == limit {
+                let next_key = sequences.last().copied();
+                return Ok((sequences, next_key));
+            }
+
+            sequences.push(sequence);
+            cursor = self.links(channel_end_path, sequence, working_set).1;
+        }
+
+        Ok((sequences, None))
+    }
+
+    /// Returns the indexed sequences of the channel in ascending order.
+    pub(crate) fn sequences<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        working_set: &mut impl TxState<S>,
+    ) -> Vec<Sequence> {
+        let mut sequences = Vec::new();
+
+        let mut cursor = self
+            .ends
+            .get(channel_end_path, working_set)
+            .map(|(first, _, _)| first);
+
+        while let Some(sequence) = cursor {
+            sequences.push(sequence);
+            cursor = self.links(channel_end_path, sequence, working_set).1;
+        }
+
+        sequences
+    }
+
+    /// Returns the number of indexed sequences of the channel.
+    pub(crate) fn len<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        working_set: &mut impl TxState<S>,
+    ) -> u64 {
+        self.ends
+            .get(channel_end_path, working_set)
+            .map(|(_, _, len)| len)
+            .unwrap_or_default()
+    }
+
+    /// Indexes the sequences of a channel imported at genesis, which holds no
+    /// indexed sequences yet. The sequences are expected to be unique.
+    pub(crate) fn import<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequences: impl IntoIterator<Item = Sequence>,
+        working_set: &mut impl GenesisState<S>,
+    ) {
+        let mut sequences = sequences.into_iter().collect::<Vec<_>>();
+        sequences.sort();
+
+        for (i, sequence) in sequences.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|prev| sequences[prev]);
+            let next = sequences.get(i + 1).copied();
+
+            self.links.set(
+                &(channel_end_path.clone(), *sequence),
+                &(prev, next),
+                working_set,
+            );
+        }
+
+        if let (Some(first), Some(last)) = (sequences.first(), sequences.last()) {
+            self.ends.set(
+                channel_end_path,
+                &(*first, *last, sequences.len() as u64),
+                working_set,
+            );
+        }
+    }
+
+    fn contains<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> bool {
+        self.links
+            .get(&(channel_end_path.clone(), sequence), working_set)
+            .is_some()
+    }
+
+    fn links<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        working_set: &mut impl TxState<S>,
+    ) -> Links {
+        self.links
+            .get(&(channel_end_path.clone(), sequence), working_set)
+            .unwrap_or_default()
+    }
+
+    fn set_links<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        links: Links,
+        working_set: &mut impl TxState<S>,
+    ) {
+        self.links
+            .set(&(channel_end_path.clone(), sequence), &links, working_set);
+    }
+
+    fn set_prev<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        prev: Option<Sequence>,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let (_, next) = self.links(channel_end_path, sequence, working_set);
+        self.set_links(channel_end_path, sequence, (prev, next), working_set);
+    }
+
+    fn set_next<S: Spec>(
+        &self,
+        channel_end_path: &ChannelEndPath,
+        sequence: Sequence,
+        next: Option<Sequence>,
+        working_set: &mut impl TxState<S>,
+    ) {
+        let (prev, _) = self.links(channel_end_path, sequence, working_set);
+        self.set_links(channel_end_path, sequence, (prev, next), working_set);
+    }
+}
+
+/// The kind of packet state whose sequences are indexed per channel.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PacketStateKind {
+    Commitment,
+    Receipt,
+    Acknowledgement,
+}
+
+/// Request of the `packetSequences` query, which pages through the sequences
+/// under which a channel stores a kind of packet state, in ascending order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryPacketSequencesRequest {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub kind: PacketStateKind,
+    /// The sequence after which the page starts, that is the `next_key` of
+    /// the previous page, if any.
+    pub start_after: Option<Sequence>,
+    /// The maximum number of sequences of the page, which must be positive
+    /// and is capped to [`MAX_PACKET_SEQUENCES_PAGE_LIMIT`].
+    pub limit: u64,
+}
+
+/// Response of the `packetSequences` query.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryPacketSequencesResponse {
+    pub sequences: Vec<Sequence>,
+    /// The sequence to start the next page after, if more sequences follow.
+    pub next_key: Option<Sequence>,
+    /// The number of sequences of the channel.
+    pub total: u64,
+}
+
+/// Moves up to `max_entries` packet paths out of the packet vectors that the
+/// rollup held before the per-channel indexes, into the indexes. The vectors
+/// are migrated one after the other, commitments first, and are cleared once
+/// migrated. Only the authority may send it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize, borsh::BorshSerialize, Clone, Debug, PartialEq, Serialize, Deserialize,
+)]
+pub struct MsgMigratePacketIndex {
+    pub max_entries: u64,
+}
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    pub(crate) fn index_of(&self, kind: PacketStateKind) -> SequenceIndex<'_> {
+        match kind {
+            PacketStateKind::Commitment => self.packet_commitment_index(),
+            PacketStateKind::Receipt => self.packet_receipt_index(),
+            PacketStateKind::Acknowledgement => self.packet_ack_index(),
+        }
+    }
+
+    /// Returns whether any packet vector still holds entries to migrate.
+    pub(crate) fn packet_index_migration_pending(&self, working_set: &mut impl TxState<S>) -> bool {
+        self.packet_commitment_vec.len(working_set) > 0
+            || self.packet_receipt_vec.len(working_set) > 0
+            || self.packet_ack_vec.len(working_set) > 0
+    }
+
+    /// Migrates up to `max_entries` entries of the packet vectors, resuming
+    /// where the previous migration stopped. Entries whose packet state was
+    /// deleted meanwhile are dropped.
+    pub(crate) fn migrate_packet_index(
+        &self,
+        msg: MsgMigratePacketIndex,
+        working_set: &mut impl TxState<S>,
+    ) -> Result<()> {
+        if msg.max_entries == 0 {
+            bail!("The number of entries to migrate must be positive");
+        }
+
+        let mut budget = msg.max_entries;
+
+        let len = self.packet_commitment_vec.len(working_set) as u64;
+
+        if self.migrate_packet_vec(
+            PacketStateKind::Commitment,
+            len,
+            &mut budget,
+            working_set,
+            |working_set, i| {
+                let path = self.packet_commitment_vec.get(i as usize, working_set)?;
+
+                self.packet_commitment_map.get(&path, working_set).map(|_| {
+                    (
+                        ChannelEndPath::new(&path.port_id, &path.channel_id),
+                        path.sequence,
+                    )
+                })
+            },
+        ) {
+            self.packet_commitment_vec.clear(working_set);
+        }
+
+        let len = self.packet_receipt_vec.len(working_set) as u64;
+
+        if self.migrate_packet_vec(
+            PacketStateKind::Receipt,
+            len,
+            &mut budget,
+            working_set,
+            |working_set, i| {
+                let path = self.packet_receipt_vec.get(i as usize, working_set)?;
+
+                self.packet_receipt_map.get(&path, working_set).map(|_| {
+                    (
+                        ChannelEndPath::new(&path.port_id, &path.channel_id),
+                        path.sequence,
+                    )
+                })
+            },
+        ) {
+            self.packet_receipt_vec.clear(working_set);
+        }
+
+        let len = self.packet_ack_vec.len(working_set) as u64;
+
+        if self.migrate_packet_vec(
+            PacketStateKind::Acknowledgement,
+            len,
+            &mut budget,
+            working_set,
+            |working_set, i| {
+                let path = self.packet_ack_vec.get(i as usize, working_set)?;
+
+                self.packet_ack_map.get(&path, working_set).map(|_| {
+                    (
+                        ChannelEndPath::new(&path.port_id, &path.channel_id),
+                        path.sequence,
+                    )
+                })
+            },
+        ) {
+            self.packet_ack_vec.clear(working_set);
+        }
+
+        Ok(())
+    }
+
+    /// Indexes the entries of a packet vector of the given length past the
+    /// migration cursor, within the budget, and returns whether the vector
+    /// is fully migrated, in which case the cursor is reset.
+    fn migrate_packet_vec<W: TxState<S>>(
+        &self,
+        kind: PacketStateKind,
+        len: u64,
+        budget: &mut u64,
+        working_set: &mut W,
+        mut stored_entry: impl FnMut(&mut W, u64) -> Option<(ChannelEndPath, Sequence)>,
+    ) -> bool {
+        // The cursor belongs to a previous vector until it is fully migrated
+        if len == 0 || *budget == 0 {
+            return false;
+        }
+
+        let mut cursor = self
+            .packet_vec_migration_cursor
+            .get(working_set)
+            .unwrap_or_default();
+
+        while cursor < len && *budget > 0 {
+            if let Some((channel_end_path, sequence)) = stored_entry(working_set, cursor) {
+                self.index_of(kind)
+                    .insert(&channel_end_path, sequence, working_set);
+            }
+
+            cursor += 1;
+            *budget -= 1;
+        }
+
+        if cursor < len {
+            self.packet_vec_migration_cursor.set(&cursor, working_set);
+            return false;
+        }
+
+        self.packet_vec_migration_cursor.delete(working_set);
+
+        info!("Migrated the {kind:?} packet vector into its index");
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ibc_core::channel::types::commitment::PacketCommitment;
+    use ibc_core::channel::types::packet::Receipt;
+    use ibc_core::host::types::path::{CommitmentPath, ReceiptPath};
+    use sov_modules_api::WorkingSet;
+    use sov_state::Prefix;
+
+    use super::*;
+    use crate::test_utils::{with_working_set, TestSpec};
+
+    fn channel() -> ChannelEndPath {
+        ChannelEndPath::new(&PortId::transfer(), &ChannelId::new(0))
+    }
+
+    fn sequences(seqs: &[u64]) -> Vec<Sequence> {
+        seqs.iter().copied().map(Sequence::from).collect()
+    }
+
+    fn with_index(f: impl FnOnce(&SequenceIndex<'_>, &mut WorkingSet<TestSpec>)) {
+        let links = StateMap::new(Prefix::new(b"links".to_vec()));
+        let ends = StateMap::new(Prefix::new(b"ends".to_vec()));
+
+        with_working_set(|working_set| f(&SequenceIndex::new(&links, &ends), working_set));
+    }
+
+    #[test]
+    fn test_insert_in_and_out_of_order() {
+        with_index(|index, working_set| {
+            for seq in [3, 5, 1, 4, 2, 4] {
+                index.insert(&channel(), Sequence::from(seq), working_set);
+            }
+
+            assert_eq!(
+                index.sequences(&channel(), working_set),
+                sequences(&[1, 2, 3, 4, 5])
+            );
+            assert_eq!(index.len(&channel(), working_set), 5);
+
+            // Other channels are left out
+            let other = ChannelEndPath::new(&PortId::transfer(), &ChannelId::new(1));
+
+            assert!(index.sequences(&other, working_set).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_remove() {
+        with_index(|index, working_set| {
+            for seq in 1..=5 {
+                index.insert(&channel(), Sequence::from(seq), working_set);
+            }
+
+            // The lowest, a middle and the highest sequence, then an unknown one
+            for seq in [1, 3, 5, 7] {
+                index.remove(&channel(), Sequence::from(seq), working_set);
+            }
+
+            assert_eq!(index.sequences(&channel(), working_set), sequences(&[2, 4]));
+            assert_eq!(index.len(&channel(), working_set), 2);
+
+            // The freed places are taken again
+            for seq in [5, 3, 1] {
+                index.insert(&channel(), Sequence::from(seq), working_set);
+            }
+
+            assert_eq!(
+                index.sequences(&channel(), working_set),
+                sequences(&[1, 2, 3, 4, 5])
+            );
+
+            for seq in 1..=5 {
+                index.remove(&channel(), Sequence::from(seq), working_set);
+            }
+
+            assert!(index.sequences(&channel(), working_set).is_empty());
+            assert_eq!(index.len(&channel(), working_set), 0);
+        });
+    }
+
+    #[test]
+    fn test_page() {
+        with_index(|index, working_set| {
+            index.import(&channel(), sequences(&[9, 2, 7, 4, 5]), working_set);
+
+            let (page, next_key) = index.page(&channel(), None, 2, working_set).unwrap();
+
+            assert_eq!(page, sequences(&[2, 4]));
+            assert_eq!(next_key, Some(Sequence::from(4)));
+
+            let (page, next_key) = index.page(&channel(), next_key, 2, working_set).unwrap();
+
+            assert_eq!(page, sequences(&[5, 7]));
+
+            // The page resumes past a sequence removed in between pages
+            index.remove(&channel(), Sequence::from(7), working_set);
+
+            let (page, next_key) = index.page(&channel(), next_key, 2, working_set).unwrap();
+
+            assert_eq!(page, sequences(&[9]));
+            assert_eq!(next_key, None);
+
+            let (page, next_key) = index.page(&channel(), None, 4, working_set).unwrap();
+
+            assert_eq!(page, sequences(&[2, 4, 5, 9]));
+            assert_eq!(next_key, None);
+
+            // Past the highest sequence, the page is empty without walking
+            let (page, next_key) = index
+                .page(&channel(), Some(Sequence::from(10)), 4, working_set)
+                .unwrap();
+
+            assert!(page.is_empty());
+            assert_eq!(next_key, None);
+
+            assert!(index.page(&channel(), None, 0, working_set).is_err());
+        });
+    }
+
+    #[test]
+    fn test_migrate_packet_index() {
+        with_working_set(|working_set| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            let (port_id, channel_id) = (PortId::transfer(), ChannelId::new(0));
+
+            // Sent packets, of which the first one was acknowledged since
+            for seq in [3, 1, 2] {
+                let path = CommitmentPath::new(&port_id, &channel_id, Sequence::from(seq));
+
+                ibc.packet_commitment_vec.push(&path, working_set);
+
+                if seq != 1 {
+                    ibc.packet_commitment_map.set(
+                        &path,
+                        &PacketCommitment::from(vec![1]),
+                        working_set,
+                    );
+                }
+            }
+
+            for seq in [1, 2] {
+                let path = ReceiptPath::new(&port_id, &channel_id, Sequence::from(seq));
+
+                ibc.packet_receipt_vec.push(&path, working_set);
+                ibc.packet_receipt_map.set(&path, &Receipt::Ok, working_set);
+            }
+
+            assert!(ibc
+                .migrate_packet_index(MsgMigratePacketIndex { max_entries: 0 }, working_set)
+                .is_err());
+
+            // Stops within the commitments, then resumes past them
+            for _ in 0..2 {
+                assert!(ibc.packet_index_migration_pending(working_set));
+
+                ibc.migrate_packet_index(MsgMigratePacketIndex { max_entries: 2 }, working_set)
+                    .unwrap();
+            }
+
+            assert_eq!(ibc.packet_commitment_vec.len(working_set), 0);
+            assert_eq!(ibc.packet_receipt_vec.len(working_set), 2);
+
+            ibc.migrate_packet_index(MsgMigratePacketIndex { max_entries: 10 }, working_set)
+                .unwrap();
+
+            assert!(!ibc.packet_index_migration_pending(working_set));
+
+            assert_eq!(
+                ibc.packet_commitment_index()
+                    .sequences(&channel(), working_set),
+                sequences(&[2, 3])
+            );
+            assert_eq!(
+                ibc.packet_receipt_index()
+                    .sequences(&channel(), working_set),
+                sequences(&[1, 2])
+            );
+        });
+    }
+}
@@ -0,0 +1,273 @@
+//! Defines the retention of the host consensus states, which the slot hooks
+//! store on every slot. Once a retention window is set at genesis, the
+//! consensus states falling out of it are pruned from the oldest one onwards,
+//! a bounded number per slot.
+use anyhow::{bail, Result};
+use ibc_core::client::types::Height;
+use ibc_core::primitives::Timestamp;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{Spec, StateCheckpoint, TxState};
+use tracing::info;
+
+use crate::context::HOST_REVISION_NUMBER;
+use crate::router::IbcRouterExtension;
+use crate::Ibc;
+
+/// The window within which the host consensus states are retained.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize,
+    borsh::BorshSerialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionWindow {
+    /// Retains the consensus states of the given number of latest slots.
+    Slots(u64),
+    /// Retains the consensus states whose timestamp is within the given
+    /// number of seconds of the latest one.
+    Seconds(u64),
+}
+
+/// The retention policy of the host consensus states.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize,
+    borsh::BorshSerialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct HostStateRetention {
+    pub window: RetentionWindow,
+    /// The maximum number of consensus states pruned within a slot, which
+    /// bounds the work of the slot hooks.
+    pub max_pruned_per_slot: u64,
+}
+
+impl HostStateRetention {
+    pub fn validate(&self) -> Result<()> {
+        if let RetentionWindow::Slots(0) | RetentionWindow::Seconds(0) = self.window {
+            bail!("Host consensus state retention window must be non-zero");
+        }
+
+        if self.max_pruned_per_slot == 0 {
+            bail!("Host consensus states pruned per slot must be non-zero");
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Prunes the host consensus states that fell out of the retention
+    /// window, given the height and timestamp of the latest one, starting
+    /// from the oldest retained height.
+    pub fn prune_host_consensus_states(
+        &self,
+        height: &Height,
+        timestamp: &Timestamp,
+        working_set: &mut StateCheckpoint<S>,
+    ) {
+        let Some(retention) = self.host_state_retention.get(working_set) else {
+            return;
+        };
+
+        let start = self
+            .host_pruned_height
+            .get(working_set)
+            .unwrap_or_default()
+            .max(1);
+
+        let mut next = start;
+
+        while next - start < retention.max_pruned_per_slot && next < height.revision_height() {
+            let target_height = Height::new(HOST_REVISION_NUMBER, next).expect("valid height");
+
+            let expired = match retention.window {
+                RetentionWindow::Slots(slots) => height.revision_height() - next >= slots,
+                RetentionWindow::Seconds(seconds) => {
+                    match self
+                        .host_consensus_state_map
+                        .get(&target_height, working_set)
+                    {
+                        Some(consensus_state) => {
+                            let consensus_timestamp: Timestamp = consensus_state.timestamp().into();
+
+                            timestamp
+                                .duration_since(&consensus_timestamp)
+                                .is_some_and(|elapsed| elapsed.as_secs() >= seconds)
+                        }
+                        // Rollup heights skipped by the visible slot number
+                        // leave gaps, which have nothing to prune
+                        None => true,
+                    }
+                }
+            };
+
+            if !expired {
+                break;
+            }
+
+            self.host_consensus_state_map
+                .delete(&target_height, working_set);
+
+            next += 1;
+        }
+
+        if next > start {
+            self.host_pruned_height.set(&next, working_set);
+
+            info!(
+                "Host consensus states are pruned below rollup height {next} at rollup height {height}"
+            );
+        }
+    }
+
+    /// Returns whether the host consensus state at the given height has been
+    /// pruned.
+    pub fn is_host_consensus_state_pruned(
+        &self,
+        height: &Height,
+        working_set: &mut impl TxState<S>,
+    ) -> bool {
+        height.revision_height() < self.host_pruned_height.get(working_set).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_celestia_client::consensus_state::ConsensusState;
+    use sov_celestia_client::types::client_state::test_util::dummy_sov_consensus_state;
+
+    use super::*;
+    use crate::test_utils::{with_state_checkpoint, TestSpec};
+
+    fn height(height: u64) -> Height {
+        Height::new(HOST_REVISION_NUMBER, height).unwrap()
+    }
+
+    fn timestamp(secs: u64) -> Timestamp {
+        Timestamp::from_nanoseconds(secs * 1_000_000_000).unwrap()
+    }
+
+    /// Stores the host consensus states at the given heights and times, in
+    /// seconds, the way the slot hooks do.
+    fn store(
+        ibc: &Ibc<TestSpec>,
+        states: &[(u64, u64)],
+        checkpoint: &mut StateCheckpoint<TestSpec>,
+    ) {
+        for (h, secs) in states {
+            ibc.host_consensus_state_map.set(
+                &height(*h),
+                &ConsensusState::from(dummy_sov_consensus_state(timestamp(*secs))),
+                checkpoint,
+            );
+        }
+    }
+
+    fn retained(
+        ibc: &Ibc<TestSpec>,
+        heights: impl IntoIterator<Item = u64>,
+        checkpoint: &mut StateCheckpoint<TestSpec>,
+    ) -> Vec<u64> {
+        heights
+            .into_iter()
+            .filter(|h| {
+                ibc.host_consensus_state_map
+                    .get(&height(*h), checkpoint)
+                    .is_some()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_prune_without_retention() {
+        with_state_checkpoint(|mut checkpoint| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            store(&ibc, &[(1, 100), (2, 200)], &mut checkpoint);
+
+            ibc.prune_host_consensus_states(&height(2), &timestamp(200), &mut checkpoint);
+
+            assert_eq!(retained(&ibc, 1..=2, &mut checkpoint), vec![1, 2]);
+        });
+    }
+
+    #[test]
+    fn test_prune_by_slots() {
+        with_state_checkpoint(|mut checkpoint| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            ibc.host_state_retention.set(
+                &HostStateRetention {
+                    window: RetentionWindow::Slots(3),
+                    max_pruned_per_slot: 2,
+                },
+                &mut checkpoint,
+            );
+
+            let states = (1..=10).map(|h| (h, 100 + h)).collect::<Vec<_>>();
+
+            store(&ibc, &states, &mut checkpoint);
+
+            // At most two consensus states are pruned per slot, until the
+            // ones of the three latest slots are left
+            for expected in [3..=10, 5..=10, 7..=10, 8..=10, 8..=10] {
+                ibc.prune_host_consensus_states(&height(10), &timestamp(110), &mut checkpoint);
+
+                assert_eq!(
+                    retained(&ibc, 1..=10, &mut checkpoint),
+                    expected.collect::<Vec<_>>()
+                );
+            }
+
+            let mut working_set = checkpoint.to_revertable_unmetered();
+
+            assert!(ibc.is_host_consensus_state_pruned(&height(7), &mut working_set));
+            assert!(!ibc.is_host_consensus_state_pruned(&height(8), &mut working_set));
+        });
+    }
+
+    #[test]
+    fn test_prune_by_seconds() {
+        with_state_checkpoint(|mut checkpoint| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            ibc.host_state_retention.set(
+                &HostStateRetention {
+                    window: RetentionWindow::Seconds(10),
+                    max_pruned_per_slot: 10,
+                },
+                &mut checkpoint,
+            );
+
+            // Rollup height 3 was skipped by the visible slot number
+            store(
+                &ibc,
+                &[(1, 100), (2, 105), (4, 112), (5, 120), (6, 125)],
+                &mut checkpoint,
+            );
+
+            ibc.prune_host_consensus_states(&height(6), &timestamp(125), &mut checkpoint);
+
+            // The gap is pruned over, up to the first consensus state within
+            // ten seconds of the latest one
+            assert_eq!(retained(&ibc, 1..=6, &mut checkpoint), vec![5, 6]);
+
+            let mut working_set = checkpoint.to_revertable_unmetered();
+
+            assert!(ibc.is_host_consensus_state_pruned(&height(4), &mut working_set));
+            assert!(!ibc.is_host_consensus_state_pruned(&height(5), &mut working_set));
+        });
+    }
+}
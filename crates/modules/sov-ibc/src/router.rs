@@ -1,58 +1,316 @@
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
-use ibc_app_transfer::types::{MODULE_ID_STR, PORT_ID_STR};
+use anyhow::bail;
+use ibc_app_nft_transfer::types::{
+    MODULE_ID_STR as NFT_MODULE_ID_STR, PORT_ID_STR as NFT_PORT_ID_STR,
+};
+use ibc_app_transfer::types::MODULE_ID_STR;
 use ibc_core::host::types::identifiers::PortId;
 use ibc_core::router::module::Module;
 use ibc_core::router::router::Router;
 use ibc_core::router::types::module::ModuleId;
+use sov_ibc_fee::middleware::FeeMiddleware;
+use sov_ibc_ica::controller::IcaControllerContext;
+use sov_ibc_ica::host::IcaHostContext;
+use sov_ibc_ica::types::{host_port_id, CONTROLLER_MODULE_ID_STR, HOST_MODULE_ID_STR};
+use sov_ibc_nft_transfer::context::IbcNftTransferContext;
+use sov_ibc_packet_forward::middleware::PacketForwardMiddleware;
 use sov_ibc_transfer::context::IbcTransferContext;
 use sov_modules_api::{Context, Spec, TxState};
 
+use crate::context::IbcContext;
+use crate::executor::{RuntimeCallExecutor, RuntimeTxExecutor};
+use crate::hooks::IbcHooksMiddleware;
 use crate::Ibc;
 
-pub struct IbcRouter<'ws, S: Spec, TS: TxState<S>> {
-    pub transfer_ctx: IbcTransferContext<'ws, S, TS>,
+/// The IBC router that dispatches channel and packet callbacks to the
+/// application modules registered on it.
+///
+/// Routes are held in memory and rebuilt for every call, as application
+/// contexts borrow the working set. Port bindings, on the other hand, are
+/// persisted in the `Ibc` module state, so that a port, once bound to a
+/// module, can neither be taken over by another module nor be lost across
+/// calls. The ICS-20 transfer, wrapped in the packet-forward and the ICS-29
+/// fee middlewares, the ICS-721 NFT transfer and the ICS-27 interchain
+/// accounts modules are always registered, and their ports bound at genesis,
+/// while the controller ports of the latter are bound upon each account
+/// registration. Rollups register their own modules through the
+/// [`IbcRouterExtension`] of their `Ibc` module, and may further wrap the
+/// transfer in the IBC hooks middleware with [`IbcRouter::enable_hooks`] and
+/// let the ICS-27 host execute transactions with
+/// [`IbcRouter::enable_ica_host`].
+pub struct IbcRouter<'ws, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S> = ()> {
+    ibc: &'ws Ibc<S, R>,
+    sdk_context: Context<S>,
+    working_set: Rc<RefCell<&'ws mut TS>>,
+    routes: BTreeMap<ModuleId, Box<dyn Module + 'ws>>,
 }
 
-impl<'ws, S: Spec, TS: TxState<S>> IbcRouter<'ws, S, TS> {
+impl<'ws, S, TS, R> IbcRouter<'ws, S, TS, R>
+where
+    S: Spec,
+    TS: TxState<S>,
+    R: IbcRouterExtension<S>,
+{
+    /// Creates a router with the application modules shipped with the `Ibc`
+    /// module, without the ones of the rollup extension. See
+    /// [`IbcRouter::with_extension`].
     pub fn new(
-        ibc_mod: &Ibc<S>,
+        ibc_mod: &'ws Ibc<S, R>,
         sdk_context: Context<S>,
         working_set: Rc<RefCell<&'ws mut TS>>,
-    ) -> IbcRouter<'ws, S, TS> {
-        IbcRouter {
-            transfer_ctx: IbcTransferContext::new(
-                ibc_mod.transfer.clone(),
-                sdk_context,
-                working_set,
+    ) -> anyhow::Result<IbcRouter<'ws, S, TS, R>> {
+        let transfer_ctx = FeeMiddleware::new(
+            ibc_mod.fee.clone(),
+            transfer_stack(ibc_mod, &sdk_context, &working_set),
+            working_set.clone(),
+        );
+
+        let nft_transfer_ctx =
+            IbcNftTransferContext::new(ibc_mod.nft_transfer.clone(), working_set.clone());
+
+        let ica_host_ctx = IcaHostContext::new(ibc_mod.ica.clone(), working_set.clone());
+
+        let ica_controller_ctx =
+            IcaControllerContext::new(ibc_mod.ica.clone(), working_set.clone());
+
+        let mut router = IbcRouter {
+            ibc: ibc_mod,
+            sdk_context,
+            working_set,
+            routes: BTreeMap::new(),
+        };
+
+        router.add_route(MODULE_ID_STR, transfer_ctx)?;
+        router.add_route(NFT_MODULE_ID_STR, nft_transfer_ctx)?;
+        router.add_route(HOST_MODULE_ID_STR, ica_host_ctx)?;
+        router.add_route(CONTROLLER_MODULE_ID_STR, ica_controller_ctx)?;
+
+        Ok(router)
+    }
+
+    /// Creates a router with the application modules shipped with the `Ibc`
+    /// module, extended with the ones the rollup registers through its
+    /// [`IbcRouterExtension`].
+    pub fn with_extension(
+        ibc_mod: &'ws Ibc<S, R>,
+        sdk_context: Context<S>,
+        working_set: Rc<RefCell<&'ws mut TS>>,
+    ) -> anyhow::Result<IbcRouter<'ws, S, TS, R>> {
+        let mut router = Self::new(ibc_mod, sdk_context, working_set)?;
+
+        R::extend(&mut router)?;
+
+        Ok(router)
+    }
+
+    /// Returns the SDK context of the call the router was created for.
+    pub fn sdk_context(&self) -> &Context<S> {
+        &self.sdk_context
+    }
+
+    /// Returns the shared working set, which application contexts should be
+    /// built on top of.
+    pub fn working_set(&self) -> Rc<RefCell<&'ws mut TS>> {
+        self.working_set.clone()
+    }
+
+    /// Enables the IBC hooks on the ICS-20 transfer, so that inbound
+    /// transfers carrying a runtime call in their memo execute it through the
+    /// given runtime call executor. Without it, such memos are ignored.
+    ///
+    /// The transfer route is replaced by one wrapping the hooks middleware
+    /// between the ICS-29 fee and the packet-forward middlewares, so that the
+    /// port bindings are left unchanged.
+    pub fn enable_hooks(&mut self, executor: impl RuntimeCallExecutor<S> + 'ws) {
+        let transfer_ctx = FeeMiddleware::new(
+            self.ibc.fee.clone(),
+            IbcHooksMiddleware::new(
+                transfer_stack(self.ibc, &self.sdk_context, &self.working_set),
+                transfer_context(self.ibc, &self.sdk_context, &self.working_set),
+                executor,
+                self.working_set.clone(),
             ),
+            self.working_set.clone(),
+        );
+
+        self.routes.insert(
+            ModuleId::new(MODULE_ID_STR.to_string()),
+            Box::new(transfer_ctx),
+        );
+    }
+
+    /// Enables the execution of interchain account transactions on the ICS-27
+    /// host, through the given runtime call executor. Without it, such
+    /// transactions are acknowledged with an error.
+    ///
+    /// The host route is replaced by one holding the executor, so that the
+    /// port bindings are left unchanged.
+    pub fn enable_ica_host(&mut self, executor: impl RuntimeCallExecutor<S> + 'ws) {
+        let ica_host_ctx = IcaHostContext::new(self.ibc.ica.clone(), self.working_set.clone())
+            .with_executor(RuntimeTxExecutor::new(executor, self.sdk_context.clone()));
+
+        self.routes.insert(
+            ModuleId::new(HOST_MODULE_ID_STR.to_string()),
+            Box::new(ica_host_ctx),
+        );
+    }
+
+    /// Registers an application module under the given module ID.
+    ///
+    /// Registering a route does not bind any port to it; use
+    /// [`IbcRouter::bind_port`] or [`IbcRouter::claim_port`] for that.
+    pub fn add_route(&mut self, module_id: &str, module: impl Module + 'ws) -> anyhow::Result<()> {
+        let module_id = ModuleId::new(module_id.to_string());
+
+        if self.routes.contains_key(&module_id) {
+            bail!("Module {module_id:?} is already registered on the router");
+        }
+
+        self.routes.insert(module_id, Box::new(module));
+
+        Ok(())
+    }
+
+    /// Binds an unbound port to a registered module. Fails if the port is
+    /// already bound, even to the same module.
+    pub fn bind_port(&mut self, port_id: &PortId, module_id: &str) -> anyhow::Result<()> {
+        if let Some(bound_module) = self.bound_module(port_id) {
+            bail!("Port {port_id} is already bound to module {bound_module}");
+        }
+
+        self.store_port_binding(port_id, module_id)
+    }
+
+    /// Claims a port for a registered module. Binds the port if it is unbound
+    /// and succeeds if it is already bound to the same module, but fails if
+    /// another module owns it.
+    pub fn claim_port(&mut self, port_id: &PortId, module_id: &str) -> anyhow::Result<()> {
+        match self.bound_module(port_id) {
+            Some(bound_module) if bound_module == module_id => Ok(()),
+            Some(bound_module) => {
+                bail!("Port {port_id} is already bound to module {bound_module}")
+            }
+            None => self.store_port_binding(port_id, module_id),
         }
     }
+
+    /// Returns the ID of the module the given port is bound to, if any.
+    pub fn bound_module(&self, port_id: &PortId) -> Option<String> {
+        self.ibc
+            .port_module_map
+            .get(port_id, *self.working_set.borrow_mut())
+    }
+
+    fn store_port_binding(&mut self, port_id: &PortId, module_id: &str) -> anyhow::Result<()> {
+        if !self
+            .routes
+            .contains_key(&ModuleId::new(module_id.to_string()))
+        {
+            bail!("Cannot bind port {port_id} to unregistered module {module_id}");
+        }
+
+        self.ibc.port_module_map.set(
+            port_id,
+            &module_id.to_string(),
+            *self.working_set.borrow_mut(),
+        );
+        self.ibc
+            .bound_ports_vec
+            .push(port_id, *self.working_set.borrow_mut());
+
+        Ok(())
+    }
 }
 
-impl<'ws, S: Spec, TS: TxState<S>> Router for IbcRouter<'ws, S, TS> {
+impl<'ws, S, TS, R> Router for IbcRouter<'ws, S, TS, R>
+where
+    S: Spec,
+    TS: TxState<S>,
+    R: IbcRouterExtension<S>,
+{
     fn get_route(&self, module_id: &ModuleId) -> Option<&dyn Module> {
-        if *module_id == ModuleId::new(MODULE_ID_STR.to_string()) {
-            Some(&self.transfer_ctx)
-        } else {
-            None
-        }
+        self.routes
+            .get(module_id)
+            .map(|module| module.as_ref() as &dyn Module)
     }
 
     fn get_route_mut(&mut self, module_id: &ModuleId) -> Option<&mut dyn Module> {
-        if *module_id == ModuleId::new(MODULE_ID_STR.to_string()) {
-            Some(&mut self.transfer_ctx)
-        } else {
-            None
-        }
+        self.routes
+            .get_mut(module_id)
+            .map(|module| module.as_mut() as &mut dyn Module)
     }
 
     fn lookup_module(&self, port_id: &PortId) -> Option<ModuleId> {
-        if port_id.as_str() == PORT_ID_STR {
-            Some(ModuleId::new(MODULE_ID_STR.to_string()))
-        } else {
-            None
-        }
+        self.bound_module(port_id).map(ModuleId::new)
     }
 }
+
+/// Returns the ICS-20 transfer application wrapped in the packet-forward
+/// middleware, which the outer middlewares of the transfer route wrap.
+fn transfer_stack<'ws, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>(
+    ibc_mod: &'ws Ibc<S, R>,
+    sdk_context: &Context<S>,
+    working_set: &Rc<RefCell<&'ws mut TS>>,
+) -> PacketForwardMiddleware<'ws, S, TS, IbcContext<'ws, S, TS, R>> {
+    PacketForwardMiddleware::new(
+        ibc_mod.packet_forward.clone(),
+        transfer_context(ibc_mod, sdk_context, working_set),
+        IbcContext::new(ibc_mod, working_set.clone()),
+        working_set.clone(),
+    )
+}
+
+/// Returns the ICS-20 transfer context, aware of the host timestamp against
+/// which the rate limits measured in seconds are enforced.
+pub(crate) fn transfer_context<'ws, S: Spec, TS: TxState<S>, R: IbcRouterExtension<S>>(
+    ibc_mod: &Ibc<S, R>,
+    sdk_context: &Context<S>,
+    working_set: &Rc<RefCell<&'ws mut TS>>,
+) -> IbcTransferContext<'ws, S, TS> {
+    let transfer_ctx = IbcTransferContext::new(
+        ibc_mod.transfer.clone(),
+        sdk_context.clone(),
+        working_set.clone(),
+    );
+
+    let host_timestamp = ibc_mod.host_timestamp_map.get(*working_set.borrow_mut());
+
+    match host_timestamp {
+        Some(host_timestamp) => transfer_ctx.with_host_timestamp(host_timestamp),
+        None => transfer_ctx,
+    }
+}
+
+/// Lets a rollup register its own application modules, and bind their ports,
+/// on every router the `Ibc` module dispatches IBC messages with, as well as
+/// enable the IBC hooks and the interchain account transactions. The rollup
+/// selects its extension through the type parameter of its `Ibc` module,
+/// which defaults to `()`, registering nothing.
+pub trait IbcRouterExtension<S: Spec>: Clone + Default + Send + Sync + 'static {
+    /// Extends the given router, which already holds the application modules
+    /// shipped with the `Ibc` module.
+    fn extend<'ws, TS: TxState<S>>(router: &mut IbcRouter<'ws, S, TS, Self>) -> anyhow::Result<()>;
+}
+
+impl<S: Spec> IbcRouterExtension<S> for () {
+    fn extend<'ws, TS: TxState<S>>(
+        _router: &mut IbcRouter<'ws, S, TS, Self>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the port bindings every `Ibc` module starts with.
+pub(crate) fn default_port_bindings() -> Vec<(PortId, String)> {
+    vec![
+        (PortId::transfer(), MODULE_ID_STR.to_string()),
+        (
+            PortId::new(NFT_PORT_ID_STR.to_string()).expect("never fails as the port ID is valid"),
+            NFT_MODULE_ID_STR.to_string(),
+        ),
+        (host_port_id(), HOST_MODULE_ID_STR.to_string()),
+    ]
+}
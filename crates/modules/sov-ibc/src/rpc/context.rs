@@ -1,5 +1,4 @@
 use borsh::BorshSerialize;
-use ibc_client_tendermint::types::client_type as tm_client_type;
 use ibc_core::channel::types::channel::{ChannelEnd, IdentifiedChannelEnd};
 use ibc_core::channel::types::commitment::{AcknowledgementCommitment, PacketCommitment};
 use ibc_core::channel::types::error::ChannelError;
@@ -19,6 +18,7 @@ use ibc_query::core::context::{ProvableContext, QueryContext};
 use jsonrpsee::core::RpcResult;
 use sov_celestia_client::client_state::ClientState as HostClientState;
 use sov_celestia_client::consensus_state::ConsensusState as HostConsensusState;
+use sov_ibc_utils::to_jsonrpsee_error;
 use sov_modules_api::{Spec, WorkingSet};
 
 use crate::context::IbcContext;
@@ -42,6 +42,22 @@ where
         )
     }
 
+    /// Fails unless the given client, which a connection or a channel looked
+    /// up is built on, is in the client registry.
+    pub fn ensure_client_registered(&self, client_id: &ClientId) -> RpcResult<()> {
+        if self
+            .ibc
+            .registered_client_type(client_id, *self.working_set.borrow_mut())
+            .is_none()
+        {
+            return Err(to_jsonrpsee_error(format!(
+                "Client {client_id} is not registered"
+            )));
+        }
+
+        Ok(())
+    }
+
     pub fn query_client_consensus_state<SV>(
         &self,
         client_id: &ClientId,
@@ -337,19 +353,15 @@ where
     S: Spec,
 {
     fn client_states(&self) -> Result<Vec<(ClientId, ClientStateRef<Self>)>, ContextError> {
-        let client_counter = self
+        let client_ids = self
             .ibc
-            .client_counter
-            .get(*self.working_set.borrow_mut())
-            .ok_or(ChannelError::Other {
-                description: "Connection counter not found".to_string(),
-            })?;
+            .client_ids_vec
+            .iter(*self.working_set.borrow_mut())
+            .collect::<Vec<_>>();
 
         let mut client_states = Vec::new();
 
-        for i in 0..client_counter {
-            // ibc-rs already only supports the Tendermint client
-            let client_id = tm_client_type().build_client_id(i);
+        for client_id in client_ids {
             let cs = self.client_state(&client_id)?;
             client_states.push((client_id, cs));
         }
@@ -361,11 +373,10 @@ where
         &self,
         client_id: &ClientId,
     ) -> Result<Vec<(Height, ConsensusStateRef<Self>)>, ContextError> {
-        let update_heights: Vec<Height> = self
+        let update_heights = self
             .ibc
-            .client_update_heights_vec
-            .iter(*self.working_set.borrow_mut())
-            .collect();
+            .consensus_height_index()
+            .heights(client_id, *self.working_set.borrow_mut())?;
 
         let mut consensus_states = Vec::new();
 
@@ -383,12 +394,11 @@ where
         Ok(consensus_states)
     }
 
-    fn consensus_state_heights(&self, _client_id: &ClientId) -> Result<Vec<Height>, ContextError> {
-        let heights: Vec<Height> = self
+    fn consensus_state_heights(&self, client_id: &ClientId) -> Result<Vec<Height>, ContextError> {
+        let heights = self
             .ibc
-            .client_update_heights_vec
-            .iter(*self.working_set.borrow_mut())
-            .collect();
+            .consensus_height_index()
+            .heights(client_id, *self.working_set.borrow_mut())?;
 
         Ok(heights)
     }
@@ -437,17 +447,25 @@ where
             .channel_counter
             .get(*self.working_set.borrow_mut())
             .ok_or(ChannelError::Other {
-                description: "Connection counter not found".to_string(),
+                description: "Channel counter not found".to_string(),
             })?;
 
         let mut chan_ends = Vec::new();
 
         for i in 0..chan_counter {
-            // ibc-rs already only supports the Token Transfer application
-            let port_id = PortId::transfer();
             let chan_id = ChannelId::new(i);
-            let chan_end_path = ChannelEndPath::new(&port_id, &chan_id);
-            let chan_end = self.channel_end(&chan_end_path)?;
+
+            // Imported channels may leave gaps below the channel counter
+            let Some(port_id) = self
+                .ibc
+                .channel_port_map
+                .get(&chan_id, *self.working_set.borrow_mut())
+            else {
+                continue;
+            };
+
+            let chan_end = self.channel_end(&ChannelEndPath::new(&port_id, &chan_id))?;
+
             chan_ends.push(IdentifiedChannelEnd::new(port_id, chan_id, chan_end));
         }
 
@@ -459,12 +477,10 @@ where
         channel_end_path: &ChannelEndPath,
     ) -> Result<Vec<PacketState>, ContextError> {
         self.ibc
-            .packet_commitment_vec
-            .iter(*self.working_set.borrow_mut())
-            .filter(|commitment_path| {
-                &ChannelEndPath::new(&commitment_path.port_id, &commitment_path.channel_id)
-                    == channel_end_path
-            })
+            .packet_commitment_index()
+            .sequences(channel_end_path, *self.working_set.borrow_mut())
+            .into_iter()
+            .map(|seq| CommitmentPath::new(&channel_end_path.0, &channel_end_path.1, seq))
             .map(|commitment_path| {
                 self.get_packet_commitment(&commitment_path)
                     .map(|packet| PacketState {
@@ -484,12 +500,10 @@ where
     ) -> Result<Vec<PacketState>, ContextError> {
         let collected_paths: Vec<_> = if sequences.len() == 0 {
             self.ibc
-                .packet_ack_vec
-                .iter(*self.working_set.borrow_mut())
-                .filter(|ack_path| {
-                    &ChannelEndPath::new(&ack_path.port_id, &ack_path.channel_id)
-                        == channel_end_path
-                })
+                .packet_ack_index()
+                .sequences(channel_end_path, *self.working_set.borrow_mut())
+                .into_iter()
+                .map(|seq| AckPath::new(&channel_end_path.0, &channel_end_path.1, seq))
                 .collect()
         } else {
             sequences
@@ -530,12 +544,10 @@ where
     ) -> Result<Vec<Sequence>, ContextError> {
         let collected_paths: Vec<_> = if sequences.len() == 0 {
             self.ibc
-                .packet_commitment_vec
-                .iter(*self.working_set.borrow_mut())
-                .filter(|commitment_path| {
-                    &ChannelEndPath::new(&commitment_path.port_id, &commitment_path.channel_id)
-                        == channel_end_path
-                })
+                .packet_commitment_index()
+                .sequences(channel_end_path, *self.working_set.borrow_mut())
+                .into_iter()
+                .map(|seq| CommitmentPath::new(&channel_end_path.0, &channel_end_path.1, seq))
                 .collect()
         } else {
             sequences
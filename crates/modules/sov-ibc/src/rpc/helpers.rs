@@ -5,7 +5,7 @@ use borsh::BorshSerialize;
 use ibc_core::client::types::Height;
 use ibc_core::host::ValidationContext;
 use jsonrpsee::core::RpcResult;
-use sov_ibc_transfer::to_jsonrpsee_error;
+use sov_ibc_utils::to_jsonrpsee_error;
 use sov_modules_api::{Spec, StateMap, WorkingSet};
 use sov_state::{StateCodec, StateItemCodec, StateItemDecoder};
 
@@ -23,10 +23,7 @@ impl<S: Spec> Ibc<S> {
         match query_height {
             Some(height) => Ok(height),
             None => {
-                let ibc_ctx = IbcContext {
-                    ibc: self,
-                    working_set: Rc::new(RefCell::new(working_set)),
-                };
+                let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
                 ibc_ctx.host_height().map_err(to_jsonrpsee_error)
             }
@@ -3,6 +3,8 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::Height;
+use ibc_core::host::types::path::ChannelEndPath;
 use ibc_core::host::ValidationContext;
 use ibc_query::core::channel::{
     query_channels, query_connection_channels, query_packet_acknowledgements,
@@ -38,12 +40,22 @@ use ibc_query::core::connection::{
     QueryConnectionsRequest, QueryConnectionsResponse,
 };
 use jsonrpsee::core::RpcResult;
-use sov_ibc_transfer::to_jsonrpsee_error;
+use sov_ibc_fee::types::{
+    IdentifiedPacketFees, PacketFee, QueryFeeEnabledChannelRequest, QueryFeeEnabledChannelResponse,
+    QueryIncentivizedPacketsRequest, QueryIncentivizedPacketsResponse, QueryPayeeRequest,
+    QueryPayeeResponse,
+};
+use sov_ibc_utils::to_jsonrpsee_error;
 use sov_modules_api::macros::rpc_gen;
 use sov_modules_api::{Spec, WorkingSet};
 
 use crate::context::IbcContext;
+use crate::export::IbcStateExport;
 use crate::helpers::{WithProof, WithoutProof};
+use crate::packet_index::{
+    QueryPacketSequencesRequest, QueryPacketSequencesResponse, MAX_PACKET_SEQUENCES_PAGE_LIMIT,
+};
+use crate::tx_logs::{parse_tx_hash, QueryTxLogsRequest, QueryTxLogsResponse};
 use crate::Ibc;
 
 /// Structure returned by the `client_state` rpc method.
@@ -81,10 +93,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryClientStatesRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryClientStatesResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_client_states(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -131,10 +140,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryConsensusStatesRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryConsensusStatesResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_consensus_states(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -145,10 +151,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryConsensusStateHeightsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryConsensusStateHeightsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_consensus_state_heights(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -159,10 +162,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryClientStatusRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryClientStatusResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_client_status(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -250,10 +250,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryConnectionsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryConnectionsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_connections(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -268,6 +265,8 @@ impl<S: Spec> Ibc<S> {
         let mut archival_working_set = working_set.get_archival_at(proof_height.revision_height());
         let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(&mut archival_working_set)));
 
+        ibc_ctx.ensure_client_registered(&request.client_id)?;
+
         let (client_connections, proof) =
             ibc_ctx.query_client_connections::<WithProof>(&request.client_id)?;
 
@@ -302,6 +301,8 @@ impl<S: Spec> Ibc<S> {
                 ))
             })?;
 
+        ibc_ctx.ensure_client_registered(connection_end.client_id())?;
+
         let (client_state, proof) =
             ibc_ctx.query_client_state::<WithProof>(connection_end.client_id())?;
 
@@ -341,6 +342,8 @@ impl<S: Spec> Ibc<S> {
                 ))
             })?;
 
+        ibc_ctx.ensure_client_registered(connection_end.client_id())?;
+
         let (consensus_state, proof) = ibc_ctx.query_client_consensus_state::<WithProof>(
             connection_end.client_id(),
             request.height.revision_number(),
@@ -368,10 +371,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryConnectionParamsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryConnectionParamsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_connection_params(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -407,10 +407,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryChannelsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryChannelsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_channels(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -421,10 +418,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryConnectionChannelsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryConnectionChannelsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_connection_channels(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -464,6 +458,8 @@ impl<S: Spec> Ibc<S> {
                 ))
             })?;
 
+        ibc_ctx.ensure_client_registered(connection_end.client_id())?;
+
         let (client_state, proof) =
             ibc_ctx.query_client_state::<WithProof>(connection_end.client_id())?;
 
@@ -519,6 +515,8 @@ impl<S: Spec> Ibc<S> {
                 ))
             })?;
 
+        ibc_ctx.ensure_client_registered(connection_end.client_id())?;
+
         let client_state = ibc_ctx
             .query_client_state::<WithoutProof>(connection_end.client_id())?
             .ok_or_else(|| {
@@ -583,10 +581,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryPacketCommitmentsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryPacketCommitmentsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_packet_commitments(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -651,10 +646,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryPacketAcknowledgementsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryPacketAcknowledgementsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_packet_acknowledgements(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -665,10 +657,7 @@ impl<S: Spec> Ibc<S> {
         request: QueryUnreceivedPacketsRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryUnreceivedPacketsResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_unreceived_packets(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
@@ -679,14 +668,56 @@ impl<S: Spec> Ibc<S> {
         request: QueryUnreceivedAcksRequest,
         working_set: &mut WorkingSet<S>,
     ) -> RpcResult<QueryUnreceivedAcksResponse> {
-        let ibc_ctx = IbcContext {
-            ibc: self,
-            working_set: Rc::new(RefCell::new(working_set)),
-        };
+        let ibc_ctx = IbcContext::new(self, Rc::new(RefCell::new(working_set)));
 
         query_unreceived_acks(&ibc_ctx, &request).map_err(to_jsonrpsee_error)
     }
 
+    #[rpc_method(name = "txLogs")]
+    pub fn tx_logs_by_hash(
+        &self,
+        request: QueryTxLogsRequest,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<QueryTxLogsResponse> {
+        let tx_hash = parse_tx_hash(&request.tx_hash).ok_or(to_jsonrpsee_error(format!(
+            "Invalid transaction hash: {}",
+            request.tx_hash
+        )))?;
+
+        Ok(QueryTxLogsResponse {
+            logs: self.tx_logs(&tx_hash, working_set),
+        })
+    }
+
+    #[rpc_method(name = "packetSequences")]
+    pub fn packet_sequences(
+        &self,
+        request: QueryPacketSequencesRequest,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<QueryPacketSequencesResponse> {
+        let index = self.index_of(request.kind);
+
+        let channel_end_path = ChannelEndPath::new(&request.port_id, &request.channel_id);
+
+        // Resuming after a sequence removed since the previous page walks the
+        // channel from its lowest sequence, which costs a read per sequence
+        // below it.
+        let (sequences, next_key) = index
+            .page(
+                &channel_end_path,
+                request.start_after,
+                request.limit.min(MAX_PACKET_SEQUENCES_PAGE_LIMIT),
+                working_set,
+            )
+            .map_err(to_jsonrpsee_error)?;
+
+        Ok(QueryPacketSequencesResponse {
+            sequences,
+            next_key,
+            total: index.len(&channel_end_path, working_set),
+        })
+    }
+
     #[rpc_method(name = "nextSequenceReceive")]
     pub fn next_sequence_receive(
         &self,
@@ -718,4 +749,106 @@ impl<S: Spec> Ibc<S> {
 
         Ok(ibc_ctx.commitment_prefix().into_vec())
     }
+
+    #[rpc_method(name = "feeEnabledChannel")]
+    pub fn fee_enabled_channel(
+        &self,
+        request: QueryFeeEnabledChannelRequest,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<QueryFeeEnabledChannelResponse> {
+        let fee_enabled = self
+            .fee
+            .fee_enabled_channels
+            .get(&(request.port_id, request.channel_id), working_set)
+            .unwrap_or_default();
+
+        Ok(QueryFeeEnabledChannelResponse { fee_enabled })
+    }
+
+    #[rpc_method(name = "incentivizedPackets")]
+    pub fn incentivized_packets(
+        &self,
+        request: QueryIncentivizedPacketsRequest,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<QueryIncentivizedPacketsResponse> {
+        let sequences =
+            self.fee
+                .incentivized_sequences(&request.port_id, &request.channel_id, working_set);
+
+        let mut incentivized_packets = Vec::with_capacity(sequences.len());
+
+        for sequence in sequences {
+            let packet_fees = self
+                .fee
+                .packet_fees
+                .get(
+                    &(
+                        request.port_id.clone(),
+                        request.channel_id.clone(),
+                        sequence,
+                    ),
+                    working_set,
+                )
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(fee, refund_address)| PacketFee {
+                    fee,
+                    refund_address: refund_address.to_string(),
+                })
+                .collect();
+
+            incentivized_packets.push(IdentifiedPacketFees {
+                port_id: request.port_id.clone(),
+                channel_id: request.channel_id.clone(),
+                sequence,
+                packet_fees,
+            });
+        }
+
+        Ok(QueryIncentivizedPacketsResponse {
+            incentivized_packets,
+        })
+    }
+
+    #[rpc_method(name = "payee")]
+    pub fn payee(
+        &self,
+        request: QueryPayeeRequest,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<QueryPayeeResponse> {
+        let relayer: S::Address = request.relayer.parse().map_err(|_| {
+            to_jsonrpsee_error(format!("Invalid relayer address {:?}", request.relayer))
+        })?;
+
+        let payee = self
+            .fee
+            .payees
+            .get(
+                &(request.port_id.clone(), request.channel_id.clone(), relayer),
+                working_set,
+            )
+            .ok_or_else(|| {
+                to_jsonrpsee_error(format!(
+                    "Payee not found for relayer {:?} on channel id {:?} and port id {:?}",
+                    request.relayer, request.channel_id, request.port_id
+                ))
+            })?;
+
+        Ok(QueryPayeeResponse {
+            payee_address: payee.to_string(),
+        })
+    }
+
+    #[rpc_method(name = "exportState")]
+    pub fn export_state_query(
+        &self,
+        query_height: Option<Height>,
+        working_set: &mut WorkingSet<S>,
+    ) -> RpcResult<IbcStateExport> {
+        let export_height = self.determine_query_height(query_height, working_set)?;
+        let mut archival_working_set = working_set.get_archival_at(export_height.revision_height());
+
+        self.export_state(&mut archival_working_set)
+            .map_err(to_jsonrpsee_error)
+    }
 }
@@ -0,0 +1,26 @@
+//! Defines the working sets the unit tests of the module run against.
+use sov_mock_zkvm::MockZkVerifier;
+use sov_modules_api::default_spec::DefaultSpec;
+use sov_modules_api::{StateCheckpoint, WorkingSet};
+use sov_prover_storage_manager::SimpleStorageManager;
+
+pub(crate) type TestSpec = DefaultSpec<MockZkVerifier, MockZkVerifier>;
+
+/// Runs the given closure against a working set over an empty storage.
+pub(crate) fn with_working_set<T>(f: impl FnOnce(&mut WorkingSet<TestSpec>) -> T) -> T {
+    let tmpdir = tempfile::tempdir().unwrap();
+
+    let storage_manager = SimpleStorageManager::new(tmpdir.path());
+
+    f(&mut WorkingSet::new(storage_manager.create_storage()))
+}
+
+/// Runs the given closure against a state checkpoint over an empty storage,
+/// for the slot hooks and the kernel calls to run against.
+pub(crate) fn with_state_checkpoint<T>(f: impl FnOnce(StateCheckpoint<TestSpec>) -> T) -> T {
+    let tmpdir = tempfile::tempdir().unwrap();
+
+    let storage_manager = SimpleStorageManager::new(tmpdir.path());
+
+    f(StateCheckpoint::new(storage_manager.create_storage()))
+}
@@ -0,0 +1,171 @@
+//! Defines the per-transaction log state, which keeps the messages `ibc-rs`
+//! logs while executing a transaction under the hash of the transaction, so
+//! that operators can query them through the `txLogs` RPC method.
+//!
+//! Modules are not aware of the hash of the transaction they execute, so the
+//! messages are held as the logs of the current transaction until the runtime
+//! records them under its hash, e.g. from its post-dispatch transaction hook.
+//! Only the logs of the latest [`MAX_RECORDED_TX_LOGS`] transactions that
+//! logged any message are kept.
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{Spec, TxState};
+
+use crate::router::IbcRouterExtension;
+use crate::Ibc;
+
+/// The number of transactions whose logs are kept, the oldest ones being
+/// dropped as new ones are recorded.
+pub const MAX_RECORDED_TX_LOGS: u64 = 10_000;
+
+/// The hash of a rollup transaction.
+pub type TxHash = [u8; 32];
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Appends a message logged by `ibc-rs` to the logs of the current
+    /// transaction.
+    pub(crate) fn push_tx_log(&self, message: String, working_set: &mut impl TxState<S>) {
+        let mut logs = self.pending_tx_logs.get(working_set).unwrap_or_default();
+
+        logs.push(message);
+
+        self.pending_tx_logs.set(&logs, working_set);
+    }
+
+    /// Records the logs of the current transaction under the given hash, and
+    /// starts over the logs of the next one. Meant to be called by the
+    /// runtime once the transaction is executed. The logs of a reverted
+    /// transaction are reverted along with it, but the error its receipt
+    /// keeps carries the messages logged by the failing message.
+    pub fn record_tx_logs(&self, tx_hash: TxHash, working_set: &mut impl TxState<S>) {
+        let Some(logs) = self.pending_tx_logs.get(working_set) else {
+            return;
+        };
+
+        self.pending_tx_logs.delete(working_set);
+
+        if logs.is_empty() {
+            return;
+        }
+
+        let count = self.tx_log_count.get(working_set).unwrap_or_default();
+
+        if count >= MAX_RECORDED_TX_LOGS {
+            let oldest = count - MAX_RECORDED_TX_LOGS;
+
+            if let Some(oldest_hash) = self.tx_log_hashes.get(&oldest, working_set) {
+                self.tx_logs_map.remove(&oldest_hash, working_set);
+                self.tx_log_hashes.remove(&oldest, working_set);
+            }
+        }
+
+        self.tx_logs_map.set(&tx_hash, &logs, working_set);
+        self.tx_log_hashes.set(&count, &tx_hash, working_set);
+        self.tx_log_count.set(&(count + 1), working_set);
+    }
+
+    /// Returns the logs recorded for the transaction of the given hash, if
+    /// they are still kept.
+    pub fn tx_logs(&self, tx_hash: &TxHash, working_set: &mut impl TxState<S>) -> Vec<String> {
+        self.tx_logs_map
+            .get(tx_hash, working_set)
+            .unwrap_or_default()
+    }
+}
+
+/// Parses a hex-encoded transaction hash, optionally prefixed with `0x`.
+pub fn parse_tx_hash(tx_hash: &str) -> Option<TxHash> {
+    let digits = tx_hash.strip_prefix("0x").unwrap_or(tx_hash);
+
+    if digits.len() != 64 || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut hash = [0; 32];
+
+    for (byte, pair) in hash.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+
+    Some(hash)
+}
+
+/// Request of the `txLogs` query.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryTxLogsRequest {
+    /// The hex-encoded hash of the transaction.
+    pub tx_hash: String,
+}
+
+/// Response of the `txLogs` query.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryTxLogsResponse {
+    /// The messages logged while executing the transaction, in order.
+    pub logs: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{with_working_set, TestSpec};
+
+    #[test]
+    fn test_record_tx_logs() {
+        with_working_set(|working_set| {
+            let ibc = Ibc::<TestSpec>::default();
+
+            ibc.push_tx_log("success: packet receive".to_string(), working_set);
+            ibc.push_tx_log(
+                "success: packet write acknowledgement".to_string(),
+                working_set,
+            );
+
+            ibc.record_tx_logs([1; 32], working_set);
+
+            // A transaction that logged nothing is not recorded
+            ibc.record_tx_logs([2; 32], working_set);
+
+            assert_eq!(
+                ibc.tx_logs(&[1; 32], working_set),
+                vec![
+                    "success: packet receive".to_string(),
+                    "success: packet write acknowledgement".to_string()
+                ]
+            );
+            assert!(ibc.tx_logs(&[2; 32], working_set).is_empty());
+            assert_eq!(ibc.tx_log_count.get(working_set), Some(1));
+
+            // The oldest logs are dropped past the number of kept ones
+            for count in 1..=MAX_RECORDED_TX_LOGS {
+                let mut tx_hash = [0; 32];
+
+                tx_hash[..8].copy_from_slice(&count.to_be_bytes());
+
+                ibc.push_tx_log(format!("message {count}"), working_set);
+                ibc.record_tx_logs(tx_hash, working_set);
+            }
+
+            assert!(ibc.tx_logs(&[1; 32], working_set).is_empty());
+
+            let mut latest_hash = [0; 32];
+
+            latest_hash[..8].copy_from_slice(&MAX_RECORDED_TX_LOGS.to_be_bytes());
+
+            assert_eq!(
+                ibc.tx_logs(&latest_hash, working_set),
+                vec![format!("message {MAX_RECORDED_TX_LOGS}")]
+            );
+        });
+    }
+
+    #[test]
+    fn test_parse_tx_hash() {
+        let tx_hash = "ab".repeat(32);
+
+        assert_eq!(parse_tx_hash(&tx_hash), Some([0xab; 32]));
+        assert_eq!(parse_tx_hash(&format!("0x{tx_hash}")), Some([0xab; 32]));
+
+        assert_eq!(parse_tx_hash(&tx_hash[2..]), None);
+        assert_eq!(parse_tx_hash(&"zz".repeat(32)), None);
+        assert_eq!(parse_tx_hash(&"é".repeat(32)), None);
+    }
+}
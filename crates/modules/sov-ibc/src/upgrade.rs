@@ -0,0 +1,168 @@
+//! Defines the scheduling of the rollup upgrades, which lets counterparties
+//! upgrade their clients of the rollup through `MsgUpgradeClient`.
+use ibc_core::client::types::Height;
+use ibc_core::host::types::path::UpgradeClientPath;
+use ibc_core::primitives::proto::Any;
+use serde::{Deserialize, Serialize};
+use sov_celestia_client::client_state::ClientState as HostClientState;
+use sov_celestia_client::consensus_state::ConsensusState as HostConsensusState;
+use sov_modules_api::{Spec, StateCheckpoint, TxState};
+use tracing::{error, info};
+
+use crate::context::HOST_REVISION_NUMBER;
+use crate::router::IbcRouterExtension;
+use crate::Ibc;
+
+/// A rollup upgrade planned at a future rollup height.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize, borsh::BorshSerialize, Clone, Debug, PartialEq, Serialize, Deserialize,
+)]
+pub struct UpgradePlan {
+    pub name: String,
+    /// The last rollup height before the upgrade, from which the
+    /// counterparty clients are upgraded.
+    pub height: u64,
+    /// The client state of the upgraded rollup, which counterparties upgrade
+    /// their clients to. Its custom fields are zeroed upon scheduling.
+    pub upgraded_client_state: Any,
+}
+
+/// Schedules a rollup upgrade, replacing any pending one. Only the authority
+/// may send it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize, borsh::BorshSerialize, Clone, Debug, PartialEq, Serialize, Deserialize,
+)]
+pub struct MsgScheduleUpgrade {
+    pub plan: UpgradePlan,
+}
+
+/// Cancels the pending rollup upgrade. Only the authority may send it.
+#[cfg_attr(feature = "native", derive(schemars::JsonSchema))]
+#[derive(
+    borsh::BorshDeserialize, borsh::BorshSerialize, Clone, Debug, PartialEq, Serialize, Deserialize,
+)]
+pub struct MsgCancelUpgrade {}
+
+impl<S: Spec, R: IbcRouterExtension<S>> Ibc<S, R> {
+    /// Returns the rollup upgrade pending at a future rollup height, if any.
+    pub fn upgrade_plan(&self, working_set: &mut impl TxState<S>) -> Option<UpgradePlan> {
+        self.upgrade_plan.get(working_set)
+    }
+
+    /// Checks the plan against the current rollup height, and stores it with
+    /// the custom fields of its upgraded client state zeroed.
+    pub(crate) fn store_upgrade_plan(
+        &self,
+        plan: UpgradePlan,
+        current_height: u64,
+        working_set: &mut impl TxState<S>,
+    ) -> anyhow::Result<()> {
+        // The upgraded states are written in the slot before the upgrade
+        // height, whose slot hook must not have run yet.
+        if plan.height <= current_height.saturating_add(1) {
+            anyhow::bail!(
+                "Upgrade height {} must be after the next rollup height {}",
+                plan.height,
+                current_height.saturating_add(1)
+            );
+        }
+
+        let mut upgraded_client_state = HostClientState::try_from(plan.upgraded_client_state)
+            .map_err(|e| anyhow::anyhow!("Invalid upgraded client state: {e}"))?
+            .into_inner();
+
+        let upgrade_height = Height::new(HOST_REVISION_NUMBER, plan.height)?;
+
+        if upgraded_client_state.latest_height_in_sov() <= upgrade_height {
+            anyhow::bail!(
+                "Latest height {} of the upgraded client state must be after the upgrade height {upgrade_height}",
+                upgraded_client_state.latest_height_in_sov()
+            );
+        }
+
+        upgraded_client_state.zero_custom_fields();
+
+        self.upgrade_plan.set(
+            &UpgradePlan {
+                upgraded_client_state: HostClientState::from(upgraded_client_state).into(),
+                ..plan
+            },
+            working_set,
+        );
+
+        Ok(())
+    }
+
+    /// Drops the pending plan before its upgraded states are written.
+    pub(crate) fn cancel_upgrade_plan(
+        &self,
+        working_set: &mut impl TxState<S>,
+    ) -> anyhow::Result<()> {
+        let plan = self
+            .upgrade_plan
+            .get(working_set)
+            .ok_or(anyhow::anyhow!("No upgrade plan is pending"))?;
+
+        self.upgrade_plan.delete(working_set);
+
+        info!(
+            "Upgrade plan {} at rollup height {} is cancelled",
+            plan.name, plan.height
+        );
+
+        Ok(())
+    }
+
+    /// Writes the upgraded client and consensus states of the pending plan
+    /// under the upgrade paths of its height, once the slot right before that
+    /// height arrives, so that they are committed under the state root of the
+    /// upgrade height, and clears the plan. A plan whose slot went by without
+    /// it being applied is applied at the first slot hook after it. Called by
+    /// the slot hook storing the host consensus state of the given height.
+    pub fn apply_upgrade_plan(
+        &self,
+        height: &Height,
+        consensus_state: &HostConsensusState,
+        working_set: &mut StateCheckpoint<S>,
+    ) {
+        let Some(plan) = self.upgrade_plan.get(working_set) else {
+            return;
+        };
+
+        if height.revision_height().saturating_add(1) < plan.height {
+            return;
+        }
+
+        self.upgrade_plan.delete(working_set);
+
+        let upgraded_client_state = match HostClientState::try_from(plan.upgraded_client_state) {
+            Ok(client_state) => client_state,
+            Err(e) => {
+                error!(
+                    "Dropping upgrade plan {} with an invalid client state: {e}",
+                    plan.name
+                );
+                return;
+            }
+        };
+
+        self.upgraded_client_state_map.set(
+            &UpgradeClientPath::UpgradedClientState(plan.height),
+            &upgraded_client_state,
+            working_set,
+        );
+
+        self.upgraded_consensus_state_map.set(
+            &UpgradeClientPath::UpgradedClientConsensusState(plan.height),
+            consensus_state,
+            working_set,
+        );
+
+        info!(
+            "Upgraded client and consensus states of plan {} are stored for rollup height {}",
+            plan.name, plan.height
+        );
+    }
+}
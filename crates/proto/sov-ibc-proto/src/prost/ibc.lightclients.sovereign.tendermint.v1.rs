@@ -91,6 +91,11 @@ pub struct TendermintConsensusParams {
     /// the hash of the next validator set
     #[prost(bytes = "vec", tag = "2")]
     pub next_validators_hash: ::prost::alloc::vec::Vec<u8>,
+    /// the hash of the Data Availability block, which the aggregated proofs of
+    /// the rollup refer to. Empty if unknown, as for a consensus state stored
+    /// upon an upgrade.
+    #[prost(bytes = "vec", tag = "3")]
+    pub block_hash: ::prost::alloc::vec::Vec<u8>,
 }
 impl ::prost::Name for TendermintConsensusParams {
     const NAME: &'static str = "TendermintConsensusParams";
@@ -146,6 +151,18 @@ pub struct Misbehaviour {
     /// the header_2 of the Sovereign SDK rollup with Tendermint-based DA layer
     #[prost(message, optional, tag = "3")]
     pub header_2: ::core::option::Option<Header>,
+    /// the aggregated_proof_1 of a fork of the Sovereign SDK rollup, submitted
+    /// along with the aggregated_proof_2 in place of the headers
+    #[prost(message, optional, tag = "4")]
+    pub aggregated_proof_1: ::core::option::Option<
+        super::super::super::super::super::sovereign::types::v1::AggregatedProof,
+    >,
+    /// the aggregated_proof_2 of a fork of the Sovereign SDK rollup, submitted
+    /// along with the aggregated_proof_1 in place of the headers
+    #[prost(message, optional, tag = "5")]
+    pub aggregated_proof_2: ::core::option::Option<
+        super::super::super::super::super::sovereign::types::v1::AggregatedProof,
+    >,
 }
 impl ::prost::Name for Misbehaviour {
     const NAME: &'static str = "Misbehaviour";
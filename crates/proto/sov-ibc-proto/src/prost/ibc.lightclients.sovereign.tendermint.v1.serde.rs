@@ -345,6 +345,12 @@ impl serde::Serialize for Misbehaviour {
         if true {
             len += 1;
         }
+        if true {
+            len += 1;
+        }
+        if true {
+            len += 1;
+        }
         let mut struct_ser = serializer.serialize_struct("ibc.lightclients.sovereign.tendermint.v1.Misbehaviour", len)?;
         if true {
             struct_ser.serialize_field("clientId", &self.client_id)?;
@@ -355,6 +361,12 @@ impl serde::Serialize for Misbehaviour {
         if let Some(v) = self.header_2.as_ref() {
             struct_ser.serialize_field("header2", v)?;
         }
+        if let Some(v) = self.aggregated_proof_1.as_ref() {
+            struct_ser.serialize_field("aggregatedProof1", v)?;
+        }
+        if let Some(v) = self.aggregated_proof_2.as_ref() {
+            struct_ser.serialize_field("aggregatedProof2", v)?;
+        }
         struct_ser.end()
     }
 }
@@ -371,6 +383,10 @@ impl<'de> serde::Deserialize<'de> for Misbehaviour {
             "header1",
             "header_2",
             "header2",
+            "aggregated_proof_1",
+            "aggregatedProof1",
+            "aggregated_proof_2",
+            "aggregatedProof2",
         ];
 
         #[allow(clippy::enum_variant_names)]
@@ -378,6 +394,8 @@ impl<'de> serde::Deserialize<'de> for Misbehaviour {
             ClientId,
             Header1,
             Header2,
+            AggregatedProof1,
+            AggregatedProof2,
         }
         impl<'de> serde::Deserialize<'de> for GeneratedField {
             fn deserialize<D>(deserializer: D) -> core::result::Result<GeneratedField, D::Error>
@@ -402,6 +420,8 @@ impl<'de> serde::Deserialize<'de> for Misbehaviour {
                             "clientId" | "client_id" => Ok(GeneratedField::ClientId),
                             "header1" | "header_1" => Ok(GeneratedField::Header1),
                             "header2" | "header_2" => Ok(GeneratedField::Header2),
+                            "aggregatedProof1" | "aggregated_proof_1" => Ok(GeneratedField::AggregatedProof1),
+                            "aggregatedProof2" | "aggregated_proof_2" => Ok(GeneratedField::AggregatedProof2),
                             _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
                         }
                     }
@@ -424,6 +444,8 @@ impl<'de> serde::Deserialize<'de> for Misbehaviour {
                 let mut client_id__ = None;
                 let mut header_1__ = None;
                 let mut header_2__ = None;
+                let mut aggregated_proof_1__ = None;
+                let mut aggregated_proof_2__ = None;
                 while let Some(k) = map_.next_key()? {
                     match k {
                         GeneratedField::ClientId => {
@@ -444,12 +466,26 @@ impl<'de> serde::Deserialize<'de> for Misbehaviour {
                             }
                             header_2__ = map_.next_value()?;
                         }
+                        GeneratedField::AggregatedProof1 => {
+                            if aggregated_proof_1__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("aggregatedProof1"));
+                            }
+                            aggregated_proof_1__ = map_.next_value()?;
+                        }
+                        GeneratedField::AggregatedProof2 => {
+                            if aggregated_proof_2__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("aggregatedProof2"));
+                            }
+                            aggregated_proof_2__ = map_.next_value()?;
+                        }
                     }
                 }
                 Ok(Misbehaviour {
                     client_id: client_id__.unwrap_or_default(),
                     header_1: header_1__,
                     header_2: header_2__,
+                    aggregated_proof_1: aggregated_proof_1__,
+                    aggregated_proof_2: aggregated_proof_2__,
                 })
             }
         }
@@ -616,6 +652,9 @@ impl serde::Serialize for TendermintConsensusParams {
         if true {
             len += 1;
         }
+        if true {
+            len += 1;
+        }
         let mut struct_ser = serializer.serialize_struct("ibc.lightclients.sovereign.tendermint.v1.TendermintConsensusParams", len)?;
         if let Some(v) = self.timestamp.as_ref() {
             struct_ser.serialize_field("timestamp", v)?;
@@ -624,6 +663,10 @@ impl serde::Serialize for TendermintConsensusParams {
             #[allow(clippy::needless_borrow)]
             struct_ser.serialize_field("nextValidatorsHash", pbjson::private::base64::encode(&self.next_validators_hash).as_str())?;
         }
+        if true {
+            #[allow(clippy::needless_borrow)]
+            struct_ser.serialize_field("blockHash", pbjson::private::base64::encode(&self.block_hash).as_str())?;
+        }
         struct_ser.end()
     }
 }
@@ -637,12 +680,15 @@ impl<'de> serde::Deserialize<'de> for TendermintConsensusParams {
             "timestamp",
             "next_validators_hash",
             "nextValidatorsHash",
+            "block_hash",
+            "blockHash",
         ];
 
         #[allow(clippy::enum_variant_names)]
         enum GeneratedField {
             Timestamp,
             NextValidatorsHash,
+            BlockHash,
         }
         impl<'de> serde::Deserialize<'de> for GeneratedField {
             fn deserialize<D>(deserializer: D) -> core::result::Result<GeneratedField, D::Error>
@@ -666,6 +712,7 @@ impl<'de> serde::Deserialize<'de> for TendermintConsensusParams {
                         match value {
                             "timestamp" => Ok(GeneratedField::Timestamp),
                             "nextValidatorsHash" | "next_validators_hash" => Ok(GeneratedField::NextValidatorsHash),
+                            "blockHash" | "block_hash" => Ok(GeneratedField::BlockHash),
                             _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
                         }
                     }
@@ -687,6 +734,7 @@ impl<'de> serde::Deserialize<'de> for TendermintConsensusParams {
             {
                 let mut timestamp__ = None;
                 let mut next_validators_hash__ = None;
+                let mut block_hash__ = None;
                 while let Some(k) = map_.next_key()? {
                     match k {
                         GeneratedField::Timestamp => {
@@ -703,11 +751,20 @@ impl<'de> serde::Deserialize<'de> for TendermintConsensusParams {
                                 Some(map_.next_value::<::pbjson::private::BytesDeserialize<_>>()?.0)
                             ;
                         }
+                        GeneratedField::BlockHash => {
+                            if block_hash__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("blockHash"));
+                            }
+                            block_hash__ = 
+                                Some(map_.next_value::<::pbjson::private::BytesDeserialize<_>>()?.0)
+                            ;
+                        }
                     }
                 }
                 Ok(TendermintConsensusParams {
                     timestamp: timestamp__,
                     next_validators_hash: next_validators_hash__.unwrap_or_default(),
+                    block_hash: block_hash__.unwrap_or_default(),
                 })
             }
         }
@@ -39,6 +39,10 @@ pub struct SovereignClientParams {
     /// under `{upgradepath}/{upgradeHeight}/consensusState`
     #[prost(string, tag = "7")]
     pub upgrade_path: ::prost::alloc::string::String,
+    /// the identifier of the zkVM the aggregated proofs are generated with, which
+    /// selects the verifier of the aggregated proofs. Empty stands for "risc0".
+    #[prost(string, tag = "8")]
+    pub zk_vm: ::prost::alloc::string::String,
 }
 impl ::prost::Name for SovereignClientParams {
     const NAME: &'static str = "SovereignClientParams";
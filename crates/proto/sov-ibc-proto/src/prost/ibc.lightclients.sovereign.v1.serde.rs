@@ -27,6 +27,9 @@ impl serde::Serialize for SovereignClientParams {
         if true {
             len += 1;
         }
+        if true {
+            len += 1;
+        }
         let mut struct_ser = serializer.serialize_struct("ibc.lightclients.sovereign.v1.SovereignClientParams", len)?;
         if true {
             #[allow(clippy::needless_borrow)]
@@ -50,6 +53,9 @@ impl serde::Serialize for SovereignClientParams {
         if true {
             struct_ser.serialize_field("upgradePath", &self.upgrade_path)?;
         }
+        if true {
+            struct_ser.serialize_field("zkVm", &self.zk_vm)?;
+        }
         struct_ser.end()
     }
 }
@@ -74,6 +80,8 @@ impl<'de> serde::Deserialize<'de> for SovereignClientParams {
             "latestHeight",
             "upgrade_path",
             "upgradePath",
+            "zk_vm",
+            "zkVm",
         ];
 
         #[allow(clippy::enum_variant_names)]
@@ -85,6 +93,7 @@ impl<'de> serde::Deserialize<'de> for SovereignClientParams {
             FrozenHeight,
             LatestHeight,
             UpgradePath,
+            ZkVm,
         }
         impl<'de> serde::Deserialize<'de> for GeneratedField {
             fn deserialize<D>(deserializer: D) -> core::result::Result<GeneratedField, D::Error>
@@ -113,6 +122,7 @@ impl<'de> serde::Deserialize<'de> for SovereignClientParams {
                             "frozenHeight" | "frozen_height" => Ok(GeneratedField::FrozenHeight),
                             "latestHeight" | "latest_height" => Ok(GeneratedField::LatestHeight),
                             "upgradePath" | "upgrade_path" => Ok(GeneratedField::UpgradePath),
+                            "zkVm" | "zk_vm" => Ok(GeneratedField::ZkVm),
                             _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
                         }
                     }
@@ -139,6 +149,7 @@ impl<'de> serde::Deserialize<'de> for SovereignClientParams {
                 let mut frozen_height__ = None;
                 let mut latest_height__ = None;
                 let mut upgrade_path__ = None;
+                let mut zk_vm__ = None;
                 while let Some(k) = map_.next_key()? {
                     match k {
                         GeneratedField::GenesisStateRoot => {
@@ -185,6 +196,12 @@ impl<'de> serde::Deserialize<'de> for SovereignClientParams {
                             }
                             upgrade_path__ = Some(map_.next_value()?);
                         }
+                        GeneratedField::ZkVm => {
+                            if zk_vm__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("zkVm"));
+                            }
+                            zk_vm__ = Some(map_.next_value()?);
+                        }
                     }
                 }
                 Ok(SovereignClientParams {
@@ -195,6 +212,7 @@ impl<'de> serde::Deserialize<'de> for SovereignClientParams {
                     frozen_height: frozen_height__,
                     latest_height: latest_height__,
                     upgrade_path: upgrade_path__.unwrap_or_default(),
+                    zk_vm: zk_vm__.unwrap_or_default(),
                 })
             }
         }
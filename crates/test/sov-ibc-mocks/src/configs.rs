@@ -84,6 +84,10 @@ impl<S: Spec, Da: DaService> TestSetupConfig<S, Da> {
             self.rollup_genesis_config.bank_config.clone(),
             self.rollup_genesis_config.ibc_config.clone(),
             self.rollup_genesis_config.ibc_transfer_config.clone(),
+            self.rollup_genesis_config.ibc_ica_config.clone(),
+            self.rollup_genesis_config.ibc_fee_config.clone(),
+            self.rollup_genesis_config.ibc_nft_transfer_config.clone(),
+            self.rollup_genesis_config.ibc_packet_forward_config.clone(),
         )
     }
 }
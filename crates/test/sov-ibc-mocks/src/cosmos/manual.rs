@@ -9,6 +9,7 @@ use ibc_core::channel::types::channel::{
 };
 use ibc_core::channel::types::Version as ChannelVersion;
 use ibc_core::client::context::client_state::ClientStateExecution;
+use ibc_core::client::types::Height;
 use ibc_core::commitment_types::commitment::CommitmentPrefix;
 use ibc_core::connection::types::version::Version as ConnectionVersion;
 use ibc_core::connection::types::{
@@ -21,31 +22,32 @@ use ibc_core::host::types::path::{
     ChannelEndPath, ConnectionPath, SeqAckPath, SeqRecvPath, SeqSendPath,
 };
 use ibc_core::host::{ExecutionContext, ValidationContext};
-use ibc_core::primitives::Timestamp;
+use ibc_core::primitives::proto::Any;
 use sov_celestia_client::client_state::ClientState;
 use sov_celestia_client::types::client_state::sov_celestia_client_type;
-use sov_celestia_client::types::client_state::test_util::{
-    dummy_sov_client_state, dummy_sov_consensus_state,
-};
+use sov_celestia_client::types::client_state::test_util::dummy_sov_client_state;
 
 use super::MockCosmosChain;
 
 impl<S: ProvableStore + Default + Debug> MockCosmosChain<S> {
-    /// Establishes a tendermint light client on the ibc module
-    pub fn setup_client(&mut self, client_chain_id: &ChainId) -> ClientId {
+    /// Establishes a sovereign light client on the ibc module, tracking the
+    /// rollup from the given height and host consensus state, from which the
+    /// aggregated proofs of the next updates chain.
+    pub fn setup_client(
+        &mut self,
+        client_chain_id: &ChainId,
+        latest_height: Height,
+        consensus_state: Any,
+    ) -> ClientId {
         let client_counter = self.ibc_ctx().client_counter().unwrap();
 
         let client_id = sov_celestia_client_type().build_client_id(client_counter);
 
-        let current_height = self.ibc_ctx().host_height().unwrap();
-
         let client_state: ClientState =
-            dummy_sov_client_state(client_chain_id.clone(), current_height).into();
-
-        let consensus_state = dummy_sov_consensus_state(Timestamp::now());
+            dummy_sov_client_state(client_chain_id.clone(), latest_height).into();
 
         client_state
-            .initialise(&mut self.ibc_ctx(), &client_id, consensus_state.into())
+            .initialise(&mut self.ibc_ctx(), &client_id, consensus_state)
             .unwrap();
 
         self.ibc_ctx().increase_client_counter().unwrap();
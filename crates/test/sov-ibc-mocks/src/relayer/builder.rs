@@ -122,7 +122,22 @@ where
 
         if self.setup_cfg.with_manual_tao {
             let cos_client_id = rollup.setup_client(cos_chain.chain_id()).await;
-            let sov_client_id = cos_chain.setup_client(rollup.chain_id());
+
+            let rollup_height = match rollup.query(QueryReq::HostHeight).await {
+                QueryResp::HostHeight(height) => height,
+                _ => panic!("Unexpected response"),
+            };
+
+            let rollup_consensus_state = match rollup
+                .query(QueryReq::HostConsensusState(rollup_height))
+                .await
+            {
+                QueryResp::HostConsensusState(cons) => cons,
+                _ => panic!("Unexpected response"),
+            };
+
+            let sov_client_id =
+                cos_chain.setup_client(rollup.chain_id(), rollup_height, rollup_consensus_state);
 
             let sov_conn_id = rollup
                 .setup_connection(cos_client_id, cos_chain.ibc_ctx().commitment_prefix())
@@ -41,7 +41,12 @@ where
             QueryReq::ClientCounter => QueryResp::ClientCounter(ibc_ctx.client_counter().unwrap()),
             QueryReq::HostHeight => QueryResp::HostHeight(ibc_ctx.host_height().unwrap()),
             QueryReq::HostConsensusState(height) => {
-                QueryResp::HostConsensusState(ibc_ctx.host_consensus_state(&height).unwrap().into())
+                let latest_height = ibc_ctx.host_height().unwrap();
+
+                QueryResp::HostConsensusState(
+                    self.host_consensus_state_at(height, latest_height, &ibc_ctx)
+                        .into(),
+                )
             }
             QueryReq::ClientState(client_id) => QueryResp::ClientState(
                 ibc_ctx
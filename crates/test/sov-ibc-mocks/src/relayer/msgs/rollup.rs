@@ -16,6 +16,7 @@ use ibc_core::host::types::identifiers::{ChannelId, PortId};
 use ibc_core::host::types::path::{CommitmentPath, Path, SeqSendPath};
 use ibc_core::primitives::{Signer, Timestamp, ToProto};
 use sov_bank::{CallMessage as BankCallMessage, TokenConfig};
+use sov_ibc::batch::MsgBatch;
 use sov_ibc::call::CallMessage;
 use sov_ibc::clients::AnyClientState;
 use sov_modules_api::Spec;
@@ -187,6 +188,20 @@ where
         CallMessage::Core(msg_recv_packet.to_any())
     }
 
+    /// Bundles the given IBC core messages into an atomic batch, so that they
+    /// are submitted within a single rollup transaction
+    pub fn build_msg_batch_for_sov(&self, msgs: Vec<CallMessage>) -> CallMessage {
+        let msgs = msgs
+            .into_iter()
+            .map(|msg| match msg {
+                CallMessage::Core(msg) => msg,
+                _ => panic!("only IBC core messages can be batched"),
+            })
+            .collect();
+
+        CallMessage::Batch(MsgBatch::new(msgs))
+    }
+
     /// Creates a token with the given configuration
     pub fn build_msg_create_token<S: Spec>(&self, token: &TokenConfig<S>) -> BankCallMessage<S> {
         BankCallMessage::CreateToken {
@@ -45,7 +45,7 @@ where
 
         let mut working_set = checkpoint.to_revertable_unmetered();
 
-        let mut ibc_ctx: IbcContext<'_, S, _> = self.ibc_ctx(&mut working_set);
+        let mut ibc_ctx: IbcContext<'_, S, _, _> = self.ibc_ctx(&mut working_set);
 
         let client_counter = ibc_ctx.client_counter().unwrap();
 
@@ -99,6 +99,8 @@ where
             .store_connection(&connection_path, connection_end)
             .unwrap();
 
+        ibc_ctx.increase_connection_counter().unwrap();
+
         self.apply_slot(working_set.checkpoint().0).await;
 
         connection_id
@@ -133,6 +135,8 @@ where
             .store_channel(&channel_end_path, channel_end)
             .unwrap();
 
+        ibc_ctx.increase_channel_counter().unwrap();
+
         self.apply_slot(working_set.checkpoint().0).await;
 
         (port_id, channel_id)
@@ -4,26 +4,31 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
+use borsh::BorshSerialize;
 use ibc_client_tendermint::types::Header;
 use ibc_core::client::types::Height;
 use ibc_core::host::types::identifiers::ChainId;
+use ibc_core::host::ValidationContext;
 use sov_bank::{CallMessage as BankCallMessage, Payable, TokenConfig, TokenId};
-use sov_celestia_client::types::client_message::test_util::dummy_sov_header;
-use sov_celestia_client::types::client_message::SovTmHeader;
+use sov_celestia_client::consensus_state::ConsensusState as HostConsensusState;
+use sov_celestia_client::types::client_message::test_util::dummy_sov_header_with_validity_conditions;
+use sov_celestia_client::types::client_message::{CelestiaValidityCondition, SovTmHeader};
 use sov_consensus_state_tracker::{ConsensusStateTracker, HasConsensusState};
 use sov_ibc::call::CallMessage as IbcCallMessage;
 use sov_ibc::context::IbcContext;
+use sov_ibc::tx_logs::TxHash;
 use sov_kernels::basic::BasicKernel;
 use sov_mock_da::MockFee;
 use sov_modules_api::{Spec, WorkingSet};
 use sov_prover_storage_manager::SimpleStorageManager;
 use sov_rollup_interface::services::da::DaService;
 use sov_state::{MerkleProofSpec, ProverStorage, Storage};
+use tendermint::crypto::default::Sha256;
 
 use super::DEFAULT_SALT;
 use crate::cosmos::MockTendermint;
 use crate::sovereign::runtime::RuntimeCall;
-use crate::sovereign::Runtime;
+use crate::sovereign::{MockRouterExtension, Runtime};
 use crate::utils::MutexUtil;
 
 type Mempool<C> = Vec<RuntimeCall<C>>;
@@ -105,10 +110,21 @@ where
         self.mempool.acquire_mutex().drain(..).collect()
     }
 
+    /// Returns the hash the IBC logs of the given runtime call are recorded
+    /// under. The mock rollup executes runtime calls rather than transactions,
+    /// so the hash of a call stands in for the hash of its transaction.
+    pub fn tx_hash(call: &RuntimeCall<S>) -> TxHash {
+        let call_bytes = call
+            .try_to_vec()
+            .expect("runtime calls are always serializable");
+
+        <sha2::Sha256 as sha2::Digest>::digest(call_bytes).into()
+    }
+
     pub fn ibc_ctx<'a>(
         &'a self,
         working_set: &'a mut WorkingSet<S>,
-    ) -> IbcContext<'a, S, WorkingSet<S>> {
+    ) -> IbcContext<'a, S, WorkingSet<S>, MockRouterExtension> {
         let shared_working_set = Rc::new(RefCell::new(working_set));
 
         IbcContext::new(&self.runtime.ibc, shared_working_set.clone())
@@ -129,6 +145,26 @@ where
 
         let target_block = blocks[da_height as usize - 1].clone();
 
+        let trusted_da_height = trusted_height.add(height_offset).revision_height();
+
+        let block_hash = |da_height: u64| -> [u8; 32] {
+            blocks[da_height as usize - 1]
+                .signed_header
+                .header()
+                .hash_with::<Sha256>()
+                .as_bytes()
+                .try_into()
+                .expect("never fails")
+        };
+
+        // The mock DA blocks are not linked to their parents, so the validity
+        // conditions link them by their hashes from the trusted DA block on.
+        let validity_conditions = (trusted_da_height + 1..=da_height)
+            .map(|height| {
+                CelestiaValidityCondition::new(block_hash(height - 1), block_hash(height))
+            })
+            .collect();
+
         let header = Header {
             signed_header: target_block.signed_header,
             validator_set: target_block.validators,
@@ -136,12 +172,48 @@ where
             trusted_next_validator_set: target_block.next_validators,
         };
 
+        let trusted_state_root = match self.state_root(trusted_height.revision_height() - 1) {
+            Some(root) => root.user_hash(),
+            None => panic!("state root not found"),
+        };
+
         let target_state_root = match self.state_root(target_revision_height - 1) {
             Some(root) => root.user_hash(),
             None => panic!("state root not found"),
         };
 
-        dummy_sov_header(header, 1, target_revision_height, target_state_root.into())
+        dummy_sov_header_with_validity_conditions(
+            header,
+            trusted_height.revision_height() + 1,
+            target_revision_height,
+            trusted_state_root.into(),
+            target_state_root.into(),
+            validity_conditions,
+        )
+    }
+
+    /// Returns the consensus state of the rollup at the given height, recording
+    /// the hash of the mock Tendermint block standing for its DA block in the
+    /// rollup headers, which the validity conditions of the next header extend.
+    pub fn host_consensus_state_at(
+        &self,
+        height: Height,
+        latest_height: Height,
+        ibc_ctx: &IbcContext<'_, S, WorkingSet<S>, MockRouterExtension>,
+    ) -> HostConsensusState {
+        let mut consensus_state = ibc_ctx.host_consensus_state(&height).unwrap().into_inner();
+
+        let da_height =
+            height.revision_height() + self.da_core.height() - latest_height.revision_height();
+
+        consensus_state.da_params.block_hash = Some(
+            self.da_core.blocks()[da_height as usize - 1]
+                .signed_header
+                .header()
+                .hash_with::<Sha256>(),
+        );
+
+        consensus_state.into()
     }
 
     /// Returns the balance of a user for a given token
@@ -154,6 +226,15 @@ where
             .unwrap()
     }
 
+    /// Returns the IBC logs recorded for the given call
+    pub fn get_tx_logs(&self, call: &RuntimeCall<S>) -> Vec<String> {
+        let mut working_set: WorkingSet<S> = WorkingSet::new(self.prover_storage());
+
+        self.runtime()
+            .ibc
+            .tx_logs(&Self::tx_hash(call), &mut working_set)
+    }
+
     pub fn get_token_id(&self, token_config: TokenConfig<S>) -> Option<TokenId> {
         self.runtime()
             .bank
@@ -190,6 +271,10 @@ impl<S: Spec> Clone for RuntimeCall<S> {
             RuntimeCall::bank(call) => RuntimeCall::bank(call.clone()),
             RuntimeCall::ibc(call) => RuntimeCall::ibc(call.clone()),
             RuntimeCall::ibc_transfer(_) => RuntimeCall::ibc_transfer(()),
+            RuntimeCall::ibc_ica(_) => RuntimeCall::ibc_ica(()),
+            RuntimeCall::ibc_fee(_) => RuntimeCall::ibc_fee(()),
+            RuntimeCall::ibc_nft_transfer(_) => RuntimeCall::ibc_nft_transfer(()),
+            RuntimeCall::ibc_packet_forward(_) => RuntimeCall::ibc_packet_forward(()),
         }
     }
 }
@@ -124,6 +124,12 @@ where
                     info!("rollup: error executing message: {e:?}");
                     CallResponse::default()
                 });
+
+            // Records the IBC logs of the call, as the post-dispatch hook of a
+            // rollup runtime does with the hash of the transaction
+            self.runtime()
+                .ibc
+                .record_tx_logs(Self::tx_hash(&m), &mut working_set);
         }
 
         working_set.checkpoint().0
@@ -1,9 +1,17 @@
 use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use ibc_core::client::types::Height;
 use sov_bank::{BankConfig, GasTokenConfig};
+use sov_celestia_client::types::client_state::test_util::mock_celestia_chain_id;
+use sov_celestia_client::types::sovereign::ZkVm;
 use sov_chain_state::ChainStateConfig;
-use sov_ibc::ExampleModuleConfig;
+use sov_ibc::genesis::SelfClientParams;
+use sov_ibc::IbcConfig;
+use sov_ibc_fee::FeeConfig;
+use sov_ibc_ica::InterchainAccountsConfig;
+use sov_ibc_nft_transfer::NftTransferConfig;
+use sov_ibc_packet_forward::PacketForwardConfig;
 use sov_ibc_transfer::TransferConfig;
 use sov_modules_api::{CryptoSpec, PrivateKey, Spec, Zkvm};
 use sov_rollup_interface::da::Time;
@@ -27,8 +35,12 @@ pub const DEFAULT_SALT: u64 = 0;
 pub struct RollupGenesisConfig<S: Spec> {
     pub chain_state_config: ChainStateConfig<S>,
     pub bank_config: BankConfig<S>,
-    pub ibc_config: ExampleModuleConfig,
+    pub ibc_config: IbcConfig,
     pub ibc_transfer_config: TransferConfig,
+    pub ibc_ica_config: InterchainAccountsConfig,
+    pub ibc_fee_config: FeeConfig,
+    pub ibc_nft_transfer_config: NftTransferConfig,
+    pub ibc_packet_forward_config: PacketForwardConfig,
 }
 
 impl<S: Spec> RollupGenesisConfig<S> {
@@ -49,6 +61,10 @@ impl<S: Spec> Clone for RollupGenesisConfig<S> {
             bank_config: self.bank_config.clone(),
             ibc_config: self.ibc_config.clone(),
             ibc_transfer_config: self.ibc_transfer_config.clone(),
+            ibc_ica_config: self.ibc_ica_config.clone(),
+            ibc_fee_config: self.ibc_fee_config.clone(),
+            ibc_nft_transfer_config: self.ibc_nft_transfer_config.clone(),
+            ibc_packet_forward_config: self.ibc_packet_forward_config.clone(),
         }
     }
 }
@@ -79,22 +95,35 @@ where
             .field("bank_config", &self.bank_config)
             .field("ibc_config", &self.ibc_config)
             .field("ibc_transfer_config", &self.ibc_transfer_config)
+            .field("ibc_ica_config", &self.ibc_ica_config)
+            .field("ibc_fee_config", &self.ibc_fee_config)
+            .field("ibc_nft_transfer_config", &self.ibc_nft_transfer_config)
+            .field("ibc_packet_forward_config", &self.ibc_packet_forward_config)
             .finish()
     }
 }
 
 impl<S: Spec> RollupGenesisConfig<S> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         chain_state_config: ChainStateConfig<S>,
         bank_config: BankConfig<S>,
-        ibc_config: ExampleModuleConfig,
+        ibc_config: IbcConfig,
         ibc_transfer_config: TransferConfig,
+        ibc_ica_config: InterchainAccountsConfig,
+        ibc_fee_config: FeeConfig,
+        ibc_nft_transfer_config: NftTransferConfig,
+        ibc_packet_forward_config: PacketForwardConfig,
     ) -> Self {
         Self {
             chain_state_config,
             bank_config,
             ibc_config,
             ibc_transfer_config,
+            ibc_ica_config,
+            ibc_fee_config,
+            ibc_nft_transfer_config,
+            ibc_packet_forward_config,
         }
     }
 }
@@ -105,15 +134,34 @@ impl<S: Spec> Default for RollupGenesisConfig<S> {
 
         let bank_config = create_bank_config(DEFAULT_ADDRESS_COUNT, DEFAULT_INIT_BALANCE);
 
-        let ibc_config = ExampleModuleConfig {};
+        let ibc_config = IbcConfig {
+            self_client: Some(create_self_client_params()),
+            ..Default::default()
+        };
 
-        let ibc_transfer_config = TransferConfig {};
+        let ibc_transfer_config = TransferConfig::default();
+
+        let ibc_ica_config = InterchainAccountsConfig {
+            host_enabled: true,
+            controller_enabled: true,
+            allow_messages: vec!["bank".to_string()],
+        };
+
+        let ibc_fee_config = FeeConfig::default();
+
+        let ibc_nft_transfer_config = NftTransferConfig::default();
+
+        let ibc_packet_forward_config = PacketForwardConfig::default();
 
         Self {
             chain_state_config,
             bank_config,
             ibc_config,
             ibc_transfer_config,
+            ibc_ica_config,
+            ibc_fee_config,
+            ibc_nft_transfer_config,
+            ibc_packet_forward_config,
         }
     }
 }
@@ -139,6 +187,19 @@ pub fn create_chain_state_config<S: Spec>() -> ChainStateConfig<S> {
     }
 }
 
+/// Creates the parameters the clients of the rollup hosted on the mock Cosmos
+/// chain are checked against, which match the dummy client states. The
+/// genesis state root is left to the first slot hook to record.
+pub fn create_self_client_params() -> SelfClientParams {
+    SelfClientParams {
+        genesis_state_root: None,
+        code_commitment: vec![1; 32],
+        genesis_da_height: Height::new(0, 3).unwrap(),
+        da_chain_id: mock_celestia_chain_id(),
+        zk_vm: ZkVm::Mock.to_string(),
+    }
+}
+
 /// Creates a bank configuration with the given number of addresses and initial balance
 pub fn create_bank_config<S: Spec>(addresses_count: u64, initial_balance: u64) -> BankConfig<S> {
     let address_and_balances: Vec<_> = (0..addresses_count)
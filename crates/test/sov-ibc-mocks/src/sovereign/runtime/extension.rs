@@ -0,0 +1,50 @@
+//! Defines the router extension of the mock rollup, which lets IBC packets
+//! trigger runtime calls, either as interchain account transactions or as IBC
+//! hooks.
+use sov_ibc::executor::RuntimeCallExecutor;
+use sov_ibc::router::{IbcRouter, IbcRouterExtension};
+use sov_modules_api::{Context, DispatchCall, Spec, TxState};
+
+use super::{Runtime, RuntimeCall};
+
+/// Lets counterparty controller chains execute runtime calls through their
+/// interchain accounts on the mock rollup, and inbound transfers execute the
+/// runtime calls carried in their memos.
+#[derive(Clone, Debug, Default)]
+pub struct MockRouterExtension;
+
+impl<S: Spec> IbcRouterExtension<S> for MockRouterExtension {
+    fn extend<'ws, TS: TxState<S>>(router: &mut IbcRouter<'ws, S, TS, Self>) -> anyhow::Result<()> {
+        router.enable_ica_host(RuntimeDispatcher);
+        router.enable_hooks(RuntimeDispatcher);
+
+        Ok(())
+    }
+}
+
+/// Dispatches the JSON-encoded runtime calls of the mock rollup.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeDispatcher;
+
+impl<S: Spec> RuntimeCallExecutor<S> for RuntimeDispatcher {
+    fn execute<TS: TxState<S>>(
+        &self,
+        call: &serde_json::Value,
+        sender: &S::Address,
+        sdk_context: &Context<S>,
+        working_set: &mut TS,
+    ) -> anyhow::Result<()> {
+        let call: RuntimeCall<S> = serde_json::from_value(call.clone())?;
+
+        let context = Context::new(
+            sender.clone(),
+            Default::default(),
+            sdk_context.sequencer().clone(),
+            sdk_context.visible_slot_number(),
+        );
+
+        Runtime::<S>::default().dispatch_call(call, working_set, &context)?;
+
+        Ok(())
+    }
+}
@@ -1,8 +1,14 @@
 //! Contains the runtime implementation for the Sovereign SDK rollup.
 mod config;
+mod extension;
 pub use config::*;
+pub use extension::*;
 use sov_bank::Bank;
 use sov_ibc::Ibc;
+use sov_ibc_fee::IbcFee;
+use sov_ibc_ica::IbcInterchainAccounts;
+use sov_ibc_nft_transfer::IbcNftTransfer;
+use sov_ibc_packet_forward::IbcPacketForward;
 use sov_ibc_transfer::IbcTransfer;
 use sov_modules_api::{DispatchCall, Genesis, MessageCodec, Spec};
 
@@ -14,6 +20,10 @@ where
     S: Spec,
 {
     pub bank: Bank<S>,
-    pub ibc: Ibc<S>,
+    pub ibc: Ibc<S, MockRouterExtension>,
     pub ibc_transfer: IbcTransfer<S>,
+    pub ibc_ica: IbcInterchainAccounts<S>,
+    pub ibc_fee: IbcFee<S>,
+    pub ibc_nft_transfer: IbcNftTransfer<S>,
+    pub ibc_packet_forward: IbcPacketForward<S>,
 }
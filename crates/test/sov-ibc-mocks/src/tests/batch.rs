@@ -0,0 +1,251 @@
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::Height;
+use ibc_core::handler::types::events::IbcEvent;
+use ibc_core::host::types::identifiers::ClientId;
+use ibc_core::host::ValidationContext;
+use ibc_core::primitives::proto::Any;
+use sov_ibc::batch::MsgBatch;
+use sov_ibc::call::CallMessage;
+use sov_ibc::clients::AnyClientState;
+use sov_ibc::event::IBC_BATCH_EVENT_KEY;
+use sov_modules_api::{
+    Context, EventContainer, GasMeter, Module, Spec, StateReaderAndWriter, TxState, WorkingSet,
+};
+use sov_state::namespaces::{Accessory, User};
+use sov_state::{SlotKey, SlotValue};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, QueryReq, QueryResp, RelayerBuilder};
+
+/// Records the events emitted on top of a working set, which otherwise keeps
+/// them out of reach of the tests.
+struct EventRecorder<'a> {
+    inner: &'a mut WorkingSet<DefaultSpec>,
+    events: Vec<(String, Box<dyn std::any::Any + Send>)>,
+}
+
+impl<'a> EventRecorder<'a> {
+    fn new(inner: &'a mut WorkingSet<DefaultSpec>) -> Self {
+        Self {
+            inner,
+            events: Vec::new(),
+        }
+    }
+
+    /// Returns the status and the error of every batched message, in the
+    /// order their events were emitted.
+    fn batch_outcomes(&self) -> Vec<(String, Option<String>)> {
+        self.events
+            .iter()
+            .filter(|(key, _)| key == IBC_BATCH_EVENT_KEY)
+            .map(|(_, event)| {
+                let Some(IbcEvent::Module(event)) = event.downcast_ref::<IbcEvent>() else {
+                    panic!("batch message event must be a module event");
+                };
+
+                let attribute = |key: &str| {
+                    event
+                        .attributes
+                        .iter()
+                        .find(|attribute| attribute.key == key)
+                        .map(|attribute| attribute.value.clone())
+                };
+
+                (attribute("status").unwrap(), attribute("error"))
+            })
+            .collect()
+    }
+}
+
+impl StateReaderAndWriter<User> for EventRecorder<'_> {
+    fn get(&mut self, key: &SlotKey) -> Option<SlotValue> {
+        StateReaderAndWriter::<User>::get(self.inner, key)
+    }
+
+    fn set(&mut self, key: &SlotKey, value: SlotValue) {
+        StateReaderAndWriter::<User>::set(self.inner, key, value)
+    }
+
+    fn delete(&mut self, key: &SlotKey) {
+        StateReaderAndWriter::<User>::delete(self.inner, key)
+    }
+}
+
+impl StateReaderAndWriter<Accessory> for EventRecorder<'_> {
+    fn get(&mut self, key: &SlotKey) -> Option<SlotValue> {
+        StateReaderAndWriter::<Accessory>::get(self.inner, key)
+    }
+
+    fn set(&mut self, key: &SlotKey, value: SlotValue) {
+        StateReaderAndWriter::<Accessory>::set(self.inner, key, value)
+    }
+
+    fn delete(&mut self, key: &SlotKey) {
+        StateReaderAndWriter::<Accessory>::delete(self.inner, key)
+    }
+}
+
+impl EventContainer for EventRecorder<'_> {
+    fn add_event<E: 'static + core::marker::Send>(&mut self, event_key: &str, event: E) {
+        self.events.push((event_key.to_string(), Box::new(event)));
+    }
+}
+
+impl GasMeter<<DefaultSpec as Spec>::Gas> for EventRecorder<'_> {
+    fn charge_gas(&mut self, gas: &<DefaultSpec as Spec>::Gas) -> anyhow::Result<()> {
+        self.inner.charge_gas(gas)
+    }
+
+    fn remaining_funds(&self) -> u64 {
+        self.inner.remaining_funds()
+    }
+}
+
+impl TxState<DefaultSpec> for EventRecorder<'_> {}
+
+/// A message which cannot be converted into an IBC core message.
+fn unknown_msg() -> Any {
+    Any {
+        type_url: "/ibc.core.client.v1.MsgUnknown".to_string(),
+        value: Vec::new(),
+    }
+}
+
+/// Returns the latest height of the given client hosted on the chain.
+async fn client_height(chain: &impl Handle, client_id: &ClientId) -> Height {
+    let any_client_state = match chain.query(QueryReq::ClientState(client_id.clone())).await {
+        QueryResp::ClientState(client_state) => client_state,
+        _ => panic!("unexpected response"),
+    };
+
+    AnyClientState::try_from(any_client_state)
+        .unwrap()
+        .latest_height()
+}
+
+/// Checks that every batched message emits its own outcome, and that a
+/// failing message is skipped by a non-atomic batch while it fails an atomic
+/// one.
+#[test(tokio::test)]
+async fn test_batch_message_events() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let target_height = match rly.dst_chain_ctx().query(QueryReq::HostHeight).await {
+        QueryResp::HostHeight(height) => height,
+        _ => panic!("unexpected response"),
+    };
+
+    let CallMessage::Core(msg_update_client) =
+        rly.build_msg_update_client_for_sov(target_height).await
+    else {
+        panic!("client update must be an IBC core message");
+    };
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let visible_slot = rollup
+        .ibc_ctx(&mut working_set)
+        .host_height()
+        .unwrap()
+        .revision_height();
+
+    let sender = rollup.relayer_address.clone();
+
+    let sdk_context = Context::new(sender.clone(), Default::default(), sender, visible_slot);
+
+    let messages = vec![msg_update_client, unknown_msg()];
+
+    {
+        let mut recorder = EventRecorder::new(&mut working_set);
+
+        let error = ibc
+            .call(
+                CallMessage::Batch(MsgBatch::new(messages.clone())),
+                &sdk_context,
+                &mut recorder,
+            )
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("Message 1 of the IBC batch (/ibc.core.client.v1.MsgUnknown) failed"));
+    }
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let mut recorder = EventRecorder::new(&mut working_set);
+
+    ibc.call(
+        CallMessage::Batch(MsgBatch::non_atomic(messages)),
+        &sdk_context,
+        &mut recorder,
+    )
+    .unwrap();
+
+    let outcomes = recorder.batch_outcomes();
+
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0], ("executed".to_string(), None));
+    assert_eq!(outcomes[1].0, "skipped");
+    assert!(outcomes[1]
+        .1
+        .as_ref()
+        .unwrap()
+        .contains("Failed to convert Any to MsgEnvelope"));
+}
+
+/// Checks that a failing message reverts the whole transaction of an atomic
+/// batch, while a non-atomic batch only reverts the failing message and
+/// still applies the others.
+#[test(tokio::test)]
+async fn test_batch_atomicity() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let initial_height = client_height(rly.src_chain_ctx(), rly.dst_client_id()).await;
+
+    let target_height = match rly.dst_chain_ctx().query(QueryReq::HostHeight).await {
+        QueryResp::HostHeight(height) => height,
+        _ => panic!("unexpected response"),
+    };
+
+    assert!(initial_height < target_height);
+
+    let CallMessage::Core(msg_update_client) =
+        rly.build_msg_update_client_for_sov(target_height).await
+    else {
+        panic!("client update must be an IBC core message");
+    };
+
+    let messages = vec![msg_update_client, unknown_msg()];
+
+    rly.src_chain_ctx()
+        .submit_msgs(vec![
+            CallMessage::Batch(MsgBatch::new(messages.clone())).into()
+        ])
+        .await;
+
+    assert_eq!(
+        client_height(rly.src_chain_ctx(), rly.dst_client_id()).await,
+        initial_height
+    );
+
+    // The failing message comes first, so that the client update is still
+    // dispatched after it
+    let messages = messages.into_iter().rev().collect();
+
+    rly.src_chain_ctx()
+        .submit_msgs(vec![
+            CallMessage::Batch(MsgBatch::non_atomic(messages)).into()
+        ])
+        .await;
+
+    assert_eq!(
+        client_height(rly.src_chain_ctx(), rly.dst_client_id()).await,
+        target_height
+    );
+}
@@ -1,16 +1,41 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str::FromStr;
+
 use basecoin::modules::ibc::AnyClientState;
 use borsh::BorshDeserialize;
+use ibc_app_transfer::types::{PrefixedDenom, TracePrefix};
 use ibc_client_tendermint::types::proto::v1::{
     ClientState as RawClientState, ConsensusState as RawConsensusState,
 };
-use ibc_client_tendermint::types::{ClientState, ConsensusState};
+use ibc_client_tendermint::types::{client_type as tm_client_type, ClientState, ConsensusState};
 use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::context::ClientExecutionContext;
+use ibc_core::client::types::Height;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
 use ibc_core::host::types::path::{ClientConsensusStatePath, ClientStatePath, Path};
+use ibc_core::host::ValidationContext;
 use ibc_core::primitives::proto::Protobuf;
+use ibc_core::primitives::ToProto;
+use ibc_query::core::channel::{QueryChannelClientStateRequest, QueryChannelConsensusStateRequest};
+use ibc_query::core::connection::QueryConnectionClientStateRequest;
+use ibc_query::core::context::QueryContext;
 use jmt::proof::SparseMerkleProof;
 use sha2::Sha256;
+use sov_celestia_client::client_state::ClientState as HostClientState;
+use sov_celestia_client::types::client_state::sov_celestia_client_type;
+use sov_celestia_client::types::client_state::test_util::{
+    dummy_sov_client_state, mock_celestia_chain_id, ClientStateConfig, TendermintParamsConfig,
+};
+use sov_celestia_client::types::sovereign::{SovereignParamsConfig, ZkVm};
+use sov_ibc::clients::AnyClientState as SovAnyClientState;
+use sov_ibc::context::IbcContext;
+use sov_ibc::genesis::SelfClientParams;
+use sov_ibc::IbcConfig;
+use sov_modules_api::{Module, StateCheckpoint, WorkingSet};
 use test_log::test;
 
+use crate::configs::TransferTestConfig;
 use crate::relayer::{Handle, QueryReq, QueryResp, RelayerBuilder};
 
 #[test(tokio::test)]
@@ -185,3 +210,313 @@ async fn test_update_client_on_cos() {
 
     assert_eq!(client_state.latest_height(), target_height);
 }
+
+/// Checks that relayers other than the IBC authority can still create and
+/// update clients on the rollup and relay packets to it.
+#[test(tokio::test)]
+async fn test_relay_without_authority() {
+    let relayer_builder = RelayerBuilder::default().await;
+
+    let mut setup_cfg = relayer_builder.setup_cfg().clone();
+
+    let authority = setup_cfg
+        .rollup_genesis_config
+        .bank_config
+        .gas_token_config
+        .address_and_balances
+        .first()
+        .unwrap()
+        .0
+        .clone();
+
+    assert_ne!(authority, setup_cfg.get_relayer_address());
+
+    setup_cfg.rollup_genesis_config.ibc_config.authority = Some(authority.to_string());
+
+    // -----------------------------------------------------------------------
+    // Create and update a client on the rollup
+    // -----------------------------------------------------------------------
+    let rly = RelayerBuilder::new(setup_cfg.clone()).setup().await;
+
+    let msg_create_client = rly.build_msg_create_client_for_sov().await;
+
+    rly.src_chain_ctx()
+        .submit_msgs(vec![msg_create_client.into()])
+        .await;
+
+    let target_height = match rly.dst_chain_ctx().query(QueryReq::HostHeight).await {
+        QueryResp::HostHeight(height) => height,
+        _ => panic!("unexpected response"),
+    };
+
+    let msg_update_client = rly.build_msg_update_client_for_sov(target_height).await;
+
+    rly.src_chain_ctx()
+        .submit_msgs(vec![msg_update_client.into()])
+        .await;
+
+    let any_client_state = match rly
+        .src_chain_ctx()
+        .query(QueryReq::ClientState(rly.dst_client_id().clone()))
+        .await
+    {
+        QueryResp::ClientState(state) => state,
+        _ => panic!("unexpected response"),
+    };
+
+    let client_state = AnyClientState::try_from(any_client_state).unwrap();
+
+    assert_eq!(client_state.latest_height(), target_height);
+
+    // -----------------------------------------------------------------------
+    // Relay a packet to the rollup
+    // -----------------------------------------------------------------------
+    let rly = RelayerBuilder::new(setup_cfg.clone())
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let cfg = TransferTestConfig::builder()
+        .sov_address(setup_cfg.get_relayer_address())
+        .build();
+
+    let msg_transfer_on_cos = rly.build_msg_transfer_for_cos(&cfg);
+
+    rly.dst_chain_ctx()
+        .submit_msgs(vec![msg_transfer_on_cos.clone().to_any()])
+        .await;
+
+    let target_height = match rly.dst_chain_ctx().query(QueryReq::HostHeight).await {
+        QueryResp::HostHeight(height) => height,
+        _ => panic!("unexpected response"),
+    };
+
+    let msg_update_client = rly.build_msg_update_client_for_sov(target_height).await;
+
+    let msg_recv_packet = rly
+        .build_msg_recv_packet_for_sov(target_height, msg_transfer_on_cos)
+        .await;
+
+    let msg_recv_packet = msg_recv_packet.into();
+
+    rly.src_chain_ctx()
+        .submit_msgs(vec![msg_update_client.into(), msg_recv_packet.clone()])
+        .await;
+
+    let recv_packet_logs = rly.src_chain_ctx().service().get_tx_logs(&msg_recv_packet);
+
+    assert!(recv_packet_logs.contains(&"success: packet receive".to_string()));
+
+    let mut prefixed_denom = PrefixedDenom::from_str(&cfg.cos_denom).unwrap();
+    prefixed_denom.add_trace_prefix(TracePrefix::new(PortId::transfer(), ChannelId::zero()));
+
+    let minted_token_id = rly
+        .src_chain_ctx()
+        .service()
+        .get_minted_token_id(prefixed_denom.to_string())
+        .unwrap();
+
+    let receiver_balance = rly
+        .src_chain_ctx()
+        .service()
+        .get_balance_of(&cfg.sov_address, minted_token_id);
+
+    assert_eq!(receiver_balance, cfg.amount);
+}
+
+/// Checks that the clients of the rollup hosted on counterparties are rejected
+/// until the parameters of the rollup and its genesis state root are known,
+/// the latter being recorded by the first slot hook when not configured, and
+/// that they must then carry them.
+#[test(tokio::test)]
+async fn test_validate_self_client() {
+    let mut setup_cfg = RelayerBuilder::default().await.setup_cfg().clone();
+
+    setup_cfg.rollup_genesis_config.ibc_config.self_client = None;
+
+    let rly = RelayerBuilder::new(setup_cfg).setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let client_state = |genesis_state_root: [u8; 32], code_commitment: Vec<u8>, zk_vm: ZkVm| {
+        let sovereign_params = SovereignParamsConfig::builder()
+            .genesis_state_root(genesis_state_root.into())
+            .code_commitment(code_commitment.into())
+            .latest_height(Height::new(0, 1).unwrap())
+            .zk_vm(zk_vm)
+            .build();
+
+        let client_state: HostClientState = ClientStateConfig::builder()
+            .sovereign_params(sovereign_params)
+            .tendermint_params(TendermintParamsConfig::builder().build())
+            .build()
+            .into();
+
+        client_state
+    };
+
+    let validate = |client_state: HostClientState, working_set: &mut _| {
+        IbcContext::new(ibc, Rc::new(RefCell::new(working_set))).validate_self_client(client_state)
+    };
+
+    let self_client = |zk_vm: &str| SelfClientParams {
+        genesis_state_root: None,
+        code_commitment: vec![1; 32],
+        genesis_da_height: Height::new(0, 3).unwrap(),
+        da_chain_id: mock_celestia_chain_id(),
+        zk_vm: zk_vm.to_string(),
+    };
+
+    let ibc_config = |zk_vm: &str| IbcConfig {
+        self_client: Some(self_client(zk_vm)),
+        ..Default::default()
+    };
+
+    // An empty zkVM stands for the default one, as in the client states
+    assert_eq!(self_client("").zk_vm().unwrap(), ZkVm::Risc0);
+    assert_eq!(self_client("risc0").zk_vm().unwrap(), ZkVm::Risc0);
+
+    let mut working_set = StateCheckpoint::new(rollup.prover_storage()).to_revertable_unmetered();
+
+    // The parameters of the rollup are not configured
+    assert!(validate(
+        client_state([0; 32], vec![1; 32], ZkVm::Mock),
+        &mut working_set
+    )
+    .is_err());
+
+    assert!(ibc
+        .genesis(&ibc_config("unknown"), &mut working_set)
+        .is_err());
+
+    ibc.genesis(&ibc_config("mock"), &mut working_set).unwrap();
+
+    // The genesis state root is not recorded yet
+    assert!(validate(
+        client_state([0; 32], vec![1; 32], ZkVm::Mock),
+        &mut working_set
+    )
+    .is_err());
+
+    let mut checkpoint = working_set.checkpoint().0;
+
+    // Only the root the first slot starts from is recorded
+    ibc.record_genesis_state_root([0; 32], &mut checkpoint);
+    ibc.record_genesis_state_root([1; 32], &mut checkpoint);
+
+    let mut working_set = checkpoint.to_revertable_unmetered();
+
+    validate(
+        client_state([0; 32], vec![1; 32], ZkVm::Mock),
+        &mut working_set,
+    )
+    .unwrap();
+
+    for invalid_client_state in [
+        client_state([1; 32], vec![1; 32], ZkVm::Mock),
+        client_state([0; 32], vec![2; 32], ZkVm::Mock),
+        client_state([0; 32], vec![1; 32], ZkVm::Risc0),
+    ] {
+        assert!(validate(invalid_client_state, &mut working_set).is_err());
+    }
+}
+
+/// Checks that the clients of every type are registered along with their type
+/// as they are stored, and that the connection and channel client lookups
+/// resolve clients through the registry.
+#[test(tokio::test)]
+async fn test_client_registry() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let tm_client_id = rly.dst_client_id().clone();
+
+    let sov_client_id = sov_celestia_client_type().build_client_id(9);
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    assert_eq!(
+        ibc.registered_client_type(&tm_client_id, &mut working_set),
+        Some(tm_client_type().to_string())
+    );
+
+    // The lookups run against the committed state, which the client stored
+    // below is left out of
+    let connection_client_state = ibc
+        .connection_client_state(
+            QueryConnectionClientStateRequest {
+                connection_id: ConnectionId::new(0),
+                query_height: None,
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(
+        connection_client_state.identified_client_state.client_id,
+        tm_client_id
+    );
+
+    let channel_client_state = ibc
+        .channel_client_state(
+            QueryChannelClientStateRequest {
+                port_id: PortId::transfer(),
+                channel_id: ChannelId::new(0),
+                query_height: None,
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(
+        channel_client_state.identified_client_state.client_id,
+        tm_client_id
+    );
+
+    ibc.channel_consensus_state(
+        QueryChannelConsensusStateRequest {
+            port_id: PortId::transfer(),
+            channel_id: ChannelId::new(0),
+            query_height: None,
+        },
+        &mut working_set,
+    )
+    .unwrap();
+
+    let mut ibc_ctx = IbcContext::new(ibc, Rc::new(RefCell::new(&mut working_set)));
+
+    let sov_client_state: SovAnyClientState =
+        dummy_sov_client_state("other-rollup".parse().unwrap(), Height::new(0, 1).unwrap()).into();
+
+    ibc_ctx
+        .store_client_state(ClientStatePath(sov_client_id.clone()), sov_client_state)
+        .unwrap();
+
+    let registered_client_ids = ibc_ctx
+        .client_states()
+        .unwrap()
+        .into_iter()
+        .map(|(client_id, _)| client_id)
+        .collect::<Vec<_>>();
+
+    assert!(registered_client_ids.contains(&tm_client_id));
+    assert!(registered_client_ids.contains(&sov_client_id));
+
+    ibc_ctx.ensure_client_registered(&sov_client_id).unwrap();
+
+    assert!(ibc_ctx
+        .ensure_client_registered(&tm_client_type().build_client_id(9))
+        .is_err());
+
+    drop(ibc_ctx);
+
+    assert_eq!(
+        ibc.registered_client_type(&sov_client_id, &mut working_set),
+        Some(sov_celestia_client_type().to_string())
+    );
+}
@@ -0,0 +1,294 @@
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use sov_bank::GAS_TOKEN_ID;
+use sov_ibc::call::CallMessage;
+use sov_ibc::export::{IbcStateExport, MsgBackfillRegistries};
+use sov_ibc::IbcConfig;
+use sov_ibc_nft_transfer::{NftClassConfig, NftConfig, NftTransferConfig};
+use sov_ibc_transfer::rate_limit::{RateLimit, RateLimitWindow};
+use sov_ibc_transfer::TransferConfig;
+use sov_modules_api::{Context, Module, Spec, WorkingSet};
+use sov_prover_storage_manager::SimpleStorageManager;
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+fn sdk_context(sender: &Address) -> Context<DefaultSpec> {
+    Context::new(sender.clone(), Default::default(), sender.clone(), 0)
+}
+
+/// Checks that the exported state survives a serialization round trip, and
+/// that a rollup importing it at genesis exports it back as it was.
+#[test(tokio::test)]
+async fn test_export_round_trip() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let runtime = rollup.runtime();
+
+    let owner = rollup.relayer_address.clone();
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    runtime
+        .ibc_transfer
+        .genesis(
+            &TransferConfig {
+                rate_limit_admin: Some(owner.to_string()),
+                rate_limits: vec![RateLimit {
+                    channel_id: ChannelId::new(0),
+                    denom: GAS_TOKEN_ID.to_string(),
+                    window: RateLimitWindow::Slots(10),
+                    max_inflow: 0,
+                    max_outflow: 100,
+                }],
+                ..Default::default()
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    runtime
+        .ibc_nft_transfer
+        .genesis(
+            &NftTransferConfig {
+                classes: vec![NftClassConfig {
+                    class_id: "class".to_string(),
+                    class_uri: None,
+                    class_data: None,
+                    nfts: vec![NftConfig {
+                        token_id: "token".to_string(),
+                        token_uri: None,
+                        token_data: None,
+                        owner: owner.to_string(),
+                    }],
+                }],
+                ..Default::default()
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    let exported = runtime.ibc.export_state(&mut working_set).unwrap();
+
+    assert!(!exported.ibc.clients.is_empty());
+    assert!(!exported.ibc.channels.is_empty());
+    assert_eq!(exported.transfer.rate_limits.len(), 1);
+    assert_eq!(exported.nft_transfer.classes.len(), 1);
+
+    let serialized = serde_json::to_string(&exported).unwrap();
+
+    assert_eq!(
+        serde_json::from_str::<IbcStateExport>(&serialized).unwrap(),
+        exported
+    );
+
+    let tmpdir = tempfile::tempdir().unwrap();
+
+    let storage_manager = SimpleStorageManager::new(tmpdir.path());
+
+    let mut imported_working_set = WorkingSet::<DefaultSpec>::new(storage_manager.create_storage());
+
+    // Imported in the order of the runtime genesis
+    runtime
+        .ibc
+        .genesis(&exported.ibc, &mut imported_working_set)
+        .unwrap();
+    runtime
+        .ibc_transfer
+        .genesis(&exported.transfer, &mut imported_working_set)
+        .unwrap();
+    runtime
+        .ibc_ica
+        .genesis(&exported.ica, &mut imported_working_set)
+        .unwrap();
+    runtime
+        .ibc_fee
+        .genesis(&exported.fee, &mut imported_working_set)
+        .unwrap();
+    runtime
+        .ibc_nft_transfer
+        .genesis(&exported.nft_transfer, &mut imported_working_set)
+        .unwrap();
+    runtime
+        .ibc_packet_forward
+        .genesis(&exported.packet_forward, &mut imported_working_set)
+        .unwrap();
+
+    let channels = exported
+        .ibc
+        .channels
+        .iter()
+        .map(|channel| (channel.port_id.clone(), channel.channel_id.clone()))
+        .collect::<Vec<_>>();
+
+    // The host height is only recorded by the slot hooks, which did not run
+    // on the imported state, so the configurations are exported one by one.
+    assert_eq!(
+        runtime
+            .ibc
+            .export_config(&mut imported_working_set)
+            .unwrap(),
+        exported.ibc
+    );
+    assert_eq!(
+        runtime
+            .ibc_transfer
+            .export_config(&channels, &mut imported_working_set)
+            .unwrap(),
+        exported.transfer
+    );
+    assert_eq!(
+        runtime.ibc_ica.export_config(&mut imported_working_set),
+        exported.ica
+    );
+    assert_eq!(
+        runtime
+            .ibc_fee
+            .export_config(&channels, &mut imported_working_set)
+            .unwrap(),
+        exported.fee
+    );
+    assert_eq!(
+        runtime
+            .ibc_nft_transfer
+            .export_config(&mut imported_working_set)
+            .unwrap(),
+        exported.nft_transfer
+    );
+    assert_eq!(
+        runtime
+            .ibc_packet_forward
+            .export_config(&[], &mut imported_working_set)
+            .unwrap(),
+        exported.packet_forward
+    );
+}
+
+/// Checks that only the authority can backfill the registries, that entries
+/// predating them get exported once backfilled, that backfilling registered
+/// entries again does not duplicate them, and that unknown entries are
+/// rejected.
+#[test(tokio::test)]
+async fn test_backfill_registries() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let authority = rollup.relayer_address.clone();
+
+    let relayer = Address::from([1; 32]);
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    ibc.genesis(
+        &IbcConfig {
+            authority: Some(authority.to_string()),
+            ..Default::default()
+        },
+        &mut working_set,
+    )
+    .unwrap();
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let channels = [(port_id.clone(), channel_id.clone())];
+
+    // A payee registered before its key was kept track of
+    ibc_fee.payees.set(
+        &(port_id.clone(), channel_id.clone(), relayer.clone()),
+        &authority,
+        &mut working_set,
+    );
+
+    assert!(ibc_fee
+        .export_config(&channels, &mut working_set)
+        .unwrap()
+        .payees
+        .is_empty());
+
+    let exported_client_ids = |working_set: &mut WorkingSet<DefaultSpec>| {
+        ibc.export_config(working_set)
+            .unwrap()
+            .clients
+            .into_iter()
+            .map(|client| client.client_id)
+            .collect::<Vec<_>>()
+    };
+
+    let client_ids = exported_client_ids(&mut working_set);
+
+    let backfill = |payees: Vec<(PortId, ChannelId, String)>| {
+        CallMessage::BackfillRegistries(MsgBackfillRegistries {
+            client_ids: client_ids.clone(),
+            minted_token_ids: Vec::new(),
+            rate_limits: Vec::new(),
+            payees,
+            nft_classes: Vec::new(),
+            nfts: Vec::new(),
+        })
+    };
+
+    let payee_key = (port_id.clone(), channel_id.clone(), relayer.to_string());
+
+    assert!(ibc
+        .call(
+            backfill(vec![payee_key.clone()]),
+            &sdk_context(&relayer),
+            &mut working_set
+        )
+        .is_err());
+
+    for _ in 0..2 {
+        ibc.call(
+            backfill(vec![payee_key.clone()]),
+            &sdk_context(&authority),
+            &mut working_set,
+        )
+        .unwrap();
+    }
+
+    let payees = ibc_fee
+        .export_config(&channels, &mut working_set)
+        .unwrap()
+        .payees;
+
+    assert_eq!(payees.len(), 1);
+    assert_eq!(payees[0].relayer, relayer.to_string());
+    assert_eq!(payees[0].payee, authority.to_string());
+
+    assert_eq!(exported_client_ids(&mut working_set), client_ids);
+
+    // No payee is registered for the authority itself
+    assert!(ibc
+        .call(
+            backfill(vec![(port_id, channel_id, authority.to_string())]),
+            &sdk_context(&authority),
+            &mut working_set
+        )
+        .is_err());
+
+    assert!(ibc
+        .call(
+            CallMessage::BackfillRegistries(MsgBackfillRegistries {
+                client_ids: vec!["07-tendermint-9".parse().unwrap()],
+                minted_token_ids: Vec::new(),
+                rate_limits: Vec::new(),
+                payees: Vec::new(),
+                nft_classes: Vec::new(),
+                nfts: Vec::new(),
+            }),
+            &sdk_context(&authority),
+            &mut working_set
+        )
+        .is_err());
+}
@@ -0,0 +1,549 @@
+use ibc_core::channel::types::commitment::PacketCommitment;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::{CommitmentPath, SeqSendPath};
+use ibc_core::host::{ExecutionContext, ValidationContext};
+use sov_bank::{Coins, Payable, GAS_TOKEN_ID};
+use sov_ibc::call::CallMessage;
+use sov_ibc_fee::types::{Fee, MsgPayPacketFee, MsgPayPacketFeeAsync};
+use sov_modules_api::{Context, Module, Spec, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+fn coins(amount: u64) -> Vec<Coins> {
+    vec![Coins {
+        amount,
+        token_id: GAS_TOKEN_ID,
+    }]
+}
+
+fn fee(recv: u64, ack: u64, timeout: u64) -> Fee {
+    Fee {
+        recv_fee: coins(recv),
+        ack_fee: coins(ack),
+        timeout_fee: coins(timeout),
+    }
+}
+
+/// Checks that fees are only escrowed on fee-enabled channels, that they are
+/// taken from the payer, and that fees of the same packet add up.
+#[test(tokio::test)]
+async fn test_escrow_packet_fee() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let bank = &rollup.runtime().bank;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let payer = rollup.relayer_address.clone();
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let sequence = Sequence::from(1);
+
+    let balance_of = |address: &Address, working_set: &mut WorkingSet<DefaultSpec>| {
+        bank.get_balance_of(address.as_token_holder(), GAS_TOKEN_ID, working_set)
+            .unwrap_or_default()
+    };
+
+    let initial_balance = balance_of(&payer, &mut working_set);
+
+    // The channel did not negotiate the fee version
+    assert!(ibc_fee
+        .escrow_packet_fee(
+            &port_id,
+            &channel_id,
+            sequence,
+            fee(10, 20, 30),
+            &payer,
+            &mut working_set,
+        )
+        .is_err());
+
+    ibc_fee.fee_enabled_channels.set(
+        &(port_id.clone(), channel_id.clone()),
+        &true,
+        &mut working_set,
+    );
+
+    // A fee paying nothing is rejected
+    assert!(ibc_fee
+        .escrow_packet_fee(
+            &port_id,
+            &channel_id,
+            sequence,
+            fee(0, 0, 0),
+            &payer,
+            &mut working_set,
+        )
+        .is_err());
+
+    for _ in 0..2 {
+        ibc_fee
+            .escrow_packet_fee(
+                &port_id,
+                &channel_id,
+                sequence,
+                fee(10, 20, 30),
+                &payer,
+                &mut working_set,
+            )
+            .unwrap();
+    }
+
+    assert_eq!(balance_of(&payer, &mut working_set), initial_balance - 120);
+
+    let packet_fees = ibc_fee
+        .packet_fees
+        .get(
+            &(port_id.clone(), channel_id.clone(), sequence),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(packet_fees.len(), 2);
+
+    // The packet is only tracked once however many times it is incentivized
+    assert_eq!(
+        ibc_fee.incentivized_sequences(&port_id, &channel_id, &mut working_set),
+        vec![sequence]
+    );
+}
+
+/// Checks that on acknowledgement the receive fee goes to the forward
+/// relayer, the acknowledgement fee to the payee registered by the relayer on
+/// that port and channel, and the timeout fee back to the payer.
+#[test(tokio::test)]
+async fn test_fee_distribution_on_acknowledgement() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let bank = &rollup.runtime().bank;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let payer = rollup.relayer_address.clone();
+
+    let forward_relayer = Address::from([1; 32]);
+
+    let relayer = Address::from([2; 32]);
+
+    let payee = Address::from([3; 32]);
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let sequence = Sequence::from(1);
+
+    let balance_of = |address: &Address, working_set: &mut WorkingSet<DefaultSpec>| {
+        bank.get_balance_of(address.as_token_holder(), GAS_TOKEN_ID, working_set)
+            .unwrap_or_default()
+    };
+
+    ibc_fee.fee_enabled_channels.set(
+        &(port_id.clone(), channel_id.clone()),
+        &true,
+        &mut working_set,
+    );
+
+    ibc_fee.register_payee(&port_id, &channel_id, &relayer, &payee, &mut working_set);
+
+    // A payee registered on another port does not apply to this channel
+    let other_port = PortId::new("other".to_string()).unwrap();
+
+    ibc_fee.register_payee(
+        &other_port,
+        &channel_id,
+        &relayer,
+        &Address::from([4; 32]),
+        &mut working_set,
+    );
+
+    ibc_fee
+        .escrow_packet_fee(
+            &port_id,
+            &channel_id,
+            sequence,
+            fee(10, 20, 30),
+            &payer,
+            &mut working_set,
+        )
+        .unwrap();
+
+    let escrowed_balance = balance_of(&payer, &mut working_set);
+
+    ibc_fee
+        .distribute_fees_on_acknowledgement(
+            &port_id,
+            &channel_id,
+            sequence,
+            &forward_relayer.to_string(),
+            Some(&relayer),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(balance_of(&forward_relayer, &mut working_set), 10);
+    assert_eq!(balance_of(&payee, &mut working_set), 20);
+    assert_eq!(balance_of(&relayer, &mut working_set), 0);
+    assert_eq!(balance_of(&Address::from([4; 32]), &mut working_set), 0);
+    assert_eq!(balance_of(&payer, &mut working_set), escrowed_balance + 30);
+
+    assert!(ibc_fee
+        .packet_fees
+        .get(
+            &(port_id.clone(), channel_id.clone(), sequence),
+            &mut working_set
+        )
+        .is_none());
+    assert_eq!(
+        ibc_fee.incentivized_sequences(&port_id, &channel_id, &mut working_set),
+        vec![]
+    );
+}
+
+/// Checks that on timeout the timeout fee goes to the relayer, which did not
+/// register a payee, while the other fees go back to the payer.
+#[test(tokio::test)]
+async fn test_fee_distribution_on_timeout() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let bank = &rollup.runtime().bank;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let payer = rollup.relayer_address.clone();
+
+    let relayer = Address::from([2; 32]);
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let sequence = Sequence::from(1);
+
+    let balance_of = |address: &Address, working_set: &mut WorkingSet<DefaultSpec>| {
+        bank.get_balance_of(address.as_token_holder(), GAS_TOKEN_ID, working_set)
+            .unwrap_or_default()
+    };
+
+    ibc_fee.fee_enabled_channels.set(
+        &(port_id.clone(), channel_id.clone()),
+        &true,
+        &mut working_set,
+    );
+
+    ibc_fee
+        .escrow_packet_fee(
+            &port_id,
+            &channel_id,
+            sequence,
+            fee(10, 20, 30),
+            &payer,
+            &mut working_set,
+        )
+        .unwrap();
+
+    let escrowed_balance = balance_of(&payer, &mut working_set);
+
+    ibc_fee
+        .distribute_fees_on_timeout(
+            &port_id,
+            &channel_id,
+            sequence,
+            Some(&relayer),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(balance_of(&relayer, &mut working_set), 30);
+    assert_eq!(balance_of(&payer, &mut working_set), escrowed_balance + 30);
+
+    // The fees are only paid out once
+    ibc_fee
+        .distribute_fees_on_timeout(
+            &port_id,
+            &channel_id,
+            sequence,
+            Some(&relayer),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(balance_of(&relayer, &mut working_set), 30);
+}
+
+/// Checks that closing a channel refunds every payer of every packet of the
+/// channel, and only of that channel.
+#[test(tokio::test)]
+async fn test_fee_refund_on_channel_close() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let bank = &rollup.runtime().bank;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let payer = rollup.relayer_address.clone();
+
+    let other_payer = Address::from([5; 32]);
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let other_channel_id = ChannelId::new(1);
+
+    let balance_of = |address: &Address, working_set: &mut WorkingSet<DefaultSpec>| {
+        bank.get_balance_of(address.as_token_holder(), GAS_TOKEN_ID, working_set)
+            .unwrap_or_default()
+    };
+
+    bank.transfer_from(
+        &payer,
+        &other_payer,
+        coins(100)[0].clone(),
+        &mut working_set,
+    )
+    .unwrap();
+
+    for channel_id in [&channel_id, &other_channel_id] {
+        ibc_fee.fee_enabled_channels.set(
+            &(port_id.clone(), channel_id.clone()),
+            &true,
+            &mut working_set,
+        );
+    }
+
+    let initial_balance = balance_of(&payer, &mut working_set);
+
+    for sequence in 1..=3 {
+        for (payer, channel_id) in [
+            (&payer, &channel_id),
+            (&other_payer, &channel_id),
+            (&payer, &other_channel_id),
+        ] {
+            ibc_fee
+                .escrow_packet_fee(
+                    &port_id,
+                    channel_id,
+                    Sequence::from(sequence),
+                    fee(1, 2, 3),
+                    payer,
+                    &mut working_set,
+                )
+                .unwrap();
+        }
+    }
+
+    assert_eq!(balance_of(&payer, &mut working_set), initial_balance - 36);
+    assert_eq!(balance_of(&other_payer, &mut working_set), 100 - 18);
+
+    ibc_fee
+        .refund_channel_fees(&port_id, &channel_id, &mut working_set)
+        .unwrap();
+
+    assert_eq!(balance_of(&payer, &mut working_set), initial_balance - 18);
+    assert_eq!(balance_of(&other_payer, &mut working_set), 100);
+
+    assert!(ibc_fee
+        .incentivized_sequences(&port_id, &channel_id, &mut working_set)
+        .is_empty());
+
+    for sequence in 1..=3 {
+        assert!(ibc_fee
+            .packet_fees
+            .get(
+                &(
+                    port_id.clone(),
+                    channel_id.clone(),
+                    Sequence::from(sequence)
+                ),
+                &mut working_set
+            )
+            .is_none());
+    }
+
+    // The fees of the other channel are still escrowed
+    assert_eq!(
+        ibc_fee.incentivized_sequences(&port_id, &other_channel_id, &mut working_set),
+        vec![1.into(), 2.into(), 3.into()]
+    );
+
+    // Settling the packets in any order keeps the rest of the list linked
+    for (sequence, remaining) in [(2, vec![1, 3]), (1, vec![3]), (3, vec![])] {
+        ibc_fee
+            .distribute_fees_on_timeout(
+                &port_id,
+                &other_channel_id,
+                sequence.into(),
+                None,
+                &mut working_set,
+            )
+            .unwrap();
+
+        assert_eq!(
+            ibc_fee.incentivized_sequences(&port_id, &other_channel_id, &mut working_set),
+            remaining
+                .into_iter()
+                .map(Sequence::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    assert_eq!(balance_of(&payer, &mut working_set), initial_balance);
+}
+
+/// Checks that `PayPacketFee` pays for any packet still in flight that is not
+/// incentivized yet, while `PayPacketFeeAsync` pays for any packet still in
+/// flight, and that both must be signed by their sender.
+#[test(tokio::test)]
+async fn test_pay_packet_fee_for_sent_packets() {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let visible_slot = rollup
+        .ibc_ctx(&mut working_set)
+        .host_height()
+        .unwrap()
+        .revision_height();
+
+    let payer = rollup.relayer_address.clone();
+
+    let sdk_context = Context::new(
+        payer.clone(),
+        Default::default(),
+        payer.clone(),
+        visible_slot,
+    );
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    ibc_fee.fee_enabled_channels.set(
+        &(port_id.clone(), channel_id.clone()),
+        &true,
+        &mut working_set,
+    );
+
+    // Packets 1 to 3 were sent, and only packets 2 and 3 are still in flight
+    {
+        let mut ibc_ctx = rollup.ibc_ctx(&mut working_set);
+
+        ibc_ctx
+            .store_next_sequence_send(&SeqSendPath::new(&port_id, &channel_id), 4.into())
+            .unwrap();
+
+        for sequence in [2, 3] {
+            ibc_ctx
+                .store_packet_commitment(
+                    &CommitmentPath::new(&port_id, &channel_id, sequence.into()),
+                    PacketCommitment::from(vec![1]),
+                )
+                .unwrap();
+        }
+    }
+
+    let pay_packet_fee_by = |sequence: u64, signer: &Address| {
+        CallMessage::PayPacketFee(MsgPayPacketFee {
+            port_id: port_id.clone(),
+            channel_id: channel_id.clone(),
+            sequence: sequence.into(),
+            fee: fee(10, 20, 30),
+            signer: signer.to_string(),
+        })
+    };
+
+    let pay_packet_fee_async_by = |sequence: u64, signer: &Address| {
+        CallMessage::PayPacketFeeAsync(MsgPayPacketFeeAsync {
+            port_id: port_id.clone(),
+            channel_id: channel_id.clone(),
+            sequence: sequence.into(),
+            fee: fee(10, 20, 30),
+            signer: signer.to_string(),
+        })
+    };
+
+    let pay_packet_fee = |sequence: u64| pay_packet_fee_by(sequence, &payer);
+
+    let pay_packet_fee_async = |sequence: u64| pay_packet_fee_async_by(sequence, &payer);
+
+    // The fees cannot be paid on behalf of another signer than the sender
+    for msg in [
+        pay_packet_fee_by(2, &Address::from([1; 32])),
+        pay_packet_fee_async_by(2, &Address::from([1; 32])),
+    ] {
+        assert!(ibc.call(msg, &sdk_context, &mut working_set).is_err());
+    }
+
+    // Neither a packet no longer in flight nor a packet yet to be sent can be
+    // paid for at send time
+    for sequence in [1, 4] {
+        assert!(ibc
+            .call(pay_packet_fee(sequence), &sdk_context, &mut working_set)
+            .is_err());
+    }
+
+    // A packet sent before the last one can be paid for, but only once
+    ibc.call(pay_packet_fee(2), &sdk_context, &mut working_set)
+        .unwrap();
+
+    assert!(ibc
+        .call(pay_packet_fee(2), &sdk_context, &mut working_set)
+        .is_err());
+
+    // Packets no longer in flight cannot be paid for asynchronously
+    assert!(ibc
+        .call(pay_packet_fee_async(1), &sdk_context, &mut working_set)
+        .is_err());
+
+    ibc.call(pay_packet_fee_async(2), &sdk_context, &mut working_set)
+        .unwrap();
+
+    let packet_fees = ibc_fee
+        .packet_fees
+        .get(
+            &(port_id.clone(), channel_id.clone(), 2.into()),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(packet_fees.len(), 2);
+
+    assert_eq!(
+        ibc_fee.incentivized_sequences(&port_id, &channel_id, &mut working_set),
+        vec![2.into()]
+    );
+}
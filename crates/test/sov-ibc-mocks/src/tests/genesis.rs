@@ -0,0 +1,385 @@
+use ibc_core::channel::types::channel::{
+    ChannelEnd, Counterparty as ChanCounterparty, Order, State as ChannelState,
+};
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::CommitmentPrefix;
+use ibc_core::connection::types::version::Version as ConnectionVersion;
+use ibc_core::connection::types::{
+    ConnectionEnd, Counterparty as ConnCounterparty, State as ConnectionState,
+};
+use ibc_core::host::types::identifiers::{ChannelId, ClientId, ConnectionId, PortId, Sequence};
+use ibc_core::primitives::Timestamp;
+use sov_bank::{Coins, IntoPayable, TokenId, GAS_TOKEN_ID};
+use sov_ibc::genesis::{
+    ChannelGenesis, ClientGenesis, ConnectionGenesis, ConsensusStateGenesis, PortBinding,
+};
+use sov_ibc::IbcConfig;
+use sov_ibc_fee::types::{Fee, Metadata as FeeMetadata};
+use sov_ibc_fee::{FeeConfig, IdentifiedPacketFees, PacketFee, RegisteredPayee};
+use sov_ibc_ica::types::{
+    controller_port_id, host_port_id, Metadata as IcaMetadata, CONTROLLER_MODULE_ID_STR,
+};
+use sov_ibc_ica::utils::compute_interchain_account_address;
+use sov_ibc_transfer::utils::compute_escrow_address;
+use sov_ibc_transfer::{EscrowAccount, MintedToken, TransferConfig};
+use sov_modules_api::{Module, Spec, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::cosmos::{dummy_tm_client_state, dummy_tm_consensus_state};
+use crate::relayer::{Handle, RelayerBuilder};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+const OWNER: &str = "cosmos1owner";
+
+/// Identifiers past the ones the relayer setup allocates, so that the
+/// imported state does not overwrite the state of the running channels.
+const SEQUENCE: u64 = 5;
+
+fn connection_id() -> ConnectionId {
+    ConnectionId::new(SEQUENCE)
+}
+
+fn channel(
+    port_id: PortId,
+    channel_id: ChannelId,
+    counterparty_port_id: PortId,
+    version: String,
+) -> ChannelGenesis {
+    let channel_end = ChannelEnd::new(
+        ChannelState::Open,
+        Order::Unordered,
+        ChanCounterparty::new(counterparty_port_id, Some(channel_id.clone())),
+        vec![connection_id()],
+        ChannelVersion::new(version),
+    )
+    .unwrap();
+
+    ChannelGenesis {
+        port_id,
+        channel_id,
+        channel_end,
+        next_sequence_send: Sequence::from(1),
+        next_sequence_recv: Sequence::from(1),
+        next_sequence_ack: Sequence::from(1),
+        commitments: Vec::new(),
+        receipts: Vec::new(),
+        acknowledgements: Vec::new(),
+    }
+}
+
+/// Builds a configuration importing a Tendermint client and an open
+/// connection on top of it, along with the given channels over it.
+fn ibc_config(port_bindings: Vec<PortBinding>, channels: Vec<ChannelGenesis>) -> IbcConfig {
+    let client_id = ClientId::new("07-tendermint", SEQUENCE).unwrap();
+
+    let height = Height::new(0, 10).unwrap();
+
+    let client_state = dummy_tm_client_state("cosmos".parse().unwrap(), height);
+
+    let connection_end = ConnectionEnd::new(
+        ConnectionState::Open,
+        client_id.clone(),
+        ConnCounterparty::new(
+            client_id.clone(),
+            Some(connection_id()),
+            CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap(),
+        ),
+        ConnectionVersion::compatibles(),
+        Default::default(),
+    )
+    .unwrap();
+
+    IbcConfig {
+        clients: vec![ClientGenesis {
+            client_id,
+            client_state: client_state.into(),
+            consensus_states: vec![ConsensusStateGenesis {
+                height,
+                consensus_state: dummy_tm_consensus_state().into(),
+                processed_time: Timestamp::from_nanoseconds(1).unwrap(),
+                processed_height: Height::new(0, 1).unwrap(),
+            }],
+        }],
+        next_client_sequence: SEQUENCE + 1,
+        connections: vec![ConnectionGenesis {
+            connection_id: connection_id(),
+            connection_end,
+        }],
+        next_connection_sequence: SEQUENCE + 1,
+        port_bindings,
+        channels,
+        next_channel_sequence: SEQUENCE + 3,
+        ..Default::default()
+    }
+}
+
+/// Checks that imported escrow accounts must be the ones derived for their
+/// channels, and that imported minted tokens must exist in the bank.
+#[test(tokio::test)]
+async fn test_transfer_genesis() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_transfer = &rollup.runtime().ibc_transfer;
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(SEQUENCE);
+
+    let escrow_account = |escrow_channel_id: &ChannelId| EscrowAccount {
+        port_id: port_id.clone(),
+        channel_id: channel_id.clone(),
+        escrow_id: compute_escrow_address::<DefaultSpec>(&port_id, escrow_channel_id),
+    };
+
+    let minted_token = |token_id: TokenId| MintedToken {
+        token_name: format!("transfer/{channel_id}/uatom"),
+        token_id,
+    };
+
+    for invalid_config in [
+        TransferConfig {
+            escrow_accounts: vec![escrow_account(&ChannelId::new(SEQUENCE + 1))],
+            ..Default::default()
+        },
+        TransferConfig {
+            minted_tokens: vec![minted_token(TokenId::from([7; 32]))],
+            ..Default::default()
+        },
+    ] {
+        let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+        assert!(ibc_transfer
+            .genesis(&invalid_config, &mut working_set)
+            .is_err());
+    }
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    ibc_transfer
+        .genesis(
+            &TransferConfig {
+                minted_tokens: vec![minted_token(GAS_TOKEN_ID)],
+                escrow_accounts: vec![escrow_account(&channel_id)],
+                ..Default::default()
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(
+        ibc_transfer
+            .minted_token_id(format!("transfer/{channel_id}/uatom"), &mut working_set)
+            .unwrap()
+            .token_id,
+        GAS_TOKEN_ID
+    );
+
+    let exported = ibc_transfer
+        .export_config(&[(port_id.clone(), channel_id.clone())], &mut working_set)
+        .unwrap();
+
+    assert_eq!(exported.escrow_accounts, vec![escrow_account(&channel_id)]);
+}
+
+/// Checks that importing channels restores the fee-enabled transfer channels
+/// and the interchain account registries out of their versions, rejecting a
+/// host channel naming an account other than the one derived for its owner,
+/// and that fees are only imported on fee-enabled channels, backed by the
+/// balance of the fee module.
+#[test(tokio::test)]
+async fn test_app_channel_genesis() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let bank = &rollup.runtime().bank;
+
+    let ibc = &rollup.runtime().ibc;
+
+    let ibc_ica = &rollup.runtime().ibc_ica;
+
+    let ibc_fee = &rollup.runtime().ibc_fee;
+
+    let relayer = rollup.relayer_address.clone();
+
+    let counterparty_controller_port_id = controller_port_id(OWNER).unwrap();
+
+    let rollup_controller_port_id = controller_port_id(&relayer.to_string()).unwrap();
+
+    let (transfer_channel_id, host_channel_id, controller_channel_id) = (
+        ChannelId::new(SEQUENCE),
+        ChannelId::new(SEQUENCE + 1),
+        ChannelId::new(SEQUENCE + 2),
+    );
+
+    let host_account = compute_interchain_account_address::<DefaultSpec>(
+        &connection_id(),
+        &counterparty_controller_port_id,
+    );
+
+    let host_channel = |address: String| {
+        let mut metadata = IcaMetadata::new(connection_id(), connection_id());
+
+        metadata.address = address;
+
+        channel(
+            host_port_id(),
+            host_channel_id.clone(),
+            counterparty_controller_port_id.clone(),
+            metadata.to_version(),
+        )
+    };
+
+    let mut controller_metadata = IcaMetadata::new(connection_id(), connection_id());
+
+    controller_metadata.address = "cosmos1account".to_string();
+
+    let controller_binding = PortBinding {
+        port_id: rollup_controller_port_id.clone(),
+        module_id: CONTROLLER_MODULE_ID_STR.to_string(),
+    };
+
+    let channels = |host_address: String| {
+        vec![
+            channel(
+                PortId::transfer(),
+                transfer_channel_id.clone(),
+                PortId::transfer(),
+                FeeMetadata::new("ics20-1".to_string())
+                    .to_version()
+                    .to_string(),
+            ),
+            host_channel(host_address),
+            channel(
+                rollup_controller_port_id.clone(),
+                controller_channel_id.clone(),
+                host_port_id(),
+                controller_metadata.to_version(),
+            ),
+        ]
+    };
+
+    {
+        let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+        assert!(ibc
+            .genesis(
+                &ibc_config(
+                    vec![controller_binding.clone()],
+                    channels(Address::from([1; 32]).to_string())
+                ),
+                &mut working_set
+            )
+            .is_err());
+    }
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    ibc.genesis(
+        &ibc_config(vec![controller_binding], channels(host_account.to_string())),
+        &mut working_set,
+    )
+    .unwrap();
+
+    assert!(ibc_fee.is_fee_enabled(&PortId::transfer(), &transfer_channel_id, &mut working_set));
+    assert!(!ibc_fee.is_fee_enabled(&host_port_id(), &host_channel_id, &mut working_set));
+
+    let host = ibc_ica
+        .host_account(
+            connection_id(),
+            counterparty_controller_port_id,
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(host.address, host_account.to_string());
+    assert_eq!(host.active_channel_id, Some(host_channel_id.clone()));
+
+    let controller = ibc_ica
+        .controller_account(connection_id(), relayer.to_string(), &mut working_set)
+        .unwrap();
+
+    assert_eq!(controller.address, "cosmos1account");
+    assert_eq!(controller.active_channel_id, Some(controller_channel_id));
+
+    let fee = |amount: u64| Fee {
+        recv_fee: vec![Coins {
+            amount,
+            token_id: GAS_TOKEN_ID,
+        }],
+        ack_fee: Vec::new(),
+        timeout_fee: Vec::new(),
+    };
+
+    let fee_config = |channel_id: &ChannelId, sequence: u64, amount: u64| FeeConfig {
+        payees: vec![RegisteredPayee {
+            port_id: PortId::transfer(),
+            channel_id: channel_id.clone(),
+            relayer: relayer.to_string(),
+            payee: Address::from([2; 32]).to_string(),
+        }],
+        packet_fees: vec![IdentifiedPacketFees {
+            port_id: PortId::transfer(),
+            channel_id: channel_id.clone(),
+            sequence: Sequence::from(sequence),
+            packet_fees: vec![PacketFee {
+                fee: fee(amount),
+                refund_address: relayer.to_string(),
+            }],
+        }],
+        ..Default::default()
+    };
+
+    bank.transfer_from(
+        &relayer,
+        ibc_fee.id.to_payable(),
+        Coins {
+            amount: 10,
+            token_id: GAS_TOKEN_ID,
+        },
+        &mut working_set,
+    )
+    .unwrap();
+
+    ibc_fee
+        .genesis(&fee_config(&transfer_channel_id, 1, 10), &mut working_set)
+        .unwrap();
+
+    assert_eq!(
+        ibc_fee.payees.get(
+            &(
+                PortId::transfer(),
+                transfer_channel_id.clone(),
+                relayer.clone()
+            ),
+            &mut working_set
+        ),
+        Some(Address::from([2; 32]))
+    );
+
+    assert_eq!(
+        ibc_fee.packet_fees.get(
+            &(
+                PortId::transfer(),
+                transfer_channel_id.clone(),
+                Sequence::from(1)
+            ),
+            &mut working_set
+        ),
+        Some(vec![(fee(10), relayer.clone())])
+    );
+
+    // Fees on the channels of the relayer setup, which are not fee-enabled,
+    // and fees not backed by the balance of the fee module are rejected
+    for invalid_config in [
+        fee_config(&ChannelId::new(0), 1, 10),
+        fee_config(&transfer_channel_id, 2, 20),
+    ] {
+        assert!(ibc_fee.genesis(&invalid_config, &mut working_set).is_err());
+    }
+}
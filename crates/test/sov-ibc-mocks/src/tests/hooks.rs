@@ -0,0 +1,191 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::bail;
+use ibc_app_transfer::types::packet::PacketData;
+use ibc_app_transfer::types::PrefixedCoin;
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::primitives::{Signer, Timestamp};
+use ibc_core::router::module::Module;
+use sov_bank::{CallMessage as BankCallMessage, Coins, Payable, TokenId};
+use sov_ibc::executor::RuntimeCallExecutor;
+use sov_ibc::hooks::{compute_hook_sender_address, IbcHooksMiddleware};
+use sov_ibc_transfer::context::IbcTransferContext;
+use sov_ibc_transfer::utils::is_ack_successful;
+use sov_modules_api::{Context, Spec, TxState, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+use crate::sovereign::{RuntimeCall, RuntimeDispatcher};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+const ORIGINAL_SENDER: &str = "cosmos1sender";
+
+/// Dispatches a runtime call, then fails as if it had failed halfway through.
+#[derive(Clone, Debug, Default)]
+struct FailingDispatcher;
+
+impl<S: Spec> RuntimeCallExecutor<S> for FailingDispatcher {
+    fn execute<TS: TxState<S>>(
+        &self,
+        call: &serde_json::Value,
+        sender: &S::Address,
+        sdk_context: &Context<S>,
+        working_set: &mut TS,
+    ) -> anyhow::Result<()> {
+        RuntimeDispatcher.execute(call, sender, sdk_context, working_set)?;
+
+        bail!("call failed after its effects")
+    }
+}
+
+fn inbound_packet(sequence: u64, receiver: &str, memo: String) -> Packet {
+    let data = PacketData {
+        token: PrefixedCoin {
+            denom: "basecoin".parse().unwrap(),
+            amount: "100".parse().unwrap(),
+        },
+        sender: Signer::from(ORIGINAL_SENDER.to_string()),
+        receiver: Signer::from(receiver.to_string()),
+        memo: memo.into(),
+    };
+
+    Packet {
+        seq_on_a: Sequence::from(sequence),
+        port_id_on_a: PortId::transfer(),
+        chan_id_on_a: ChannelId::new(0),
+        port_id_on_b: PortId::transfer(),
+        chan_id_on_b: ChannelId::new(0),
+        data: serde_json::to_vec(&data).unwrap(),
+        timeout_height_on_b: TimeoutHeight::Never,
+        timeout_timestamp_on_b: Timestamp::none(),
+    }
+}
+
+/// Checks that the runtime call carried in the memo of an inbound transfer is
+/// executed by the hook sender with the received tokens, and that a failed
+/// call is acknowledged with an error, with both its effects and the receipt
+/// of the tokens reverted.
+#[test(tokio::test)]
+async fn test_hook_call_execution() {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let bank = &rollup.runtime().bank;
+
+    let ibc_transfer = &rollup.runtime().ibc_transfer;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let shared_working_set = Rc::new(RefCell::new(&mut working_set));
+
+    let sdk_context = Context::new(
+        rollup.relayer_address.clone(),
+        Default::default(),
+        rollup.relayer_address.clone(),
+        0,
+    );
+
+    let transfer_ctx = || {
+        IbcTransferContext::new(
+            ibc_transfer.clone(),
+            sdk_context.clone(),
+            shared_working_set.clone(),
+        )
+    };
+
+    let relayer = Signer::from(rollup.relayer_address.to_string());
+
+    let hook_sender =
+        compute_hook_sender_address::<DefaultSpec>(&ChannelId::new(0), ORIGINAL_SENDER);
+
+    let receiver = Address::from([1; 32]);
+
+    // Plain transfers mint the vouchers the calls then spend
+    let (_, ack) = transfer_ctx().on_recv_packet_execute(
+        &inbound_packet(1, &receiver.to_string(), String::new()),
+        &relayer,
+    );
+
+    assert!(is_ack_successful(&ack));
+
+    let voucher_id: TokenId = ibc_transfer
+        .minted_token_id(
+            "transfer/channel-0/basecoin".to_string(),
+            *shared_working_set.borrow_mut(),
+        )
+        .unwrap()
+        .token_id;
+
+    let balance_of = |address: &Address| {
+        bank.get_balance_of(
+            address.as_token_holder(),
+            voucher_id,
+            *shared_working_set.borrow_mut(),
+        )
+        .unwrap_or_default()
+    };
+
+    let transfer_memo = |amount: u64| {
+        let call = serde_json::to_value(RuntimeCall::<DefaultSpec>::bank(
+            BankCallMessage::Transfer {
+                to: receiver.clone(),
+                coins: Coins {
+                    amount,
+                    token_id: voucher_id,
+                },
+            },
+        ))
+        .unwrap();
+
+        serde_json::json!({ "call": call }).to_string()
+    };
+
+    let mut hooks = IbcHooksMiddleware::new(
+        transfer_ctx(),
+        transfer_ctx(),
+        RuntimeDispatcher,
+        shared_working_set.clone(),
+    );
+
+    // The hook sender spends the received tokens
+    let (_, ack) =
+        hooks.on_recv_packet_execute(&inbound_packet(2, "ignored", transfer_memo(60)), &relayer);
+
+    assert!(is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 160);
+    assert_eq!(balance_of(&hook_sender), 40);
+
+    // A call failing on its own leaves the state as it was
+    let (_, ack) =
+        hooks.on_recv_packet_execute(&inbound_packet(3, "ignored", transfer_memo(1000)), &relayer);
+
+    assert!(!is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 160);
+    assert_eq!(balance_of(&hook_sender), 40);
+
+    // A call failing after moving funds has them moved back, and the tokens
+    // it received taken back
+    let mut failing_hooks = IbcHooksMiddleware::new(
+        transfer_ctx(),
+        transfer_ctx(),
+        FailingDispatcher,
+        shared_working_set.clone(),
+    );
+
+    let (_, ack) = failing_hooks
+        .on_recv_packet_execute(&inbound_packet(4, "ignored", transfer_memo(60)), &relayer);
+
+    assert!(!is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 160);
+    assert_eq!(balance_of(&hook_sender), 40);
+}
@@ -0,0 +1,433 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_core::channel::types::channel::{
+    ChannelEnd, Counterparty as ChanCounterparty, Order, State as ChannelState,
+};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::channel::types::Version as ChannelVersion;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, Sequence};
+use ibc_core::host::types::path::{ChannelEndPath, CommitmentPath, SeqSendPath};
+use ibc_core::host::{ExecutionContext, ValidationContext};
+use ibc_core::primitives::{Signer, Timestamp};
+use ibc_core::router::module::Module;
+use sov_bank::{CallMessage as BankCallMessage, Coins, Payable, GAS_TOKEN_ID};
+use sov_ibc::call::CallMessage;
+use sov_ibc::executor::RuntimeTxExecutor;
+use sov_ibc_ica::controller::IcaControllerContext;
+use sov_ibc_ica::host::IcaHostContext;
+use sov_ibc_ica::types::{
+    controller_port_id, host_port_id, InterchainAccountPacketData, Metadata,
+    MsgRegisterInterchainAccount, MsgSendTx,
+};
+use sov_ibc_ica::utils::compute_interchain_account_address;
+use sov_ibc_transfer::utils::is_ack_successful;
+use sov_modules_api::{Context, Module as _, Spec, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+use crate::sovereign::{RuntimeCall, RuntimeDispatcher};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+/// Checks that a rollup user can register an interchain account, that the
+/// version returned by the host is checked against the channel own connection
+/// hop and proposed version, that transactions are sent over the active
+/// channel only, and that the owner must be the sender.
+#[test(tokio::test)]
+async fn test_register_and_send_interchain_tx() {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let visible_slot = rollup
+        .ibc_ctx(&mut working_set)
+        .host_height()
+        .unwrap()
+        .revision_height();
+
+    let owner = rollup.relayer_address.clone();
+
+    let sdk_context = Context::new(
+        owner.clone(),
+        Default::default(),
+        owner.clone(),
+        visible_slot,
+    );
+
+    let connection_id = ConnectionId::new(0);
+
+    let msg_register = CallMessage::RegisterInterchainAccount(MsgRegisterInterchainAccount {
+        owner: owner.to_string(),
+        connection_id: connection_id.clone(),
+    });
+
+    // The interchain account cannot be registered on behalf of another owner
+    // than the sender
+    assert!(ibc
+        .call(
+            CallMessage::RegisterInterchainAccount(MsgRegisterInterchainAccount {
+                owner: Address::from([1; 32]).to_string(),
+                connection_id: connection_id.clone(),
+            }),
+            &sdk_context,
+            &mut working_set
+        )
+        .is_err());
+
+    ibc.call(msg_register.clone(), &sdk_context, &mut working_set)
+        .unwrap();
+
+    // The manual TAO opens `channel-0` on the transfer port beforehand
+    let port_id = controller_port_id(&owner.to_string()).unwrap();
+
+    let channel_id = ChannelId::new(1);
+
+    let channel_end_path = ChannelEndPath::new(&port_id, &channel_id);
+
+    let channel_end = rollup
+        .ibc_ctx(&mut working_set)
+        .channel_end(&channel_end_path)
+        .unwrap();
+
+    assert!(channel_end.state_matches(&ChannelState::Init));
+
+    let proposed_metadata = Metadata::from_version(&channel_end.version().to_string()).unwrap();
+
+    assert_eq!(
+        proposed_metadata,
+        Metadata::new(connection_id.clone(), connection_id.clone())
+    );
+
+    let host_version = |controller_connection_id: u64, host_connection_id: u64, address: &str| {
+        ChannelVersion::new(
+            Metadata {
+                address: address.to_string(),
+                ..Metadata::new(
+                    ConnectionId::new(controller_connection_id),
+                    ConnectionId::new(host_connection_id),
+                )
+            }
+            .to_version(),
+        )
+    };
+
+    let valid_version = host_version(0, 0, "host-account");
+
+    {
+        let shared_working_set = Rc::new(RefCell::new(&mut working_set));
+
+        let mut controller_ctx =
+            IcaControllerContext::new(rollup.runtime().ibc_ica.clone(), shared_working_set);
+
+        // The metadata must match the channel connection hop and the proposed
+        // host connection, and carry the interchain account address
+        for invalid_version in [
+            host_version(1, 0, "host-account"),
+            host_version(0, 1, "host-account"),
+            host_version(0, 0, ""),
+        ] {
+            assert!(controller_ctx
+                .on_chan_open_ack_validate(&port_id, &channel_id, &invalid_version)
+                .is_err());
+        }
+
+        // Channels not opened by the controller are rejected
+        assert!(controller_ctx
+            .on_chan_open_ack_validate(&port_id, &ChannelId::new(0), &valid_version)
+            .is_err());
+
+        controller_ctx
+            .on_chan_open_ack_execute(&port_id, &channel_id, &valid_version)
+            .unwrap();
+
+        assert_eq!(
+            controller_ctx.active_channel(&connection_id, &port_id),
+            Some(channel_id.clone())
+        );
+    }
+
+    // No other channel can be opened while this one is active
+    assert!(ibc
+        .call(msg_register, &sdk_context, &mut working_set)
+        .is_err());
+
+    // Completes the handshake the way the core `ChanOpenAck` handler does
+    let open_channel_end = ChannelEnd::new(
+        ChannelState::Open,
+        Order::Ordered,
+        ChanCounterparty::new(host_port_id(), Some(ChannelId::new(0))),
+        vec![connection_id.clone()],
+        valid_version,
+    )
+    .unwrap();
+
+    rollup
+        .ibc_ctx(&mut working_set)
+        .store_channel(&channel_end_path, open_channel_end)
+        .unwrap();
+
+    let packet_data = InterchainAccountPacketData::from_runtime_calls(
+        &[serde_json::json!({"bank": {}})],
+        String::new(),
+    );
+
+    let msg_send_tx = |connection_id: ConnectionId| {
+        CallMessage::SendInterchainTx(MsgSendTx {
+            owner: owner.to_string(),
+            connection_id,
+            packet_data: packet_data.clone(),
+            relative_timeout: 1_000_000_000,
+        })
+    };
+
+    ibc.call(
+        msg_send_tx(connection_id.clone()),
+        &sdk_context,
+        &mut working_set,
+    )
+    .unwrap();
+
+    let mut ibc_ctx = rollup.ibc_ctx(&mut working_set);
+
+    assert_eq!(
+        ibc_ctx
+            .get_next_sequence_send(&SeqSendPath::new(&port_id, &channel_id))
+            .unwrap(),
+        Sequence::from(2)
+    );
+
+    assert!(ibc_ctx
+        .get_packet_commitment(&CommitmentPath::new(
+            &port_id,
+            &channel_id,
+            Sequence::from(1)
+        ))
+        .is_ok());
+
+    // There is no active channel over any other connection
+    assert!(ibc
+        .call(
+            msg_send_tx(ConnectionId::new(1)),
+            &sdk_context,
+            &mut working_set
+        )
+        .is_err());
+}
+
+/// Checks that the host registers one interchain account per controller port
+/// and connection, with a single active channel, and executes the
+/// transactions of the account atomically through the runtime.
+#[test(tokio::test)]
+async fn test_interchain_tx_execution_on_host() {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let visible_slot = rollup
+        .ibc_ctx(&mut working_set)
+        .host_height()
+        .unwrap()
+        .revision_height();
+
+    let relayer = rollup.relayer_address.clone();
+
+    let sdk_context = Context::new(
+        relayer.clone(),
+        Default::default(),
+        relayer.clone(),
+        visible_slot,
+    );
+
+    let shared_working_set = Rc::new(RefCell::new(&mut working_set));
+
+    let mut host_ctx =
+        IcaHostContext::new(rollup.runtime().ibc_ica.clone(), shared_working_set.clone())
+            .with_executor(RuntimeTxExecutor::new(RuntimeDispatcher, sdk_context));
+
+    let connection_id = ConnectionId::new(0);
+
+    let controller_port = controller_port_id("owner").unwrap();
+
+    let host_channel = ChannelId::new(1);
+
+    let counterparty = ChanCounterparty::new(controller_port.clone(), Some(ChannelId::new(3)));
+
+    let version = ChannelVersion::new(
+        Metadata::new(connection_id.clone(), connection_id.clone()).to_version(),
+    );
+
+    // Only ordered channels with no preset address are accepted
+    assert!(host_ctx
+        .on_chan_open_try_validate(
+            Order::Unordered,
+            &[connection_id.clone()],
+            &host_port_id(),
+            &host_channel,
+            &counterparty,
+            &version,
+        )
+        .is_err());
+
+    let preset_version = ChannelVersion::new(
+        Metadata {
+            address: "preset".to_string(),
+            ..Metadata::new(connection_id.clone(), connection_id.clone())
+        }
+        .to_version(),
+    );
+
+    assert!(host_ctx
+        .on_chan_open_try_validate(
+            Order::Ordered,
+            &[connection_id.clone()],
+            &host_port_id(),
+            &host_channel,
+            &counterparty,
+            &preset_version,
+        )
+        .is_err());
+
+    let (_, host_version) = host_ctx
+        .on_chan_open_try_execute(
+            Order::Ordered,
+            &[connection_id.clone()],
+            &host_port_id(),
+            &host_channel,
+            &counterparty,
+            &version,
+        )
+        .unwrap();
+
+    let account =
+        compute_interchain_account_address::<DefaultSpec>(&connection_id, &controller_port);
+
+    assert_eq!(
+        Metadata::from_version(&host_version.to_string())
+            .unwrap()
+            .address,
+        account.to_string()
+    );
+
+    host_ctx
+        .on_chan_open_confirm_execute(&host_port_id(), &host_channel)
+        .unwrap();
+
+    // A second channel for the same interchain account is rejected
+    assert!(host_ctx
+        .on_chan_open_try_validate(
+            Order::Ordered,
+            &[connection_id.clone()],
+            &host_port_id(),
+            &ChannelId::new(2),
+            &counterparty,
+            &version,
+        )
+        .is_err());
+
+    let bank = &rollup.runtime().bank;
+
+    bank.transfer_from(
+        &relayer,
+        &account,
+        Coins {
+            amount: 100,
+            token_id: GAS_TOKEN_ID,
+        },
+        *shared_working_set.borrow_mut(),
+    )
+    .unwrap();
+
+    let receiver =
+        compute_interchain_account_address::<DefaultSpec>(&ConnectionId::new(9), &controller_port);
+
+    let balance_of = |address: &<DefaultSpec as sov_modules_api::Spec>::Address| {
+        bank.get_balance_of(
+            address.as_token_holder(),
+            GAS_TOKEN_ID,
+            *shared_working_set.borrow_mut(),
+        )
+        .unwrap_or_default()
+    };
+
+    let transfer = |amount: u64| {
+        serde_json::to_value(RuntimeCall::<DefaultSpec>::bank(
+            BankCallMessage::Transfer {
+                to: receiver.clone(),
+                coins: Coins {
+                    amount,
+                    token_id: GAS_TOKEN_ID,
+                },
+            },
+        ))
+        .unwrap()
+    };
+
+    let packet = |sequence: u64, calls: &[serde_json::Value]| Packet {
+        seq_on_a: Sequence::from(sequence),
+        port_id_on_a: controller_port.clone(),
+        chan_id_on_a: ChannelId::new(3),
+        port_id_on_b: host_port_id(),
+        chan_id_on_b: host_channel.clone(),
+        data: InterchainAccountPacketData::from_runtime_calls(calls, String::new())
+            .to_packet_bytes(),
+        timeout_height_on_b: TimeoutHeight::Never,
+        timeout_timestamp_on_b: Timestamp::none(),
+    };
+
+    let relayer_signer = Signer::from(relayer.to_string());
+
+    // Every call of the transaction gets executed by the interchain account
+    let (_, ack) =
+        host_ctx.on_recv_packet_execute(&packet(1, &[transfer(30), transfer(20)]), &relayer_signer);
+
+    assert!(is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 50);
+    assert_eq!(balance_of(&account), 50);
+
+    // A failing call reverts the ones executed before it
+    let (_, ack) = host_ctx
+        .on_recv_packet_execute(&packet(2, &[transfer(10), transfer(1000)]), &relayer_signer);
+
+    assert!(!is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 50);
+    assert_eq!(balance_of(&account), 50);
+
+    // Only the allowed runtime modules can be called
+    let disallowed_call =
+        serde_json::to_value(RuntimeCall::<DefaultSpec>::ibc_transfer(())).unwrap();
+
+    let (_, ack) = host_ctx.on_recv_packet_execute(
+        &packet(3, &[transfer(10), disallowed_call]),
+        &relayer_signer,
+    );
+
+    assert!(!is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 50);
+
+    // Without an executor, the transactions are acknowledged with an error
+    let mut host_ctx_without_executor =
+        IcaHostContext::new(rollup.runtime().ibc_ica.clone(), shared_working_set.clone());
+
+    let (_, ack) = host_ctx_without_executor
+        .on_recv_packet_execute(&packet(4, &[transfer(10)]), &relayer_signer);
+
+    assert!(!is_ack_successful(&ack));
+    assert_eq!(balance_of(&receiver), 50);
+}
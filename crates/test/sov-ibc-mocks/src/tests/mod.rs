@@ -1,2 +1,13 @@
+pub mod batch;
 pub mod client;
+pub mod export;
+pub mod fee;
+pub mod genesis;
+pub mod hooks;
+pub mod ica;
+pub mod nft;
+pub mod packet_forward;
+pub mod rate_limit;
+pub mod router;
 pub mod transfer;
+pub mod upgrade;
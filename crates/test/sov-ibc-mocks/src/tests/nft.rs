@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_app_nft_transfer::context::{NftTransferExecutionContext, NftTransferValidationContext};
+use ibc_app_nft_transfer::types::{Memo, PrefixedClassId, TokenId};
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use sov_ibc_nft_transfer::context::{Address, IbcNftTransferContext};
+use sov_ibc_nft_transfer::{NftClassConfig, NftConfig, NftTransferConfig};
+use sov_modules_api::{Module, Spec, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+
+fn class_config(class_id: &str, owner: &<DefaultSpec as Spec>::Address) -> NftClassConfig {
+    NftClassConfig {
+        class_id: class_id.to_string(),
+        class_uri: None,
+        class_data: None,
+        nfts: vec![NftConfig {
+            token_id: "token".to_string(),
+            token_uri: None,
+            token_data: None,
+            owner: owner.to_string(),
+        }],
+    }
+}
+
+/// Checks that the classes issued at genesis are recorded along with the
+/// owners of their NFTs, and that class IDs which are duplicated or
+/// trace-prefixed, and hence could collide with the classes created through
+/// IBC, are rejected, while the imported classes created through IBC must be
+/// trace-prefixed.
+#[test(tokio::test)]
+async fn test_nft_genesis() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_nft_transfer = &rollup.runtime().ibc_nft_transfer;
+
+    let owner = rollup.relayer_address.clone();
+
+    let config = |class_ids: &[&str]| NftTransferConfig {
+        classes: class_ids
+            .iter()
+            .map(|class_id| class_config(class_id, &owner))
+            .collect(),
+        ibc_classes: Vec::new(),
+    };
+
+    let ibc_config = |class_id: &str| NftTransferConfig {
+        ibc_classes: vec![class_config(class_id, &owner)],
+        ..Default::default()
+    };
+
+    for invalid_config in [
+        config(&["class", "class"]),
+        config(&["nft-transfer/channel-0/class"]),
+        config(&["class", "transfer/channel-7/other"]),
+        ibc_config("remote"),
+    ] {
+        let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+        assert!(ibc_nft_transfer
+            .genesis(&invalid_config, &mut working_set)
+            .is_err());
+    }
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    // Class IDs containing slashes which are not trace prefixes are allowed
+    ibc_nft_transfer
+        .genesis(&config(&["class", "collection/class"]), &mut working_set)
+        .unwrap();
+
+    for class_id in ["class", "collection/class"] {
+        let nft_owner = ibc_nft_transfer
+            .nft_owner(class_id.to_string(), "token".to_string(), &mut working_set)
+            .unwrap();
+
+        assert_eq!(nft_owner.owner, owner.to_string());
+    }
+
+    // Classes issued at genesis have no trace
+    assert!(ibc_nft_transfer
+        .class_traces(&mut working_set)
+        .unwrap()
+        .is_empty());
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    // Classes created through IBC keep their trace
+    ibc_nft_transfer
+        .genesis(
+            &ibc_config("nft-transfer/channel-0/remote"),
+            &mut working_set,
+        )
+        .unwrap();
+
+    let class_traces = ibc_nft_transfer.class_traces(&mut working_set).unwrap();
+
+    assert_eq!(class_traces.len(), 1);
+    assert_eq!(class_traces[0].trace_path, "nft-transfer/channel-0");
+    assert_eq!(class_traces[0].base_class_id, "remote");
+
+    assert_eq!(
+        ibc_nft_transfer
+            .nft_owner(
+                "nft-transfer/channel-0/remote".to_string(),
+                "token".to_string(),
+                &mut working_set
+            )
+            .unwrap()
+            .owner,
+        owner.to_string()
+    );
+}
+
+/// Checks that native NFTs are escrowed on the channel they are sent over and
+/// unescrowed back to the receiver, while NFTs coming from the counterparty
+/// are minted under their trace-prefixed class ID and burnt when sent back.
+#[test(tokio::test)]
+async fn test_nft_escrow_and_mint() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let owner = rollup.relayer_address.clone();
+
+    let receiver = <DefaultSpec as Spec>::Address::from([1; 32]);
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let ibc_nft_transfer = rollup.runtime().ibc_nft_transfer.clone();
+
+    ibc_nft_transfer
+        .genesis(
+            &NftTransferConfig {
+                classes: vec![class_config("class", &owner)],
+                ..Default::default()
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    let port_id = PortId::new("nft-transfer".to_string()).unwrap();
+
+    let channel_id = ChannelId::new(0);
+
+    let token_id: TokenId = "token".parse().unwrap();
+
+    let memo: Memo = "".parse().unwrap();
+
+    let native_class_id: PrefixedClassId = "class".parse().unwrap();
+
+    let ibc_class_id: PrefixedClassId = "nft-transfer/channel-0/remote".parse().unwrap();
+
+    {
+        let mut nft_ctx = IbcNftTransferContext::new(
+            ibc_nft_transfer.clone(),
+            Rc::new(RefCell::new(&mut working_set)),
+        );
+
+        let account = |address: &<DefaultSpec as Spec>::Address| Address::<DefaultSpec> {
+            address: address.clone(),
+        };
+
+        // Only the owner can send its native NFT
+        assert!(nft_ctx
+            .escrow_nft_validate(
+                &account(&receiver),
+                &port_id,
+                &channel_id,
+                &native_class_id,
+                &token_id,
+                &memo,
+            )
+            .is_err());
+
+        nft_ctx
+            .escrow_nft_validate(
+                &account(&owner),
+                &port_id,
+                &channel_id,
+                &native_class_id,
+                &token_id,
+                &memo,
+            )
+            .unwrap();
+
+        nft_ctx
+            .escrow_nft_execute(
+                &account(&owner),
+                &port_id,
+                &channel_id,
+                &native_class_id,
+                &token_id,
+                &memo,
+            )
+            .unwrap();
+
+        // The NFT is held by the escrow address of the channel it was sent
+        // over, and can only come back over that channel
+        assert!(nft_ctx
+            .unescrow_nft_validate(
+                &account(&receiver),
+                &port_id,
+                &ChannelId::new(1),
+                &native_class_id,
+                &token_id,
+            )
+            .is_err());
+
+        nft_ctx
+            .unescrow_nft_validate(
+                &account(&receiver),
+                &port_id,
+                &channel_id,
+                &native_class_id,
+                &token_id,
+            )
+            .unwrap();
+
+        nft_ctx
+            .unescrow_nft_execute(
+                &account(&receiver),
+                &port_id,
+                &channel_id,
+                &native_class_id,
+                &token_id,
+            )
+            .unwrap();
+
+        // NFTs coming from the counterparty get minted
+        nft_ctx
+            .create_or_update_class_execute(&ibc_class_id, None, None)
+            .unwrap();
+
+        nft_ctx
+            .mint_nft_validate(&account(&receiver), &ibc_class_id, &token_id, None, None)
+            .unwrap();
+
+        nft_ctx
+            .mint_nft_execute(&account(&receiver), &ibc_class_id, &token_id, None, None)
+            .unwrap();
+
+        assert!(nft_ctx
+            .mint_nft_validate(&account(&receiver), &ibc_class_id, &token_id, None, None)
+            .is_err());
+    }
+
+    let nft_owner = |class_id: &PrefixedClassId, working_set: &mut WorkingSet<DefaultSpec>| {
+        ibc_nft_transfer
+            .nft_owner(class_id.to_string(), token_id.to_string(), working_set)
+            .map(|response| response.owner)
+    };
+
+    assert_eq!(
+        nft_owner(&native_class_id, &mut working_set).unwrap(),
+        receiver.to_string()
+    );
+    assert_eq!(
+        nft_owner(&ibc_class_id, &mut working_set).unwrap(),
+        receiver.to_string()
+    );
+
+    let class_traces = ibc_nft_transfer.class_traces(&mut working_set).unwrap();
+
+    assert_eq!(class_traces.len(), 1);
+    assert_eq!(class_traces[0].trace_path, "nft-transfer/channel-0");
+    assert_eq!(class_traces[0].base_class_id, "remote");
+
+    {
+        let mut nft_ctx = IbcNftTransferContext::new(
+            ibc_nft_transfer.clone(),
+            Rc::new(RefCell::new(&mut working_set)),
+        );
+
+        let account = |address: &<DefaultSpec as Spec>::Address| Address::<DefaultSpec> {
+            address: address.clone(),
+        };
+
+        // Only the owner can send the NFT back, which burns it
+        assert!(nft_ctx
+            .burn_nft_validate(&account(&owner), &ibc_class_id, &token_id, &memo)
+            .is_err());
+
+        nft_ctx
+            .burn_nft_validate(&account(&receiver), &ibc_class_id, &token_id, &memo)
+            .unwrap();
+
+        nft_ctx
+            .burn_nft_execute(&account(&receiver), &ibc_class_id, &token_id, &memo)
+            .unwrap();
+    }
+
+    assert!(nft_owner(&ibc_class_id, &mut working_set).is_err());
+}
@@ -0,0 +1,298 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_app_transfer::types::packet::PacketData;
+use ibc_app_transfer::types::{ack_success_b64, PrefixedCoin};
+use ibc_core::channel::types::acknowledgement::{Acknowledgement, AcknowledgementStatus};
+use ibc_core::channel::types::commitment::compute_ack_commitment;
+use ibc_core::channel::types::msgs::{MsgAcknowledgement, MsgRecvPacket, MsgTimeout, PacketMsg};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::client::types::Height;
+use ibc_core::entrypoint::execute;
+use ibc_core::handler::types::msgs::MsgEnvelope;
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::{AckPath, CommitmentPath, SeqSendPath};
+use ibc_core::host::{ExecutionContext, ValidationContext};
+use ibc_core::primitives::{Signer, Timestamp};
+use sov_bank::Payable;
+use sov_ibc::context::IbcContext;
+use sov_ibc::router::IbcRouter;
+use sov_ibc_packet_forward::utils::compute_intermediate_address;
+use sov_ibc_transfer::utils::error_acknowledgement;
+use sov_modules_api::{Context, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+
+const ORIGINAL_SENDER: &str = "cosmos1sender";
+
+const FINAL_RECEIVER: &str = "cosmos1receiver";
+
+const BASE_DENOM: &str = "basecoin";
+
+/// How the packet forwarded through the rollup settles.
+enum Settlement {
+    Acknowledged(Acknowledgement),
+    TimedOut,
+}
+
+fn packet_data(denom: &str, sender: &str, receiver: &str, memo: &str) -> Vec<u8> {
+    serde_json::to_vec(&PacketData {
+        token: PrefixedCoin {
+            denom: denom.parse().unwrap(),
+            amount: "100".parse().unwrap(),
+        },
+        sender: Signer::from(sender.to_string()),
+        receiver: Signer::from(receiver.to_string()),
+        memo: memo.to_string().into(),
+    })
+    .unwrap()
+}
+
+/// Sends a packet carrying forwarding instructions from the counterparty
+/// chain to the rollup, which forwards its tokens back over the same channel,
+/// and settles the forwarded packet as given. Checks that the acknowledgement
+/// of the inbound packet is held back until then, and that the one written
+/// upon settlement is the expected one.
+async fn forward_and_settle(settlement: Settlement, expected_ack: Acknowledgement) {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let signer = rly.src_chain_ctx().signer().clone();
+
+    let ibc_packet_forward = &rollup.runtime().ibc_packet_forward;
+
+    let bank = &rollup.runtime().bank;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let intermediate_address =
+        compute_intermediate_address::<DefaultSpec>(&channel_id, ORIGINAL_SENDER);
+
+    let voucher_denom = format!("{port_id}/{channel_id}/{BASE_DENOM}");
+
+    let proof_height = Height::new(0, 1).unwrap();
+
+    let memo = serde_json::json!({
+        "forward": {
+            "receiver": FINAL_RECEIVER,
+            "port": port_id.to_string(),
+            "channel": channel_id.to_string(),
+        }
+    })
+    .to_string();
+
+    let inbound_packet = Packet {
+        seq_on_a: Sequence::from(1),
+        port_id_on_a: port_id.clone(),
+        chan_id_on_a: channel_id.clone(),
+        port_id_on_b: port_id.clone(),
+        chan_id_on_b: channel_id.clone(),
+        data: packet_data(BASE_DENOM, ORIGINAL_SENDER, "ignored", &memo),
+        timeout_height_on_b: TimeoutHeight::Never,
+        timeout_timestamp_on_b: Timestamp::none(),
+    };
+
+    let inbound_ack_path = AckPath::new(&port_id, &channel_id, inbound_packet.seq_on_a);
+
+    let execute_msg = |msg: PacketMsg, working_set: &mut WorkingSet<DefaultSpec>| {
+        let shared_working_set = Rc::new(RefCell::new(working_set));
+
+        let sdk_context = Context::new(
+            rollup.relayer_address.clone(),
+            Default::default(),
+            rollup.relayer_address.clone(),
+            0,
+        );
+
+        let mut ibc_ctx = IbcContext::new(&rollup.runtime().ibc, shared_working_set.clone());
+
+        let mut router =
+            IbcRouter::new(&rollup.runtime().ibc, sdk_context, shared_working_set).unwrap();
+
+        execute(&mut ibc_ctx, &mut router, MsgEnvelope::Packet(msg)).unwrap();
+    };
+
+    let forwarded_seq = {
+        let ibc_ctx = IbcContext::new(
+            &rollup.runtime().ibc,
+            Rc::new(RefCell::new(&mut working_set)),
+        );
+
+        ibc_ctx
+            .get_next_sequence_send(&SeqSendPath::new(&port_id, &channel_id))
+            .unwrap()
+    };
+
+    execute_msg(
+        PacketMsg::Recv(MsgRecvPacket {
+            packet: inbound_packet.clone(),
+            proof_commitment_on_a: vec![1].try_into().unwrap(),
+            proof_height_on_a: proof_height,
+            signer: signer.clone(),
+        }),
+        &mut working_set,
+    );
+
+    // The vouchers received by the intermediate account were sent onwards,
+    // hence burnt as they go back to their source
+    let voucher_id = rollup
+        .runtime()
+        .ibc_transfer
+        .minted_token_id(voucher_denom.clone(), &mut working_set)
+        .unwrap()
+        .token_id;
+
+    let voucher_balance = |working_set: &mut WorkingSet<DefaultSpec>| {
+        bank.get_balance_of(
+            intermediate_address.as_token_holder(),
+            voucher_id,
+            working_set,
+        )
+        .unwrap_or_default()
+    };
+
+    assert_eq!(voucher_balance(&mut working_set), 0);
+
+    let held_ack = ibc_packet_forward
+        .held_acknowledgements
+        .get(
+            &(port_id.clone(), channel_id.clone(), inbound_packet.seq_on_a),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(
+        held_ack.forwarded_packet,
+        (port_id.clone(), channel_id.clone(), forwarded_seq)
+    );
+    assert!(held_ack.ack_withheld && held_ack.event_withheld);
+
+    {
+        let mut ibc_ctx = IbcContext::new(
+            &rollup.runtime().ibc,
+            Rc::new(RefCell::new(&mut working_set)),
+        );
+
+        // The acknowledgement returned upon receipt was held back
+        assert!(ibc_ctx
+            .get_packet_acknowledgement(&inbound_ack_path)
+            .is_err());
+
+        assert!(ibc_ctx
+            .get_packet_commitment(&CommitmentPath::new(&port_id, &channel_id, forwarded_seq))
+            .is_ok());
+
+        // Any other acknowledgement stored while the packet is in flight is
+        // rejected rather than dropped
+        assert!(ibc_ctx
+            .store_packet_acknowledgement(&inbound_ack_path, compute_ack_commitment(&expected_ack))
+            .is_err());
+    }
+
+    let forwarded_packet = Packet {
+        seq_on_a: forwarded_seq,
+        port_id_on_a: port_id.clone(),
+        chan_id_on_a: channel_id.clone(),
+        port_id_on_b: port_id.clone(),
+        chan_id_on_b: channel_id.clone(),
+        data: packet_data(
+            &voucher_denom,
+            &intermediate_address.to_string(),
+            FINAL_RECEIVER,
+            "",
+        ),
+        timeout_height_on_b: TimeoutHeight::Never,
+        timeout_timestamp_on_b: Timestamp::none(),
+    };
+
+    let settle_msg = match settlement {
+        Settlement::Acknowledged(acknowledgement) => PacketMsg::Ack(MsgAcknowledgement {
+            packet: forwarded_packet,
+            acknowledgement,
+            proof_acked_on_b: vec![1].try_into().unwrap(),
+            proof_height_on_b: proof_height,
+            signer,
+        }),
+        Settlement::TimedOut => PacketMsg::Timeout(MsgTimeout {
+            packet: forwarded_packet,
+            next_seq_recv_on_b: forwarded_seq,
+            proof_unreceived_on_b: vec![1].try_into().unwrap(),
+            proof_height_on_b: proof_height,
+            signer,
+        }),
+    };
+
+    execute_msg(settle_msg, &mut working_set);
+
+    // Refunded vouchers are taken back from the intermediate account, while
+    // delivered ones never come back
+    assert_eq!(voucher_balance(&mut working_set), 0);
+
+    assert!(ibc_packet_forward
+        .held_acknowledgements
+        .get(
+            &(port_id.clone(), channel_id.clone(), inbound_packet.seq_on_a),
+            &mut working_set,
+        )
+        .is_none());
+
+    let ibc_ctx = IbcContext::new(
+        &rollup.runtime().ibc,
+        Rc::new(RefCell::new(&mut working_set)),
+    );
+
+    assert_eq!(
+        ibc_ctx
+            .get_packet_acknowledgement(&inbound_ack_path)
+            .unwrap(),
+        compute_ack_commitment(&expected_ack)
+    );
+}
+
+/// Checks that the successful acknowledgement of a forwarded packet is passed
+/// on to the inbound packet.
+#[test(tokio::test)]
+async fn test_forward_acknowledged() {
+    let ack: Acknowledgement = AcknowledgementStatus::success(ack_success_b64()).into();
+
+    forward_and_settle(Settlement::Acknowledged(ack.clone()), ack).await;
+}
+
+/// Checks that a forwarded packet acknowledged with an error is refunded to
+/// the intermediate account, which is then taken back, and that the inbound
+/// packet is acknowledged with an error.
+#[test(tokio::test)]
+async fn test_forward_error_acknowledged() {
+    let ack = error_acknowledgement("receiver rejected the tokens");
+
+    let expected_ack = error_acknowledgement(format!(
+        "forwarded packet failed: {}",
+        String::from_utf8_lossy(ack.as_ref())
+    ));
+
+    forward_and_settle(Settlement::Acknowledged(ack), expected_ack).await;
+}
+
+/// Checks that a forwarded packet timing out is refunded to the intermediate
+/// account, which is then taken back, and that the inbound packet is
+/// acknowledged with an error.
+#[test(tokio::test)]
+async fn test_forward_timed_out() {
+    forward_and_settle(
+        Settlement::TimedOut,
+        error_acknowledgement("forwarded packet timed out"),
+    )
+    .await;
+}
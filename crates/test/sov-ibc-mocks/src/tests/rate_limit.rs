@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use ibc_app_transfer::types::msgs::transfer::MsgTransfer;
+use ibc_app_transfer::types::packet::PacketData;
+use ibc_app_transfer::types::PrefixedCoin;
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::host::types::identifiers::{ChannelId, PortId};
+use ibc_core::host::types::path::SeqSendPath;
+use ibc_core::host::ValidationContext;
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use sov_bank::GAS_TOKEN_ID;
+use sov_ibc::call::CallMessage;
+use sov_ibc::context::IbcContext;
+use sov_ibc_transfer::context::IbcTransferContext;
+use sov_ibc_transfer::rate_limit::{
+    MsgRemoveRateLimit, MsgSetRateLimit, RateLimit, RateLimitWindow,
+};
+use sov_ibc_transfer::TransferConfig;
+use sov_modules_api::{Context, Module as _, Spec, WorkingSet};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+fn rate_limit(max_outflow: u64) -> RateLimit {
+    RateLimit {
+        channel_id: ChannelId::new(0),
+        denom: GAS_TOKEN_ID.to_string(),
+        window: RateLimitWindow::Slots(10),
+        max_inflow: 0,
+        max_outflow,
+    }
+}
+
+fn sdk_context(sender: &Address, slot: u64) -> Context<DefaultSpec> {
+    Context::new(sender.clone(), Default::default(), sender.clone(), slot)
+}
+
+/// Checks that only the rate limit admin can set and lift quotas.
+#[test(tokio::test)]
+async fn test_rate_limit_admin() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let ibc_transfer = &rollup.runtime().ibc_transfer;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let admin = rollup.relayer_address.clone();
+
+    let other = Address::from([1; 32]);
+
+    ibc_transfer
+        .genesis(
+            &TransferConfig {
+                rate_limit_admin: Some(admin.to_string()),
+                ..Default::default()
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    let set_rate_limit = CallMessage::SetRateLimit(MsgSetRateLimit {
+        rate_limit: rate_limit(100),
+    });
+
+    let remove_rate_limit = CallMessage::RemoveRateLimit(MsgRemoveRateLimit {
+        channel_id: ChannelId::new(0),
+        denom: GAS_TOKEN_ID.to_string(),
+    });
+
+    let channel_id = ChannelId::new(0);
+
+    let denom = GAS_TOKEN_ID.to_string();
+
+    assert!(ibc
+        .call(
+            set_rate_limit.clone(),
+            &sdk_context(&other, 0),
+            &mut working_set
+        )
+        .is_err());
+    assert!(ibc_transfer
+        .rate_limit(&channel_id, &denom, &mut working_set)
+        .is_none());
+
+    ibc.call(set_rate_limit, &sdk_context(&admin, 0), &mut working_set)
+        .unwrap();
+
+    assert_eq!(
+        ibc_transfer.rate_limit(&channel_id, &denom, &mut working_set),
+        Some(rate_limit(100))
+    );
+
+    assert!(ibc
+        .call(
+            remove_rate_limit.clone(),
+            &sdk_context(&other, 0),
+            &mut working_set
+        )
+        .is_err());
+    assert!(ibc_transfer
+        .rate_limit(&channel_id, &denom, &mut working_set)
+        .is_some());
+
+    ibc.call(
+        remove_rate_limit.clone(),
+        &sdk_context(&admin, 0),
+        &mut working_set,
+    )
+    .unwrap();
+
+    assert!(ibc_transfer
+        .rate_limit(&channel_id, &denom, &mut working_set)
+        .is_none());
+
+    // A quota that is not set cannot be lifted
+    assert!(ibc
+        .call(remove_rate_limit, &sdk_context(&admin, 0), &mut working_set)
+        .is_err());
+}
+
+/// Checks that transfers taking the net outflow over the quota of the sliding
+/// window are rejected, and that refunding a timed out transfer only frees up
+/// quota within the window its outflow was recorded in.
+#[test(tokio::test)]
+async fn test_outflow_quota_and_refund() {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc_transfer = &rollup.runtime().ibc_transfer;
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let sender = rollup.relayer_address.clone();
+
+    ibc_transfer
+        .genesis(
+            &TransferConfig {
+                rate_limits: vec![rate_limit(100)],
+                ..Default::default()
+            },
+            &mut working_set,
+        )
+        .unwrap();
+
+    let shared_working_set = Rc::new(RefCell::new(&mut working_set));
+
+    let port_id = PortId::transfer();
+
+    let channel_id = ChannelId::new(0);
+
+    let denom = GAS_TOKEN_ID.to_string();
+
+    let mut ibc_ctx = IbcContext::new(&rollup.runtime().ibc, shared_working_set.clone());
+
+    let timeout_timestamp_on_b =
+        (ibc_ctx.host_timestamp().unwrap() + Duration::from_secs(24 * 60 * 60)).unwrap();
+
+    let transfer_ctx = |slot: u64| {
+        IbcTransferContext::new(
+            ibc_transfer.clone(),
+            sdk_context(&sender, slot),
+            shared_working_set.clone(),
+        )
+    };
+
+    let packet_data = PacketData {
+        token: PrefixedCoin {
+            denom: denom.parse().unwrap(),
+            amount: "60".parse().unwrap(),
+        },
+        sender: Signer::from(sender.to_string()),
+        receiver: Signer::from("cosmos1receiver".to_string()),
+        memo: String::new().into(),
+    };
+
+    // Sends 60 tokens at the given slot, returning the packet on success
+    let mut send = |slot: u64| {
+        let seq_on_a = ibc_ctx
+            .get_next_sequence_send(&SeqSendPath::new(&port_id, &channel_id))
+            .unwrap();
+
+        let msg = MsgTransfer {
+            port_id_on_a: port_id.clone(),
+            chan_id_on_a: channel_id.clone(),
+            packet_data: packet_data.clone(),
+            timeout_height_on_b: TimeoutHeight::Never,
+            timeout_timestamp_on_b,
+        };
+
+        transfer_ctx(slot)
+            .send_transfer(&mut ibc_ctx, msg)
+            .map(|_| Packet {
+                seq_on_a,
+                port_id_on_a: port_id.clone(),
+                chan_id_on_a: channel_id.clone(),
+                port_id_on_b: port_id.clone(),
+                chan_id_on_b: channel_id.clone(),
+                data: serde_json::to_vec(&packet_data).unwrap(),
+                timeout_height_on_b: TimeoutHeight::Never,
+                timeout_timestamp_on_b,
+            })
+    };
+
+    let time_out = |packet: &Packet, slot: u64| {
+        let (_, res) =
+            transfer_ctx(slot).on_timeout_packet_execute(packet, &Signer::from(sender.to_string()));
+
+        res.unwrap();
+    };
+
+    let outflow = || {
+        ibc_transfer
+            .rate_limit_usage(&channel_id, &denom, *shared_working_set.borrow_mut())
+            .unwrap()
+            .outflow
+    };
+
+    let first_packet = send(0).unwrap();
+
+    assert!(send(0).is_err());
+
+    // Refunding a transfer frees up its quota
+    time_out(&first_packet, 0);
+
+    assert_eq!(outflow(), 0);
+
+    let second_packet = send(0).unwrap();
+
+    // The outflow of the previous window still partly counts
+    assert!(send(12).is_err());
+
+    // Once a whole window elapsed, it no longer does, and refunding the
+    // transfers sent back then does not free up quota in the current window
+    send(25).unwrap();
+
+    assert_eq!(outflow(), 60);
+
+    time_out(&second_packet, 25);
+
+    assert_eq!(outflow(), 60);
+}
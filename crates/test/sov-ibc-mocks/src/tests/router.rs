@@ -0,0 +1,286 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_app_transfer::types::MODULE_ID_STR;
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
+use ibc_core::channel::types::channel::{Counterparty, Order};
+use ibc_core::channel::types::error::{ChannelError, PacketError};
+use ibc_core::channel::types::msgs::{ChannelMsg, MsgChannelOpenInit};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::Version;
+use ibc_core::entrypoint::dispatch;
+use ibc_core::handler::types::msgs::MsgEnvelope;
+use ibc_core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc_core::host::types::path::ChannelEndPath;
+use ibc_core::host::ValidationContext;
+use ibc_core::primitives::Signer;
+use ibc_core::router::module::Module;
+use ibc_core::router::router::Router;
+use ibc_core::router::types::module::{ModuleExtras, ModuleId};
+use sov_ibc::context::IbcContext;
+use sov_ibc::router::IbcRouter;
+use sov_modules_api::{Context, WorkingSet};
+use test_log::test;
+
+use crate::relayer::{Handle, RelayerBuilder};
+
+const CUSTOM_MODULE_ID_STR: &str = "custom";
+
+/// A module recording the channels opened on it, standing for the modules
+/// rollups register on the router.
+#[derive(Debug, Default)]
+struct CustomModule {
+    opened_channels: Rc<RefCell<Vec<(PortId, ChannelId)>>>,
+}
+
+impl Module for CustomModule {
+    fn on_chan_open_init_validate(
+        &self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        version: &Version,
+    ) -> Result<Version, ChannelError> {
+        Ok(version.clone())
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        version: &Version,
+    ) -> Result<(ModuleExtras, Version), ChannelError> {
+        self.opened_channels
+            .borrow_mut()
+            .push((port_id.clone(), channel_id.clone()));
+
+        Ok((ModuleExtras::empty(), version.clone()))
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        counterparty_version: &Version,
+    ) -> Result<Version, ChannelError> {
+        Ok(counterparty_version.clone())
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        counterparty_version: &Version,
+    ) -> Result<(ModuleExtras, Version), ChannelError> {
+        self.opened_channels
+            .borrow_mut()
+            .push((port_id.clone(), channel_id.clone()));
+
+        Ok((ModuleExtras::empty(), counterparty_version.clone()))
+    }
+
+    fn on_recv_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        (
+            ModuleExtras::empty(),
+            Acknowledgement::try_from(vec![1]).expect("never fails as the ack is not empty"),
+        )
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Ok(())
+    }
+
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (ModuleExtras::empty(), Ok(()))
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Ok(())
+    }
+
+    fn on_timeout_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (ModuleExtras::empty(), Ok(()))
+    }
+}
+
+/// Checks that ports bound at genesis can neither be re-bound nor claimed by
+/// another module, and that ports can only be bound to registered modules.
+#[test(tokio::test)]
+async fn test_port_binding_conflicts() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let sdk_context = Context::new(
+        rollup.relayer_address.clone(),
+        Default::default(),
+        rollup.relayer_address.clone(),
+        0,
+    );
+
+    let mut router = IbcRouter::new(
+        &rollup.runtime().ibc,
+        sdk_context,
+        Rc::new(RefCell::new(&mut working_set)),
+    )
+    .unwrap();
+
+    let transfer_port = PortId::transfer();
+
+    // The transfer port is bound at genesis
+    assert_eq!(
+        router.bound_module(&transfer_port),
+        Some(MODULE_ID_STR.to_string())
+    );
+    assert!(router.bind_port(&transfer_port, MODULE_ID_STR).is_err());
+    assert!(router.claim_port(&transfer_port, MODULE_ID_STR).is_ok());
+
+    // Neither an unregistered nor another registered module can take it over
+    assert!(router
+        .claim_port(&transfer_port, CUSTOM_MODULE_ID_STR)
+        .is_err());
+
+    router
+        .add_route(CUSTOM_MODULE_ID_STR, CustomModule::default())
+        .unwrap();
+
+    assert!(router
+        .add_route(CUSTOM_MODULE_ID_STR, CustomModule::default())
+        .is_err());
+    assert!(router
+        .claim_port(&transfer_port, CUSTOM_MODULE_ID_STR)
+        .is_err());
+
+    // Unbound ports can only be bound to registered modules, and only once
+    let custom_port = PortId::new(CUSTOM_MODULE_ID_STR.to_string()).unwrap();
+
+    assert!(router.bind_port(&custom_port, "unregistered").is_err());
+
+    router
+        .bind_port(&custom_port, CUSTOM_MODULE_ID_STR)
+        .unwrap();
+
+    assert!(router
+        .bind_port(&custom_port, CUSTOM_MODULE_ID_STR)
+        .is_err());
+    assert!(router
+        .claim_port(&custom_port, CUSTOM_MODULE_ID_STR)
+        .is_ok());
+    assert!(router.claim_port(&custom_port, MODULE_ID_STR).is_err());
+
+    assert_eq!(
+        router.lookup_module(&custom_port),
+        Some(ModuleId::new(CUSTOM_MODULE_ID_STR.to_string()))
+    );
+    assert_eq!(
+        router.lookup_module(&transfer_port),
+        Some(ModuleId::new(MODULE_ID_STR.to_string()))
+    );
+}
+
+/// Checks that channel handshakes on a port bound to a module registered on
+/// the router are routed to that module.
+#[test(tokio::test)]
+async fn test_routing_to_custom_module() {
+    let rly = RelayerBuilder::default()
+        .await
+        .with_manual_tao()
+        .setup()
+        .await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let mut working_set = WorkingSet::new(rollup.prover_storage());
+
+    let shared_working_set = Rc::new(RefCell::new(&mut working_set));
+
+    let sdk_context = Context::new(
+        rollup.relayer_address.clone(),
+        Default::default(),
+        rollup.relayer_address.clone(),
+        0,
+    );
+
+    let mut ibc_ctx = IbcContext::new(&rollup.runtime().ibc, shared_working_set.clone());
+
+    let mut router =
+        IbcRouter::new(&rollup.runtime().ibc, sdk_context, shared_working_set).unwrap();
+
+    let custom_module = CustomModule::default();
+
+    let opened_channels = custom_module.opened_channels.clone();
+
+    let custom_port = PortId::new(CUSTOM_MODULE_ID_STR.to_string()).unwrap();
+
+    router
+        .add_route(CUSTOM_MODULE_ID_STR, custom_module)
+        .unwrap();
+    router
+        .bind_port(&custom_port, CUSTOM_MODULE_ID_STR)
+        .unwrap();
+
+    let msg_chan_open_init = MsgChannelOpenInit {
+        port_id_on_a: custom_port.clone(),
+        connection_hops_on_a: vec![ConnectionId::new(0)],
+        port_id_on_b: custom_port.clone(),
+        ordering: Order::Unordered,
+        signer: rly.src_chain_ctx().signer().clone(),
+        version_proposal: Version::new("custom-1".to_string()),
+    };
+
+    dispatch(
+        &mut ibc_ctx,
+        &mut router,
+        MsgEnvelope::Channel(ChannelMsg::OpenInit(msg_chan_open_init)),
+    )
+    .unwrap();
+
+    let opened_channels = opened_channels.borrow().clone();
+
+    assert_eq!(opened_channels.len(), 1);
+
+    let (port_id, channel_id) = &opened_channels[0];
+
+    assert_eq!(port_id, &custom_port);
+
+    let channel_end = ibc_ctx
+        .channel_end(&ChannelEndPath::new(port_id, channel_id))
+        .unwrap();
+
+    assert_eq!(channel_end.version(), &Version::new("custom-1".to_string()));
+}
@@ -24,11 +24,17 @@ async fn test_escrow_unescrow_on_sov() {
     // set transfer parameters
     let gas_token = relayer_builder.setup_cfg().gas_token_config();
 
+    // The transfer sender must be the sender of the rollup transactions, which
+    // is the relayer address
     let mut cfg = TransferTestConfig::builder()
-        .sov_address(gas_token.address_and_balances[0].0)
+        .sov_address(relayer_builder.setup_cfg().get_relayer_address())
         .build();
 
-    let expected_sender_balance = gas_token.address_and_balances[0].1 - cfg.amount * 2;
+    let expected_sender_balance = rly
+        .src_chain_ctx()
+        .service()
+        .get_balance_of(&cfg.sov_address, gas_token.token_id)
+        - cfg.amount * 2;
 
     // -----------------------------------------------------------------------
     // Send a `MsgTransfer` to the rollup (twice)
@@ -141,8 +147,10 @@ async fn test_mint_burn_on_sov() {
 
     // set transfer parameters
     let gas_token = relayer_builder.setup_cfg().gas_token_config();
+    // The transfer sender must be the sender of the rollup transactions, which
+    // is the relayer address
     let mut cfg = TransferTestConfig::builder()
-        .sov_address(gas_token.address_and_balances[0].0)
+        .sov_address(relayer_builder.setup_cfg().get_relayer_address())
         .build();
 
     // Fake token with the same parameters as the gas token but a different name
@@ -237,7 +245,7 @@ async fn test_mint_burn_on_sov() {
     };
 
     // -----------------------------------------------------------------------
-    // Send a `MsgRecvPacket` paired with a `MsgUpdateClient` to the rollup
+    // Send a `MsgRecvPacket` batched with a `MsgUpdateClient` to the rollup
     // -----------------------------------------------------------------------
 
     let msg_update_client = rly.build_msg_update_client_for_sov(target_height).await;
@@ -246,8 +254,10 @@ async fn test_mint_burn_on_sov() {
         .build_msg_recv_packet_for_sov(target_height, msg_transfer_on_cos.clone())
         .await;
 
+    let msg_batch = rly.build_msg_batch_for_sov(vec![msg_update_client, msg_recv_packet]);
+
     rly.src_chain_ctx()
-        .submit_msgs(vec![msg_update_client.into(), msg_recv_packet.into()])
+        .submit_msgs(vec![msg_batch.into()])
         .await;
 
     // -----------------------------------------------------------------------
@@ -0,0 +1,241 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ibc_core::client::types::proto::v1::MsgIbcSoftwareUpgrade as RawMsgIbcSoftwareUpgrade;
+use ibc_core::client::types::Height;
+use ibc_core::primitives::proto::Any;
+use ibc_core::primitives::Timestamp;
+use prost::Message;
+use sov_celestia_client::client_state::ClientState as HostClientState;
+use sov_celestia_client::consensus_state::ConsensusState as HostConsensusState;
+use sov_celestia_client::types::client_state::test_util::{
+    dummy_sov_client_state, dummy_sov_consensus_state,
+};
+use sov_ibc::call::{CallMessage, IBC_SOFTWARE_UPGRADE_TYPE_URL};
+use sov_ibc::context::IbcContext;
+use sov_ibc::helpers::WithoutProof;
+use sov_ibc::upgrade::{MsgCancelUpgrade, MsgScheduleUpgrade, UpgradePlan};
+use sov_ibc::IbcConfig;
+use sov_modules_api::{Context, Module, Spec, StateCheckpoint};
+use test_log::test;
+
+use crate::configs::DefaultSpec;
+use crate::relayer::{Handle, RelayerBuilder};
+
+type Address = <DefaultSpec as Spec>::Address;
+
+const UPGRADE_HEIGHT: u64 = 10;
+
+fn sdk_context(sender: &Address) -> Context<DefaultSpec> {
+    Context::new(sender.clone(), Default::default(), sender.clone(), 0)
+}
+
+fn upgraded_client_state() -> Any {
+    let client_state: HostClientState = dummy_sov_client_state(
+        "upgraded-rollup".parse().unwrap(),
+        Height::new(0, UPGRADE_HEIGHT + 10).unwrap(),
+    )
+    .into();
+
+    client_state.into()
+}
+
+fn schedule_upgrade(name: &str) -> CallMessage {
+    CallMessage::ScheduleUpgrade(MsgScheduleUpgrade {
+        plan: UpgradePlan {
+            name: name.to_string(),
+            height: UPGRADE_HEIGHT,
+            upgraded_client_state: upgraded_client_state(),
+        },
+    })
+}
+
+fn ibc_software_upgrade(name: &str, signer: &Address) -> CallMessage {
+    let mut msg = RawMsgIbcSoftwareUpgrade {
+        upgraded_client_state: Some(upgraded_client_state()),
+        signer: signer.to_string(),
+        ..Default::default()
+    };
+
+    let plan = msg.plan.get_or_insert_with(Default::default);
+
+    plan.name = name.to_string();
+    plan.height = UPGRADE_HEIGHT as i64;
+
+    CallMessage::Core(Any {
+        type_url: IBC_SOFTWARE_UPGRADE_TYPE_URL.to_string(),
+        value: msg.encode_to_vec(),
+    })
+}
+
+/// Checks that the authority can cancel a pending upgrade, and that a plan is
+/// applied and cleared by the first slot hook at or after the slot before its
+/// height, even if that slot went by.
+#[test(tokio::test)]
+async fn test_upgrade_plan() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let authority = rollup.relayer_address.clone();
+
+    let mut working_set = StateCheckpoint::new(rollup.prover_storage()).to_revertable_unmetered();
+
+    ibc.genesis(
+        &IbcConfig {
+            authority: Some(authority.to_string()),
+            ..Default::default()
+        },
+        &mut working_set,
+    )
+    .unwrap();
+
+    // Nothing to cancel yet
+    assert!(ibc
+        .call(
+            CallMessage::CancelUpgrade(MsgCancelUpgrade {}),
+            &sdk_context(&authority),
+            &mut working_set
+        )
+        .is_err());
+
+    ibc.call(
+        schedule_upgrade("cancelled"),
+        &sdk_context(&authority),
+        &mut working_set,
+    )
+    .unwrap();
+
+    assert_eq!(
+        ibc.upgrade_plan(&mut working_set).unwrap().name,
+        "cancelled"
+    );
+
+    ibc.call(
+        CallMessage::CancelUpgrade(MsgCancelUpgrade {}),
+        &sdk_context(&authority),
+        &mut working_set,
+    )
+    .unwrap();
+
+    assert!(ibc.upgrade_plan(&mut working_set).is_none());
+
+    ibc.call(
+        schedule_upgrade("applied"),
+        &sdk_context(&authority),
+        &mut working_set,
+    )
+    .unwrap();
+
+    let consensus_state: HostConsensusState =
+        dummy_sov_consensus_state(Timestamp::from_nanoseconds(1).unwrap()).into();
+
+    let mut checkpoint = working_set.checkpoint().0;
+
+    // The slots before the one preceding the upgrade height leave it pending
+    ibc.apply_upgrade_plan(
+        &Height::new(0, UPGRADE_HEIGHT - 2).unwrap(),
+        &consensus_state,
+        &mut checkpoint,
+    );
+
+    let mut working_set = checkpoint.to_revertable_unmetered();
+
+    assert!(ibc.upgrade_plan(&mut working_set).is_some());
+
+    let mut checkpoint = working_set.checkpoint().0;
+
+    // The slot before the upgrade height went by without the plan applied
+    ibc.apply_upgrade_plan(
+        &Height::new(0, UPGRADE_HEIGHT).unwrap(),
+        &consensus_state,
+        &mut checkpoint,
+    );
+
+    let mut working_set = checkpoint.to_revertable_unmetered();
+
+    assert!(ibc.upgrade_plan(&mut working_set).is_none());
+
+    let ibc_ctx = IbcContext::new(ibc, Rc::new(RefCell::new(&mut working_set)));
+
+    assert!(ibc_ctx
+        .query_upgraded_client_state::<WithoutProof>(UPGRADE_HEIGHT)
+        .unwrap()
+        .is_some());
+
+    assert_eq!(
+        ibc_ctx
+            .query_upgraded_consensus_state::<WithoutProof>(UPGRADE_HEIGHT)
+            .unwrap(),
+        Some(consensus_state)
+    );
+}
+
+/// Checks that rollup upgrades can only be scheduled and cancelled by the
+/// authority, whether through the module messages or `MsgIbcSoftwareUpgrade`,
+/// whose signer must also be the sender.
+#[test(tokio::test)]
+async fn test_upgrade_authority() {
+    let rly = RelayerBuilder::default().await.setup().await;
+
+    let rollup = rly.src_chain_ctx().service();
+
+    let ibc = &rollup.runtime().ibc;
+
+    let authority = rollup.relayer_address.clone();
+
+    let other = Address::from([1; 32]);
+
+    let mut working_set = StateCheckpoint::new(rollup.prover_storage()).to_revertable_unmetered();
+
+    ibc.genesis(
+        &IbcConfig {
+            authority: Some(authority.to_string()),
+            ..Default::default()
+        },
+        &mut working_set,
+    )
+    .unwrap();
+
+    for msg in [
+        schedule_upgrade("rejected"),
+        ibc_software_upgrade("rejected", &other),
+    ] {
+        assert!(ibc
+            .call(msg, &sdk_context(&other), &mut working_set)
+            .is_err());
+    }
+
+    // The authority cannot be named as the signer by another sender
+    assert!(ibc
+        .call(
+            ibc_software_upgrade("rejected", &authority),
+            &sdk_context(&other),
+            &mut working_set
+        )
+        .is_err());
+
+    assert!(ibc.upgrade_plan(&mut working_set).is_none());
+
+    ibc.call(
+        ibc_software_upgrade("scheduled", &authority),
+        &sdk_context(&authority),
+        &mut working_set,
+    )
+    .unwrap();
+
+    assert!(ibc
+        .call(
+            CallMessage::CancelUpgrade(MsgCancelUpgrade {}),
+            &sdk_context(&other),
+            &mut working_set
+        )
+        .is_err());
+
+    let plan = ibc.upgrade_plan(&mut working_set).unwrap();
+
+    assert_eq!(plan.name, "scheduled");
+    assert_eq!(plan.height, UPGRADE_HEIGHT);
+}